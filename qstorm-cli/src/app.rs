@@ -1,9 +1,25 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 use anyhow::{Result, anyhow};
+use chrono::{DateTime, Local, Utc};
+use futures::FutureExt;
+use futures::stream::{BoxStream, StreamExt};
+#[cfg(feature = "notifications")]
+use qstorm_core::Notifier;
 use qstorm_core::{
-    BurstMetrics, Config, EmbeddedQuery, Embedder, QueryFile, SearchResults,
-    config::{ProviderConfig, ProviderKind},
+    BurstLog, BurstMetrics, ChaosProvider, Config, Dashboard, EmbeddedQuery, FailureLog, Metrics,
+    Notification, PrometheusExporter, ProviderFactory, ProviderRegistry, QueryEmbedder, QueryFile,
+    QuerySource, RerankStage, Resumed, RunReport, RunnerEvent, SampleLog, SearchMode,
+    SearchResults, SlowQueryLog, StatsdSink, SweepPoint,
+    config::{BenchmarkConfig, ChaosConfig, ProviderConfig},
     runner::BenchmarkRunner,
 };
+use serde::{Deserialize, Serialize};
+
+use crate::session::{SessionEvent, SessionRecorder};
+use crate::theme::Theme;
 
 /// Which TUI view is active
 #[derive(Default, Clone, Copy, PartialEq, Eq)]
@@ -11,28 +27,197 @@ pub enum View {
     #[default]
     Dashboard,
     Results,
+    Errors,
+    /// The loaded config, credentials redacted
+    Config,
+}
+
+/// A load setting that can be changed from the TUI between bursts
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Tunable {
+    Concurrency,
+    BurstSize,
+    TopK,
+}
+
+/// The load settings the TUI can change
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoadSettings {
+    pub concurrency: usize,
+    pub burst_size: usize,
+    pub top_k: usize,
+}
+
+impl LoadSettings {
+    fn of(config: &BenchmarkConfig) -> Self {
+        Self {
+            concurrency: config.concurrency,
+            burst_size: config.burst_size,
+            top_k: config.top_k,
+        }
+    }
+}
+
+/// A second provider benchmarked alongside the configured one (`--versus`),
+/// with the same queries and load settings
+pub struct Rival {
+    provider: ProviderConfig,
+    runner: Option<BenchmarkRunner>,
+}
+
+/// A breach of the configured SLOs, shown as a banner until a burst meets
+/// every objective again
+pub struct SloBreach {
+    pub since: DateTime<Utc>,
+    /// Breaching bursts in a row so far
+    pub bursts: usize,
+    /// Objectives the latest burst broke
+    pub reasons: Vec<String>,
+}
+
+/// Failed requests kept for the errors view
+const RECENT_FAILURES: usize = 200;
+
+/// How long a notice stays in the header
+const NOTICE_DURATION: Duration = Duration::from_secs(5);
+
+/// What the TUI's export key writes: the charted bursts and a summary of
+/// the whole run up to that moment
+#[derive(Serialize)]
+struct Snapshot<'a> {
+    taken_at: DateTime<Utc>,
+    /// Load settings at the time, with any changes from the keyboard
+    settings: LoadSettings,
+    summary: RunReport,
+    annotations: &'a [Annotation],
+    /// SLO breaches that started while the charted bursts ran
+    slo_breaches: &'a [Annotation],
+    /// The bursts in the charts, oldest first
+    bursts: &'a [BurstMetrics],
+}
+
+/// A settings change or SLO breach noted in the history
+#[derive(Serialize)]
+pub struct Annotation {
+    /// Run-wide index of the first burst it applies to
+    pub burst: usize,
+    pub note: String,
+}
+
+/// Sample and custom query results kept for browsing in the results view
+const SAMPLE_HISTORY: usize = 50;
+
+/// Where a sample result's query came from, so it can be run again
+#[derive(Clone)]
+pub enum SampleQuery {
+    /// Index into the loaded query set
+    Loaded(usize),
+    /// Typed into the search bar
+    Custom(EmbeddedQuery),
 }
 
 /// A captured sample query result for display
 pub struct SampleResult {
     pub query: String,
+    pub source: SampleQuery,
+    /// Mode the results were fetched in, the benchmark's own
+    pub mode: SearchMode,
     pub results: SearchResults,
+    /// The same query in the other mode, when comparing modes
+    pub other: Option<OtherMode>,
+}
+
+/// A sample query's results in the mode the benchmark isn't using
+pub struct OtherMode {
+    /// Why the provider couldn't run it, e.g. no native hybrid search
+    pub results: std::result::Result<SearchResults, String>,
+}
+
+/// Picker over the loaded query set, narrowed by what's been typed
+#[derive(Default)]
+pub struct QueryPicker {
+    pub filter: String,
+    /// Position in the filtered list
+    pub selected: usize,
 }
 
 /// Application state
 pub struct App {
     pub config: Config,
     runner: Option<BenchmarkRunner>,
-    embedder: Option<Embedder>,
-    queries: Vec<EmbeddedQuery>,
+    embedder: Option<QueryEmbedder>,
+    queries: Arc<[EmbeddedQuery]>,
     pub state: AppState,
     pub view: View,
+    /// Colors and drawing characters from the config's `tui` section
+    pub theme: Theme,
+    /// Show captured log lines under the current view
+    pub show_logs: bool,
     pub history: MetricsHistory,
     pub status_message: Option<String>,
-    pub last_sample: Option<SampleResult>,
+    /// Queries run in the results view, oldest first
+    pub samples: Vec<SampleResult>,
+    /// Which of `samples` is shown
+    pub sample_index: usize,
     pub results_scroll: usize,
+    /// Open while choosing a query from the loaded set
+    pub picker: Option<QueryPicker>,
+    /// Run results-view queries in vector and hybrid mode side by side
+    pub compare_modes: bool,
     pub query_input: String,
     pub editing: bool,
+    /// Recent failures and counts per class, for the errors view
+    pub failures: FailureLog,
+    pub errors_scroll: usize,
+    /// First line of the config shown in the config view
+    pub config_scroll: usize,
+    /// Stop condition that paused the run, shown until resumed
+    pub stop_reason: Option<String>,
+    /// What put the app in the error state, shown until reconnected
+    pub last_error: Option<String>,
+    /// When the next automatic reconnect is due
+    pub reconnect_at: Option<Instant>,
+    /// Load settings of the next burst, including changes from the keyboard
+    pub settings: LoadSettings,
+    /// `settings` were changed from the keyboard since the last burst started
+    retuned: bool,
+    /// Stands in for the run's start in snapshots
+    started_at: DateTime<Utc>,
+    /// A snapshot is waiting for the runner to come back from a burst
+    snapshot_requested: bool,
+    /// Short-lived message for the header, e.g. where a snapshot went
+    notice: Option<(Instant, String)>,
+    /// The whole run summed up, shown after quitting
+    pub summary: Option<RunReport>,
+    /// Ongoing breach of `config.slo`, if the latest burst broke it
+    pub slo_breach: Option<SloBreach>,
+    /// A breach started and the bell hasn't been rung for it yet
+    bell_pending: bool,
+    /// Prometheus endpoint fed with every finished burst
+    exporter: Option<PrometheusExporter>,
+    /// Web dashboard sent every finished burst
+    dashboard: Option<Dashboard>,
+    /// StatsD agent pushed every finished burst
+    statsd: Option<StatsdSink>,
+    /// Webhook told when the run ends, fails, or breaks its SLOs
+    #[cfg(feature = "notifications")]
+    notifier: Option<Notifier>,
+    /// File every finished burst is appended to (`--out`)
+    burst_log: Option<BurstLog>,
+    /// Provider run burst for burst alongside this one, for comparison
+    rival: Option<Rival>,
+    /// Recording of the session (`--record`)
+    recorder: Option<SessionRecorder>,
+    /// Queries the replayed session had loaded, when replaying
+    replayed_queries: Option<usize>,
+    /// What the runner is doing, caught up on between frames
+    runner_events: Option<BoxStream<'static, RunnerEvent>>,
+    /// Requests finished so far in the burst in flight
+    pub burst_progress: Option<usize>,
+    /// Whether the runner last said the provider was reachable
+    provider_healthy: Option<bool>,
+    /// A change in the provider's health not yet taken, with its reason
+    health_change: Option<(bool, Option<String>)>,
 }
 
 #[derive(Default, Clone, Copy, PartialEq, Eq)]
@@ -50,6 +235,17 @@ pub enum AppState {
 pub struct MetricsHistory {
     pub bursts: Vec<BurstMetrics>,
     pub max_history: usize,
+    /// Chart the bursts started this long before the latest one, rather
+    /// than the last `max_history`
+    pub window: Option<Duration>,
+    /// Bursts pushed over the whole run, including those scrolled out
+    pushed: usize,
+    /// Settings changes made while the charted bursts ran
+    annotations: Vec<Annotation>,
+    /// SLO breaches that started while the charted bursts ran
+    breaches: Vec<Annotation>,
+    /// Bursts from the `--versus` provider over the same span, oldest first
+    pub rival_bursts: Vec<BurstMetrics>,
 }
 
 impl Default for MetricsHistory {
@@ -57,6 +253,11 @@ impl Default for MetricsHistory {
         Self {
             bursts: Vec::new(),
             max_history: 100,
+            window: None,
+            pushed: 0,
+            annotations: Vec::new(),
+            breaches: Vec::new(),
+            rival_bursts: Vec::new(),
         }
     }
 }
@@ -64,11 +265,114 @@ impl Default for MetricsHistory {
 impl MetricsHistory {
     pub fn push(&mut self, metrics: BurstMetrics) {
         self.bursts.push(metrics);
-        if self.bursts.len() > self.max_history {
-            self.bursts.remove(0);
+        self.pushed += 1;
+        let expired = self.expired(&self.bursts);
+        self.bursts.drain(..expired);
+        let first = self.pushed - self.bursts.len();
+        self.annotations.retain(|a| a.burst >= first);
+        self.breaches.retain(|a| a.burst >= first);
+    }
+
+    /// Keep a burst from the `--versus` provider, charted alongside
+    pub fn push_rival(&mut self, metrics: BurstMetrics) {
+        self.rival_bursts.push(metrics);
+        let expired = self.expired(&self.rival_bursts);
+        self.rival_bursts.drain(..expired);
+    }
+
+    /// How many of the oldest `bursts` have scrolled out of the charts
+    fn expired(&self, bursts: &[BurstMetrics]) -> usize {
+        match self.window {
+            Some(_) => {
+                let since = self.origin();
+                bursts.iter().take_while(|m| m.timestamp < since).count()
+            }
+            None => bursts.len().saturating_sub(self.max_history),
         }
     }
 
+    /// Note a change that applies from the next burst on
+    pub fn annotate(&mut self, note: String) {
+        self.annotations.push(Annotation {
+            burst: self.pushed,
+            note,
+        });
+    }
+
+    /// Note an SLO breach that started with the latest burst
+    pub fn mark_breach(&mut self, note: String) {
+        self.breaches.push(Annotation {
+            burst: self.pushed.saturating_sub(1),
+            note,
+        });
+    }
+
+    /// Chart positions of the settings changes still in view, with their
+    /// notes. A change that hasn't reached a burst yet sits at the right edge.
+    pub fn annotations(&self) -> Vec<(f64, &str)> {
+        self.positions(&self.annotations)
+    }
+
+    /// Chart positions of the SLO breaches still in view, with what broke
+    pub fn breaches(&self) -> Vec<(f64, &str)> {
+        self.positions(&self.breaches)
+    }
+
+    fn positions<'a>(&'a self, notes: &'a [Annotation]) -> Vec<(f64, &'a str)> {
+        let first = self.pushed - self.bursts.len();
+        let edge = self.x_bounds()[1];
+        notes
+            .iter()
+            .map(|a| {
+                let x = self.bursts.get(a.burst - first).map_or(edge, |m| self.x(m));
+                (x, a.note.as_str())
+            })
+            .collect()
+    }
+
+    /// Wall-clock time at the left edge of the charts
+    fn origin(&self) -> DateTime<Utc> {
+        match (self.window, self.bursts.last()) {
+            (Some(window), Some(last)) => chrono::Duration::from_std(window)
+                .ok()
+                .and_then(|window| last.timestamp.checked_sub_signed(window))
+                .unwrap_or(DateTime::<Utc>::MIN_UTC),
+            _ => self.bursts.first().map_or_else(Utc::now, |m| m.timestamp),
+        }
+    }
+
+    /// Chart position of a burst: seconds from the left edge
+    fn x(&self, metrics: &BurstMetrics) -> f64 {
+        (metrics.timestamp - self.origin()).num_milliseconds() as f64 / 1000.0
+    }
+
+    /// Time span the charts' x axis covers, in seconds from the left edge
+    pub fn x_bounds(&self) -> [f64; 2] {
+        let span = match (self.window, self.bursts.last()) {
+            (Some(window), _) => window.as_secs_f64(),
+            (None, Some(last)) => self.x(last),
+            (None, None) => 0.0,
+        };
+        [0.0, span.max(1.0)]
+    }
+
+    /// Wall-clock times (local) at the left edge, middle, and right edge
+    /// of the charts
+    pub fn x_labels(&self) -> Vec<String> {
+        if self.bursts.is_empty() {
+            return Vec::new();
+        }
+        let origin = self.origin();
+        let [_, span] = self.x_bounds();
+        [0.0, span / 2.0, span]
+            .into_iter()
+            .map(|offset| {
+                let at = origin + chrono::Duration::milliseconds((offset * 1000.0) as i64);
+                at.with_timezone(&Local).format("%H:%M:%S").to_string()
+            })
+            .collect()
+    }
+
     pub fn latest(&self) -> Option<&BurstMetrics> {
         self.bursts.last()
     }
@@ -76,60 +380,373 @@ impl MetricsHistory {
     pub fn qps_series(&self) -> Vec<(f64, f64)> {
         self.bursts
             .iter()
-            .enumerate()
-            .map(|(i, m)| (i as f64, m.qps))
+            .map(|m| (self.x(m), m.qps))
             .collect()
     }
 
     pub fn p50_series(&self) -> Vec<(f64, f64)> {
         self.bursts
             .iter()
-            .enumerate()
-            .map(|(i, m)| (i as f64, m.latency.p50_us as f64 / 1000.0))
+            .map(|m| (self.x(m), m.latency.p50_us as f64 / 1000.0))
             .collect()
     }
 
     pub fn p99_series(&self) -> Vec<(f64, f64)> {
         self.bursts
             .iter()
-            .enumerate()
-            .map(|(i, m)| (i as f64, m.latency.p99_us as f64 / 1000.0))
+            .map(|m| (self.x(m), m.latency.p99_us as f64 / 1000.0))
+            .collect()
+    }
+
+    /// p99 of engine-reported processing time, for providers that report it
+    pub fn server_p99_series(&self) -> Vec<(f64, f64)> {
+        self.bursts
+            .iter()
+            .filter_map(|m| {
+                m.server
+                    .as_ref()
+                    .map(|s| (self.x(m), s.latency.p99_us as f64 / 1000.0))
+            })
+            .collect()
+    }
+
+    /// p99 of client-observed latency beyond the engine's processing time
+    pub fn overhead_p99_series(&self) -> Vec<(f64, f64)> {
+        self.bursts
+            .iter()
+            .filter_map(|m| {
+                m.server
+                    .as_ref()
+                    .map(|s| (self.x(m), s.overhead.p99_us as f64 / 1000.0))
+            })
             .collect()
     }
 
     pub fn recall_series(&self) -> Vec<(f64, f64)> {
         self.bursts
             .iter()
-            .enumerate()
-            .filter_map(|(i, m)| m.recall_at_k.map(|r| (i as f64, r * 100.0)))
+            .filter_map(|m| m.recall_at_k.map(|r| (self.x(m), r * 100.0)))
+            .collect()
+    }
+
+    /// Recall of the fixed probe sample, for bursts a probe followed
+    pub fn probe_recall_series(&self) -> Vec<(f64, f64)> {
+        self.bursts
+            .iter()
+            .filter_map(|m| m.probe_recall.map(|r| (self.x(m), r * 100.0)))
+            .collect()
+    }
+
+    pub fn ndcg_series(&self) -> Vec<(f64, f64)> {
+        self.bursts
+            .iter()
+            .filter_map(|m| m.ndcg_at_k.map(|n| (self.x(m), n * 100.0)))
+            .collect()
+    }
+
+    pub fn rival_qps_series(&self) -> Vec<(f64, f64)> {
+        self.rival_bursts.iter().map(|m| (self.x(m), m.qps)).collect()
+    }
+
+    pub fn rival_p99_series(&self) -> Vec<(f64, f64)> {
+        self.rival_bursts
+            .iter()
+            .map(|m| (self.x(m), m.latency.p99_us as f64 / 1000.0))
+            .collect()
+    }
+
+    /// Share of each burst's queries that failed, in percent
+    pub fn error_rate_series(&self) -> Vec<(f64, f64)> {
+        self.rate_series(|m| m.failure_count)
+    }
+
+    /// Share of each burst's queries that timed out, in percent
+    pub fn timeout_rate_series(&self) -> Vec<(f64, f64)> {
+        self.rate_series(|m| m.timeout_count)
+    }
+
+    fn rate_series(&self, count: impl Fn(&BurstMetrics) -> usize) -> Vec<(f64, f64)> {
+        self.bursts
+            .iter()
+            .map(|m| {
+                let total = m.success_count + m.failure_count;
+                let rate = if total == 0 {
+                    0.0
+                } else {
+                    count(m) as f64 / total as f64 * 100.0
+                };
+                (self.x(m), rate)
+            })
+            .collect()
+    }
+
+    pub fn mrr_series(&self) -> Vec<(f64, f64)> {
+        self.bursts
+            .iter()
+            .filter_map(|m| m.mrr.map(|r| (self.x(m), r * 100.0)))
             .collect()
     }
 }
 
 impl App {
     pub fn new(config: Config) -> Result<Self> {
+        let statsd = config
+            .statsd
+            .as_ref()
+            .map(StatsdSink::connect)
+            .transpose()?;
+        #[cfg(feature = "notifications")]
+        let notifier = config.notify.as_ref().map(Notifier::new).transpose()?;
+        #[cfg(not(feature = "notifications"))]
+        if config.notify.is_some() {
+            return Err(anyhow!(
+                "notify requires the 'notifications' feature. Rebuild with --features notifications"
+            ));
+        }
         Ok(Self {
+            settings: LoadSettings::of(&config.benchmark),
+            theme: Theme::new(&config.tui),
+            show_logs: false,
             config,
             runner: None,
             embedder: None,
-            queries: Vec::new(),
+            queries: Arc::default(),
             state: AppState::Idle,
             view: View::default(),
             history: MetricsHistory::default(),
             status_message: None,
-            last_sample: None,
+            samples: Vec::new(),
+            sample_index: 0,
             results_scroll: 0,
+            picker: None,
+            compare_modes: false,
             query_input: String::new(),
             editing: false,
+            failures: FailureLog::new(RECENT_FAILURES),
+            errors_scroll: 0,
+            config_scroll: 0,
+            stop_reason: None,
+            last_error: None,
+            reconnect_at: None,
+            retuned: false,
+            started_at: Utc::now(),
+            snapshot_requested: false,
+            notice: None,
+            summary: None,
+            slo_breach: None,
+            bell_pending: false,
+            exporter: None,
+            dashboard: None,
+            statsd,
+            #[cfg(feature = "notifications")]
+            notifier,
+            burst_log: None,
+            rival: None,
+            recorder: None,
+            replayed_queries: None,
+            runner_events: None,
+            burst_progress: None,
+            provider_healthy: None,
+            health_change: None,
         })
     }
 
+    /// An app that plays back a recorded session instead of connecting
+    pub fn replaying(mut config: Config, queries: usize) -> Result<Self> {
+        // Nothing is measured, so nothing is reported
+        config.statsd = None;
+        config.notify = None;
+        let mut app = Self::new(config)?;
+        app.replayed_queries = Some(queries);
+        Ok(app)
+    }
+
+    /// Record the session to `path` from now on, for `qstorm replay`
+    pub fn record_to(&mut self, path: &Path) -> Result<()> {
+        self.recorder = Some(SessionRecorder::create(
+            path,
+            &self.config,
+            self.query_count(),
+        )?);
+        Ok(())
+    }
+
+    /// Show the next event of a recorded session as if it just happened
+    pub fn replay(&mut self, event: SessionEvent) -> Result<()> {
+        match event {
+            SessionEvent::Start { .. } => Ok(()),
+            SessionEvent::Burst(metrics) => self.record_burst(*metrics),
+            SessionEvent::Tuned { note, settings } => {
+                self.settings = settings;
+                self.history.annotate(note);
+                Ok(())
+            }
+            SessionEvent::Warmup => {
+                self.history.annotate("warmup".into());
+                Ok(())
+            }
+        }
+    }
+
+    /// Serve each burst's metrics from `exporter` as the run goes
+    pub fn set_exporter(&mut self, exporter: PrometheusExporter) {
+        self.exporter = Some(exporter);
+    }
+
+    /// Chart each burst on `dashboard` as the run goes
+    pub fn set_dashboard(&mut self, dashboard: Dashboard) {
+        self.dashboard = Some(dashboard);
+    }
+
+    /// Run every burst against `provider` too, taking turns, and chart
+    /// its QPS and p99 next to the configured provider's
+    pub fn set_rival(&mut self, provider: ProviderConfig) {
+        self.rival = Some(Rival {
+            provider,
+            runner: None,
+        });
+    }
+
+    /// Name of the `--versus` provider, if there is one
+    pub fn rival_name(&self) -> Option<&str> {
+        self.rival.as_ref().map(|r| r.provider.name.as_str())
+    }
+
+    /// Append each finished burst to `log`
+    pub fn set_burst_log(&mut self, log: BurstLog) {
+        self.burst_log = Some(log);
+    }
+
+    /// Keep a finished burst for charting and export
+    pub fn record_burst(&mut self, metrics: BurstMetrics) -> Result<()> {
+        if let (Some(exporter), Some(runner)) = (&self.exporter, &self.runner) {
+            exporter.record(&metrics, runner.metrics().last_burst_samples());
+        }
+        if let Some(dashboard) = &self.dashboard
+            && let Err(e) = dashboard.record(&metrics)
+        {
+            tracing::warn!("Failed to send the burst to the dashboard: {e}");
+        }
+        if let Some(statsd) = &self.statsd {
+            statsd.record(&metrics);
+        }
+        let logged = match &mut self.burst_log {
+            Some(log) => log.write(&metrics),
+            None => Ok(()),
+        };
+        let recorded = match &mut self.recorder {
+            Some(recorder) => recorder.record(&SessionEvent::Burst(Box::new(metrics.clone()))),
+            None => Ok(()),
+        };
+        let breaches = self
+            .config
+            .slo
+            .as_ref()
+            .map(|slo| slo.breaches(&metrics))
+            .unwrap_or_default();
+        if !breaches.is_empty() && self.slo_breach.is_none() {
+            tokio::spawn(self.notify(Notification::SloBreach {
+                provider: self.provider_name(),
+                breaches: &breaches,
+                burst: &metrics,
+            }));
+        }
+        let since = metrics.timestamp;
+        self.history.push(metrics);
+        self.track_slo(breaches, since);
+        logged?;
+        recorded
+    }
+
+    /// Start, extend, or clear the SLO breach after a burst broke
+    /// `breaches` (none if it met every objective)
+    fn track_slo(&mut self, breaches: Vec<String>, at: DateTime<Utc>) {
+        if breaches.is_empty() {
+            if self.slo_breach.take().is_some() {
+                tracing::info!("SLOs met again");
+            }
+            return;
+        }
+        match &mut self.slo_breach {
+            Some(breach) => {
+                breach.bursts += 1;
+                breach.reasons = breaches;
+            }
+            None => {
+                let note = breaches.join(", ");
+                tracing::warn!(breach = %note, "SLO breached");
+                self.history.mark_breach(note);
+                self.bell_pending = self.config.slo.as_ref().is_some_and(|slo| slo.bell);
+                self.slo_breach = Some(SloBreach {
+                    since: at,
+                    bursts: 1,
+                    reasons: breaches,
+                });
+            }
+        }
+    }
+
+    /// Whether the terminal bell should ring for a breach that just started
+    pub fn take_bell(&mut self) -> bool {
+        std::mem::take(&mut self.bell_pending)
+    }
+
     pub fn provider_name(&self) -> &str {
         &self.config.provider.name
     }
 
+    /// Playing back a recording rather than running a benchmark
+    pub fn is_replay(&self) -> bool {
+        self.replayed_queries.is_some()
+    }
+
     pub fn query_count(&self) -> usize {
-        self.queries.len()
+        self.replayed_queries.unwrap_or(self.queries.len())
+    }
+
+    /// Shared handle to the embedded queries
+    pub fn queries(&self) -> Arc<[EmbeddedQuery]> {
+        Arc::clone(&self.queries)
+    }
+
+    /// Catch up on the runner's events since the last frame
+    pub fn poll_runner_events(&mut self) {
+        let Some(events) = self.runner_events.as_mut() else {
+            return;
+        };
+        while let Some(Some(event)) = events.next().now_or_never() {
+            match event {
+                RunnerEvent::BurstStarted { .. } => self.burst_progress = Some(0),
+                RunnerEvent::RequestCompleted { .. } => {
+                    if let Some(done) = &mut self.burst_progress {
+                        *done += 1;
+                    }
+                }
+                // Nearly everything a burst sends is a finished request
+                RunnerEvent::Lagged { missed } => {
+                    if let Some(done) = &mut self.burst_progress {
+                        *done += missed as usize;
+                    }
+                }
+                RunnerEvent::BurstFinished(_) | RunnerEvent::Error { .. } => {
+                    self.burst_progress = None;
+                }
+                RunnerEvent::HealthChanged { healthy, reason } => {
+                    // Coming up on the first connect isn't news
+                    let was_known = self.provider_healthy.replace(healthy).is_some();
+                    if was_known || !healthy {
+                        self.health_change = Some((healthy, reason));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The provider going down or coming back since the last call, with
+    /// why it went down
+    pub fn take_health_change(&mut self) -> Option<(bool, Option<String>)> {
+        self.health_change.take()
     }
 
     pub fn take_runner(&mut self) -> Option<BenchmarkRunner> {
@@ -137,32 +754,76 @@ impl App {
     }
 
     pub fn put_runner(&mut self, runner: BenchmarkRunner) {
+        // A scenario phase may have moved the settings on
+        if !self.retuned {
+            self.settings = LoadSettings::of(runner.config());
+        }
         self.runner = Some(runner);
     }
 
+    /// The `--versus` provider's runner, to run a burst next to the main one
+    pub fn take_rival_runner(&mut self) -> Option<BenchmarkRunner> {
+        self.rival.as_mut().and_then(|r| r.runner.take())
+    }
+
+    /// Hand back the `--versus` runner with the burst it ran, keeping the
+    /// burst for the charts
+    pub fn record_rival_burst(
+        &mut self,
+        runner: BenchmarkRunner,
+        result: std::result::Result<BurstMetrics, qstorm_core::Error>,
+    ) -> Result<()> {
+        let Some(rival) = &mut self.rival else {
+            return Ok(());
+        };
+        rival.runner = Some(runner);
+        let metrics = result.map_err(|e| anyhow!("{}: {e}", rival.provider.name))?;
+        self.history.push_rival(metrics);
+        Ok(())
+    }
+
     pub fn has_runner(&self) -> bool {
         self.runner.is_some()
     }
 
     /// Load queries from file and embed them
-    pub async fn load_and_embed_queries(&mut self, query_file_path: &str) -> Result<()> {
+    pub async fn load_and_embed_queries(&mut self, sources: &[QuerySource]) -> Result<()> {
         self.status_message = Some("Loading queries...".into());
 
-        let query_file = QueryFile::from_file(query_file_path)?;
+        let mut query_file = QueryFile::from_sources(sources, self.config.seed)?;
         if query_file.queries.is_empty() {
             return Err(anyhow!("Query file contains no queries"));
         }
 
+        if let Some(perturbation) = &self.config.perturbation {
+            let mutated = query_file.perturb(perturbation, self.config.seed)?;
+            tracing::info!(
+                mutated,
+                total = query_file.queries.len(),
+                "Perturbed queries"
+            );
+        }
+
         self.status_message = Some(format!("Embedding {} queries...", query_file.queries.len()));
 
-        let embedding_config = self.config.embedding.clone().unwrap_or_default();
-        let embedder = Embedder::from_config(&embedding_config)
-            .map_err(|e| anyhow!("{e}"))?;
-        self.queries = embedder
-            .embed_queries(&query_file.queries)
-            .await
-            .map_err(|e| anyhow!("{e}"))?;
-        self.embedder = Some(embedder);
+        let embedder = QueryEmbedder::from_config(&self.config);
+        let queries = match query_file.into_precomputed() {
+            Ok(queries) => {
+                // Vectors ship with the query file; an embedder is only needed for ad-hoc queries
+                self.embedder = embedder.ok();
+                queries
+            }
+            Err(query_file) => {
+                let embedder = embedder.map_err(|e| anyhow!("{e}"))?;
+                let queries = embedder
+                    .embed_query_file(query_file)
+                    .await
+                    .map_err(|e| anyhow!("{e}"))?;
+                self.embedder = Some(embedder);
+                queries
+            }
+        };
+        self.queries = queries.into();
 
         self.status_message = Some(format!("Loaded {} queries", self.queries.len()));
         Ok(())
@@ -172,19 +833,135 @@ impl App {
         self.state = AppState::Connecting;
         self.status_message = Some("Connecting to provider...".into());
 
-        let provider = create_provider(&self.config.provider)?;
-        let runner = BenchmarkRunner::new(provider, self.config.benchmark.clone())
-            .with_queries(self.queries.clone());
+        let (provider, factory) = run_provider(&self.config.provider, self.config.chaos.as_ref())?;
+        let mut runner = BenchmarkRunner::new(provider, self.config.benchmark.clone())
+            .with_queries(Arc::clone(&self.queries))
+            .with_provider_factory(factory)
+            .with_failure_log(self.failures.clone());
+        if let Some(seed) = self.config.seed {
+            runner = runner.with_seed(seed);
+        }
+        if let Some(samples) = &self.config.samples {
+            runner = runner.with_sample_log(SampleLog::create(&samples.path)?);
+        }
+        if let Some(slow) = &self.config.slow_queries {
+            runner = runner.with_slow_query_log(SlowQueryLog::create(slow)?);
+        }
+        if let Some(rerank) = &self.config.rerank {
+            self.status_message = Some("Loading reranker...".into());
+            runner = runner.with_rerank(RerankStage::from_config(rerank)?);
+        }
+        if let Some(scenario) = &self.config.scenario {
+            scenario.validate()?;
+            runner = runner.with_scenario(scenario.clone());
+        }
 
-        let mut runner = runner;
+        self.runner_events = Some(runner.events().boxed());
         runner.connect().await?;
+
+        if let Some(ground_truth) = &self.config.ground_truth {
+            self.status_message = Some("Computing ground truth...".into());
+            // Release our handle so attaching ground truth doesn't copy the query set
+            self.queries = Arc::default();
+            let result = runner.compute_ground_truth(ground_truth).await;
+            self.queries = runner.queries();
+            result?;
+        }
+
         self.runner = Some(runner);
+        self.connect_rival().await?;
         self.state = AppState::Idle;
         self.status_message = Some("Connected".into());
         Ok(())
     }
 
+    /// Connect a fresh runner for the `--versus` provider, sharing the
+    /// queries (and any ground truth) of the main one
+    async fn connect_rival(&mut self) -> Result<()> {
+        let Some(rival) = &mut self.rival else {
+            return Ok(());
+        };
+        self.status_message = Some(format!("Connecting to {}...", rival.provider.name));
+
+        let (provider, factory) = run_provider(&rival.provider, self.config.chaos.as_ref())?;
+        let mut runner = BenchmarkRunner::new(provider, self.config.benchmark.clone())
+            .with_queries(Arc::clone(&self.queries))
+            .with_provider_factory(factory);
+        if let Some(seed) = self.config.seed {
+            runner = runner.with_seed(seed);
+        }
+        if let Some(scenario) = &self.config.scenario {
+            runner = runner.with_scenario(scenario.clone());
+        }
+        if let Some(main) = &self.runner {
+            // Start from any settings changed from the keyboard
+            *runner.config_mut() = main.config().clone();
+        }
+
+        runner.connect().await?;
+        rival.runner = Some(runner);
+        Ok(())
+    }
+
+    /// Enter the error state, keeping `error` to show
+    pub fn fail(&mut self, error: impl std::fmt::Display) {
+        let error = error.to_string();
+        // Only once per error, not again while it's still showing
+        if self.state != AppState::Error {
+            let report = self.report(self.started_at);
+            tokio::spawn(self.notify(Notification::Failed {
+                provider: self.provider_name(),
+                error: &error,
+                report: report.as_ref(),
+            }));
+        }
+        self.state = AppState::Error;
+        self.last_error = Some(error);
+    }
+
+    /// Connect the provider again after an error and carry on with bursts.
+    /// The chart history is kept, and so are the runner's metrics unless the
+    /// runner itself was lost.
+    pub async fn reconnect(&mut self) -> Result<()> {
+        self.state = AppState::Connecting;
+        let reconnected = match &mut self.runner {
+            Some(runner) => match runner.reconnect().await {
+                Ok(()) => self.reconnect_rival().await,
+                Err(e) => Err(anyhow!("{e}")),
+            },
+            None => {
+                // A new runner starts from the configured settings
+                self.retuned = true;
+                self.connect().await
+            }
+        };
+        match reconnected {
+            Ok(()) => {
+                self.state = AppState::Idle;
+                self.last_error = None;
+                self.status_message = Some("Reconnected".into());
+                Ok(())
+            }
+            Err(e) => {
+                self.fail(&e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Reconnect the `--versus` provider, or connect it again if its
+    /// runner was lost
+    async fn reconnect_rival(&mut self) -> Result<()> {
+        match self.rival.as_mut().and_then(|r| r.runner.as_mut()) {
+            Some(runner) => runner.reconnect().await.map_err(|e| anyhow!("{e}")),
+            None => self.connect_rival().await,
+        }
+    }
+
     pub async fn disconnect(&mut self) -> Result<()> {
+        if let Some(runner) = self.rival.as_mut().and_then(|r| r.runner.as_mut()) {
+            runner.disconnect().await?;
+        }
         if let Some(runner) = &mut self.runner {
             runner.disconnect().await?;
         }
@@ -201,12 +978,49 @@ impl App {
         if let Some(runner) = &mut self.runner {
             runner.warmup().await?;
         }
+        if let Some(runner) = self.rival.as_mut().and_then(|r| r.runner.as_mut()) {
+            runner.warmup().await?;
+        }
 
         self.state = AppState::Idle;
         self.status_message = Some("Warmup complete".into());
         Ok(())
     }
 
+    /// Run warmup again while paused, e.g. after changing settings on the
+    /// server, and mark the next burst in the charts
+    pub async fn rewarm(&mut self) -> Result<()> {
+        self.warmup().await?;
+        self.state = AppState::Paused;
+        self.notice = Some((Instant::now(), "Warmup complete".into()));
+        if let Some(recorder) = &mut self.recorder
+            && let Err(e) = recorder.record(&SessionEvent::Warmup)
+        {
+            tracing::error!("Failed to record warmup: {e}");
+        }
+        self.history.annotate("warmup".into());
+        Ok(())
+    }
+
+    /// Carry on from an interrupted soak run's checkpoint. Call after
+    /// connecting, before the first burst.
+    pub fn resume(&mut self, resumed: Resumed) -> Result<()> {
+        let runner = self
+            .runner
+            .as_mut()
+            .ok_or_else(|| anyhow!("Not connected"))?;
+
+        let skip = resumed
+            .bursts
+            .len()
+            .saturating_sub(self.history.max_history);
+        for burst in resumed.bursts.iter().skip(skip) {
+            self.history.push(burst.clone());
+        }
+        runner.resume(resumed);
+        Ok(())
+    }
+
     pub async fn run_burst(&mut self) -> Result<BurstMetrics> {
         self.state = AppState::Running;
 
@@ -216,39 +1030,421 @@ impl App {
             .ok_or_else(|| anyhow!("Not connected"))?;
 
         let metrics = runner.run_burst().await?;
-        self.history.push(metrics.clone());
+        self.record_burst(metrics.clone())?;
         self.state = AppState::Idle;
         Ok(metrics)
     }
 
+    pub async fn run_sweep_step(
+        &mut self,
+        concurrency: usize,
+        bursts: usize,
+    ) -> Result<SweepPoint> {
+        self.state = AppState::Running;
+
+        let runner = self
+            .runner
+            .as_mut()
+            .ok_or_else(|| anyhow!("Not connected"))?;
+
+        let point = runner.run_sweep_step(concurrency, bursts).await?;
+        self.state = AppState::Idle;
+        Ok(point)
+    }
+
+    /// Run bursts at a constant offered rate and return their metrics
+    pub async fn run_rate_step(&mut self, qps: f64, bursts: usize) -> Result<Metrics> {
+        self.state = AppState::Running;
+
+        let runner = self
+            .runner
+            .as_mut()
+            .ok_or_else(|| anyhow!("Not connected"))?;
+
+        let metrics = runner.run_rate_step(qps, bursts).await?;
+        self.state = AppState::Idle;
+        Ok(metrics)
+    }
+
+    /// Settings the next burst runs with
+    pub fn benchmark(&self) -> &BenchmarkConfig {
+        self.runner
+            .as_ref()
+            .map_or(&self.config.benchmark, |runner| runner.config())
+    }
+
+    /// Change the load settings from the next burst on, for this run and
+    /// any started after it
+    pub fn change_load(&mut self, change: impl Fn(&mut BenchmarkConfig)) {
+        change(&mut self.config.benchmark);
+        if let Some(runner) = &mut self.runner {
+            change(runner.config_mut());
+        }
+    }
+
+    /// Cumulative metrics from the connected runner
+    pub fn metrics(&self) -> Option<&Metrics> {
+        self.runner.as_ref().map(|runner| runner.metrics())
+    }
+
+    /// True once, after the last burst of the configured scenario
+    pub fn take_scenario_complete(&mut self) -> bool {
+        self.runner
+            .as_mut()
+            .is_some_and(|runner| runner.take_scenario_complete())
+    }
+
+    /// True once the run has sent `benchmark.max_total_queries` requests
+    pub fn budget_exhausted(&self) -> bool {
+        self.runner
+            .as_ref()
+            .is_some_and(|runner| runner.budget_exhausted())
+    }
+
+    /// Why the run should end, if a stop condition was met by the last burst
+    pub fn take_stop_reason(&mut self) -> Option<String> {
+        self.runner.as_mut()?.take_stop_reason()
+    }
+
     pub fn toggle_pause(&mut self) {
         self.state = match self.state {
             AppState::Running | AppState::Idle => AppState::Paused,
             AppState::Paused => AppState::Idle,
             _ => self.state,
         };
+        self.stop_reason = None;
+    }
+
+    /// Double or halve a load setting. It takes effect when the next burst
+    /// starts; see [`App::apply_tuning`].
+    pub fn tune(&mut self, setting: Tunable, up: bool) {
+        let value = match setting {
+            Tunable::Concurrency => &mut self.settings.concurrency,
+            Tunable::BurstSize => &mut self.settings.burst_size,
+            Tunable::TopK => &mut self.settings.top_k,
+        };
+        *value = if up {
+            value.saturating_mul(2)
+        } else {
+            (*value / 2).max(1)
+        };
+        self.retuned = true;
+    }
+
+    /// Hand settings changed with [`App::tune`] to `runner` and note the
+    /// change in the history. Call as the next burst starts.
+    pub fn apply_tuning(&mut self, runner: &mut BenchmarkRunner) {
+        if !std::mem::take(&mut self.retuned) {
+            return;
+        }
+
+        let wanted = self.settings;
+        let current = runner.config_mut();
+        let mut changes = Vec::new();
+        for (name, from, to) in [
+            ("concurrency", &mut current.concurrency, wanted.concurrency),
+            ("burst", &mut current.burst_size, wanted.burst_size),
+            ("k", &mut current.top_k, wanted.top_k),
+        ] {
+            if *from != to {
+                changes.push(format!("{name} {from}→{to}"));
+                *from = to;
+            }
+        }
+        if let Some(rival) = self.rival.as_mut().and_then(|r| r.runner.as_mut()) {
+            let config = rival.config_mut();
+            config.concurrency = wanted.concurrency;
+            config.burst_size = wanted.burst_size;
+            config.top_k = wanted.top_k;
+        }
+        if !changes.is_empty() {
+            let note = changes.join(", ");
+            tracing::info!(change = %note, "Load settings changed");
+            if let Some(recorder) = &mut self.recorder {
+                let event = SessionEvent::Tuned {
+                    note: note.clone(),
+                    settings: wanted,
+                };
+                if let Err(e) = recorder.record(&event) {
+                    tracing::error!("Failed to record settings change: {e}");
+                }
+            }
+            self.history.annotate(note);
+        }
+    }
+
+    /// Write a snapshot as soon as no burst is in flight
+    pub fn request_snapshot(&mut self) {
+        self.snapshot_requested = true;
+    }
+
+    /// Write a requested snapshot, if the runner is back between bursts
+    pub fn write_pending_snapshot(&mut self) {
+        if !self.snapshot_requested || !self.has_runner() {
+            return;
+        }
+        self.snapshot_requested = false;
+        let notice = match self.export_snapshot() {
+            Ok(path) => format!("Snapshot saved to {}", path.display()),
+            Err(e) => {
+                tracing::error!("Failed to write snapshot: {e}");
+                format!("Snapshot failed: {e}")
+            }
+        };
+        self.notice = Some((Instant::now(), notice));
+    }
+
+    /// Write the charted bursts, with a summary of the run so far, to a
+    /// timestamped JSON file in the current directory
+    pub fn export_snapshot(&self) -> Result<PathBuf> {
+        let metrics = self.metrics().ok_or_else(|| anyhow!("Not connected"))?;
+        let taken_at = Utc::now();
+        let snapshot = Snapshot {
+            taken_at,
+            settings: self.settings,
+            summary: RunReport::new(&self.config, metrics, self.started_at),
+            annotations: &self.history.annotations,
+            slo_breaches: &self.history.breaches,
+            bursts: &self.history.bursts,
+        };
+        let path = PathBuf::from(format!(
+            "qstorm-snapshot-{}.json",
+            taken_at.format("%Y%m%dT%H%M%S%.3fZ")
+        ));
+        std::fs::write(&path, serde_json::to_string_pretty(&snapshot)?)?;
+        Ok(path)
+    }
+
+    /// Sum up the run for the summary screen. Call before disconnecting,
+    /// while the runner's metrics are still there. Returns false if no
+    /// burst finished, leaving nothing to sum up.
+    pub fn summarize(&mut self) -> bool {
+        let Some(mut report) = self.report(self.started_at) else {
+            return false;
+        };
+        report.stop_reason = self.stop_reason.clone();
+        self.summary = Some(report);
+        true
+    }
+
+    /// The run so far as a report, if any burst finished
+    pub fn report(&self, started_at: DateTime<Utc>) -> Option<RunReport> {
+        let report = RunReport::new(&self.config, self.metrics()?, started_at);
+        (report.bursts > 0).then_some(report)
+    }
+
+    /// Post `notification` to the `notify` webhook, if there is one. The
+    /// returned future borrows nothing: await it to make sure it's delivered
+    /// before exiting, or spawn it. A failed delivery is logged.
+    #[cfg(feature = "notifications")]
+    pub fn notify(
+        &self,
+        notification: Notification<'_>,
+    ) -> impl Future<Output = ()> + Send + 'static {
+        let sent = self
+            .notifier
+            .as_ref()
+            .map(|notifier| notifier.send(notification));
+        async move {
+            if let Some(sent) = sent
+                && let Err(e) = sent.await
+            {
+                tracing::warn!("Failed to post a notification: {e}");
+            }
+        }
+    }
+
+    #[cfg(not(feature = "notifications"))]
+    pub fn notify(
+        &self,
+        _notification: Notification<'_>,
+    ) -> impl Future<Output = ()> + Send + 'static {
+        // `new` turns down a `notify` config without the feature
+        std::future::ready(())
+    }
+
+    /// Post, in the background, that a stop condition ended the run with
+    /// `reason`
+    pub fn notify_stopped(&self, reason: &str) {
+        if let Some(mut report) = self.report(self.started_at) {
+            report.stop_reason = Some(reason.to_string());
+            tokio::spawn(self.notify(Notification::Stopped(&report)));
+        }
+    }
+
+    /// Save the summary as a report file in the current directory, noting
+    /// where it went
+    pub fn save_summary(&mut self) {
+        let Some(report) = &self.summary else {
+            return;
+        };
+        let path = PathBuf::from(format!(
+            "qstorm-summary-{}.json",
+            report.finished_at.format("%Y%m%dT%H%M%S%.3fZ")
+        ));
+        let notice = match report.save(&path) {
+            Ok(()) => format!("Summary saved to {}", path.display()),
+            Err(e) => {
+                tracing::error!("Failed to write summary: {e}");
+                format!("Summary failed: {e}")
+            }
+        };
+        self.notice = Some((Instant::now(), notice));
+    }
+
+    /// The header notice, while it's fresh
+    pub fn notice(&self) -> Option<&str> {
+        self.notice
+            .as_ref()
+            .filter(|(at, _)| at.elapsed() < NOTICE_DURATION)
+            .map(|(_, notice)| notice.as_str())
     }
 
     pub fn toggle_view(&mut self) {
         self.view = match self.view {
             View::Dashboard => View::Results,
-            View::Results => View::Dashboard,
+            View::Results => View::Errors,
+            View::Errors | View::Config => View::Dashboard,
         };
     }
 
-    /// Run a single sample query and store the results for display
+    /// The sample result on screen, if any query has been run
+    pub fn sample(&self) -> Option<&SampleResult> {
+        self.samples.get(self.sample_index)
+    }
+
+    /// Run the query on screen again, or the first loaded query if none is
     pub async fn run_sample(&mut self) -> Result<()> {
+        let source = self
+            .sample()
+            .map(|s| s.source.clone())
+            .unwrap_or(SampleQuery::Loaded(0));
+        self.run_query(source).await
+    }
+
+    /// Run a query with payloads included and show its results as the
+    /// newest entry in the history
+    async fn run_query(&mut self, source: SampleQuery) -> Result<()> {
         let runner = self
             .runner
             .as_ref()
             .ok_or_else(|| anyhow!("Not connected"))?;
 
-        let (query, results) = runner.run_sample_query().await.map_err(|e| anyhow!("{e}"))?;
-        self.last_sample = Some(SampleResult { query, results });
+        let (query, results) = match &source {
+            SampleQuery::Loaded(index) => runner.run_sample_query(*index).await,
+            SampleQuery::Custom(query) => runner.run_custom_query(query).await,
+        }
+        .map_err(|e| anyhow!("{e}"))?;
+
+        let mode = runner.search_mode();
+        let mut other = None;
+        if self.compare_modes {
+            let loaded = runner.queries();
+            let embedded = match &source {
+                SampleQuery::Loaded(index) => &loaded[*index],
+                SampleQuery::Custom(query) => query,
+            };
+            let other_mode = match mode {
+                SearchMode::Vector => SearchMode::Hybrid,
+                SearchMode::Hybrid => SearchMode::Vector,
+            };
+            other = Some(OtherMode {
+                results: runner
+                    .run_query_in_mode(embedded, other_mode)
+                    .await
+                    .map_err(|e| e.to_string()),
+            });
+        }
+
+        self.samples.push(SampleResult {
+            query,
+            source,
+            mode,
+            results,
+            other,
+        });
+        if self.samples.len() > SAMPLE_HISTORY {
+            self.samples.remove(0);
+        }
+        self.sample_index = self.samples.len() - 1;
         self.results_scroll = 0;
         Ok(())
     }
 
+    /// Step through earlier (negative) or later (positive) sample results
+    pub fn browse_samples(&mut self, delta: isize) {
+        let last = self.samples.len().saturating_sub(1) as isize;
+        let index = (self.sample_index as isize + delta).clamp(0, last) as usize;
+        if index != self.sample_index {
+            self.sample_index = index;
+            self.results_scroll = 0;
+        }
+    }
+
+    /// Turn the side-by-side mode comparison on or off. Returns true if the
+    /// shown query needs running again to fill in the other mode.
+    pub fn toggle_compare_modes(&mut self) -> bool {
+        self.compare_modes = !self.compare_modes;
+        self.compare_modes && self.sample().is_none_or(|s| s.other.is_none())
+    }
+
+    pub fn open_picker(&mut self) {
+        self.picker = Some(QueryPicker::default());
+    }
+
+    pub fn close_picker(&mut self) {
+        self.picker = None;
+    }
+
+    /// Loaded queries whose text contains the picker's filter, ignoring
+    /// case, with their positions in the set
+    pub fn picker_matches(&self) -> Vec<(usize, &str)> {
+        let filter = self
+            .picker
+            .as_ref()
+            .map(|p| p.filter.to_lowercase())
+            .unwrap_or_default();
+        self.queries
+            .iter()
+            .enumerate()
+            .filter(|(_, q)| filter.is_empty() || q.text.to_lowercase().contains(&filter))
+            .map(|(i, q)| (i, &*q.text))
+            .collect()
+    }
+
+    /// Narrow the picker by one more typed character, or widen it again
+    /// with `None` (backspace)
+    pub fn edit_picker_filter(&mut self, c: Option<char>) {
+        if let Some(picker) = &mut self.picker {
+            match c {
+                Some(c) => picker.filter.push(c),
+                None => {
+                    picker.filter.pop();
+                }
+            }
+            picker.selected = 0;
+        }
+    }
+
+    pub fn move_picker(&mut self, delta: isize) {
+        let last = self.picker_matches().len().saturating_sub(1) as isize;
+        if let Some(picker) = &mut self.picker {
+            picker.selected = (picker.selected as isize + delta).clamp(0, last) as usize;
+        }
+    }
+
+    /// Run the highlighted query and close the picker
+    pub async fn submit_picker(&mut self) -> Result<()> {
+        let Some(selected) = self.picker.as_ref().map(|p| p.selected) else {
+            return Ok(());
+        };
+        let Some(&(index, _)) = self.picker_matches().get(selected) else {
+            return Ok(());
+        };
+        self.picker = None;
+        self.run_query(SampleQuery::Loaded(index)).await
+    }
+
     pub fn start_editing(&mut self) {
         self.editing = true;
         self.query_input.clear();
@@ -279,46 +1475,64 @@ impl App {
             .pop()
             .ok_or_else(|| anyhow!("Embedding returned no results"))?;
 
-        let runner = self
-            .runner
-            .as_ref()
-            .ok_or_else(|| anyhow!("Not connected"))?;
-
-        let (query, results) = runner.run_custom_query(&eq).await.map_err(|e| anyhow!("{e}"))?;
-        self.last_sample = Some(SampleResult { query, results });
-        self.results_scroll = 0;
+        self.run_query(SampleQuery::Custom(eq)).await?;
         self.editing = false;
         Ok(())
     }
 
     pub fn scroll_results(&mut self, delta: isize) {
         let max = self
-            .last_sample
-            .as_ref()
+            .sample()
             .map(|s| s.results.results.len().saturating_sub(1))
             .unwrap_or(0);
 
         let current = self.results_scroll as isize;
         self.results_scroll = (current + delta).clamp(0, max as isize) as usize;
     }
-}
 
-fn create_provider(config: &ProviderConfig) -> Result<Box<dyn qstorm_core::SearchProvider>> {
-    let name = config.name.clone();
-    match &config.provider {
-        #[cfg(feature = "elasticsearch")]
-        ProviderKind::Elasticsearch(c) => Ok(Box::new(
-            qstorm_core::providers::ElasticsearchProvider::new(name, c.clone()),
-        )),
+    /// Move the highlighted row of the errors view, newest failure first
+    pub fn scroll_errors(&mut self, delta: isize) {
+        let max = self.failures.recent().len().saturating_sub(1);
+        let current = self.errors_scroll as isize;
+        self.errors_scroll = (current + delta).clamp(0, max as isize) as usize;
+    }
 
-        #[cfg(feature = "qdrant")]
-        ProviderKind::Qdrant(c) => Ok(Box::new(
-            qstorm_core::providers::QdrantProvider::new(name, c.clone()),
-        )),
+    /// Scroll the config view by `delta` lines
+    pub fn scroll_config(&mut self, delta: isize) {
+        let max = self.config_yaml().lines().count().saturating_sub(1);
+        let current = self.config_scroll as isize;
+        self.config_scroll = (current + delta).clamp(0, max as isize) as usize;
+    }
 
-        #[cfg(feature = "pgvector")]
-        ProviderKind::Pgvector(c) => Ok(Box::new(
-            qstorm_core::providers::PgvectorProvider::new(name, c.clone()),
-        )),
+    /// The config as YAML, with credentials redacted so it's safe to show
+    pub fn config_yaml(&self) -> String {
+        serde_yaml::to_string(&self.config.redacted())
+            .unwrap_or_else(|e| format!("Failed to show config: {e}"))
     }
+}
+
+/// Builds fresh providers from `config`, e.g. for each client thread
+pub fn provider_factory(config: &ProviderConfig) -> ProviderFactory {
+    ProviderRegistry::factory(config)
+}
+
+pub fn create_provider(config: &ProviderConfig) -> Result<Box<dyn qstorm_core::SearchProvider>> {
+    Ok(ProviderRegistry::create(config)?)
+}
+
+/// The provider and client-thread factory for a benchmark run, both wrapped
+/// to inject `chaos` when it's set
+pub fn run_provider(
+    config: &ProviderConfig,
+    chaos: Option<&ChaosConfig>,
+) -> Result<(Box<dyn qstorm_core::SearchProvider>, ProviderFactory)> {
+    let provider = create_provider(config)?;
+    let factory = provider_factory(config);
+    Ok(match chaos {
+        Some(chaos) => (
+            Box::new(ChaosProvider::new(provider, chaos.clone())),
+            ChaosProvider::factory(factory, chaos.clone()),
+        ),
+        None => (provider, factory),
+    })
 }
\ No newline at end of file
@@ -0,0 +1,128 @@
+//! Log output, and the lines kept for the TUI's logs pane.
+//!
+//! Log lines written to the terminal while the TUI is up land in the middle of
+//! the dashboard, so from `tui::init` to `tui::restore` they're kept in memory
+//! instead. The pane shows warnings and errors unless `RUST_LOG` asks for more.
+
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use chrono::{DateTime, Local};
+use tracing::{Level, Metadata};
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::filter::{FilterExt, filter_fn};
+use tracing_subscriber::fmt::{self, MakeWriter};
+use tracing_subscriber::prelude::*;
+
+/// Lines kept for the pane; older ones are dropped
+const CAPACITY: usize = 500;
+
+static CAPTURING: AtomicBool = AtomicBool::new(false);
+static LINES: Mutex<VecDeque<LogLine>> = Mutex::new(VecDeque::new());
+
+/// One captured event
+#[derive(Clone)]
+pub struct LogLine {
+    pub at: DateTime<Local>,
+    pub level: Level,
+    /// The message and its fields, as they'd be printed
+    pub message: String,
+}
+
+/// Send log output to stdout, or to the pane while capturing
+pub fn init() {
+    let printed = fmt::layer()
+        .with_target(false)
+        .with_filter(EnvFilter::from_default_env().and(filter_fn(|_| !capturing())));
+    let captured = fmt::layer()
+        .with_ansi(false)
+        .without_time()
+        .with_level(false)
+        .with_target(false)
+        .with_writer(Capture)
+        .with_filter(
+            EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| EnvFilter::new("warn"))
+                .and(filter_fn(|_| capturing())),
+        );
+    tracing_subscriber::registry()
+        .with(printed)
+        .with(captured)
+        .init();
+}
+
+/// Start or stop keeping log lines for the pane instead of printing them
+pub fn capture(on: bool) {
+    CAPTURING.store(on, Ordering::Relaxed);
+}
+
+fn capturing() -> bool {
+    CAPTURING.load(Ordering::Relaxed)
+}
+
+/// The last `count` lines captured, oldest first
+pub fn recent(count: usize) -> Vec<LogLine> {
+    let lines = LINES.lock().unwrap_or_else(|e| e.into_inner());
+    lines
+        .iter()
+        .skip(lines.len().saturating_sub(count))
+        .cloned()
+        .collect()
+}
+
+struct Capture;
+
+impl<'a> MakeWriter<'a> for Capture {
+    type Writer = CaptureWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        CaptureWriter {
+            level: Level::INFO,
+            buffer: Vec::new(),
+        }
+    }
+
+    fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+        CaptureWriter {
+            level: *meta.level(),
+            buffer: Vec::new(),
+        }
+    }
+}
+
+/// Collects one formatted event, and keeps it once it's been written
+struct CaptureWriter {
+    level: Level,
+    buffer: Vec<u8>,
+}
+
+impl Write for CaptureWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for CaptureWriter {
+    fn drop(&mut self) {
+        let message = String::from_utf8_lossy(&self.buffer).trim().to_string();
+        if message.is_empty() {
+            return;
+        }
+        let mut lines = LINES.lock().unwrap_or_else(|e| e.into_inner());
+        if lines.len() == CAPACITY {
+            lines.pop_front();
+        }
+        lines.push_back(LogLine {
+            at: Local::now(),
+            level: self.level,
+            message,
+        });
+    }
+}
@@ -1,25 +1,66 @@
 mod app;
+mod logs;
+mod progress;
+mod serve;
+mod session;
+mod theme;
 mod tui;
 mod ui;
 
-use std::path::PathBuf;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
-use anyhow::{Result, anyhow};
-use clap::Parser;
-use tracing_subscriber::EnvFilter;
+use anyhow::{Context, Result, anyhow};
+use clap::{Args, Parser, Subcommand};
+use qstorm_core::config::{DistanceMetric, ProviderConfig};
+use qstorm_core::distributed::{Connection, Coordinator, CoordinatorMessage, WorkerMessage};
+use qstorm_core::ingest::{CorpusFields, IngestOptions};
+use qstorm_core::runner::BenchmarkRunner;
+use qstorm_core::{
+    Assertion, BurstLog, Checkpoint, Comparison, Dashboard, Notification, PrometheusExporter,
+    QueryEmbedder, QueryFile, QuerySource, Record, RegressionThresholds, Rotation, RunHeader,
+    RunReport, SoakRecorder, ValidationReport,
+};
+
+use crate::progress::Progress;
 
 #[derive(Parser)]
 #[command(name = "qstorm")]
 #[command(about = "Vector search load testing tool", long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Path to configuration file
-    #[arg(short, long, default_value = "qstorm.yaml")]
+    #[arg(short, long, default_value = "qstorm.yaml", global = true)]
     config: PathBuf,
 
-    /// Path to queries file (YAML with list of text queries to embed)
-    #[arg(short, long)]
-    queries: PathBuf,
+    /// Query file (YAML with list of text queries to embed), directory, or glob.
+    /// Repeat to mix several sets; a `:weight` suffix (`head.yaml:0.6`) sets
+    /// their proportions.
+    #[arg(short, long, global = true)]
+    queries: Vec<QuerySource>,
+
+    /// Seed for all random choices; overrides `seed` in the config (random if neither is set)
+    #[arg(long, global = true)]
+    seed: Option<u64>,
+
+    /// Apply this entry of the config's `profiles` over its shared settings
+    #[arg(short, long, global = true)]
+    profile: Option<String>,
 
+    /// `run` flags, also accepted without a subcommand
+    #[command(flatten, next_help_heading = "Run options (without a command)")]
+    run: RunArgs,
+}
+
+#[derive(Args)]
+struct RunArgs {
     /// Run in headless mode (no TUI, just output results)
     #[arg(long)]
     headless: bool,
@@ -28,9 +69,296 @@ struct Cli {
     #[arg(short, long, default_value = "0")]
     bursts: usize,
 
-    /// Output format for headless mode
+    /// Stop a headless run after this many seconds, at the end of the burst in progress
+    #[arg(long, value_name = "SECS")]
+    duration: Option<u64>,
+
+    /// Don't show the status line on stderr during a headless run
+    #[arg(long)]
+    no_progress: bool,
+
+    /// Carry on an interrupted soak run from its checkpoint.json (or the directory holding it)
+    #[arg(long, value_name = "CHECKPOINT")]
+    resume: Option<PathBuf>,
+
+    /// Output format for headless mode; ndjson adds a run header and summary around the bursts
     #[arg(long, default_value = "json")]
     output: OutputFormat,
+
+    /// Sweep these concurrency levels (comma-separated) and report the curve; implies --headless
+    #[arg(long, value_delimiter = ',')]
+    sweep: Option<Vec<usize>>,
+
+    /// Search for the highest request rate that meets the `capacity` SLO; implies --headless
+    #[arg(long)]
+    find_capacity: bool,
+
+    /// Spread the load over this many workers started with `qstorm worker`; implies --headless
+    #[arg(long)]
+    workers: Option<usize>,
+
+    /// Address to wait for workers on
+    #[arg(long, default_value = qstorm_core::distributed::DEFAULT_LISTEN_ADDR)]
+    listen: String,
+
+    /// Token workers must present to join (default: $QSTORM_TOKEN, else a random one that is printed)
+    #[arg(long)]
+    token: Option<String>,
+
+    /// In the TUI, reconnect on its own after a provider error, backing off between attempts
+    #[arg(long)]
+    auto_reconnect: bool,
+
+    /// In the TUI, run every burst against the provider in this config file too, charting the two side by side
+    #[arg(long, value_name = "CONFIG")]
+    versus: Option<PathBuf>,
+
+    /// In the TUI, record every burst and settings change to this file for `qstorm replay`
+    #[arg(long, value_name = "FILE")]
+    record: Option<PathBuf>,
+
+    /// In the TUI, chart the last this many seconds of bursts instead of the last 100 bursts
+    #[arg(long, value_name = "SECS")]
+    chart_window: Option<u64>,
+
+    /// Serve Prometheus metrics on this port at /metrics while the benchmark runs
+    #[arg(long)]
+    metrics_port: Option<u16>,
+
+    /// Serve a live web dashboard of the run on this port
+    #[arg(long)]
+    dashboard_port: Option<u16>,
+
+    /// Write a JSON summary of the whole run to this file when a headless run ends
+    #[arg(long)]
+    report_file: Option<PathBuf>,
+
+    /// Write a standalone HTML report with charts to this file when a headless run ends
+    #[arg(long)]
+    html_report: Option<PathBuf>,
+
+    /// Write HdrHistogram percentile distributions (.hgrm) for each burst and the whole run here
+    #[arg(long)]
+    hgrm_dir: Option<PathBuf>,
+
+    /// Fail the run unless this holds when it ends, e.g. "p99_ms < 150" (repeatable)
+    #[arg(long = "assert", value_name = "EXPR")]
+    assertions: Vec<Assertion>,
+
+    /// Append each burst's metrics to this file, in the TUI or headless; Parquet if it ends in .parquet, NDJSON otherwise
+    #[arg(long, alias = "bursts-file")]
+    out: Option<PathBuf>,
+
+    /// Start a new numbered --out file once the current one reaches this many megabytes
+    #[arg(long, requires = "out")]
+    rotate_mb: Option<u64>,
+
+    /// Start a new numbered --out file once the current one has been open this many seconds
+    #[arg(long, requires = "out")]
+    rotate_secs: Option<u64>,
+
+    /// Write the sweep or capacity curve (offered load, QPS, p50/p99) here; CSV if it ends in .csv, JSON otherwise
+    #[arg(long)]
+    curve: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run a benchmark: the TUI by default, or headless, a sweep, or a capacity search
+    Run(Box<RunArgs>),
+
+    /// Generate a query file by sampling documents from the target index
+    GenerateQueries(GenerateQueriesArgs),
+
+    /// Embed a query file once and save the vectors, so later runs skip embedding
+    Embed(EmbedArgs),
+
+    /// Load a document corpus into the configured provider, creating its index if needed
+    Ingest(IngestArgs),
+
+    /// Check the config, query file, and compiled features for problems without running
+    Validate(ValidateArgs),
+
+    /// Download a standard dataset and convert it to qstorm query/ground-truth files
+    #[cfg(feature = "datasets")]
+    FetchDataset(FetchDatasetArgs),
+
+    /// Generate load on behalf of a coordinator started with `--workers`
+    Worker(WorkerArgs),
+
+    /// Compare two `--report-file` reports and fail if the candidate regressed
+    Compare(CompareArgs),
+
+    /// Summarize a `--report-file` report, or render it as HTML
+    Report(ReportArgs),
+
+    /// Play back a TUI session recorded with `--record`
+    Replay(ReplayArgs),
+
+    /// Wait for runs to be started, steered, and read over a REST API
+    Serve(ServeArgs),
+}
+
+#[derive(Args)]
+struct ServeArgs {
+    /// Address to serve the API on
+    #[arg(long, default_value = "127.0.0.1:7800")]
+    listen: String,
+}
+
+#[derive(Args)]
+struct ReplayArgs {
+    /// Recording written by `--record`
+    recording: PathBuf,
+
+    /// Play back this many times faster than recorded (0.5 for half speed)
+    #[arg(long, default_value = "1")]
+    speed: f64,
+
+    /// Chart the last this many seconds of bursts instead of the last 100 bursts
+    #[arg(long, value_name = "SECS")]
+    chart_window: Option<u64>,
+}
+
+#[derive(Args)]
+struct ReportArgs {
+    /// Report written by `--report-file`
+    report: PathBuf,
+
+    /// Write a standalone HTML page here instead of printing a summary
+    #[arg(long)]
+    html: Option<PathBuf>,
+
+    /// NDJSON `--out` file from the same run, to chart per-burst metrics in the HTML
+    #[arg(long)]
+    bursts: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct ValidateArgs {
+    /// Also connect to the provider, load the embedder, and check the index, ground truth, and dimensions
+    #[arg(long)]
+    connect: bool,
+}
+
+#[derive(Args)]
+struct CompareArgs {
+    /// Report from the known-good run
+    baseline: PathBuf,
+
+    /// Report from the run being checked
+    candidate: PathBuf,
+
+    /// Largest allowed p50 latency increase, in percent
+    #[arg(long, default_value = "10")]
+    max_p50_increase: f64,
+
+    /// Largest allowed p99 latency increase, in percent
+    #[arg(long, default_value = "10")]
+    max_p99_increase: f64,
+
+    /// Largest allowed QPS decrease, in percent
+    #[arg(long, default_value = "10")]
+    max_qps_decrease: f64,
+
+    /// Largest allowed drop in mean recall, as an absolute difference (0.01 = one point)
+    #[arg(long, default_value = "0.01")]
+    max_recall_decrease: f64,
+}
+
+#[derive(Args)]
+struct WorkerArgs {
+    /// Coordinator address (host:port)
+    #[arg(long)]
+    join: String,
+
+    /// The coordinator's token (default: $QSTORM_TOKEN)
+    #[arg(long)]
+    token: Option<String>,
+}
+
+#[cfg(feature = "datasets")]
+#[derive(Args)]
+struct FetchDatasetArgs {
+    /// Dataset to fetch: msmarco, sift, gist, or beir/<name>[:<split>]
+    dataset: String,
+
+    /// Directory to download and extract into
+    #[arg(short, long, default_value = "datasets")]
+    dir: PathBuf,
+
+    /// Use an already-downloaded archive instead of downloading
+    #[arg(long)]
+    archive: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct EmbedArgs {
+    /// Query file to write; its vectors go beside it with an .fvecs extension
+    #[arg(short, long, default_value = "queries.embedded.yaml")]
+    output: PathBuf,
+}
+
+#[derive(Args)]
+struct IngestArgs {
+    /// Corpus to load: JSON lines, .csv with a header row, or .parquet
+    corpus: PathBuf,
+
+    /// Field holding each document's ID (the row number is used when it's missing)
+    #[arg(long, default_value = "id")]
+    id_field: String,
+
+    /// Field holding the text to embed
+    #[arg(long, default_value = "text")]
+    text_field: String,
+
+    /// Field holding a precomputed vector; documents with one aren't embedded
+    #[arg(long, default_value = "vector")]
+    vector_field: String,
+
+    /// Documents embedded and upserted per request
+    #[arg(long, default_value = "256")]
+    batch_size: usize,
+
+    /// Similarity to create the index with if it doesn't exist: cosine, dot, or euclidean
+    #[arg(long, default_value = "cosine", value_parser = parse_metric)]
+    metric: DistanceMetric,
+}
+
+fn parse_metric(value: &str) -> Result<DistanceMetric, String> {
+    serde_json::from_value(serde_json::Value::String(value.into()))
+        .map_err(|_| format!("unknown metric '{value}' (expected cosine, dot, or euclidean)"))
+}
+
+#[derive(Args)]
+struct GenerateQueriesArgs {
+    /// Where to write the generated query file
+    #[arg(short, long, default_value = "queries.yaml")]
+    output: PathBuf,
+
+    /// Number of queries to generate
+    #[arg(short = 'n', long, default_value = "100")]
+    count: usize,
+
+    /// Payload field containing document text (dotted path; defaults to the longest string)
+    #[arg(long)]
+    text_field: Option<String>,
+
+    /// Maximum number of documents to scan when sampling
+    #[arg(long, default_value = "10000")]
+    max_documents: usize,
+
+    /// Record each source document ID as the query's expected result
+    #[arg(long)]
+    with_source_ids: bool,
+
+    /// Chat model used to write queries when OPENAI_API_KEY is set
+    #[arg(long, default_value = "gpt-4o-mini")]
+    llm_model: String,
+
+    /// Always extract sentences, even if an OpenAI key is available
+    #[arg(long)]
+    no_llm: bool,
 }
 
 #[derive(Clone, Copy, Default, clap::ValueEnum)]
@@ -38,100 +366,1649 @@ enum OutputFormat {
     #[default]
     Json,
     Csv,
+    /// A header record, one record per burst, and a summary record
+    Ndjson,
+}
+
+/// Exit status for a run that finished but failed a check: an `--assert`,
+/// a stop condition, or a `compare` regression
+const EXIT_CHECK_FAILED: u8 = 4;
+
+/// An error that ends qstorm with its own exit status instead of 1
+#[derive(Debug)]
+struct Exit {
+    code: u8,
+    message: String,
+}
+
+impl Exit {
+    fn check_failed(message: impl Into<String>) -> anyhow::Error {
+        Self {
+            code: EXIT_CHECK_FAILED,
+            message: message.into(),
+        }
+        .into()
+    }
+}
+
+impl fmt::Display for Exit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Exit {}
+
+/// Exit status for an error: 2 if the provider couldn't be reached, the
+/// error's own for an `Exit`, and 1 for anything else
+fn exit_code(error: &anyhow::Error) -> u8 {
+    if let Some(exit) = error.downcast_ref::<Exit>() {
+        return exit.code;
+    }
+    match error.downcast_ref::<qstorm_core::Error>() {
+        Some(
+            qstorm_core::Error::Connection { .. }
+            | qstorm_core::Error::Authentication(_)
+            | qstorm_core::Error::NotConnected,
+        ) => 2,
+        _ => 1,
+    }
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     let cli = Cli::parse();
 
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
-        .with_target(false)
-        .init();
+    logs::init();
+
+    // Finish sample and burst files however the command ended, so a Parquet
+    // file left open by an early error still gets its footer
+    let result = dispatch(cli).await;
+    let closed = qstorm_core::close_outputs().context("failed to finish output files");
+    match result.and(closed) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e:?}");
+            ExitCode::from(exit_code(&e))
+        }
+    }
+}
+
+async fn dispatch(cli: Cli) -> Result<()> {
+    match cli.command.unwrap_or(Command::Run(Box::new(cli.run))) {
+        Command::Run(args) => {
+            let config = load_config(&cli.config, cli.profile.as_deref(), cli.seed)?;
+            run(config, cli.queries, *args).await
+        }
+        Command::GenerateQueries(args) => {
+            let config = load_config(&cli.config, cli.profile.as_deref(), cli.seed)?;
+            generate_queries(config, args).await
+        }
+        Command::Embed(args) => {
+            let config = load_config(&cli.config, cli.profile.as_deref(), cli.seed)?;
+            embed(config, cli.queries, args).await
+        }
+        Command::Ingest(args) => {
+            let config = load_config(&cli.config, cli.profile.as_deref(), cli.seed)?;
+            ingest(config, args).await
+        }
+        Command::Validate(args) => {
+            validate(&cli.config, cli.profile.as_deref(), &cli.queries, args).await
+        }
+        #[cfg(feature = "datasets")]
+        Command::FetchDataset(args) => fetch_dataset(args).await,
+        Command::Worker(args) => run_worker(args, &cli.config, cli.profile.as_deref()).await,
+        Command::Compare(args) => compare(args),
+        Command::Report(args) => report(args),
+        Command::Replay(args) => replay(&cli.config, args),
+        Command::Serve(args) => {
+            let config = load_config(&cli.config, cli.profile.as_deref(), cli.seed)?;
+            serve(config, cli.queries, args).await
+        }
+    }
+}
+
+/// Load the config and settle on a seed
+fn load_config(
+    path: &Path,
+    profile: Option<&str>,
+    seed: Option<u64>,
+) -> Result<qstorm_core::Config> {
+    let mut config = qstorm_core::Config::from_file_with_profile(path, profile)?;
+
+    // Always run seeded so any run can be reproduced from its logged seed
+    let seed = seed.or(config.seed).unwrap_or_else(rand::random);
+    config.seed = Some(seed);
+    tracing::info!(seed, "Using random seed");
+    Ok(config)
+}
+
+async fn run(
+    mut config: qstorm_core::Config,
+    queries: Vec<QuerySource>,
+    args: RunArgs,
+) -> Result<()> {
+    // Validate queries files exist
+    if queries.is_empty() {
+        return Err(anyhow!("--queries is required to run a benchmark"));
+    }
+    for source in &queries {
+        if source.files().is_err() {
+            return Err(Exit {
+                code: 3,
+                message: format!("Queries file not found: {}", source.path.display()),
+            }
+            .into());
+        }
+    }
+
+    let sweep = match args.sweep {
+        Some(concurrency) => Some(qstorm_core::config::SweepConfig {
+            concurrency,
+            ..config.sweep.clone().unwrap_or_default()
+        }),
+        None => config.sweep.clone(),
+    };
+
+    let capacity = match config.capacity.clone() {
+        None if args.find_capacity => Some(Default::default()),
+        capacity => capacity,
+    };
+
+    if let Some(checkpoint) = &args.resume {
+        if !args.headless || sweep.is_some() || capacity.is_some() || args.workers.is_some() {
+            return Err(anyhow!(
+                "--resume only applies to --headless runs without a sweep, capacity search, or --workers"
+            ));
+        }
+        if config.scenario.is_some() {
+            return Err(anyhow!(
+                "--resume can't pick up a scenario part way through"
+            ));
+        }
+        // The checkpoint's directory is the soak directory, whatever the config says
+        let dir = if checkpoint.is_dir() {
+            checkpoint.as_path()
+        } else {
+            checkpoint.parent().unwrap_or(Path::new("."))
+        };
+        config.soak = Some(qstorm_core::config::SoakConfig {
+            dir: dir.to_string_lossy().into_owned(),
+            ..config.soak.clone().unwrap_or_default()
+        });
 
-    // Load configuration
-    let config = qstorm_core::Config::from_file(&cli.config)?;
+        // Reuse the interrupted run's seed so shuffling carries on where it was
+        let checkpoint = Checkpoint::load(dir.join(qstorm_core::soak::CHECKPOINT_FILE))
+            .map_err(|e| anyhow!("Failed to read the checkpoint in {}: {e}", dir.display()))?;
+        if checkpoint.seed.is_some() {
+            config.seed = checkpoint.seed;
+        }
+    }
 
-    // Validate queries file exists
-    if !cli.queries.exists() {
-        return Err(anyhow!("Queries file not found: {}", cli.queries.display()));
+    if let Some(seed) = config.seed
+        && (args.headless || sweep.is_some() || capacity.is_some() || args.workers.is_some())
+    {
+        eprintln!("Using seed {seed}");
     }
 
-    let queries_path = cli.queries.to_string_lossy().to_string();
+    if sweep.is_some() && config.scenario.is_some() {
+        return Err(anyhow!(
+            "A scenario can't be combined with a concurrency sweep"
+        ));
+    }
+    if capacity.is_some() && (sweep.is_some() || config.scenario.is_some()) {
+        return Err(anyhow!(
+            "A capacity search can't be combined with a sweep or scenario"
+        ));
+    }
 
-    if cli.headless {
-        run_headless(config, &queries_path, cli.bursts, cli.output).await
+    if args.auto_reconnect
+        && (args.headless || sweep.is_some() || capacity.is_some() || args.workers.is_some())
+    {
+        return Err(anyhow!("--auto-reconnect only applies to the TUI"));
+    }
+    if args.chart_window.is_some()
+        && (args.headless || sweep.is_some() || capacity.is_some() || args.workers.is_some())
+    {
+        return Err(anyhow!("--chart-window only applies to the TUI"));
+    }
+    if args.record.is_some()
+        && (args.headless || sweep.is_some() || capacity.is_some() || args.workers.is_some())
+    {
+        return Err(anyhow!("--record only applies to the TUI"));
+    }
+    if args.versus.is_some()
+        && (args.headless || sweep.is_some() || capacity.is_some() || args.workers.is_some())
+    {
+        return Err(anyhow!("--versus only applies to the TUI"));
+    }
+    if args.chart_window == Some(0) {
+        return Err(anyhow!("--chart-window must be at least 1 second"));
+    }
+
+    if args.metrics_port.is_some()
+        && (sweep.is_some() || capacity.is_some() || args.workers.is_some())
+    {
+        return Err(anyhow!(
+            "--metrics-port can't be combined with a sweep, capacity search, or --workers"
+        ));
+    }
+    if args.dashboard_port.is_some()
+        && (sweep.is_some() || capacity.is_some() || args.workers.is_some())
+    {
+        return Err(anyhow!(
+            "--dashboard-port can't be combined with a sweep, capacity search, or --workers"
+        ));
+    }
+
+    if (args.report_file.is_some() || args.html_report.is_some() || args.hgrm_dir.is_some())
+        && (!args.headless || sweep.is_some() || capacity.is_some() || args.workers.is_some())
+    {
+        return Err(anyhow!(
+            "--report-file, --html-report, and --hgrm-dir only apply to --headless runs without a sweep, capacity search, or --workers"
+        ));
+    }
+
+    if args.duration.is_some()
+        && (!args.headless || sweep.is_some() || capacity.is_some() || args.workers.is_some())
+    {
+        return Err(anyhow!(
+            "--duration only applies to --headless runs without a sweep, capacity search, or --workers"
+        ));
+    }
+    if args.duration == Some(0) {
+        return Err(anyhow!("--duration must be at least 1 second"));
+    }
+
+    if !args.assertions.is_empty()
+        && (!args.headless || sweep.is_some() || capacity.is_some() || args.workers.is_some())
+    {
+        return Err(anyhow!(
+            "--assert only applies to --headless runs without a sweep, capacity search, or --workers"
+        ));
+    }
+
+    if args.out.is_some() && (sweep.is_some() || capacity.is_some() || args.workers.is_some()) {
+        return Err(anyhow!(
+            "--out can't be combined with a sweep, capacity search, or --workers"
+        ));
+    }
+    if args.rotate_mb == Some(0) || args.rotate_secs == Some(0) {
+        return Err(anyhow!("--rotate-mb and --rotate-secs must be at least 1"));
+    }
+
+    if matches!(args.output, OutputFormat::Ndjson)
+        && (sweep.is_some() || capacity.is_some() || args.workers.is_some())
+    {
+        return Err(anyhow!(
+            "--output ndjson can't be combined with a sweep, capacity search, or --workers"
+        ));
+    }
+
+    if args.curve.is_some() && sweep.is_none() && capacity.is_none() {
+        return Err(anyhow!(
+            "--curve only applies to a sweep or capacity search"
+        ));
+    }
+
+    if let Some(workers) = args.workers {
+        if sweep.is_some()
+            || capacity.is_some()
+            || config.scenario.is_some()
+            || config.soak.is_some()
+        {
+            return Err(anyhow!(
+                "--workers can't be combined with a sweep, capacity search, scenario, or soak run"
+            ));
+        }
+        if workers == 0 {
+            return Err(anyhow!("--workers must be at least 1"));
+        }
+        if config.benchmark.max_total_queries.is_some() {
+            return Err(anyhow!(
+                "--workers can't enforce benchmark.max_total_queries across workers"
+            ));
+        }
+        if config.rerank.is_some() {
+            return Err(anyhow!("--workers doesn't support a rerank stage yet"));
+        }
+        let token = match args.token.clone().or_else(|| std::env::var(TOKEN_ENV).ok()) {
+            Some(token) => token,
+            None => {
+                let token = format!("{:032x}", rand::random::<u128>());
+                eprintln!("Workers join with --token {token}");
+                token
+            }
+        };
+        let run = DistributedRun {
+            workers,
+            listen: &args.listen,
+            token: &token,
+            bursts: args.bursts,
+            output: args.output,
+        };
+        run_distributed(config, &queries, run).await
+    } else if let Some(sweep) = sweep {
+        run_sweep(config, &queries, &sweep, args.output, args.curve.as_deref()).await
+    } else if let Some(capacity) = capacity {
+        run_capacity(
+            config,
+            &queries,
+            capacity,
+            args.output,
+            args.curve.as_deref(),
+        )
+        .await
+    } else if args.headless {
+        let run = HeadlessRun {
+            bursts: args.bursts,
+            output: args.output,
+            metrics_port: args.metrics_port,
+            dashboard_port: args.dashboard_port,
+            report_file: args.report_file.as_deref(),
+            html_report: args.html_report.as_deref(),
+            hgrm_dir: args.hgrm_dir.as_deref(),
+            duration: args.duration.map(Duration::from_secs),
+            progress: !args.no_progress,
+            resume: args.resume.is_some(),
+            assertions: &args.assertions,
+            burst_log: open_burst_log(args.out.as_deref(), args.rotate_mb, args.rotate_secs)?,
+        };
+        run_headless(config, &queries, run).await
     } else {
-        run_tui(config, &queries_path).await
+        let burst_log = open_burst_log(args.out.as_deref(), args.rotate_mb, args.rotate_secs)?;
+        // Only the provider comes from the other file; load settings are shared
+        let rival = args
+            .versus
+            .as_deref()
+            .map(|path| qstorm_core::Config::from_file_with_profile(path, None))
+            .transpose()?
+            .map(|config| config.provider);
+        let run = TuiRun {
+            metrics_port: args.metrics_port,
+            dashboard_port: args.dashboard_port,
+            burst_log,
+            auto_reconnect: args.auto_reconnect,
+            chart_window: args.chart_window.map(Duration::from_secs),
+            rival,
+            record: args.record.as_deref(),
+        };
+        run_tui(config, &queries, run).await
     }
 }
 
+/// The `--out` file, rotating if `--rotate-mb` or `--rotate-secs` was given
+fn open_burst_log(
+    path: Option<&Path>,
+    rotate_mb: Option<u64>,
+    rotate_secs: Option<u64>,
+) -> Result<Option<BurstLog>> {
+    let Some(path) = path else {
+        return Ok(None);
+    };
+    let rotation = Rotation {
+        max_bytes: rotate_mb.map(|mb| mb * 1024 * 1024),
+        max_age: rotate_secs.map(Duration::from_secs),
+    };
+    let log = BurstLog::rotating(path, rotation)
+        .map_err(|e| anyhow!("Failed to create {}: {e}", path.display()))?;
+    Ok(Some(log))
+}
+
+/// Settings for a headless run
+struct HeadlessRun<'a> {
+    bursts: usize,
+    output: OutputFormat,
+    metrics_port: Option<u16>,
+    dashboard_port: Option<u16>,
+    report_file: Option<&'a Path>,
+    html_report: Option<&'a Path>,
+    hgrm_dir: Option<&'a Path>,
+    /// Stop once the run has gone on this long
+    duration: Option<Duration>,
+    /// Show a status line on stderr, with a progress bar when the run has a
+    /// burst count or duration
+    progress: bool,
+    /// Carry on the soak run in `soak.dir` instead of starting a new one
+    resume: bool,
+    assertions: &'a [Assertion],
+    burst_log: Option<BurstLog>,
+}
+
 async fn run_headless(
     config: qstorm_core::Config,
-    queries_path: &str,
-    burst_count: usize,
-    output: OutputFormat,
+    queries: &[QuerySource],
+    run: HeadlessRun<'_>,
 ) -> Result<()> {
+    let HeadlessRun {
+        bursts: burst_count,
+        output,
+        metrics_port,
+        dashboard_port,
+        report_file,
+        html_report,
+        hgrm_dir,
+        duration,
+        progress,
+        resume,
+        assertions,
+        burst_log,
+    } = run;
+    if let Some(dir) = hgrm_dir {
+        std::fs::create_dir_all(dir)?;
+    }
+
     eprintln!("Loading and embedding queries...");
     let mut app = app::App::new(config)?;
-    app.load_and_embed_queries(queries_path).await?;
+    app.load_and_embed_queries(queries).await?;
     eprintln!("Embedded {} queries", app.query_count());
 
+    if let Some(port) = metrics_port {
+        let exporter = serve_metrics(&app, port).await?;
+        eprintln!(
+            "Serving metrics at http://{}/metrics",
+            exporter.local_addr()
+        );
+        app.set_exporter(exporter);
+    }
+
+    if let Some(port) = dashboard_port {
+        let dashboard = serve_dashboard(&app, port).await?;
+        eprintln!(
+            "Serving the dashboard at http://{}/",
+            dashboard.local_addr()
+        );
+        app.set_dashboard(dashboard);
+    }
+
+    if let Some(log) = burst_log {
+        eprintln!("Writing bursts to {}", log.current_path().display());
+        app.set_burst_log(log);
+    }
+
     eprintln!("Connecting to provider...");
-    app.connect().await?;
+    if let Err(e) = app.connect().await {
+        return Err(notify_failure(&app, e, chrono::Utc::now()).await);
+    }
 
     eprintln!("Running warmup...");
-    app.warmup().await?;
+    if let Err(e) = app.warmup().await {
+        return Err(notify_failure(&app, e, chrono::Utc::now()).await);
+    }
+
+    let mut started_at = chrono::Utc::now();
+    // Bursts and running time from before a resume, counted towards the limits
+    let mut done_bursts = 0;
+    let mut ran_for = Duration::ZERO;
+    let mut soak = match &app.config.soak {
+        Some(soak) if resume => {
+            let (recorder, resumed) = SoakRecorder::resume(soak)?;
+            let checkpoint = &resumed.checkpoint;
+            if checkpoint.provider != app.provider_name() {
+                return Err(anyhow!(
+                    "The checkpoint is from a run against '{}', not '{}'",
+                    checkpoint.provider,
+                    app.provider_name()
+                ));
+            }
+            eprintln!(
+                "Resuming soak run in {} after {} bursts ({} queries)",
+                soak.dir, checkpoint.bursts_completed, checkpoint.total_queries
+            );
+            started_at = checkpoint.started_at;
+            done_bursts = checkpoint.bursts_completed;
+            ran_for = checkpoint.ran_for();
+            app.resume(resumed)?;
+            Some(recorder)
+        }
+        Some(soak) => {
+            eprintln!("Soak mode: checkpointing to {}", soak.dir);
+            Some(SoakRecorder::create(
+                soak,
+                app.provider_name(),
+                app.config.seed,
+            )?)
+        }
+        None => None,
+    };
 
     eprintln!("Starting benchmark...");
+    // `--bursts` counts the bursts run before a resume too
     let count = if burst_count == 0 {
         usize::MAX
     } else {
-        burst_count
+        burst_count.saturating_sub(done_bursts)
     };
 
-    // Print CSV header
-    if matches!(output, OutputFormat::Csv) {
-        println!("timestamp,qps,p50_ms,p90_ms,p99_ms,success,failure");
+    // Ingest runs report write throughput instead of search metrics
+    let ingest = app.config.benchmark.ingest.is_some();
+    print_header(output, ingest, &app.config.benchmark.percentiles);
+    if matches!(output, OutputFormat::Ndjson) {
+        let mut header = RunHeader::new(&app.config, started_at);
+        header.git_commit = git_commit();
+        println!(
+            "{}",
+            serde_json::to_string(&Record::Header(Box::new(header)))?
+        );
     }
 
+    // A soak's own duration counts towards the progress bar too
+    let soak_duration = app
+        .config
+        .soak
+        .as_ref()
+        .and_then(|soak| soak.duration_secs)
+        .map(|secs| Duration::from_secs(secs).saturating_sub(ran_for));
+    let mut progress = Progress::new(
+        (burst_count > 0).then_some(count),
+        duration
+            .map(|duration| duration.saturating_sub(ran_for))
+            .into_iter()
+            .chain(soak_duration)
+            .min(),
+        progress,
+    );
+
+    let interrupted = shutdown_flag();
+    let mut stop_reason = None;
+    let mut phase = None;
+    let deadline = duration.map(|duration| Instant::now() + duration.saturating_sub(ran_for));
     for _ in 0..count {
-        let metrics = app.run_burst().await?;
+        if soak.as_ref().is_some_and(|soak| soak.expired()) {
+            break;
+        }
+        if interrupted.load(Ordering::Relaxed) {
+            break;
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            progress.suspend(|| eprintln!("Duration reached"));
+            break;
+        }
+
+        let metrics = match app.run_burst().await {
+            Ok(metrics) => metrics,
+            Err(e) => return Err(notify_failure(&app, e, started_at).await),
+        };
+        app.poll_runner_events();
+        match app.take_health_change() {
+            Some((false, reason)) => progress.suspend(|| {
+                eprintln!(
+                    "Provider unreachable: {}",
+                    reason.as_deref().unwrap_or("unknown reason")
+                )
+            }),
+            Some((true, _)) => progress.suspend(|| eprintln!("Provider reachable again")),
+            None => {}
+        }
+        if metrics.phase.is_some() && metrics.phase != phase {
+            phase = metrics.phase.clone();
+            progress
+                .suspend(|| eprintln!("Scenario phase: {}", phase.as_deref().unwrap_or_default()));
+        }
+
+        progress.suspend(|| print_burst(&metrics, output, ingest))?;
+        progress.update(&metrics);
+
+        if let (Some(dir), Some(cumulative)) = (hgrm_dir, app.metrics()) {
+            let burst = cumulative.bursts().len();
+            let histogram = cumulative.last_burst_samples().latency_histogram();
+            qstorm_core::hgrm::save(dir.join(format!("burst-{burst:05}.hgrm")), &histogram)?;
+        }
+
+        if let (Some(soak), Some(cumulative)) = (&mut soak, app.metrics()) {
+            soak.record(&metrics, cumulative)?;
+        }
 
-        match output {
-            OutputFormat::Json => {
-                println!("{}", serde_json::to_string(&metrics)?);
+        stop_reason = app.take_stop_reason();
+        if stop_reason.is_some() {
+            break;
+        }
+        if app.take_scenario_complete() {
+            progress.suspend(|| eprintln!("Scenario complete"));
+            break;
+        }
+        if app.budget_exhausted() {
+            progress.suspend(|| eprintln!("Query budget reached"));
+            break;
+        }
+    }
+    progress.finish();
+
+    let interrupted = interrupted.load(Ordering::Relaxed);
+    let mut failed_assertions = 0;
+    if let (Some(soak), Some(cumulative)) = (soak, app.metrics()) {
+        // An interrupted soak keeps an unfinished checkpoint
+        let (checkpoint, verb) = if interrupted {
+            (soak.close(cumulative)?, "interrupted")
+        } else {
+            (soak.finish(cumulative)?, "finished")
+        };
+        eprintln!(
+            "Soak {verb} after {} bursts ({} queries)",
+            checkpoint.bursts_completed, checkpoint.total_queries
+        );
+    }
+
+    if let Some(cumulative) = app.metrics() {
+        print_summary(cumulative, ingest);
+
+        if let Some(dir) = hgrm_dir {
+            qstorm_core::hgrm::save(dir.join("run.hgrm"), cumulative.latency_histogram())?;
+            eprintln!("Wrote latency distributions to {}", dir.display());
+        }
+
+        let ndjson = matches!(output, OutputFormat::Ndjson);
+        if report_file.is_some() || html_report.is_some() || ndjson || !assertions.is_empty() {
+            let mut report = RunReport::new(&app.config, cumulative, started_at);
+            report.git_commit = git_commit();
+            report.stop_reason = stop_reason.clone();
+            report.assertions = assertions.iter().map(|a| a.check(&report)).collect();
+            for result in &report.assertions {
+                let actual = match (result.actual, &result.note) {
+                    (_, Some(note)) => note.clone(),
+                    (Some(actual), None) => format!("{actual:.4}"),
+                    (None, None) => "-".into(),
+                };
+                let verdict = if result.passed { "PASS" } else { "FAIL" };
+                eprintln!("{verdict} {} (actual: {actual})", result.assertion);
+            }
+            failed_assertions = report.assertions.iter().filter(|r| !r.passed).count();
+            if let Some(path) = report_file {
+                report.save(path)?;
+                eprintln!("Wrote run report to {}", path.display());
             }
-            OutputFormat::Csv => {
+            if let Some(path) = html_report {
+                report.save_html(path, cumulative.bursts())?;
+                eprintln!("Wrote HTML report to {}", path.display());
+            }
+            if ndjson {
                 println!(
-                    "{},{:.2},{:.2},{:.2},{:.2},{},{}",
-                    metrics.timestamp,
-                    metrics.qps,
-                    metrics.latency.p50_us as f64 / 1000.0,
-                    metrics.latency.p90_us as f64 / 1000.0,
-                    metrics.latency.p99_us as f64 / 1000.0,
-                    metrics.success_count,
-                    metrics.failure_count,
+                    "{}",
+                    serde_json::to_string(&Record::Summary(Box::new(report)))?
                 );
             }
         }
     }
 
+    if let Some(mut report) = app.report(started_at) {
+        report.stop_reason = stop_reason.clone();
+        let notification = if stop_reason.is_some() {
+            Notification::Stopped(&report)
+        } else {
+            Notification::Finished(&report)
+        };
+        app.notify(notification).await;
+    }
+
+    app.disconnect().await?;
+    if let Some(reason) = stop_reason {
+        return Err(Exit::check_failed(format!("Stopped early: {reason}")));
+    }
+    if failed_assertions > 0 {
+        return Err(Exit::check_failed(format!(
+            "{failed_assertions} of {} assertions failed",
+            assertions.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Post that the run failed with `error`, handing the error back
+async fn notify_failure(
+    app: &app::App,
+    error: anyhow::Error,
+    started_at: chrono::DateTime<chrono::Utc>,
+) -> anyhow::Error {
+    let report = app.report(started_at);
+    app.notify(Notification::Failed {
+        provider: app.provider_name(),
+        error: &error.to_string(),
+        report: report.as_ref(),
+    })
+    .await;
+    error
+}
+
+/// Print the CSV header for per-burst output. Extra configured percentiles
+/// get trailing columns so existing columns keep their positions.
+fn print_header(output: OutputFormat, ingest: bool, percentiles: &[f64]) {
+    if matches!(output, OutputFormat::Csv) {
+        if ingest {
+            println!("timestamp,docs_per_sec,p50_ms,p90_ms,p99_ms,batches,failures");
+        } else {
+            let mut header = "timestamp,qps,p50_ms,p90_ms,p99_ms,success,failure".to_string();
+            for p in percentiles {
+                header.push_str(&format!(",p{p}_ms"));
+            }
+            println!("{header}");
+        }
+    }
+}
+
+/// Print one burst as a JSON, CSV, or NDJSON record line
+fn print_burst(
+    metrics: &qstorm_core::BurstMetrics,
+    output: OutputFormat,
+    ingest: bool,
+) -> Result<()> {
+    match output {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string(metrics)?);
+        }
+        OutputFormat::Ndjson => {
+            let record = Record::Burst(Box::new(metrics.clone()));
+            println!("{}", serde_json::to_string(&record)?);
+        }
+        OutputFormat::Csv if ingest => {
+            let writes = metrics.writes.clone().unwrap_or_default();
+            println!(
+                "{},{:.2},{:.2},{:.2},{:.2},{},{}",
+                metrics.timestamp,
+                writes.docs_per_sec,
+                writes.latency.p50_us as f64 / 1000.0,
+                writes.latency.p90_us as f64 / 1000.0,
+                writes.latency.p99_us as f64 / 1000.0,
+                writes.request_count,
+                writes.failure_count,
+            );
+        }
+        OutputFormat::Csv => {
+            let mut line = format!(
+                "{},{:.2},{:.2},{:.2},{:.2},{},{}",
+                metrics.timestamp,
+                metrics.qps,
+                metrics.latency.p50_us as f64 / 1000.0,
+                metrics.latency.p90_us as f64 / 1000.0,
+                metrics.latency.p99_us as f64 / 1000.0,
+                metrics.success_count,
+                metrics.failure_count,
+            );
+            for p in &metrics.latency.percentiles {
+                line.push_str(&format!(",{:.2}", p.latency_us as f64 / 1000.0));
+            }
+            println!("{line}");
+        }
+    }
+    Ok(())
+}
+
+/// Totals across every burst of the run, on stderr so they don't mix with the output
+fn print_summary(metrics: &qstorm_core::Metrics, ingest: bool) {
+    let bursts = metrics.bursts();
+    if ingest {
+        let writes = bursts.iter().filter_map(|b| b.writes.as_ref());
+        let (documents, requests, failures) = writes.fold((0, 0, 0), |(d, r, f), w| {
+            (
+                d + w.document_count,
+                r + w.request_count,
+                f + w.failure_count,
+            )
+        });
+        eprintln!(
+            "Summary: {} bursts, {documents} documents in {requests} batches, {failures} failed",
+            bursts.len()
+        );
+        return;
+    }
+
+    let queries = metrics.total_queries();
+    let latency = metrics.aggregate_latency();
+    let mut summary = format!(
+        "Summary: {} bursts, {queries} queries, {} failed, {:.1} avg QPS, p50 {:.2} ms, p99 {:.2} ms",
+        bursts.len(),
+        queries - metrics.total_successes(),
+        metrics.average_qps(),
+        latency.p50_us as f64 / 1000.0,
+        latency.p99_us as f64 / 1000.0,
+    );
+    for p in &latency.percentiles {
+        summary.push_str(&format!(
+            ", p{} {:.2} ms",
+            p.percentile,
+            p.latency_us as f64 / 1000.0
+        ));
+    }
+    summary.push_str(&format!(
+        ", stddev {:.2} ms (CV {:.2}), IQR {:.2} ms",
+        latency.stddev_us / 1000.0,
+        latency.cv,
+        latency.iqr_us as f64 / 1000.0
+    ));
+    if let Some(recall) = metrics.average_recall() {
+        summary.push_str(&format!(", recall {:.1}%", recall * 100.0));
+    }
+    if let Some(ndcg) = metrics.average_ndcg() {
+        summary.push_str(&format!(", NDCG {:.3}", ndcg));
+    }
+    if let Some(mrr) = metrics.average_mrr() {
+        summary.push_str(&format!(", MRR {:.3}", mrr));
+    }
+    eprintln!("{summary}");
+    for (tag, tagged) in metrics.aggregate_tags() {
+        let mut line = format!(
+            "  [{tag}] {} queries, {} failed, p50 {:.2} ms, p99 {:.2} ms",
+            tagged.query_count,
+            tagged.failure_count,
+            tagged.latency.p50_us as f64 / 1000.0,
+            tagged.latency.p99_us as f64 / 1000.0,
+        );
+        if let Some(recall) = tagged.recall_at_k {
+            line.push_str(&format!(", recall {:.1}%", recall * 100.0));
+        }
+        eprintln!("{line}");
+    }
+}
+
+/// Set once SIGINT or SIGTERM arrives so the current burst can finish and be
+/// reported before the run ends. A second signal exits immediately, after
+/// finishing the output files written so far.
+fn shutdown_flag() -> Arc<AtomicBool> {
+    let flag = Arc::new(AtomicBool::new(false));
+    let set = Arc::clone(&flag);
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        eprintln!("Interrupted, finishing the current burst (interrupt again to abort)");
+        set.store(true, Ordering::Relaxed);
+        shutdown_signal().await;
+        if let Err(e) = qstorm_core::close_outputs() {
+            eprintln!("Error: failed to finish output files: {e}");
+        }
+        std::process::exit(130);
+    });
+    flag
+}
+
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            return;
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+async fn serve(
+    config: qstorm_core::Config,
+    queries: Vec<QuerySource>,
+    args: ServeArgs,
+) -> Result<()> {
+    if queries.is_empty() {
+        return Err(anyhow!("--queries is required to serve runs"));
+    }
+
+    eprintln!("Loading and embedding queries...");
+    let mut app = app::App::new(config)?;
+    app.load_and_embed_queries(&queries).await?;
+    eprintln!("Embedded {} queries", app.query_count());
+
+    serve::serve(app, &args.listen).await
+}
+
+/// Coordinator settings for a distributed run
+struct DistributedRun<'a> {
+    workers: usize,
+    listen: &'a str,
+    token: &'a str,
+    bursts: usize,
+    output: OutputFormat,
+}
+
+async fn run_distributed(
+    config: qstorm_core::Config,
+    queries: &[QuerySource],
+    run: DistributedRun<'_>,
+) -> Result<()> {
+    eprintln!("Loading and embedding queries...");
+    let mut app = app::App::new(config)?;
+    app.load_and_embed_queries(queries).await?;
+    eprintln!("Embedded {} queries", app.query_count());
+
+    if app.config.ground_truth.is_some() {
+        // Workers receive the queries with their expected IDs already attached
+        eprintln!("Computing ground truth...");
+        app.connect().await?;
+        app.disconnect().await?;
+    }
+
+    eprintln!("Waiting for {} workers on {}...", run.workers, run.listen);
+    let mut coordinator = Coordinator::listen(run.listen, run.workers, run.token).await?;
+
+    eprintln!("Sending queries to workers...");
+    coordinator
+        .assign(
+            &app.config.provider,
+            &app.config.benchmark,
+            app.config.seed,
+            &app.queries(),
+            app.config.chaos.as_ref(),
+        )
+        .await?;
+
+    eprintln!(
+        "Starting benchmark on {} workers...",
+        coordinator.worker_count()
+    );
+    let count = if run.bursts == 0 {
+        usize::MAX
+    } else {
+        run.bursts
+    };
+
+    let ingest = app.config.benchmark.ingest.is_some();
+    print_header(run.output, ingest, &app.config.benchmark.percentiles);
+    for _ in 0..count {
+        let metrics = coordinator.run_burst().await?;
+        print_burst(&metrics, run.output, ingest)?;
+    }
+
+    coordinator.finish().await?;
+    Ok(())
+}
+
+/// Environment variable holding the token for distributed runs
+const TOKEN_ENV: &str = "QSTORM_TOKEN";
+
+async fn run_worker(args: WorkerArgs, config_path: &Path, profile: Option<&str>) -> Result<()> {
+    let token = args
+        .token
+        .or_else(|| std::env::var(TOKEN_ENV).ok())
+        .ok_or_else(|| anyhow!("qstorm worker needs the coordinator's --token or ${TOKEN_ENV}"))?;
+    eprintln!("Joining coordinator at {}...", args.join);
+    let mut link = Connection::join(&args.join, &token).await?;
+
+    let (worker, provider, benchmark, seed, queries, chaos) = match link.recv().await? {
+        Some(CoordinatorMessage::Assign {
+            worker,
+            provider,
+            benchmark,
+            seed,
+            queries,
+            chaos,
+        }) => (worker, provider, benchmark, seed, queries, chaos),
+        Some(CoordinatorMessage::Rejected { reason }) => {
+            return Err(anyhow!("The coordinator turned us away: {reason}"));
+        }
+        _ => return Err(anyhow!("Expected an assignment from the coordinator")),
+    };
+
+    eprintln!("Worker {worker}: connecting to {}...", provider.name);
+    let setup = async {
+        // Secrets arrive masked; only read the local config when there are any
+        let provider = if provider.has_masked_secrets() {
+            let local =
+                qstorm_core::Config::from_file_with_profile(config_path, profile).map_err(|e| {
+                    anyhow!(
+                        "The provider's secrets are masked; reading them from {} failed: {e}",
+                        config_path.display()
+                    )
+                })?;
+            provider.with_secrets_from(&local.provider)?
+        } else {
+            provider
+        };
+        let (provider, factory) = app::run_provider(&provider, chaos.as_ref())?;
+        let mut runner = BenchmarkRunner::new(provider, benchmark)
+            .with_queries(queries)
+            .with_provider_factory(factory);
+        if let Some(seed) = seed {
+            runner = runner.with_seed(seed);
+        }
+        runner.connect().await?;
+        runner.warmup().await?;
+        Ok::<_, anyhow::Error>(runner)
+    };
+    let mut runner = match setup.await {
+        Ok(runner) => runner,
+        Err(e) => {
+            // Let the coordinator fail fast instead of waiting on us
+            link.send(&WorkerMessage::Failed {
+                error: e.to_string(),
+            })
+            .await?;
+            return Err(e);
+        }
+    };
+    link.send(&WorkerMessage::Ready).await?;
+    eprintln!("Worker {worker}: ready");
+
+    loop {
+        match link.recv().await? {
+            Some(CoordinatorMessage::Burst { index }) => {
+                let message = match runner.run_burst().await {
+                    Ok(metrics) => WorkerMessage::Burst {
+                        index,
+                        metrics,
+                        samples: runner.metrics().last_burst_samples().clone(),
+                    },
+                    Err(e) => WorkerMessage::Failed {
+                        error: e.to_string(),
+                    },
+                };
+                link.send(&message).await?;
+            }
+            Some(CoordinatorMessage::Done) | None => break,
+            Some(CoordinatorMessage::Assign { .. } | CoordinatorMessage::Rejected { .. }) => {
+                return Err(anyhow!("Worker {worker} was assigned twice"));
+            }
+        }
+    }
+
+    runner.disconnect().await?;
+    eprintln!("Worker {worker}: done");
+    Ok(())
+}
+
+async fn run_sweep(
+    config: qstorm_core::Config,
+    queries: &[QuerySource],
+    sweep: &qstorm_core::config::SweepConfig,
+    output: OutputFormat,
+    curve: Option<&Path>,
+) -> Result<()> {
+    eprintln!("Loading and embedding queries...");
+    let mut app = app::App::new(config)?;
+    app.load_and_embed_queries(queries).await?;
+    eprintln!("Embedded {} queries", app.query_count());
+
+    eprintln!("Connecting to provider...");
+    app.connect().await?;
+
+    eprintln!("Running warmup...");
+    app.warmup().await?;
+
+    if matches!(output, OutputFormat::Csv) {
+        println!("concurrency,qps,p50_ms,p90_ms,p99_ms,success,failure");
+    }
+
+    let mut points = Vec::with_capacity(sweep.concurrency.len());
+    for &concurrency in &sweep.concurrency {
+        eprintln!("Measuring concurrency {concurrency}...");
+        let point = app
+            .run_sweep_step(concurrency, sweep.bursts_per_step)
+            .await?;
+
+        if matches!(output, OutputFormat::Csv) {
+            println!(
+                "{},{:.2},{:.2},{:.2},{:.2},{},{}",
+                point.concurrency,
+                point.qps,
+                point.latency.p50_us as f64 / 1000.0,
+                point.latency.p90_us as f64 / 1000.0,
+                point.latency.p99_us as f64 / 1000.0,
+                point.success_count,
+                point.failure_count,
+            );
+        }
+        points.push(point);
+
+        if let Some(reason) = app.take_stop_reason() {
+            eprintln!("Ending sweep at concurrency {concurrency}: {reason}");
+            break;
+        }
+    }
+
+    let report = qstorm_core::SweepReport {
+        provider: app.provider_name().to_string(),
+        points,
+    };
+    if matches!(output, OutputFormat::Json) {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    if let Some(path) = curve {
+        qstorm_core::Curve::from_sweep(&report).save(path)?;
+        eprintln!("Wrote curve to {}", path.display());
+    }
+
+    app.disconnect().await?;
+    Ok(())
+}
+
+async fn run_capacity(
+    config: qstorm_core::Config,
+    queries: &[QuerySource],
+    capacity: qstorm_core::config::CapacityConfig,
+    output: OutputFormat,
+    curve: Option<&Path>,
+) -> Result<()> {
+    let mut search = qstorm_core::CapacitySearch::new(capacity)?;
+
+    eprintln!("Loading and embedding queries...");
+    let mut app = app::App::new(config)?;
+    app.load_and_embed_queries(queries).await?;
+    eprintln!("Embedded {} queries", app.query_count());
+
+    eprintln!("Connecting to provider...");
+    app.connect().await?;
+
+    eprintln!("Running warmup...");
+    app.warmup().await?;
+
+    if matches!(output, OutputFormat::Csv) {
+        println!("offered_qps,qps,p50_ms,p90_ms,p99_ms,error_rate,passed");
+    }
+
+    let bursts = search.config().bursts_per_step;
+    let mut steps = Vec::new();
+    while let Some(rate) = search.next_rate() {
+        eprintln!("Offering {rate:.1} QPS...");
+        let metrics = app.run_rate_step(rate, bursts).await?;
+        let step = search.record(rate, &metrics);
+
+        if matches!(output, OutputFormat::Csv) {
+            println!(
+                "{:.1},{:.2},{:.2},{:.2},{:.2},{:.4},{}",
+                step.offered_qps,
+                step.qps,
+                step.latency.p50_us as f64 / 1000.0,
+                step.latency.p90_us as f64 / 1000.0,
+                step.latency.p99_us as f64 / 1000.0,
+                step.error_rate,
+                step.passed,
+            );
+        }
+        steps.push(step);
+
+        if let Some(reason) = app.take_stop_reason() {
+            eprintln!("Ending capacity search at {rate:.1} QPS: {reason}");
+            break;
+        }
+    }
+
+    match search.max_sustainable_qps() {
+        Some(qps) => eprintln!("Max sustainable QPS: {qps:.1}"),
+        None => eprintln!("No rate tried met the SLO"),
+    }
+
+    let report = qstorm_core::CapacityReport {
+        provider: app.provider_name().to_string(),
+        p99_ms: search.config().p99_ms,
+        max_error_rate: search.config().max_error_rate,
+        max_sustainable_qps: search.max_sustainable_qps(),
+        steps,
+    };
+    if matches!(output, OutputFormat::Json) {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    if let Some(path) = curve {
+        qstorm_core::Curve::from_capacity(&report).save(path)?;
+        eprintln!("Wrote curve to {}", path.display());
+    }
+
     app.disconnect().await?;
     Ok(())
 }
 
-async fn run_tui(config: qstorm_core::Config, queries_path: &str) -> Result<()> {
+async fn embed(
+    config: qstorm_core::Config,
+    queries: Vec<QuerySource>,
+    args: EmbedArgs,
+) -> Result<()> {
+    if queries.is_empty() {
+        return Err(anyhow!("--queries is required to embed queries"));
+    }
+    if !config.vectors.is_empty() {
+        return Err(anyhow!(
+            "embed only supports the default embedding, not named vectors"
+        ));
+    }
+
+    let query_file = QueryFile::from_sources(&queries, config.seed)?;
+    if query_file.queries.is_empty() {
+        return Err(anyhow!("Query file contains no queries"));
+    }
+
+    let embedded = match query_file.into_precomputed() {
+        Ok(embedded) => {
+            eprintln!("The queries already have a vector each");
+            embedded
+        }
+        Err(query_file) => {
+            eprintln!("Embedding {} queries...", query_file.queries.len());
+            let embedder = QueryEmbedder::from_config(&config)?;
+            embedder.embed_query_file(query_file).await?
+        }
+    };
+
+    let vectors = QueryFile::save_embedded(&embedded, &args.output)?;
+    eprintln!(
+        "Wrote {} queries to {} with vectors in {}",
+        embedded.len(),
+        args.output.display(),
+        vectors.display()
+    );
+    Ok(())
+}
+
+async fn ingest(config: qstorm_core::Config, args: IngestArgs) -> Result<()> {
+    let rows = qstorm_core::ingest::read_rows(&args.corpus)
+        .map_err(|e| anyhow!("Failed to read {}: {e}", args.corpus.display()))?;
+    let options = IngestOptions {
+        fields: CorpusFields {
+            id: args.id_field,
+            text: args.text_field,
+            vector: args.vector_field,
+        },
+        batch_size: args.batch_size,
+        metric: args.metric,
+        embedding: config.embedding.clone().unwrap_or_default(),
+    };
+
+    let mut provider = app::create_provider(&config.provider)?;
+    if !provider.capabilities().upsert {
+        return Err(anyhow!(
+            "Provider '{}' doesn't support writing documents",
+            provider.name()
+        ));
+    }
+    eprintln!("Connecting to provider...");
+    provider.connect().await?;
+
+    eprintln!(
+        "Loading {} into {}...",
+        args.corpus.display(),
+        provider.name()
+    );
+    let mut last_report = Instant::now();
+    let progress = qstorm_core::ingest::ingest(provider.as_ref(), rows, &options, |progress| {
+        if progress.batches == 1 && progress.created_index {
+            eprintln!("Created the index");
+        }
+        // At most one line a second, however small the batches
+        if last_report.elapsed() >= Duration::from_secs(1) {
+            eprintln!(
+                "{} documents ({:.0} docs/s)",
+                progress.documents,
+                progress.docs_per_sec()
+            );
+            last_report = Instant::now();
+        }
+    })
+    .await?;
+
+    eprintln!(
+        "Loaded {} documents ({} embedded) in {:.1}s, {:.0} docs/s",
+        progress.documents,
+        progress.embedded,
+        progress.elapsed.as_secs_f64(),
+        progress.docs_per_sec()
+    );
+    provider.disconnect().await?;
+    Ok(())
+}
+
+async fn generate_queries(config: qstorm_core::Config, args: GenerateQueriesArgs) -> Result<()> {
+    let options = qstorm_core::GenerateOptions {
+        count: args.count,
+        text_field: args.text_field,
+        max_documents: args.max_documents,
+        with_source_ids: args.with_source_ids,
+        seed: config.seed,
+        ..Default::default()
+    };
+    let generator = qstorm_core::QueryGenerator::new(options);
+
+    #[cfg(feature = "openai-embeddings")]
+    let generator = match std::env::var("OPENAI_API_KEY") {
+        Ok(key) if !args.no_llm => {
+            eprintln!("Writing queries with {}...", args.llm_model);
+            generator.with_llm(args.llm_model, key)
+        }
+        _ => generator,
+    };
+    #[cfg(not(feature = "openai-embeddings"))]
+    let _ = (args.llm_model, args.no_llm);
+
+    eprintln!("Connecting to provider...");
+    let mut provider = app::create_provider(&config.provider)?;
+    provider.connect().await?;
+
+    eprintln!("Sampling documents...");
+    let query_file = generator.generate(provider.as_ref()).await;
+    provider.disconnect().await?;
+    let query_file = query_file?;
+
+    std::fs::write(&args.output, serde_yaml::to_string(&query_file)?)?;
+    eprintln!(
+        "Wrote {} queries to {}",
+        query_file.queries.len(),
+        args.output.display()
+    );
+    Ok(())
+}
+
+#[cfg(feature = "datasets")]
+async fn fetch_dataset(args: FetchDatasetArgs) -> Result<()> {
+    let dataset: qstorm_core::datasets::Dataset = args.dataset.parse()?;
+
+    eprintln!("Fetching {}...", dataset.name());
+    let fetched =
+        qstorm_core::datasets::fetch(&dataset, &args.dir, args.archive.as_deref()).await?;
+
+    eprintln!("Queries:      {}", fetched.queries.display());
+    eprintln!("Ground truth: {}", fetched.ground_truth.display());
+    eprintln!("Corpus:       {}", fetched.corpus.display());
+    Ok(())
+}
+
+async fn validate(
+    config_path: &Path,
+    profile: Option<&str>,
+    queries: &[QuerySource],
+    args: ValidateArgs,
+) -> Result<()> {
+    let mut report = ValidationReport::new();
+
+    let config = match qstorm_core::Config::from_file_with_profile(config_path, profile) {
+        Ok(config) => Some(config),
+        Err(e) => {
+            report.error(format!("config {}: {e}", config_path.display()));
+            None
+        }
+    };
+
+    let query_file = if queries.is_empty() {
+        report.warning("no --queries file given; query and ground-truth checks skipped");
+        None
+    } else {
+        match QueryFile::from_sources(queries, None) {
+            Ok(query_file) => {
+                report.check_queries(&query_file);
+                Some(query_file)
+            }
+            Err(e) => {
+                let sources: Vec<String> = queries.iter().map(ToString::to_string).collect();
+                report.error(format!("queries {}: {e}", sources.join(", ")));
+                None
+            }
+        }
+    };
+
+    // Precomputed query vectors don't need an embedder
+    let precomputed = query_file
+        .as_ref()
+        .is_some_and(|f| f.queries.iter().all(|q| q.vector.is_some()));
+    // Checked on the raw YAML, so models are still checked when the config
+    // fails to parse for another reason
+    if let Some(raw) = std::fs::read_to_string(config_path)
+        .ok()
+        .and_then(|text| serde_yaml::from_str(&text).ok())
+    {
+        report.check_features(&raw, precomputed);
+    }
+
+    if let Some(config) = config.as_ref().filter(|_| args.connect) {
+        let embedder = if precomputed {
+            None
+        } else {
+            eprintln!("Loading embedder...");
+            QueryEmbedder::from_config(config)
+                .map_err(|e| report.error(format!("embedding: {e}")))
+                .ok()
+        };
+
+        eprintln!("Connecting to provider...");
+        match app::create_provider(&config.provider) {
+            Ok(mut provider) => match provider.connect().await {
+                Ok(()) => {
+                    report
+                        .check_provider(provider.as_ref(), query_file.as_ref(), embedder.as_ref())
+                        .await;
+                    provider.disconnect().await?;
+                }
+                Err(e) => report.error(format!("provider '{}': {e}", config.provider.name)),
+            },
+            Err(e) => report.error(format!("provider '{}': {e}", config.provider.name)),
+        }
+    }
+
+    if config.is_some() && !args.connect {
+        eprintln!("Provider, ground truth, and dimensions not checked (pass --connect)");
+    }
+
+    for issue in &report.issues {
+        println!("{issue}");
+    }
+
+    if report.is_ok() {
+        println!("OK ({} warnings)", report.warning_count());
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "validation failed with {} errors and {} warnings",
+            report.error_count(),
+            report.warning_count()
+        ))
+    }
+}
+
+/// Settings for a TUI run
+struct TuiRun<'a> {
+    metrics_port: Option<u16>,
+    dashboard_port: Option<u16>,
+    burst_log: Option<BurstLog>,
+    auto_reconnect: bool,
+    /// Seconds of history the charts show, all of it if unset
+    chart_window: Option<Duration>,
+    /// Provider to run the same load against alongside the main one
+    rival: Option<ProviderConfig>,
+    /// Where to record the session for `qstorm replay`
+    record: Option<&'a Path>,
+}
+
+async fn run_tui(
+    config: qstorm_core::Config,
+    queries: &[QuerySource],
+    run: TuiRun<'_>,
+) -> Result<()> {
     let mut app = app::App::new(config)?;
+    if let Some(log) = run.burst_log {
+        app.set_burst_log(log);
+    }
+    app.history.window = run.chart_window;
+    if let Some(provider) = run.rival {
+        app.set_rival(provider);
+    }
 
     // Load and embed queries before starting TUI
     eprintln!("Loading and embedding queries (this may take a moment)...");
-    app.load_and_embed_queries(queries_path).await?;
+    app.load_and_embed_queries(queries).await?;
     eprintln!("Embedded {} queries. Starting TUI...", app.query_count());
+    if let Some(path) = run.record {
+        app.record_to(path)?;
+    }
+
+    if let Some(port) = run.metrics_port {
+        let exporter = serve_metrics(&app, port).await?;
+        app.set_exporter(exporter);
+    }
+    if let Some(port) = run.dashboard_port {
+        let dashboard = serve_dashboard(&app, port).await?;
+        app.set_dashboard(dashboard);
+    }
 
     let mut terminal = tui::init()?;
-    let result = tui::run(&mut terminal, app).await;
+    let result = tui::run(&mut terminal, app, run.auto_reconnect).await;
     tui::restore()?;
     result
 }
+
+fn compare(args: CompareArgs) -> Result<()> {
+    let baseline = RunReport::load(&args.baseline)
+        .map_err(|e| anyhow!("Failed to read {}: {e}", args.baseline.display()))?;
+    let candidate = RunReport::load(&args.candidate)
+        .map_err(|e| anyhow!("Failed to read {}: {e}", args.candidate.display()))?;
+    if baseline.provider != candidate.provider {
+        eprintln!(
+            "Note: comparing different providers ('{}' vs '{}')",
+            baseline.provider, candidate.provider
+        );
+    }
+
+    let thresholds = RegressionThresholds {
+        p50_increase_pct: args.max_p50_increase,
+        p99_increase_pct: args.max_p99_increase,
+        qps_decrease_pct: args.max_qps_decrease,
+        recall_decrease: args.max_recall_decrease,
+    };
+    let comparison = Comparison::new(&baseline, &candidate, &thresholds);
+
+    println!(
+        "{:<12} {:>12} {:>12} {:>9}",
+        "metric", "baseline", "candidate", "change"
+    );
+    for metric in &comparison.metrics {
+        let change = metric
+            .change_pct
+            .map_or_else(|| "-".to_string(), |c| format!("{c:+.1}%"));
+        println!(
+            "{:<12} {:>12.3} {:>12.3} {:>9}{}",
+            metric.name,
+            metric.baseline,
+            metric.candidate,
+            change,
+            if metric.regressed { "  REGRESSION" } else { "" }
+        );
+    }
+
+    if comparison.regressed() {
+        Err(Exit::check_failed(
+            "Candidate regressed against the baseline",
+        ))
+    } else {
+        eprintln!("No regressions");
+        Ok(())
+    }
+}
+
+fn replay(config_path: &Path, args: ReplayArgs) -> Result<()> {
+    if !(args.speed > 0.0 && args.speed.is_finite()) {
+        return Err(anyhow!("--speed must be a positive number"));
+    }
+    if args.chart_window == Some(0) {
+        return Err(anyhow!("--chart-window must be at least 1 second"));
+    }
+    let mut recording = session::Recording::load(&args.recording)?;
+    // Draw for this terminal rather than the one the session was recorded on
+    if config_path.exists() {
+        recording.config.tui = qstorm_core::Config::from_file(config_path)?.tui;
+    }
+    let mut app = app::App::replaying(recording.config, recording.queries)?;
+    app.history.window = args.chart_window.map(Duration::from_secs);
+
+    let mut terminal = tui::init()?;
+    let result = tui::replay(&mut terminal, app, recording.events, args.speed);
+    tui::restore()?;
+    result
+}
+
+fn report(args: ReportArgs) -> Result<()> {
+    let report = RunReport::load(&args.report)
+        .map_err(|e| anyhow!("Failed to read {}: {e}", args.report.display()))?;
+
+    let Some(html) = args.html else {
+        if args.bursts.is_some() {
+            return Err(anyhow!("--bursts only applies with --html"));
+        }
+        print_report(&report);
+        return Ok(());
+    };
+    let bursts = match &args.bursts {
+        Some(path) => {
+            let file = std::fs::File::open(path)
+                .map_err(|e| anyhow!("Failed to read {}: {e}", path.display()))?;
+            serde_json::Deserializer::from_reader(std::io::BufReader::new(file))
+                .into_iter::<qstorm_core::BurstMetrics>()
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| anyhow!("{} isn't an NDJSON bursts file: {e}", path.display()))?
+        }
+        None => Vec::new(),
+    };
+    report.save_html(&html, &bursts)?;
+    eprintln!("Wrote {}", html.display());
+    Ok(())
+}
+
+fn print_report(report: &RunReport) {
+    let ms = |us: u64| format!("{:.2} ms", us as f64 / 1000.0);
+    let mut rows = vec![
+        ("provider", report.provider.clone()),
+        ("started", report.started_at.to_rfc3339()),
+        ("duration", format!("{:.1} s", report.duration_secs)),
+        ("bursts", report.bursts.to_string()),
+        ("queries", report.total_queries.to_string()),
+        (
+            "failures",
+            format!(
+                "{} ({:.2}%, {} timeouts)",
+                report.errors.failures,
+                report.errors.error_rate * 100.0,
+                report.errors.timeouts
+            ),
+        ),
+        ("qps", format!("{:.1}", report.qps)),
+        ("p50", ms(report.latency.p50_us)),
+        ("p90", ms(report.latency.p90_us)),
+        ("p99", ms(report.latency.p99_us)),
+        ("max", ms(report.latency.max_us)),
+    ];
+    let quality = [
+        ("recall_at_k", &report.recall_at_k),
+        ("ndcg_at_k", &report.ndcg_at_k),
+        ("mrr", &report.mrr),
+    ];
+    for (name, stats) in quality {
+        if let Some(stats) = stats {
+            rows.push((
+                name,
+                format!(
+                    "{:.3} (min {:.3}, max {:.3})",
+                    stats.mean, stats.min, stats.max
+                ),
+            ));
+        }
+    }
+    if let Some(reason) = &report.stop_reason {
+        rows.push(("stopped", reason.clone()));
+    }
+    if !report.assertions.is_empty() {
+        let passed = report.assertions.iter().filter(|r| r.passed).count();
+        rows.push((
+            "assertions",
+            format!("{passed} of {} passed", report.assertions.len()),
+        ));
+    }
+    for (name, value) in rows {
+        println!("{name:<12} {value}");
+    }
+}
+
+/// Commit checked out in the working directory, if it's a git repository
+fn git_commit() -> Option<String> {
+    let output = std::process::Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Start the Prometheus endpoint on every interface at `port`
+async fn serve_metrics(app: &app::App, port: u16) -> Result<PrometheusExporter> {
+    PrometheusExporter::bind(("0.0.0.0", port), app.provider_name())
+        .await
+        .map_err(|e| anyhow!("Failed to serve metrics on port {port}: {e}"))
+}
+
+async fn serve_dashboard(app: &app::App, port: u16) -> Result<Dashboard> {
+    Dashboard::bind(("0.0.0.0", port), app.provider_name())
+        .await
+        .map_err(|e| anyhow!("Failed to serve the dashboard on port {port}: {e}"))
+}
@@ -0,0 +1,154 @@
+//! Status line for headless runs.
+//!
+//! Headless results go to stdout for other tools to read, so the run's status
+//! goes to stderr: redrawn in place when stderr is a terminal, or written as a
+//! plain line every few seconds when it's redirected to a log.
+
+use std::io::{IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+use qstorm_core::BurstMetrics;
+
+/// How often a plain status line is written when stderr isn't a terminal
+const LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+const BAR_WIDTH: usize = 24;
+
+/// How a run is going: elapsed time, bursts, QPS, p99, and errors, plus a
+/// progress bar and ETA when the run has a burst count or duration to
+/// measure against.
+pub struct Progress {
+    /// False with the status line turned off
+    visible: bool,
+    total_bursts: Option<usize>,
+    duration: Option<Duration>,
+    started: Instant,
+    bursts: usize,
+    /// Failed queries across the run so far
+    errors: usize,
+    terminal: bool,
+    /// The line currently drawn on the terminal, if any
+    drawn: Option<String>,
+    logged: Option<Instant>,
+}
+
+impl Progress {
+    /// Progress towards `total_bursts` bursts or `duration`, whichever comes
+    /// first, shown only if `visible`
+    pub fn new(total_bursts: Option<usize>, duration: Option<Duration>, visible: bool) -> Self {
+        Self {
+            visible,
+            total_bursts,
+            duration,
+            started: Instant::now(),
+            bursts: 0,
+            errors: 0,
+            terminal: std::io::stderr().is_terminal(),
+            drawn: None,
+            logged: None,
+        }
+    }
+
+    /// Count a finished burst and show the run's status
+    pub fn update(&mut self, burst: &BurstMetrics) {
+        self.bursts += 1;
+        self.errors += burst.failure_count;
+        if !self.visible {
+            return;
+        }
+        let line = self.line(burst);
+        if self.terminal {
+            eprint!("\r\x1b[2K{line}");
+            let _ = std::io::stderr().flush();
+            self.drawn = Some(line);
+        } else if self.logged.is_none_or(|at| at.elapsed() >= LOG_INTERVAL) {
+            eprintln!("{line}");
+            self.logged = Some(Instant::now());
+        }
+    }
+
+    /// Run `print` with the status line out of the way, then redraw it, so
+    /// other output on the same terminal isn't written over the bar
+    pub fn suspend<T>(&self, print: impl FnOnce() -> T) -> T {
+        if self.drawn.is_some() {
+            eprint!("\r\x1b[2K");
+        }
+        let printed = print();
+        if let Some(line) = &self.drawn {
+            eprint!("{line}");
+            let _ = std::io::stderr().flush();
+        }
+        printed
+    }
+
+    /// Leave the last status line in place and move past it
+    pub fn finish(&mut self) {
+        if self.drawn.take().is_some() {
+            eprintln!();
+        }
+    }
+
+    fn line(&self, burst: &BurstMetrics) -> String {
+        let elapsed = self.started.elapsed();
+        let by_bursts = self
+            .total_bursts
+            .map(|total| (self.bursts as f64 / total as f64, total));
+        let by_time = self
+            .duration
+            .map(|duration| elapsed.as_secs_f64() / duration.as_secs_f64());
+
+        let mut line = String::new();
+        let mut eta = None;
+        if by_bursts.is_some() || by_time.is_some() {
+            // The run ends at whichever limit it reaches first
+            let fraction = by_bursts
+                .map(|(fraction, _)| fraction)
+                .into_iter()
+                .chain(by_time)
+                .fold(0.0, f64::max)
+                .min(1.0);
+            let filled = (fraction * BAR_WIDTH as f64).round() as usize;
+            line.push_str(&format!(
+                "[{}{}] {:>3.0}%  ",
+                "#".repeat(filled),
+                "-".repeat(BAR_WIDTH - filled),
+                fraction * 100.0
+            ));
+            if fraction > 0.0 {
+                eta = Some(elapsed.mul_f64((1.0 - fraction) / fraction));
+            }
+        }
+
+        match by_bursts {
+            Some((_, total)) => line.push_str(&format!("{}/{total} bursts", self.bursts)),
+            None => line.push_str(&format!("{} bursts", self.bursts)),
+        }
+        line.push_str(&format!("  {} elapsed", format_duration(elapsed)));
+        if let Some(eta) = eta {
+            line.push_str(&format!("  ETA {}", format_duration(eta)));
+        }
+
+        // The shortest rolling window when configured, the burst itself otherwise
+        let (p99_us, label) = match burst.windows.iter().min_by_key(|w| w.window_secs) {
+            Some(window) => (window.latency.p99_us, format!(" ({}s)", window.window_secs)),
+            None => (burst.latency.p99_us, String::new()),
+        };
+        line.push_str(&format!(
+            "  {:.1} qps  p99{label} {:.1} ms  {} errors",
+            burst.qps,
+            p99_us as f64 / 1000.0,
+            self.errors
+        ));
+        line
+    }
+}
+
+/// `1h02m`, `3m05s`, or `42s`
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{s}s"),
+        (0, m, s) => format!("{m}m{s:02}s"),
+        (h, m, _) => format!("{h}h{m:02}m"),
+    }
+}
@@ -0,0 +1,447 @@
+//! `qstorm serve`: runs started, steered, and read over a small REST API.
+//!
+//! One task answers HTTP requests; the loop in [`serve`] owns the app and
+//! runs bursts. Reads (`GET /status`, `GET /metrics`) are answered at once
+//! from what the loop last published. Control requests are handed to the
+//! loop, which applies them between bursts and answers with the new status,
+//! so they take effect at the end of the burst in progress.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use qstorm_core::config::LoadConfig;
+use qstorm_core::http::{self, Request};
+use qstorm_core::{Notification, RunReport};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::error::TryRecvError;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, info};
+
+use crate::app::App;
+
+/// Largest request, head and body together, read before answering
+const MAX_REQUEST_BYTES: usize = 64 * 1024;
+
+/// How often an idle server checks whether it was interrupted
+const IDLE_POLL: Duration = Duration::from_millis(200);
+
+/// Where the current run is
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum ServeState {
+    /// No run started yet
+    Idle,
+    /// Connecting and warming up
+    Starting,
+    Running,
+    Paused,
+    /// Stopped, or ended on its own; its metrics stay readable
+    Finished,
+    /// Ended by an error
+    Failed,
+}
+
+/// A change asked for over the API
+enum Control {
+    Start(StartRequest),
+    Pause,
+    Resume,
+    Stop,
+    Load(LoadChange),
+}
+
+/// Body of `POST /start`
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StartRequest {
+    /// Finish after this many bursts (run until stopped if unset)
+    bursts: Option<usize>,
+}
+
+/// Body of `POST /load`; unset fields keep their value
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LoadChange {
+    concurrency: Option<usize>,
+    burst_size: Option<usize>,
+    top_k: Option<usize>,
+    load: Option<LoadConfig>,
+}
+
+impl LoadChange {
+    fn validate(&self) -> std::result::Result<(), String> {
+        for (name, value) in [
+            ("concurrency", self.concurrency),
+            ("burst_size", self.burst_size),
+            ("top_k", self.top_k),
+        ] {
+            if value == Some(0) {
+                return Err(format!("{name} must be at least 1"));
+            }
+        }
+        if let Some(LoadConfig::ConstantRate { qps, .. }) = self.load
+            && !(qps.is_finite() && qps > 0.0)
+        {
+            return Err(format!("load.qps must be a positive number, got {qps}"));
+        }
+        Ok(())
+    }
+}
+
+/// An HTTP status and JSON body
+struct Response {
+    status: u16,
+    body: Value,
+}
+
+impl Response {
+    fn ok(body: Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: json!({ "error": message.into() }),
+        }
+    }
+}
+
+/// What reads are answered from, rewritten by the loop as the run moves on
+#[derive(Default)]
+struct Published {
+    status: Value,
+    metrics: Value,
+}
+
+type Requests = mpsc::Sender<(Control, oneshot::Sender<Response>)>;
+
+/// Serve the API on `listen` until interrupted, running benchmarks with
+/// `app`'s config and queries as they're asked for
+pub async fn serve(app: App, listen: &str) -> Result<()> {
+    let listener = TcpListener::bind(listen)
+        .await
+        .map_err(|e| anyhow!("Failed to listen on {listen}: {e}"))?;
+    eprintln!(
+        "Serving the control API at http://{}",
+        listener.local_addr()?
+    );
+
+    let published = Arc::new(Mutex::new(Published::default()));
+    let (requests, mut controls) = mpsc::channel(16);
+    let server = tokio::spawn(accept(listener, Arc::clone(&published), requests));
+
+    let mut controller = Controller {
+        app,
+        state: ServeState::Idle,
+        started_at: None,
+        remaining: None,
+        stop_reason: None,
+        error: None,
+        published,
+    };
+    controller.publish();
+
+    let interrupted = crate::shutdown_flag();
+    while !interrupted.load(std::sync::atomic::Ordering::Relaxed) {
+        let next = if controller.state == ServeState::Running {
+            match controls.try_recv() {
+                Ok(next) => Some(next),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => break,
+            }
+        } else {
+            match tokio::time::timeout(IDLE_POLL, controls.recv()).await {
+                Ok(Some(next)) => Some(next),
+                Ok(None) => break,
+                Err(_) => continue,
+            }
+        };
+        match next {
+            Some((control, reply)) => {
+                let response = controller.handle(control).await;
+                // The client may have hung up while the burst finished
+                let _ = reply.send(response);
+            }
+            None => controller.run_burst().await,
+        }
+        controller.publish();
+    }
+
+    server.abort();
+    if controller.app.has_runner() {
+        controller.app.disconnect().await?;
+    }
+    Ok(())
+}
+
+/// Owns the app and moves the run between states
+struct Controller {
+    app: App,
+    state: ServeState,
+    started_at: Option<DateTime<Utc>>,
+    /// Bursts left before the run finishes on its own
+    remaining: Option<usize>,
+    /// Why the run ended on its own
+    stop_reason: Option<String>,
+    /// What failed the run
+    error: Option<String>,
+    published: Arc<Mutex<Published>>,
+}
+
+impl Controller {
+    async fn handle(&mut self, control: Control) -> Response {
+        match control {
+            Control::Start(request) => {
+                if matches!(self.state, ServeState::Running | ServeState::Paused) {
+                    return Response::error(409, "A run is already in progress; stop it first");
+                }
+                if request.bursts == Some(0) {
+                    return Response::error(400, "bursts must be at least 1");
+                }
+                self.state = ServeState::Starting;
+                self.stop_reason = None;
+                self.error = None;
+                self.publish();
+                match self.start().await {
+                    Ok(()) => {
+                        info!("Run started");
+                        self.state = ServeState::Running;
+                        self.started_at = Some(Utc::now());
+                        self.remaining = request.bursts;
+                    }
+                    Err(e) => self.fail(e),
+                }
+            }
+            Control::Pause => {
+                if self.state != ServeState::Running {
+                    return Response::error(409, "No run is in progress");
+                }
+                self.state = ServeState::Paused;
+            }
+            Control::Resume => {
+                if self.state != ServeState::Paused {
+                    return Response::error(409, "The run isn't paused");
+                }
+                self.state = ServeState::Running;
+            }
+            Control::Stop => {
+                if !matches!(self.state, ServeState::Running | ServeState::Paused) {
+                    return Response::error(409, "No run is in progress");
+                }
+                info!("Run stopped");
+                self.state = ServeState::Finished;
+                self.notify_end(None);
+            }
+            Control::Load(change) => {
+                if let Err(message) = change.validate() {
+                    return Response::error(400, message);
+                }
+                self.app.change_load(|benchmark| {
+                    if let Some(concurrency) = change.concurrency {
+                        benchmark.concurrency = concurrency;
+                    }
+                    if let Some(burst_size) = change.burst_size {
+                        benchmark.burst_size = burst_size;
+                    }
+                    if let Some(top_k) = change.top_k {
+                        benchmark.top_k = top_k;
+                    }
+                    if let Some(load) = change.load {
+                        benchmark.load = load;
+                    }
+                });
+            }
+        }
+        Response::ok(self.status())
+    }
+
+    /// Connect a fresh runner, so the run's metrics start from nothing, and
+    /// warm it up
+    async fn start(&mut self) -> Result<()> {
+        if self.app.has_runner() {
+            self.app.disconnect().await?;
+        }
+        self.app.connect().await?;
+        self.app.warmup().await
+    }
+
+    async fn run_burst(&mut self) {
+        if let Err(e) = self.app.run_burst().await {
+            self.fail(e);
+            return;
+        }
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= 1;
+        }
+
+        let stop_condition = self.app.take_stop_reason();
+        let reason = if let Some(reason) = &stop_condition {
+            Some(reason.clone())
+        } else if self.app.take_scenario_complete() {
+            Some("scenario complete".to_string())
+        } else if self.app.budget_exhausted() {
+            Some("query budget reached".to_string())
+        } else if self.remaining == Some(0) {
+            Some("burst count reached".to_string())
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            info!(%reason, "Run finished");
+            self.state = ServeState::Finished;
+            self.stop_reason = Some(reason);
+            self.notify_end(stop_condition);
+        }
+    }
+
+    fn fail(&mut self, error: anyhow::Error) {
+        tracing::error!("Run failed: {error}");
+        let error = error.to_string();
+        let report = self.report();
+        tokio::spawn(self.app.notify(Notification::Failed {
+            provider: self.app.provider_name(),
+            error: &error,
+            report: report.as_ref(),
+        }));
+        self.state = ServeState::Failed;
+        self.error = Some(error);
+    }
+
+    /// The run so far, if it started and finished a burst
+    fn report(&self) -> Option<RunReport> {
+        self.app.report(self.started_at?)
+    }
+
+    /// Post, in the background, that the run ended; `stop_condition` is set
+    /// when a stop condition ended it
+    fn notify_end(&self, stop_condition: Option<String>) {
+        let Some(mut report) = self.report() else {
+            return;
+        };
+        let notification = match stop_condition {
+            Some(reason) => {
+                report.stop_reason = Some(reason);
+                Notification::Stopped(&report)
+            }
+            None => Notification::Finished(&report),
+        };
+        tokio::spawn(self.app.notify(notification));
+    }
+
+    fn status(&self) -> Value {
+        let benchmark = self.app.benchmark();
+        json!({
+            "state": self.state,
+            "provider": self.app.provider_name(),
+            "queries": self.app.query_count(),
+            "bursts": self.app.metrics().map_or(0, |metrics| metrics.bursts().len()),
+            "remaining_bursts": self.remaining,
+            "started_at": self.started_at,
+            "load": {
+                "concurrency": benchmark.concurrency,
+                "burst_size": benchmark.burst_size,
+                "top_k": benchmark.top_k,
+                "load": benchmark.load,
+            },
+            "stop_reason": self.stop_reason,
+            "error": self.error,
+        })
+    }
+
+    /// Rewrite what reads are answered from
+    fn publish(&self) {
+        let metrics = match (self.app.metrics(), self.started_at) {
+            (Some(metrics), Some(started_at)) if !metrics.bursts().is_empty() => json!({
+                "summary": RunReport::new(&self.app.config, metrics, started_at),
+                "last_burst": metrics.last_burst(),
+            }),
+            _ => json!({ "summary": null, "last_burst": null }),
+        };
+        let mut published = self.published.lock().unwrap_or_else(|e| e.into_inner());
+        published.status = self.status();
+        published.metrics = metrics;
+    }
+}
+
+async fn accept(listener: TcpListener, published: Arc<Mutex<Published>>, requests: Requests) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                debug!("Control API accept failed: {e}");
+                continue;
+            }
+        };
+        let published = Arc::clone(&published);
+        let requests = requests.clone();
+        tokio::spawn(async move {
+            if let Err(e) = respond(stream, &published, &requests).await {
+                debug!(%peer, "Control API request failed: {e}");
+            }
+        });
+    }
+}
+
+/// Answer a single HTTP/1.1 request and close the connection
+async fn respond(
+    mut stream: TcpStream,
+    published: &Mutex<Published>,
+    requests: &Requests,
+) -> std::io::Result<()> {
+    let response = match http::read_request(&mut stream, MAX_REQUEST_BYTES).await? {
+        Some(Request { method, path, body }) => {
+            route(&method, &path, &body, published, requests).await
+        }
+        None => Response::error(400, "Malformed request"),
+    };
+    let body = response.body.to_string();
+    http::respond(&mut stream, response.status, "application/json", &body).await
+}
+
+async fn route(
+    method: &str,
+    path: &str,
+    body: &[u8],
+    published: &Mutex<Published>,
+    requests: &Requests,
+) -> Response {
+    let read = |pick: fn(&Published) -> &Value| {
+        let published = published.lock().unwrap_or_else(|e| e.into_inner());
+        Response::ok(pick(&published).clone())
+    };
+    let control = match (method, path) {
+        ("GET", "/status") => return read(|published| &published.status),
+        ("GET", "/metrics") => return read(|published| &published.metrics),
+        ("POST", "/start") if body.iter().all(u8::is_ascii_whitespace) => {
+            Control::Start(StartRequest::default())
+        }
+        ("POST", "/start") => match serde_json::from_slice(body) {
+            Ok(request) => Control::Start(request),
+            Err(e) => return Response::error(400, format!("Invalid start request: {e}")),
+        },
+        ("POST", "/pause") => Control::Pause,
+        ("POST", "/resume") => Control::Resume,
+        ("POST", "/stop") => Control::Stop,
+        ("POST", "/load") => match serde_json::from_slice(body) {
+            Ok(change) => Control::Load(change),
+            Err(e) => return Response::error(400, format!("Invalid load change: {e}")),
+        },
+        (_, "/status" | "/metrics" | "/start" | "/pause" | "/resume" | "/stop" | "/load") => {
+            return Response::error(405, "Method not allowed");
+        }
+        _ => return Response::error(404, "Not found"),
+    };
+
+    let (reply, response) = oneshot::channel();
+    if requests.send((control, reply)).await.is_err() {
+        return Response::error(503, "Shutting down");
+    }
+    response
+        .await
+        .unwrap_or_else(|_| Response::error(503, "Shutting down"))
+}
@@ -0,0 +1,110 @@
+//! TUI session recordings.
+//!
+//! `--record` writes what the dashboard showed as NDJSON: a `start` line
+//! with the (redacted) config, then a line per burst and per settings change
+//! as they happened. `qstorm replay` plays a recording back in the TUI
+//! without connecting to anything.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use anyhow::{Result, anyhow};
+use qstorm_core::{BurstMetrics, Config};
+use serde::{Deserialize, Serialize};
+
+use crate::app::LoadSettings;
+
+/// One line of a recording
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum SessionEvent {
+    /// First line: what was benchmarked, with secrets removed
+    Start { config: Box<Config>, queries: usize },
+    /// A finished burst
+    Burst(Box<BurstMetrics>),
+    /// Load settings changed from the keyboard, from the next burst on
+    Tuned {
+        note: String,
+        settings: LoadSettings,
+    },
+    /// Warmup run again from the keyboard before the next burst
+    Warmup,
+}
+
+/// Appends a session's events to a recording as they happen
+pub struct SessionRecorder {
+    writer: BufWriter<File>,
+}
+
+impl SessionRecorder {
+    /// Create (or truncate) the recording at `path`, starting with `config`
+    /// and the number of queries loaded
+    pub fn create(path: &Path, config: &Config, queries: usize) -> Result<Self> {
+        let file =
+            File::create(path).map_err(|e| anyhow!("Failed to create {}: {e}", path.display()))?;
+        let mut recorder = Self {
+            writer: BufWriter::new(file),
+        };
+        recorder.record(&SessionEvent::Start {
+            config: Box::new(config.redacted()),
+            queries,
+        })?;
+        Ok(recorder)
+    }
+
+    /// Append an event, flushed so a crashed session still leaves a
+    /// recording behind
+    pub fn record(&mut self, event: &SessionEvent) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, event)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// A recording read back: the config and query count it started with, and
+/// everything that happened after
+pub struct Recording {
+    pub config: Config,
+    pub queries: usize,
+    pub events: Vec<SessionEvent>,
+}
+
+impl Recording {
+    pub fn load(path: &Path) -> Result<Self> {
+        let file =
+            File::open(path).map_err(|e| anyhow!("Failed to read {}: {e}", path.display()))?;
+        let mut lines = BufReader::new(file).lines();
+        let invalid = |line: usize, e: &dyn std::fmt::Display| {
+            anyhow!("{}:{line} isn't a qstorm recording: {e}", path.display())
+        };
+
+        let first = lines
+            .next()
+            .ok_or_else(|| anyhow!("{} is empty", path.display()))??;
+        let Ok(SessionEvent::Start { config, queries }) = serde_json::from_str(&first) else {
+            return Err(invalid(1, &"expected a start line"));
+        };
+
+        let mut events = Vec::new();
+        for (i, line) in lines.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            // A session cut short may end in a partial line
+            match serde_json::from_str(&line) {
+                Ok(event) => events.push(event),
+                Err(e) if e.is_eof() => break,
+                Err(e) => return Err(invalid(i + 2, &e)),
+            }
+        }
+
+        Ok(Self {
+            config: *config,
+            queries,
+            events,
+        })
+    }
+}
@@ -0,0 +1,168 @@
+//! TUI colors and drawing characters.
+//!
+//! The config's `tui` section picks a color scheme for dark or light
+//! terminals, or none at all, and can restrict drawing to ASCII for terminals
+//! and recordings that mangle box-drawing and Braille characters.
+
+use qstorm_core::config::{TuiConfig, TuiTheme};
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::symbols::Marker;
+
+/// Colors by what they mark, so each scheme can pick ones that read well on
+/// its background
+pub struct Theme {
+    /// Titles, table headers, and the provider name
+    pub accent: Color,
+    /// Notices, typed input, query text, and settings changes in the charts
+    pub highlight: Color,
+    /// Secondary text and axes
+    pub muted: Color,
+    /// Plain text that still needs a color, such as the IDLE state
+    pub text: Color,
+    pub good: Color,
+    pub bad: Color,
+    /// SLO breaches marked in the charts
+    pub alert: Color,
+    /// The `--versus` provider
+    pub rival: Color,
+    /// More line colors, for charts with several lines
+    pub secondary: Color,
+    pub tertiary: Color,
+    /// The selected row in a table
+    pub selected: Style,
+    /// The SLO banner, flashing between the two
+    pub banner: [Style; 2],
+    /// How chart lines are drawn
+    pub marker: Marker,
+    /// Replace everything outside ASCII once the frame is drawn
+    pub ascii: bool,
+}
+
+impl Theme {
+    pub fn new(config: &TuiConfig) -> Self {
+        let scheme = config.theme.unwrap_or_else(|| {
+            // https://no-color.org: set and not empty
+            if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+                TuiTheme::Mono
+            } else {
+                TuiTheme::Dark
+            }
+        });
+        let mut theme = match scheme {
+            TuiTheme::Dark => Self::dark(),
+            TuiTheme::Light => Self::light(),
+            TuiTheme::Mono => Self::mono(),
+        };
+        if config.ascii {
+            // One point per cell, so each maps to a single ASCII character
+            theme.marker = Marker::Dot;
+            theme.ascii = true;
+        }
+        theme
+    }
+
+    fn dark() -> Self {
+        Self {
+            accent: Color::Cyan,
+            highlight: Color::Yellow,
+            muted: Color::DarkGray,
+            text: Color::White,
+            good: Color::Green,
+            bad: Color::Red,
+            alert: Color::LightRed,
+            rival: Color::LightMagenta,
+            secondary: Color::Magenta,
+            tertiary: Color::Blue,
+            selected: Style::default().bg(Color::DarkGray),
+            banner: [
+                Style::default().fg(Color::White).bg(Color::Red).bold(),
+                Style::default().fg(Color::Red).bg(Color::Black).bold(),
+            ],
+            marker: Marker::Braille,
+            ascii: false,
+        }
+    }
+
+    /// 256-color shades dark enough to read on white; the basic ANSI yellow
+    /// and cyan vanish there
+    fn light() -> Self {
+        Self {
+            accent: Color::Indexed(25),
+            highlight: Color::Indexed(130),
+            muted: Color::Indexed(244),
+            text: Color::Reset,
+            good: Color::Indexed(28),
+            bad: Color::Indexed(160),
+            alert: Color::Indexed(198),
+            rival: Color::Indexed(127),
+            secondary: Color::Indexed(91),
+            tertiary: Color::Indexed(31),
+            selected: Style::default().bg(Color::Indexed(252)),
+            banner: [
+                Style::default()
+                    .fg(Color::White)
+                    .bg(Color::Indexed(160))
+                    .bold(),
+                Style::default()
+                    .fg(Color::Indexed(160))
+                    .bg(Color::Indexed(255))
+                    .bold(),
+            ],
+            marker: Marker::Braille,
+            ascii: false,
+        }
+    }
+
+    fn mono() -> Self {
+        Self {
+            accent: Color::Reset,
+            highlight: Color::Reset,
+            muted: Color::Reset,
+            text: Color::Reset,
+            good: Color::Reset,
+            bad: Color::Reset,
+            alert: Color::Reset,
+            rival: Color::Reset,
+            secondary: Color::Reset,
+            tertiary: Color::Reset,
+            selected: Style::default().add_modifier(Modifier::REVERSED),
+            banner: [
+                Style::default().add_modifier(Modifier::REVERSED).bold(),
+                Style::default().bold(),
+            ],
+            marker: Marker::Braille,
+            ascii: false,
+        }
+    }
+}
+
+/// Swap every character outside ASCII in a drawn frame for the nearest ASCII
+/// one: `-`, `|`, and `+` for lines and corners, `*` for chart points, and
+/// `#` for filled blocks
+pub fn asciify(buffer: &mut Buffer) {
+    for cell in &mut buffer.content {
+        let Some(c) = cell.symbol().chars().next() else {
+            continue;
+        };
+        if !c.is_ascii() {
+            cell.set_symbol(ascii_for(c));
+        }
+    }
+}
+
+fn ascii_for(c: char) -> &'static str {
+    match c {
+        '─' | '━' | '═' | '┄' | '┅' | '┈' | '┉' | '╌' | '╍' => "-",
+        '│' | '┃' | '║' | '┆' | '┇' | '┊' | '┋' | '╎' | '╏' => "|",
+        // The rest of the box-drawing block: corners, tees, and crossings
+        '\u{2500}'..='\u{257F}' => "+",
+        // An empty Braille cell
+        '\u{2800}' => " ",
+        '\u{2801}'..='\u{28FF}' | '•' => "*",
+        '\u{2580}'..='\u{259F}' => "#",
+        '→' => ">",
+        '…' => ".",
+        _ => "?",
+    }
+}
@@ -1,36 +1,49 @@
-use std::io::{Stdout, stdout};
-use std::time::Duration;
+use std::io::{Stdout, Write, stdout};
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use crossterm::{
     event::{self, Event, KeyCode, KeyEventKind},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
-use qstorm_core::{BurstMetrics, runner::BenchmarkRunner};
+use qstorm_core::{BurstMetrics, Notification, runner::BenchmarkRunner};
 use ratatui::prelude::*;
 use tokio::sync::oneshot;
 
-use crate::app::{App, AppState, View};
-use crate::ui;
+use crate::app::{App, AppState, Tunable, View};
+use crate::session::SessionEvent;
+use crate::{logs, ui};
 
 pub type Tui = Terminal<CrosstermBackend<Stdout>>;
 
+/// A runner handed back from a burst, with how the burst went
+type Burst = (BenchmarkRunner, std::result::Result<BurstMetrics, qstorm_core::Error>);
+
+/// Wait before the first automatic reconnect, doubled after each failed attempt
+const RECONNECT_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(30);
+
 pub fn init() -> Result<Tui> {
     execute!(stdout(), EnterAlternateScreen)?;
     enable_raw_mode()?;
+    logs::capture(true);
     let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
     terminal.clear()?;
     Ok(terminal)
 }
 
 pub fn restore() -> Result<()> {
+    logs::capture(false);
     execute!(stdout(), LeaveAlternateScreen)?;
     disable_raw_mode()?;
     Ok(())
 }
 
-pub async fn run(terminal: &mut Tui, mut app: App) -> Result<()> {
+/// Run the TUI until the user quits. With `auto_reconnect`, a provider error
+/// is followed by reconnect attempts with exponential backoff.
+pub async fn run(terminal: &mut Tui, mut app: App, auto_reconnect: bool) -> Result<()> {
     // Initial connection (blocking is fine — TUI hasn't started yet)
     app.connect().await?;
     app.warmup().await?;
@@ -39,129 +52,430 @@ pub async fn run(terminal: &mut Tui, mut app: App) -> Result<()> {
     let burst_interval = Duration::from_secs(1);
     let mut last_burst = std::time::Instant::now();
 
-    // In-flight burst: runner is temporarily taken out of App
-    let mut burst_rx: Option<
-        oneshot::Receiver<(BenchmarkRunner, std::result::Result<BurstMetrics, qstorm_core::Error>)>,
-    > = None;
+    // In-flight burst: runner is temporarily taken out of App, along with
+    // the `--versus` runner if there is one
+    let mut burst_rx: Option<oneshot::Receiver<(Burst, Option<Burst>)>> = None;
+    // Whether the `--versus` provider takes its turn first next burst
+    let mut rival_first = false;
+
+    // A burst asked for with `b` while paused, and whether the one in flight
+    // was one, so the run pauses again once it's in
+    let mut burst_requested = false;
+    let mut single_burst = false;
+
+    // Automatic reconnects since the last good burst, and when to try next
+    let mut reconnect_attempts = 0;
+    let mut next_reconnect: Option<Instant> = None;
 
     loop {
+        app.poll_runner_events();
+        match app.take_health_change() {
+            Some((false, reason)) => tracing::warn!(
+                "Provider unreachable: {}",
+                reason.as_deref().unwrap_or("unknown reason")
+            ),
+            Some((true, _)) => tracing::warn!("Provider reachable again"),
+            None => {}
+        }
         terminal.draw(|frame| ui::render(frame, &app))?;
+        if app.take_bell() {
+            // BEL, for a breach that just started
+            stdout().write_all(b"\x07")?;
+            stdout().flush()?;
+        }
+
+        if app.state == AppState::Error && burst_rx.is_none() && auto_reconnect {
+            match next_reconnect {
+                None => {
+                    let backoff = RECONNECT_BACKOFF
+                        .saturating_mul(1 << reconnect_attempts.min(16))
+                        .min(MAX_RECONNECT_BACKOFF);
+                    next_reconnect = Some(Instant::now() + backoff);
+                    app.reconnect_at = next_reconnect;
+                }
+                Some(at) if Instant::now() >= at => {
+                    next_reconnect = None;
+                    app.reconnect_at = None;
+                    reconnect(terminal, &mut app).await;
+                    reconnect_attempts += 1;
+                }
+                Some(_) => {}
+            }
+        }
 
         // Poll for completed burst (non-blocking)
         if let Some(rx) = &mut burst_rx {
             match rx.try_recv() {
-                Ok((runner, result)) => {
+                Ok(((runner, result), rival)) => {
                     app.put_runner(runner);
                     burst_rx = None;
                     match result {
                         Ok(metrics) => {
-                            app.history.push(metrics);
-                            // Don't override Paused state
-                            if app.state != AppState::Paused {
+                            reconnect_attempts = 0;
+                            if let Err(e) = app.record_burst(metrics) {
+                                tracing::error!("Failed to write burst: {}", e);
+                                app.fail(format!("Failed to write burst: {e}"));
+                            } else if let Some(reason) = app.take_stop_reason() {
+                                // Pause rather than exit so the charts stay visible
+                                app.state = AppState::Paused;
+                                app.notify_stopped(&reason);
+                                app.stop_reason = Some(reason);
+                            } else if app.budget_exhausted() {
+                                app.state = AppState::Paused;
+                                app.stop_reason = Some("query budget reached".into());
+                            } else if app.take_scenario_complete() {
+                                // Resuming keeps running with the last phase's settings
+                                app.state = AppState::Paused;
+                                app.stop_reason = Some("scenario complete".into());
+                            } else if single_burst {
+                                app.state = AppState::Paused;
+                            } else if app.state != AppState::Paused {
+                                // Don't override Paused state
                                 app.state = AppState::Idle;
                             }
                         }
                         Err(e) => {
                             tracing::error!("Burst failed: {}", e);
-                            app.state = AppState::Error;
+                            app.fail(e);
                         }
                     }
+                    if let Some((runner, result)) = rival
+                        && let Err(e) = app.record_rival_burst(runner, result)
+                    {
+                        tracing::error!("Burst failed: {}", e);
+                        app.fail(e);
+                    }
                 }
                 Err(oneshot::error::TryRecvError::Empty) => {}
                 Err(oneshot::error::TryRecvError::Closed) => {
                     tracing::error!("Burst task dropped without completing");
-                    app.state = AppState::Error;
+                    app.fail("Burst task dropped without completing");
                     burst_rx = None;
                 }
             }
         }
 
+        app.write_pending_snapshot();
+
         // Handle input with timeout
-        if event::poll(tick_rate)? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    if app.editing {
-                        match key.code {
-                            KeyCode::Enter => {
-                                if app.has_runner() {
-                                    let _ = app.submit_query().await;
-                                }
-                            }
-                            KeyCode::Esc => {
-                                app.cancel_editing();
-                            }
-                            KeyCode::Backspace => {
-                                app.query_input.pop();
-                            }
-                            KeyCode::Char(c) => {
-                                app.query_input.push(c);
+        if event::poll(tick_rate)?
+            && let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            if app.editing {
+                match key.code {
+                    KeyCode::Enter if app.has_runner() => {
+                        let _ = app.submit_query().await;
+                    }
+                    KeyCode::Esc => {
+                        app.cancel_editing();
+                    }
+                    KeyCode::Backspace => {
+                        app.query_input.pop();
+                    }
+                    KeyCode::Char(c) => {
+                        app.query_input.push(c);
+                    }
+                    _ => {}
+                }
+            } else if app.picker.is_some() {
+                match key.code {
+                    KeyCode::Enter if app.has_runner() => {
+                        let _ = app.submit_picker().await;
+                    }
+                    KeyCode::Esc => {
+                        app.close_picker();
+                    }
+                    KeyCode::Up => {
+                        app.move_picker(-1);
+                    }
+                    KeyCode::Down => {
+                        app.move_picker(1);
+                    }
+                    KeyCode::PageUp => {
+                        app.move_picker(-10);
+                    }
+                    KeyCode::PageDown => {
+                        app.move_picker(10);
+                    }
+                    KeyCode::Backspace => {
+                        app.edit_picker_filter(None);
+                    }
+                    KeyCode::Char(c) => {
+                        app.edit_picker_filter(Some(c));
+                    }
+                    _ => {}
+                }
+            } else {
+                let paused_between_bursts =
+                    app.state == AppState::Paused && burst_rx.is_none() && app.has_runner();
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => {
+                        // Wait for in-flight burst before disconnecting
+                        if let Some(rx) = burst_rx.take()
+                            && let Ok(Ok(((runner, _), rival))) =
+                                tokio::time::timeout(Duration::from_secs(2), rx).await
+                        {
+                            app.put_runner(runner);
+                            if let Some((runner, result)) = rival {
+                                let _ = app.record_rival_burst(runner, result);
                             }
-                            _ => {}
                         }
-                    } else {
-                        match key.code {
-                            KeyCode::Char('q') | KeyCode::Esc => {
-                                // Wait for in-flight burst before disconnecting
-                                if let Some(rx) = burst_rx.take() {
-                                    if let Ok(Ok((runner, _))) =
-                                        tokio::time::timeout(Duration::from_secs(2), rx).await
-                                    {
-                                        app.put_runner(runner);
-                                    }
-                                }
-                                app.disconnect().await?;
-                                return Ok(());
-                            }
-                            KeyCode::Char(' ') => {
-                                app.toggle_pause();
-                            }
-                            KeyCode::Tab => {
-                                app.toggle_view();
-                                if app.view == View::Results
-                                    && app.last_sample.is_none()
-                                    && app.has_runner()
-                                {
-                                    let _ = app.run_sample().await;
-                                }
-                            }
-                            KeyCode::Char('/') if app.view == View::Results => {
-                                app.start_editing();
-                            }
-                            KeyCode::Char('r') if app.view == View::Results => {
-                                if app.has_runner() {
-                                    let _ = app.run_sample().await;
-                                }
-                            }
-                            KeyCode::Up | KeyCode::Char('k') if app.view == View::Results => {
-                                app.scroll_results(-1);
-                            }
-                            KeyCode::Down | KeyCode::Char('j') if app.view == View::Results => {
-                                app.scroll_results(1);
-                            }
-                            _ => {}
+                        let summarized = app.summarize();
+                        if let Some(report) = &app.summary {
+                            app.notify(Notification::Finished(report)).await;
+                        }
+                        let disconnected = app.disconnect().await;
+                        if summarized {
+                            show_summary(terminal, &mut app)?;
+                        }
+                        return disconnected;
+                    }
+                    KeyCode::Char(' ') => {
+                        app.toggle_pause();
+                    }
+                    KeyCode::Tab => {
+                        app.toggle_view();
+                        if app.view == View::Results
+                            && app.samples.is_empty()
+                            && app.has_runner()
+                        {
+                            let _ = app.run_sample().await;
+                        }
+                    }
+                    KeyCode::Char('+') | KeyCode::Char('=') => {
+                        app.tune(Tunable::Concurrency, true);
+                    }
+                    KeyCode::Char('-') => {
+                        app.tune(Tunable::Concurrency, false);
+                    }
+                    KeyCode::Char(']') => {
+                        app.tune(Tunable::BurstSize, true);
+                    }
+                    KeyCode::Char('[') => {
+                        app.tune(Tunable::BurstSize, false);
+                    }
+                    KeyCode::Char('>') | KeyCode::Char('.') => {
+                        app.tune(Tunable::TopK, true);
+                    }
+                    KeyCode::Char('<') | KeyCode::Char(',') => {
+                        app.tune(Tunable::TopK, false);
+                    }
+                    KeyCode::Char('R') if app.state == AppState::Error && burst_rx.is_none() => {
+                        next_reconnect = None;
+                        app.reconnect_at = None;
+                        reconnect(terminal, &mut app).await;
+                    }
+                    KeyCode::Char('e') => {
+                        app.request_snapshot();
+                    }
+                    KeyCode::Char('b') if paused_between_bursts => {
+                        burst_requested = true;
+                    }
+                    KeyCode::Char('w') if paused_between_bursts => {
+                        rewarm(terminal, &mut app).await;
+                    }
+                    KeyCode::Char('L') => app.show_logs = !app.show_logs,
+                    KeyCode::Char('E') => {
+                        app.view = if app.view == View::Errors {
+                            View::Dashboard
+                        } else {
+                            View::Errors
+                        };
+                    }
+                    KeyCode::Char('i') => toggle_config_view(&mut app),
+                    KeyCode::Char('/') if app.view == View::Results => {
+                        app.start_editing();
+                    }
+                    KeyCode::Char('r') if app.view == View::Results && app.has_runner() => {
+                        let _ = app.run_sample().await;
+                    }
+                    KeyCode::Char('c') if app.view == View::Results => {
+                        let rerun = app.toggle_compare_modes();
+                        if rerun && app.has_runner() {
+                            let _ = app.run_sample().await;
                         }
                     }
+                    KeyCode::Char('p') if app.view == View::Results => {
+                        app.open_picker();
+                    }
+                    KeyCode::Left | KeyCode::Char('h') if app.view == View::Results => {
+                        app.browse_samples(-1);
+                    }
+                    KeyCode::Right | KeyCode::Char('l') if app.view == View::Results => {
+                        app.browse_samples(1);
+                    }
+                    KeyCode::Up | KeyCode::Char('k') if app.view == View::Results => {
+                        app.scroll_results(-1);
+                    }
+                    KeyCode::Down | KeyCode::Char('j') if app.view == View::Results => {
+                        app.scroll_results(1);
+                    }
+                    KeyCode::Up | KeyCode::Char('k') if app.view == View::Errors => {
+                        app.scroll_errors(-1);
+                    }
+                    KeyCode::Down | KeyCode::Char('j') if app.view == View::Errors => {
+                        app.scroll_errors(1);
+                    }
+                    KeyCode::Up | KeyCode::Char('k') if app.view == View::Config => {
+                        app.scroll_config(-1);
+                    }
+                    KeyCode::Down | KeyCode::Char('j') if app.view == View::Config => {
+                        app.scroll_config(1);
+                    }
+                    _ => {}
                 }
             }
         }
 
         // Spawn burst in background if needed
         if burst_rx.is_none()
-            && app.state != AppState::Paused
             && app.state != AppState::Error
             && app.has_runner()
-            && last_burst.elapsed() >= burst_interval
+            && (burst_requested
+                || (app.state != AppState::Paused && last_burst.elapsed() >= burst_interval))
+            && let Some(mut runner) = app.take_runner()
         {
-            if let Some(mut runner) = app.take_runner() {
-                let (tx, rx) = oneshot::channel();
-                app.state = AppState::Running;
-                tokio::spawn(async move {
+            single_burst = std::mem::take(&mut burst_requested);
+            // Settings changed from the keyboard start with this burst
+            app.apply_tuning(&mut runner);
+            let rival = app.take_rival_runner();
+            let rival_goes_first = rival.is_some() && rival_first;
+            rival_first = rival.is_some() && !rival_first;
+            let (tx, rx) = oneshot::channel();
+            app.state = AppState::Running;
+            tokio::spawn(async move {
+                // The providers take turns rather than sharing the client
+                // machine, and swap order each burst so neither always
+                // runs on the warmer side of the pair
+                let rival_burst = async move {
+                    let mut rival = rival?;
+                    let result = rival.run_burst().await;
+                    Some((rival, result))
+                };
+                let (result, rival) = if rival_goes_first {
+                    let rival = rival_burst.await;
+                    (runner.run_burst().await, rival)
+                } else {
                     let result = runner.run_burst().await;
-                    let _ = tx.send((runner, result));
-                });
-                burst_rx = Some(rx);
-                last_burst = std::time::Instant::now();
+                    (result, rival_burst.await)
+                };
+                let _ = tx.send(((runner, result), rival));
+            });
+            burst_rx = Some(rx);
+            last_burst = std::time::Instant::now();
+        }
+    }
+}
+
+/// Play back a recorded session until the user quits, with the time
+/// between bursts divided by `speed`
+pub fn replay(
+    terminal: &mut Tui,
+    mut app: App,
+    events: Vec<SessionEvent>,
+    speed: f64,
+) -> Result<()> {
+    let tick_rate = Duration::from_millis(100);
+    let mut events = events.into_iter().peekable();
+    // When the last burst was shown, and when it was recorded
+    let mut last_shown: Option<(Instant, DateTime<Utc>)> = None;
+    app.state = AppState::Running;
+
+    loop {
+        terminal.draw(|frame| ui::render(frame, &app))?;
+
+        if app.state != AppState::Paused {
+            while let Some(event) = events.peek() {
+                if let SessionEvent::Burst(metrics) = event
+                    && let Some((shown_at, recorded_at)) = last_shown
+                {
+                    let gap = (metrics.timestamp - recorded_at).to_std().unwrap_or_default();
+                    if shown_at.elapsed() < gap.div_f64(speed) {
+                        break;
+                    }
+                }
+                let Some(event) = events.next() else {
+                    break;
+                };
+                if let SessionEvent::Burst(metrics) = &event {
+                    last_shown = Some((Instant::now(), metrics.timestamp));
+                }
+                if let Err(e) = app.replay(event) {
+                    app.fail(e);
+                }
+            }
+            if events.peek().is_none() && app.state != AppState::Error {
+                app.state = AppState::Paused;
+                app.stop_reason = Some("replay finished".into());
             }
         }
+
+        if event::poll(tick_rate)?
+            && let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char(' ') if events.peek().is_some() => {
+                    app.toggle_pause();
+                    // Pick up from the next burst rather than catching up
+                    last_shown = last_shown.map(|(_, recorded_at)| (Instant::now(), recorded_at));
+                }
+                KeyCode::Char('L') => app.show_logs = !app.show_logs,
+                KeyCode::Char('i') => toggle_config_view(&mut app),
+                KeyCode::Up | KeyCode::Char('k') if app.view == View::Config => {
+                    app.scroll_config(-1);
+                }
+                KeyCode::Down | KeyCode::Char('j') if app.view == View::Config => {
+                    app.scroll_config(1);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Show the run summary until the user leaves it, saving it on request
+fn show_summary(terminal: &mut Tui, app: &mut App) -> Result<()> {
+    loop {
+        terminal.draw(|frame| ui::render_summary(frame, app))?;
+        if event::poll(Duration::from_millis(100))?
+            && let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            match key.code {
+                KeyCode::Char('s') => app.save_summary(),
+                KeyCode::Char('q') | KeyCode::Esc | KeyCode::Enter => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Reconnect, showing the attempt on screen while it runs
+fn toggle_config_view(app: &mut App) {
+    app.view = if app.view == View::Config {
+        View::Dashboard
+    } else {
+        View::Config
+    };
+}
+
+/// Run warmup again, redrawing first so the header shows it
+async fn rewarm(terminal: &mut Tui, app: &mut App) {
+    app.state = AppState::Warming;
+    let _ = terminal.draw(|frame| ui::render(frame, app));
+    if let Err(e) = app.rewarm().await {
+        tracing::error!("Warmup failed: {}", e);
+        app.fail(format!("Warmup failed: {e}"));
+    }
+}
+
+async fn reconnect(terminal: &mut Tui, app: &mut App) {
+    app.state = AppState::Connecting;
+    let _ = terminal.draw(|frame| ui::render(frame, app));
+    if let Err(e) = app.reconnect().await {
+        tracing::error!("Reconnect failed: {}", e);
     }
 }
@@ -1,70 +1,391 @@
+use std::time::Instant;
+
 use ratatui::{
     Frame,
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style, Stylize},
     symbols::Marker,
     text::{Line, Span},
     widgets::{
-        Axis, Block, Borders, Chart, Dataset, GraphType, Paragraph, Row, Table, Wrap,
+        Axis, Block, Borders, Chart, Dataset, Gauge, GraphType, Paragraph, Row, Table, TableState,
+        Wrap,
     },
 };
+use chrono::{Local, Utc};
+use qstorm_core::{SearchMode, SearchResults};
+use tracing::Level;
+
+use crate::app::{App, AppState, OtherMode, QueryPicker, SampleQuery, SampleResult, View};
+use crate::{logs, theme};
 
-use crate::app::{App, AppState, View};
+/// Rows given to the logs pane, borders included
+const LOGS_HEIGHT: u16 = 10;
 
 pub fn render(frame: &mut Frame, app: &App) {
+    let banner_height = if app.slo_breach.is_some() { 3 } else { 0 };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3), // Header
+            Constraint::Length(banner_height), // SLO banner
             Constraint::Min(0),    // Main content
             Constraint::Length(3), // Footer/status
         ])
         .split(frame.area());
 
     render_header(frame, chunks[0], app);
+    render_slo_banner(frame, chunks[1], app);
+
+    let logs_height = if app.show_logs { LOGS_HEIGHT } else { 0 };
+    let content = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(logs_height)])
+        .split(chunks[2]);
 
     match app.view {
-        View::Dashboard => render_charts(frame, chunks[1], app),
-        View::Results => render_results(frame, chunks[1], app),
+        View::Dashboard => render_charts(frame, content[0], app),
+        View::Results => render_results(frame, content[0], app),
+        View::Errors => render_errors(frame, content[0], app),
+        View::Config => render_config(frame, content[0], app),
+    }
+    if app.show_logs {
+        render_logs(frame, content[1], app);
     }
 
-    render_footer(frame, chunks[2], app);
+    render_footer(frame, chunks[3], app);
+
+    if app.theme.ascii {
+        theme::asciify(frame.buffer_mut());
+    }
+}
+
+/// What the run was started with, above the whole config with credentials
+/// redacted
+fn render_config(frame: &mut Frame, area: Rect, app: &App) {
+    let config = &app.config;
+    let embedding = if config.vectors.is_empty() {
+        config.embedding.clone().unwrap_or_default().model
+    } else {
+        config
+            .vectors
+            .iter()
+            .map(|vector| format!("{}: {}", vector.name, vector.embedding.model))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let field = |name: &str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("{name:<12}"), Style::default().fg(app.theme.muted)),
+            Span::raw(value),
+        ])
+    };
+    let summary = vec![
+        field("Provider", config.provider.name.clone()),
+        field(
+            "Profile",
+            config.profile.clone().unwrap_or_else(|| "-".to_string()),
+        ),
+        field("Embedding", embedding),
+        field("Queries", app.query_count().to_string()),
+    ];
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(summary.len() as u16 + 2), // Summary
+            Constraint::Min(0),                           // Full config
+        ])
+        .split(area);
+
+    let summary = Paragraph::new(summary).block(
+        Block::default()
+            .title(" Run ")
+            .title_style(Style::default().bold().fg(app.theme.accent))
+            .borders(Borders::ALL),
+    );
+    frame.render_widget(summary, chunks[0]);
+
+    let yaml = Paragraph::new(app.config_yaml())
+        .scroll((app.config_scroll as u16, 0))
+        .block(
+            Block::default()
+                .title(" Config (credentials redacted) ")
+                .title_style(Style::default().bold().fg(app.theme.accent))
+                .borders(Borders::ALL),
+        );
+    frame.render_widget(yaml, chunks[1]);
+}
+
+/// Most recent log lines, newest at the bottom
+fn render_logs(frame: &mut Frame, area: Rect, app: &App) {
+    let mut lines: Vec<Line> = logs::recent(area.height.saturating_sub(2) as usize)
+        .into_iter()
+        .map(|line| {
+            let color = match line.level {
+                Level::ERROR => app.theme.bad,
+                Level::WARN => app.theme.highlight,
+                Level::INFO => app.theme.good,
+                _ => app.theme.muted,
+            };
+            Line::from(vec![
+                Span::styled(
+                    line.at.format("%H:%M:%S ").to_string(),
+                    Style::default().fg(app.theme.muted),
+                ),
+                Span::styled(format!("{:<5} ", line.level), Style::default().fg(color)),
+                Span::raw(line.message),
+            ])
+        })
+        .collect();
+
+    if lines.is_empty() {
+        lines.push(Line::styled(
+            "No warnings or errors yet (set RUST_LOG for more)",
+            Style::default().fg(app.theme.muted),
+        ));
+    }
+    let logs = Paragraph::new(lines).block(Block::default().title(" Logs ").borders(Borders::ALL));
+    frame.render_widget(logs, area);
+}
+
+/// The whole run summed up, shown after quitting
+pub fn render_summary(frame: &mut Frame, app: &App) {
+    let Some(report) = &app.summary else {
+        return;
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // Header
+            Constraint::Min(0),    // Summary
+            Constraint::Length(3), // Footer
+        ])
+        .split(frame.area());
+
+    let header = Paragraph::new(Line::from(vec![
+        Span::raw("qstorm "),
+        Span::styled(
+            format!("[{}]", report.provider),
+            Style::default().fg(app.theme.accent),
+        ),
+        Span::raw(" - "),
+        Span::styled("RUN SUMMARY", Style::default().bold()),
+        Span::styled(
+            app.notice().map(|notice| format!("  {notice}")).unwrap_or_default(),
+            Style::default().fg(app.theme.highlight),
+        ),
+    ]))
+    .block(Block::default().borders(Borders::ALL));
+    frame.render_widget(header, chunks[0]);
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(chunks[1]);
+
+    let ms = |us: u64| format!("{:.2} ms", us as f64 / 1000.0);
+    let mut rows = vec![
+        ("Duration", format!("{:.1} s", report.duration_secs)),
+        ("Bursts", report.bursts.to_string()),
+        ("Queries", report.total_queries.to_string()),
+        ("QPS (mean)", format!("{:.1}", report.qps)),
+        ("Latency mean", format!("{:.2} ms", report.latency.mean_us / 1000.0)),
+        ("Latency p50", ms(report.latency.p50_us)),
+        ("Latency p90", ms(report.latency.p90_us)),
+        ("Latency p95", ms(report.latency.p95_us)),
+        ("Latency p99", ms(report.latency.p99_us)),
+        ("Latency max", ms(report.latency.max_us)),
+    ];
+    for (name, stats) in [
+        ("Recall@k", &report.recall_at_k),
+        ("NDCG@k", &report.ndcg_at_k),
+        ("MRR", &report.mrr),
+    ] {
+        if let Some(stats) = stats {
+            rows.push((
+                name,
+                format!("{:.3} (min {:.3}, max {:.3})", stats.mean, stats.min, stats.max),
+            ));
+        }
+    }
+    if let Some(reason) = &report.stop_reason {
+        rows.push(("Stopped", reason.clone()));
+    }
+    let run_table = Table::new(
+        rows.into_iter()
+            .map(|(name, value)| Row::new(vec![name.to_string(), value])),
+        [
+            Constraint::Length(14),     // Metric
+            Constraint::Min(10),       // Value
+        ],
+    )
+    .block(
+        Block::default()
+            .title(" Run ")
+            .borders(Borders::ALL),
+    );
+    frame.render_widget(run_table, columns[0]);
+
+    let counts = app.failures.counts();
+    let right = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(7), // Error totals
+            Constraint::Min(0),    // Failures by class
+        ])
+        .split(columns[1]);
+
+    let errors = &report.errors;
+    let error_rows = [
+        ("Failed", format!("{} ({:.2}%)", errors.failures, errors.error_rate * 100.0)),
+        ("Timeouts", errors.timeouts.to_string()),
+        ("Errors", errors.errors.to_string()),
+        ("Write failures", errors.write_failures.to_string()),
+    ];
+    let error_table = Table::new(
+        error_rows
+            .into_iter()
+            .map(|(name, value)| Row::new(vec![name.to_string(), value])),
+        [
+            Constraint::Length(16),     // Kind
+            Constraint::Min(10),       // Count
+        ],
+    )
+    .block(
+        Block::default()
+            .title(" Errors ")
+            .borders(Borders::ALL),
+    );
+    frame.render_widget(error_table, right[0]);
+
+    let class_rows: Vec<Row> = counts
+        .iter()
+        .map(|(class, count)| Row::new(vec![class.clone(), count.to_string()]))
+        .collect();
+    let class_table = Table::new(
+        class_rows,
+        [
+            Constraint::Length(20),     // Class
+            Constraint::Min(10),       // Count
+        ],
+    )
+    .header(Row::new(vec!["Class", "Count"]).style(Style::default().bold().fg(app.theme.accent)))
+    .block(
+        Block::default()
+            .title(" Failures by Class ")
+            .borders(Borders::ALL),
+    );
+    frame.render_widget(class_table, right[1]);
+
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("[s]", Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(" Save report "),
+        Span::styled("[q]", Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(" Exit"),
+    ]))
+    .block(Block::default().borders(Borders::ALL));
+    frame.render_widget(footer, chunks[2]);
+
+    if app.theme.ascii {
+        theme::asciify(frame.buffer_mut());
+    }
+}
+
+/// Flashing alert while the latest burst breaks an SLO
+fn render_slo_banner(frame: &mut Frame, area: Rect, app: &App) {
+    let Some(breach) = &app.slo_breach else {
+        return;
+    };
+
+    // Swap colors every half second so the banner catches the eye
+    let flash = Utc::now().timestamp_subsec_millis() < 500;
+    let style = app.theme.banner[usize::from(!flash)];
+    let bursts = if breach.bursts == 1 {
+        "1 burst".to_string()
+    } else {
+        format!("{} bursts", breach.bursts)
+    };
+    let banner = Paragraph::new(format!(
+        "SLO BREACH: {} (since {}, {bursts})",
+        breach.reasons.join(", "),
+        breach.since.with_timezone(&Local).format("%H:%M:%S"),
+    ))
+    .style(style)
+    .alignment(Alignment::Center)
+    .block(Block::default().borders(Borders::ALL).border_style(style));
+    frame.render_widget(banner, area);
 }
 
 fn render_header(frame: &mut Frame, area: Rect, app: &App) {
     let state_text = match app.state {
+        AppState::Running | AppState::Idle if app.is_replay() => "REPLAYING".to_string(),
         AppState::Idle => "IDLE".to_string(),
         AppState::Connecting => "CONNECTING...".to_string(),
         AppState::Warming => "WARMING UP...".to_string(),
-        AppState::Running => "RUNNING".to_string(),
-        AppState::Paused => "PAUSED".to_string(),
-        AppState::Error => "ERROR".to_string(),
+        AppState::Running => match app.burst_progress {
+            Some(done) if done > 0 => format!("RUNNING ({done} done)"),
+            _ => "RUNNING".to_string(),
+        },
+        AppState::Paused => match &app.stop_reason {
+            Some(reason) => format!("STOPPED ({reason})"),
+            None => "PAUSED".to_string(),
+        },
+        AppState::Error => {
+            let error = app.last_error.as_deref().unwrap_or("unknown error");
+            match app.reconnect_at {
+                Some(at) => format!(
+                    "ERROR ({error}) - reconnecting in {}s",
+                    at.saturating_duration_since(Instant::now()).as_secs() + 1
+                ),
+                None => format!("ERROR ({error}) - press R to reconnect"),
+            }
+        }
     };
 
     let state_color = match app.state {
-        AppState::Running => Color::Green,
-        AppState::Paused => Color::Yellow,
-        AppState::Error => Color::Red,
-        _ => Color::White,
+        AppState::Running => app.theme.good,
+        AppState::Paused => app.theme.highlight,
+        AppState::Error => app.theme.bad,
+        _ => app.theme.text,
     };
 
     let view_label = match app.view {
         View::Dashboard => "Dashboard",
         View::Results => "Results",
+        View::Errors => "Errors",
+        View::Config => "Config",
     };
 
     let header = Paragraph::new(Line::from(vec![
         Span::raw("qstorm "),
         Span::styled(
             format!("[{}]", app.provider_name()),
-            Style::default().fg(Color::Cyan),
+            Style::default().fg(app.theme.accent),
+        ),
+        Span::styled(
+            app.rival_name()
+                .map(|name| format!(" vs [{name}]"))
+                .unwrap_or_default(),
+            Style::default().fg(app.theme.rival),
         ),
         Span::raw(format!(" ({} queries) - ", app.query_count())),
         Span::styled(state_text, Style::default().fg(state_color).bold()),
         Span::raw("  "),
         Span::styled(
             format!("[{}]", view_label),
-            Style::default().fg(Color::Magenta),
+            Style::default().fg(app.theme.secondary),
+        ),
+        Span::styled(
+            format!(
+                "  concurrency {} | burst {} | k {}",
+                app.settings.concurrency, app.settings.burst_size, app.settings.top_k
+            ),
+            Style::default().fg(app.theme.muted),
+        ),
+        Span::styled(
+            app.notice().map(|notice| format!("  {notice}")).unwrap_or_default(),
+            Style::default().fg(app.theme.highlight),
         ),
     ]))
     .block(Block::default().borders(Borders::ALL));
@@ -84,15 +405,30 @@ fn render_charts(frame: &mut Frame, area: Rect, app: &App) {
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
         .split(rows[0]);
 
+    // Errors take the quality chart's place when there's no ground truth,
+    // and squeeze in beside it when there is
+    let has_quality = !app.history.recall_series().is_empty();
+    let bottom_constraints = if has_quality {
+        vec![
+            Constraint::Percentage(50),
+            Constraint::Percentage(25),
+            Constraint::Percentage(25),
+        ]
+    } else {
+        vec![Constraint::Percentage(50), Constraint::Percentage(50)]
+    };
     let bottom_row = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .constraints(bottom_constraints)
         .split(rows[1]);
 
     render_qps_chart(frame, top_row[0], app);
     render_latency_chart(frame, top_row[1], app);
     render_p99_chart(frame, bottom_row[0], app);
-    render_recall_chart(frame, bottom_row[1], app);
+    if has_quality {
+        render_quality_chart(frame, bottom_row[1], app);
+    }
+    render_errors_panel(frame, bottom_row[bottom_row.len() - 1], app);
 }
 
 fn render_results(frame: &mut Frame, area: Rect, app: &App) {
@@ -107,41 +443,70 @@ fn render_results(frame: &mut Frame, area: Rect, app: &App) {
     // Query bar — input mode vs display mode
     if app.editing {
         let query_bar = Paragraph::new(Line::from(vec![
-            Span::styled("/ ", Style::default().fg(Color::Yellow).bold()),
+            Span::styled("/ ", Style::default().fg(app.theme.highlight).bold()),
             Span::raw(&app.query_input),
-            Span::styled("_", Style::default().fg(Color::Yellow).add_modifier(Modifier::SLOW_BLINK)),
+            Span::styled(
+                "_",
+                Style::default().fg(app.theme.highlight).add_modifier(Modifier::SLOW_BLINK),
+            ),
         ]))
         .block(
             Block::default()
                 .title(" Search ")
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::Yellow)),
+                .border_style(Style::default().fg(app.theme.highlight)),
+        );
+        frame.render_widget(query_bar, chunks[0]);
+    } else if let Some(picker) = &app.picker {
+        let query_bar = Paragraph::new(Line::from(vec![
+            Span::styled("> ", Style::default().fg(app.theme.highlight).bold()),
+            Span::raw(&picker.filter),
+            Span::styled(
+                "_",
+                Style::default().fg(app.theme.highlight).add_modifier(Modifier::SLOW_BLINK),
+            ),
+        ]))
+        .block(
+            Block::default()
+                .title(" Pick a Query ")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(app.theme.highlight)),
         );
         frame.render_widget(query_bar, chunks[0]);
-    } else if let Some(sample) = &app.last_sample {
+    } else if let Some(sample) = app.sample() {
         let hit_count = sample.results.results.len();
         let took = sample
             .results
-            .took_ms
-            .map(|t| format!(" in {}ms", t))
+            .took_us
+            .map(|t| format!(" in {:.2}ms", t as f64 / 1000.0))
             .unwrap_or_default();
+        let origin = match sample.source {
+            SampleQuery::Loaded(index) => format!("query #{}", index + 1),
+            SampleQuery::Custom(_) => "custom".to_string(),
+        };
 
         let query_info = Paragraph::new(Line::from(vec![
+            Span::styled(
+                format!("[{}/{}] ", app.sample_index + 1, app.samples.len()),
+                Style::default().fg(app.theme.muted),
+            ),
             Span::styled("Query: ", Style::default().bold()),
             Span::styled(
                 &sample.query,
-                Style::default().fg(Color::Yellow),
+                Style::default().fg(app.theme.highlight),
             ),
-            Span::raw(format!("  ({} hits{})", hit_count, took)),
+            Span::raw(format!("  ({} hits{}, {})", hit_count, took, origin)),
         ]))
         .block(Block::default().borders(Borders::ALL));
         frame.render_widget(query_info, chunks[0]);
     } else {
         let placeholder = Paragraph::new(
             Line::from(vec![
-                Span::styled("Press ", Style::default().fg(Color::DarkGray)),
-                Span::styled("[/]", Style::default().fg(Color::DarkGray).bold()),
-                Span::styled(" to search", Style::default().fg(Color::DarkGray)),
+                Span::styled("Press ", Style::default().fg(app.theme.muted)),
+                Span::styled("[/]", Style::default().fg(app.theme.muted).bold()),
+                Span::styled(" to search or ", Style::default().fg(app.theme.muted)),
+                Span::styled("[p]", Style::default().fg(app.theme.muted).bold()),
+                Span::styled(" to pick a loaded query", Style::default().fg(app.theme.muted)),
             ]),
         )
         .block(
@@ -152,8 +517,13 @@ fn render_results(frame: &mut Frame, area: Rect, app: &App) {
         frame.render_widget(placeholder, chunks[0]);
     }
 
+    if let Some(picker) = &app.picker {
+        render_picker(frame, chunks[1], app, picker);
+        return;
+    }
+
     // Results table (or empty placeholder)
-    let Some(sample) = &app.last_sample else {
+    let Some(sample) = app.sample() else {
         let placeholder = Paragraph::new("")
             .block(
                 Block::default()
@@ -164,6 +534,13 @@ fn render_results(frame: &mut Frame, area: Rect, app: &App) {
         return;
     };
 
+    if app.compare_modes
+        && let Some(other) = &sample.other
+    {
+        render_mode_comparison(frame, chunks[1], app, sample, other);
+        return;
+    }
+
     // Results table
     let header = Row::new(vec![
         "#",
@@ -171,7 +548,7 @@ fn render_results(frame: &mut Frame, area: Rect, app: &App) {
         "Score",
         "Payload",
     ])
-    .style(Style::default().bold().fg(Color::Cyan))
+    .style(Style::default().bold().fg(app.theme.accent))
     .bottom_margin(1);
 
     let rows: Vec<Row> = sample
@@ -195,7 +572,7 @@ fn render_results(frame: &mut Frame, area: Rect, app: &App) {
                 .unwrap_or_else(|| "-".to_string());
 
             let style = if i == app.results_scroll {
-                Style::default().bg(Color::DarkGray)
+                app.theme.selected
             } else {
                 Style::default()
             };
@@ -229,43 +606,285 @@ fn render_results(frame: &mut Frame, area: Rect, app: &App) {
     frame.render_widget(table, chunks[1]);
 }
 
+/// Vector results on the left, hybrid on the right, with the hits both
+/// modes returned in green
+fn render_mode_comparison(
+    frame: &mut Frame,
+    area: Rect,
+    app: &App,
+    sample: &SampleResult,
+    other: &OtherMode,
+) {
+    let own = Ok(&sample.results);
+    let other_results = other.results.as_ref();
+    let (vector, hybrid) = match sample.mode {
+        SearchMode::Vector => (own, other_results),
+        SearchMode::Hybrid => (other_results, own),
+    };
+
+    let halves = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+
+    let label = |mode: SearchMode, name: &str| {
+        if mode == sample.mode {
+            format!("{name} (benchmarked)")
+        } else {
+            name.to_string()
+        }
+    };
+    render_ranking(frame, halves[0], app, &label(SearchMode::Vector, "Vector"), vector, hybrid.ok());
+    render_ranking(frame, halves[1], app, &label(SearchMode::Hybrid, "Hybrid"), hybrid, vector.ok());
+}
+
+/// One side of the mode comparison: each hit with its rank on the other side
+fn render_ranking(
+    frame: &mut Frame,
+    area: Rect,
+    app: &App,
+    title: &str,
+    ranking: Result<&SearchResults, &String>,
+    against: Option<&SearchResults>,
+) {
+    let results = match ranking {
+        Ok(results) => results,
+        Err(error) => {
+            let message = Paragraph::new(error.as_str())
+                .style(Style::default().fg(app.theme.bad))
+                .wrap(Wrap { trim: true })
+                .block(
+                    Block::default()
+                        .title(format!(" {title} "))
+                        .borders(Borders::ALL),
+                );
+            frame.render_widget(message, area);
+            return;
+        }
+    };
+
+    let rank_in_other = |id: &str| {
+        against.and_then(|other| other.results.iter().position(|r| r.id == id))
+    };
+    let shared = results
+        .results
+        .iter()
+        .filter(|r| rank_in_other(&r.id).is_some())
+        .count();
+
+    let rows: Vec<Row> = results
+        .results
+        .iter()
+        .enumerate()
+        .map(|(i, result)| {
+            let other_rank = rank_in_other(&result.id);
+            let style = if other_rank.is_some() {
+                Style::default().fg(app.theme.good)
+            } else {
+                Style::default()
+            };
+            Row::new(vec![
+                format!("{}", i + 1),
+                result.id.clone(),
+                format!("{:.4}", result.score),
+                other_rank
+                    .map(|rank| format!("#{}", rank + 1))
+                    .unwrap_or_else(|| "-".to_string()),
+            ])
+            .style(style)
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(4),      // #
+            Constraint::Min(12),       // ID
+            Constraint::Length(10),     // Score
+            Constraint::Length(7),      // Rank on the other side
+        ],
+    )
+    .header(
+        Row::new(vec!["#", "ID", "Score", "Other"])
+            .style(Style::default().bold().fg(app.theme.accent))
+            .bottom_margin(1),
+    )
+    .row_highlight_style(app.theme.selected)
+    .block(
+        Block::default()
+            .title(format!(
+                " {title} - {shared}/{} shared ",
+                results.results.len()
+            ))
+            .borders(Borders::ALL),
+    );
+
+    let mut state = TableState::default().with_selected(Some(app.results_scroll));
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+/// Loaded queries matching the picker's filter, highlighted one selected
+fn render_picker(frame: &mut Frame, area: Rect, app: &App, picker: &QueryPicker) {
+    let matches = app.picker_matches();
+    let rows: Vec<Row> = matches
+        .iter()
+        .map(|(index, text)| Row::new(vec![format!("{}", index + 1), text.to_string()]))
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(7),      // #
+            Constraint::Min(20),       // Query
+        ],
+    )
+    .header(
+        Row::new(vec!["#", "Query"])
+            .style(Style::default().bold().fg(app.theme.accent))
+            .bottom_margin(1),
+    )
+    .row_highlight_style(app.theme.selected)
+    .block(
+        Block::default()
+            .title(format!(" Queries ({} of {}) ", matches.len(), app.query_count()))
+            .borders(Borders::ALL),
+    );
+
+    let mut state = TableState::default().with_selected(Some(picker.selected));
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+fn render_errors(frame: &mut Frame, area: Rect, app: &App) {
+    let counts = app.failures.counts();
+    let recent = app.failures.recent();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(counts.len().clamp(1, 6) as u16 + 3), // Counts per class
+            Constraint::Min(0),                                       // Recent failures
+        ])
+        .split(area);
+
+    let total: usize = counts.iter().map(|(_, count)| count).sum();
+    let count_rows: Vec<Row> = counts
+        .iter()
+        .map(|(class, count)| {
+            Row::new(vec![
+                class.clone(),
+                count.to_string(),
+                format!("{:.1}%", *count as f64 / total as f64 * 100.0),
+            ])
+        })
+        .collect();
+
+    let counts_table = Table::new(
+        count_rows,
+        [
+            Constraint::Length(20),     // Class
+            Constraint::Length(10),     // Count
+            Constraint::Length(8),      // Share
+        ],
+    )
+    .header(
+        Row::new(vec!["Class", "Count", "Share"])
+            .style(Style::default().bold().fg(app.theme.accent)),
+    )
+    .block(
+        Block::default()
+            .title(format!(" Failures by Class ({total}) "))
+            .borders(Borders::ALL),
+    );
+    frame.render_widget(counts_table, chunks[0]);
+
+    if recent.is_empty() {
+        let placeholder = Paragraph::new("No failures so far")
+            .block(
+                Block::default()
+                    .title(" Recent Failures ")
+                    .borders(Borders::ALL),
+            )
+            .style(Style::default().fg(app.theme.muted));
+        frame.render_widget(placeholder, chunks[1]);
+        return;
+    }
+
+    let header = Row::new(vec!["Time", "Class", "Error", "Query"])
+        .style(Style::default().bold().fg(app.theme.accent))
+        .bottom_margin(1);
+
+    let rows: Vec<Row> = recent
+        .iter()
+        .map(|failure| {
+            Row::new(vec![
+                failure.timestamp.format("%H:%M:%S%.3f").to_string(),
+                failure.class.clone(),
+                failure.message.clone(),
+                failure.query.as_deref().unwrap_or("(upsert)").to_string(),
+            ])
+            .style(Style::default().fg(app.theme.bad))
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(12),     // Time
+            Constraint::Length(16),     // Class
+            Constraint::Percentage(55), // Error
+            Constraint::Min(20),       // Query
+        ],
+    )
+    .header(header)
+    .row_highlight_style(app.theme.selected)
+    .block(
+        Block::default()
+            .title(format!(" Recent Failures (last {}) ", recent.len()))
+            .borders(Borders::ALL),
+    );
+
+    // The table scrolls to keep the highlighted failure in view
+    let mut state = TableState::default().with_selected(Some(app.errors_scroll));
+    frame.render_stateful_widget(table, chunks[1], &mut state);
+}
+
 fn render_qps_chart(frame: &mut Frame, area: Rect, app: &App) {
     let data = app.history.qps_series();
+    let rival_data = app.history.rival_qps_series();
     let max_y = data
         .iter()
+        .chain(&rival_data)
         .map(|(_, y)| *y)
         .fold(0.0_f64, f64::max)
         .max(1.0);
 
-    // Render name with running average QPS
-    let name = format!(
-        "QPS (avg: {:.1})",
-        app.history
-            .qps_series()
-            .into_iter()
-            .reduce(|a, b| (0.0, a.1 + b.1))
-            .map(|(_, sum)| sum / data.len() as f64)
-            .unwrap_or(0.0)
-    );
+    // Render name with running average QPS, per provider when comparing
+    let label = if app.rival_name().is_some() {
+        app.provider_name()
+    } else {
+        "QPS"
+    };
+    let name = format!("{label} (avg: {:.1})", average(&data));
 
     let dataset = Dataset::default()
         .name(name)
-        .marker(Marker::Braille)
+        .marker(app.theme.marker)
         .graph_type(GraphType::Line)
-        .style(Style::default().fg(Color::Cyan))
+        .style(Style::default().fg(app.theme.accent))
         .data(&data);
 
-    let chart = Chart::new(vec![dataset])
+    let marks = tuning_marks(app, max_y * 1.1);
+    let mut datasets = vec![dataset];
+    datasets.extend(rival_dataset(app, &rival_data));
+    datasets.extend(mark_datasets(&marks, false, app.theme.highlight, app.theme.marker));
+
+    let chart = Chart::new(datasets)
         .block(
             Block::default()
                 .title(" Queries/Second ")
                 .borders(Borders::ALL),
         )
-        .x_axis(
-            Axis::default()
-                .bounds([0.0, data.len().max(1) as f64])
-                .labels::<Vec<Span>>(vec![]),
-        )
+        .x_axis(time_axis(app))
         .y_axis(Axis::default().bounds([0.0, max_y * 1.1]).labels(vec![
             Span::raw("0"),
             Span::raw(format!("{:.0}", max_y / 2.0)),
@@ -296,22 +915,22 @@ fn render_latency_chart(frame: &mut Frame, area: Rect, app: &App) {
 
     let dataset = Dataset::default()
         .name(name)
-        .marker(Marker::Braille)
+        .marker(app.theme.marker)
         .graph_type(GraphType::Line)
-        .style(Style::default().fg(Color::Green))
+        .style(Style::default().fg(app.theme.good))
         .data(&p50_data);
 
-    let chart = Chart::new(vec![dataset])
+    let marks = tuning_marks(app, max_y * 1.1);
+    let mut datasets = vec![dataset];
+    datasets.extend(mark_datasets(&marks, false, app.theme.highlight, app.theme.marker));
+
+    let chart = Chart::new(datasets)
         .block(
             Block::default()
                 .title(" Latency p50 (ms) ")
                 .borders(Borders::ALL),
         )
-        .x_axis(
-            Axis::default()
-                .bounds([0.0, p50_data.len().max(1) as f64])
-                .labels::<Vec<Span>>(vec![]),
-        )
+        .x_axis(time_axis(app))
         .y_axis(Axis::default().bounds([0.0, max_y * 1.1]).labels(vec![
             Span::raw("0"),
             Span::raw(format!("{:.1}", max_y / 2.0)),
@@ -323,42 +942,67 @@ fn render_latency_chart(frame: &mut Frame, area: Rect, app: &App) {
 
 fn render_p99_chart(frame: &mut Frame, area: Rect, app: &App) {
     let p99_data = app.history.p99_series();
+    let server_data = app.history.server_p99_series();
+    let overhead_data = app.history.overhead_p99_series();
+    let rival_data = app.history.rival_p99_series();
     let max_y = p99_data
         .iter()
+        .chain(&rival_data)
         .map(|(_, y)| *y)
         .fold(0.0_f64, f64::max)
         .max(1.0);
 
-    // Render name with running average QPS
-    let name = format!(
-        "p99 (avg: {:.1})",
-        app.history
-            .p99_series()
-            .into_iter()
-            .reduce(|a, b| (0.0, a.1 + b.1))
-            .map(|(_, sum)| sum / p99_data.len() as f64)
-            .unwrap_or(0.0)
-    );
+    // Render name with running average p99, per provider when comparing
+    let label = if app.rival_name().is_some() {
+        app.provider_name()
+    } else {
+        "p99"
+    };
+    let name = format!("{label} (avg: {:.1})", average(&p99_data));
 
 
-    let dataset = Dataset::default()
+    let mut datasets = vec![Dataset::default()
         .name(name)
-        .marker(Marker::Braille)
+        .marker(app.theme.marker)
         .graph_type(GraphType::Line)
-        .style(Style::default().fg(Color::Red))
-        .data(&p99_data);
+        .style(Style::default().fg(app.theme.bad))
+        .data(&p99_data)];
+
+    // Split out the engine's share when the provider reports it
+    if !server_data.is_empty() {
+        datasets.push(
+            Dataset::default()
+                .name("server")
+                .marker(app.theme.marker)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(app.theme.tertiary))
+                .data(&server_data),
+        );
+        datasets.push(
+            Dataset::default()
+                .name("overhead")
+                .marker(app.theme.marker)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(app.theme.muted))
+                .data(&overhead_data),
+        );
+    }
+
+    datasets.extend(rival_dataset(app, &rival_data));
+
+    // The latest settings change is named in the legend
+    let marks = tuning_marks(app, max_y * 1.1);
+    datasets.extend(mark_datasets(&marks, true, app.theme.highlight, app.theme.marker));
+    let breaches = breach_marks(app, max_y * 1.1);
+    datasets.extend(mark_datasets(&breaches, false, app.theme.alert, app.theme.marker));
 
-    let chart = Chart::new(vec![dataset])
+    let chart = Chart::new(datasets)
         .block(
             Block::default()
                 .title(" Latency p99 (ms) ")
                 .borders(Borders::ALL),
         )
-        .x_axis(
-            Axis::default()
-                .bounds([0.0, p99_data.len().max(1) as f64])
-                .labels::<Vec<Span>>(vec![]),
-        )
+        .x_axis(time_axis(app))
         .y_axis(Axis::default().bounds([0.0, max_y * 1.1]).labels(vec![
             Span::raw("0"),
             Span::raw(format!("{:.1}", max_y / 2.0)),
@@ -368,41 +1012,197 @@ fn render_p99_chart(frame: &mut Frame, area: Rect, app: &App) {
     frame.render_widget(chart, area);
 }
 
-fn render_recall_chart(frame: &mut Frame, area: Rect, app: &App) {
-    let recall_data = app.history.recall_series();
-
-    if recall_data.is_empty() {
-        // Show placeholder when no recall data
-        let placeholder = Paragraph::new("Recall@k\n(no ground truth)")
-            .block(
-                Block::default()
-                    .title(" Recall@k (%) ")
-                    .borders(Borders::ALL),
-            )
-            .style(Style::default().fg(Color::DarkGray))
-            .wrap(Wrap { trim: true });
-        frame.render_widget(placeholder, area);
-        return;
+/// Vertical lines, `top` high, where load settings were changed from the
+/// keyboard, each with its note
+/// Mean of a series' values
+fn average(data: &[(f64, f64)]) -> f64 {
+    if data.is_empty() {
+        return 0.0;
     }
+    data.iter().map(|(_, y)| y).sum::<f64>() / data.len() as f64
+}
 
-    let dataset = Dataset::default()
-        .name("recall")
-        .marker(Marker::Braille)
-        .graph_type(GraphType::Line)
-        .style(Style::default().fg(Color::Magenta))
-        .data(&recall_data);
+/// The `--versus` provider's line, named with its average
+fn rival_dataset<'a>(app: &'a App, data: &'a [(f64, f64)]) -> Option<Dataset<'a>> {
+    let name = app.rival_name()?;
+    Some(
+        Dataset::default()
+            .name(format!("{name} (avg: {:.1})", average(data)))
+            .marker(app.theme.marker)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(app.theme.rival))
+            .data(data),
+    )
+}
+
+/// X axis with wall-clock times of the charted bursts
+fn time_axis(app: &App) -> Axis<'static> {
+    Axis::default()
+        .bounds(app.history.x_bounds())
+        .labels(app.history.x_labels())
+        .style(Style::default().fg(app.theme.muted))
+}
+
+fn tuning_marks(app: &App, top: f64) -> Vec<(Vec<(f64, f64)>, &str)> {
+    vertical_lines(app.history.annotations(), top)
+}
+
+/// Where SLO breaches started
+fn breach_marks(app: &App, top: f64) -> Vec<(Vec<(f64, f64)>, &str)> {
+    vertical_lines(app.history.breaches(), top)
+}
+
+fn vertical_lines(notes: Vec<(f64, &str)>, top: f64) -> Vec<(Vec<(f64, f64)>, &str)> {
+    notes
+        .into_iter()
+        .map(|(x, note)| (vec![(x, 0.0), (x, top)], note))
+        .collect()
+}
+
+fn mark_datasets<'a>(
+    marks: &'a [(Vec<(f64, f64)>, &'a str)],
+    name_latest: bool,
+    color: Color,
+    marker: Marker,
+) -> impl Iterator<Item = Dataset<'a>> {
+    marks.iter().enumerate().map(move |(i, (line, note))| {
+        let dataset = Dataset::default()
+            .marker(marker)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(color))
+            .data(line);
+        if name_latest && i == marks.len() - 1 {
+            dataset.name(*note)
+        } else {
+            dataset
+        }
+    })
+}
 
-    let chart = Chart::new(vec![dataset])
+/// Success ratio of the latest burst over the error and timeout rates of
+/// every charted burst
+fn render_errors_panel(frame: &mut Frame, area: Rect, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // Success gauge
+            Constraint::Min(0),    // Error rate chart
+        ])
+        .split(area);
+
+    let ratio = app
+        .history
+        .latest()
+        .map(|m| {
+            let total = m.success_count + m.failure_count;
+            if total == 0 {
+                1.0
+            } else {
+                m.success_count as f64 / total as f64
+            }
+        })
+        .unwrap_or(1.0);
+    let color = if ratio >= 0.99 {
+        app.theme.good
+    } else if ratio >= 0.95 {
+        app.theme.highlight
+    } else {
+        app.theme.bad
+    };
+    let gauge = Gauge::default()
+        .block(
+            Block::default()
+                .title(" Success (last burst) ")
+                .borders(Borders::ALL),
+        )
+        .gauge_style(Style::default().fg(color))
+        .ratio(ratio)
+        .label(format!("{:.1}%", ratio * 100.0));
+    frame.render_widget(gauge, chunks[0]);
+
+    let error_data = app.history.error_rate_series();
+    let timeout_data = app.history.timeout_rate_series();
+    let max_y = error_data
+        .iter()
+        .map(|(_, y)| *y)
+        .fold(0.0_f64, f64::max)
+        .max(1.0);
+
+    let mut datasets = vec![
+        Dataset::default()
+            .name("errors")
+            .marker(app.theme.marker)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(app.theme.bad))
+            .data(&error_data),
+        Dataset::default()
+            .name("timeouts")
+            .marker(app.theme.marker)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(app.theme.muted))
+            .data(&timeout_data),
+    ];
+    let marks = tuning_marks(app, max_y * 1.1);
+    datasets.extend(mark_datasets(&marks, false, app.theme.highlight, app.theme.marker));
+    let breaches = breach_marks(app, max_y * 1.1);
+    datasets.extend(mark_datasets(&breaches, false, app.theme.alert, app.theme.marker));
+
+    let chart = Chart::new(datasets)
         .block(
             Block::default()
-                .title(" Recall@k (%) ")
+                .title(" Error Rate (%) ")
                 .borders(Borders::ALL),
         )
-        .x_axis(
-            Axis::default()
-                .bounds([0.0, recall_data.len().max(1) as f64])
-                .labels::<Vec<Span>>(vec![]),
+        .x_axis(time_axis(app))
+        .y_axis(Axis::default().bounds([0.0, max_y * 1.1]).labels(vec![
+            Span::raw("0"),
+            Span::raw(format!("{:.1}", max_y / 2.0)),
+            Span::raw(format!("{:.1}", max_y)),
+        ]));
+
+    frame.render_widget(chart, chunks[1]);
+}
+
+fn render_quality_chart(frame: &mut Frame, area: Rect, app: &App) {
+    let recall_data = app.history.recall_series();
+    let probe_data = app.history.probe_recall_series();
+    let ndcg_data = app.history.ndcg_series();
+    let mrr_data = app.history.mrr_series();
+
+    let datasets = vec![
+        Dataset::default()
+            .name("recall")
+            .marker(app.theme.marker)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(app.theme.secondary))
+            .data(&recall_data),
+        Dataset::default()
+            .name("probe")
+            .marker(app.theme.marker)
+            .graph_type(GraphType::Scatter)
+            .style(Style::default().fg(app.theme.secondary))
+            .data(&probe_data),
+        Dataset::default()
+            .name("ndcg")
+            .marker(app.theme.marker)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(app.theme.accent))
+            .data(&ndcg_data),
+        Dataset::default()
+            .name("mrr")
+            .marker(app.theme.marker)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(app.theme.highlight))
+            .data(&mrr_data),
+    ];
+
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .title(" Recall / NDCG / MRR @k (%) ")
+                .borders(Borders::ALL),
         )
+        .x_axis(time_axis(app))
         .y_axis(Axis::default().bounds([0.0, 100.0]).labels(vec![
             Span::raw("0"),
             Span::raw("50"),
@@ -417,28 +1217,82 @@ fn render_footer(frame: &mut Frame, area: Rect, app: &App) {
         View::Dashboard => {
             let latest = app.history.latest();
             let stats = if let Some(m) = latest {
-                format!(
-                    "QPS: {:.1} | p50: {:.2}ms | p99: {:.2}ms | Success: {} | Failed: {}",
+                let mut stats = format!(
+                    "QPS: {:.1} | p50: {:.2}ms | p99: {:.2}ms",
                     m.qps,
                     m.latency.p50_us as f64 / 1000.0,
                     m.latency.p99_us as f64 / 1000.0,
-                    m.success_count,
-                    m.failure_count,
-                )
+                );
+                for p in &m.latency.percentiles {
+                    stats.push_str(&format!(
+                        " | p{}: {:.2}ms",
+                        p.percentile,
+                        p.latency_us as f64 / 1000.0
+                    ));
+                }
+                stats.push_str(&format!(
+                    " | Success: {} | Failed: {} ({} timed out)",
+                    m.success_count, m.failure_count, m.timeout_count,
+                ));
+                if let Some(stability) = m.stability {
+                    stats.push_str(&format!(" | Stability: {:.2}", stability));
+                }
+                stats
             } else {
                 "Waiting for data...".to_string()
             };
 
-            Line::from(vec![
-                Span::raw(stats),
-                Span::raw(" | "),
-                Span::styled("[Space]", Style::default().add_modifier(Modifier::BOLD)),
-                Span::raw(" Pause "),
-                Span::styled("[Tab]", Style::default().add_modifier(Modifier::BOLD)),
-                Span::raw(" Results "),
-                Span::styled("[q]", Style::default().add_modifier(Modifier::BOLD)),
-                Span::raw(" Quit"),
-            ])
+            // A replay can't be tuned, searched, or broken down by failure
+            if app.is_replay() {
+                Line::from(vec![
+                    Span::raw(stats),
+                    Span::raw(" | "),
+                    Span::styled("[Space]", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" Pause "),
+                    Span::styled("[i]", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" Config "),
+                    Span::styled("[L]", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" Logs "),
+                    Span::styled("[q]", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" Quit"),
+                ])
+            } else {
+                let mut spans = vec![
+                    Span::raw(stats),
+                    Span::raw(" | "),
+                    Span::styled("[Space]", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" Pause "),
+                ];
+                if app.state == AppState::Paused {
+                    spans.extend([
+                        Span::styled("[b]", Style::default().add_modifier(Modifier::BOLD)),
+                        Span::raw(" One burst "),
+                        Span::styled("[w]", Style::default().add_modifier(Modifier::BOLD)),
+                        Span::raw(" Warmup "),
+                    ]);
+                }
+                spans.extend([
+                    Span::styled("[Tab]", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" Results "),
+                    Span::styled("[E]", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" Errors "),
+                    Span::styled("[+/-]", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" Concurrency "),
+                    Span::styled("[[/]]", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" Burst "),
+                    Span::styled("[</>]", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" k "),
+                    Span::styled("[e]", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" Snapshot "),
+                    Span::styled("[i]", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" Config "),
+                    Span::styled("[L]", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" Logs "),
+                    Span::styled("[q]", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" Quit"),
+                ]);
+                Line::from(spans)
+            }
         }
         View::Results if app.editing => Line::from(vec![
             Span::styled("[Enter]", Style::default().add_modifier(Modifier::BOLD)),
@@ -446,15 +1300,47 @@ fn render_footer(frame: &mut Frame, area: Rect, app: &App) {
             Span::styled("[Esc]", Style::default().add_modifier(Modifier::BOLD)),
             Span::raw(" Cancel"),
         ]),
+        View::Results if app.picker.is_some() => Line::from(vec![
+            Span::styled("[Enter]", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" Run "),
+            Span::styled("[Up/Down]", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" Select "),
+            Span::styled("[Esc]", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" Cancel"),
+        ]),
         View::Results => Line::from(vec![
             Span::styled("[/]", Style::default().add_modifier(Modifier::BOLD)),
             Span::raw(" Search "),
+            Span::styled("[p]", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" Pick "),
             Span::styled("[r]", Style::default().add_modifier(Modifier::BOLD)),
             Span::raw(" Refresh "),
+            Span::styled("[h/l]", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" History "),
+            Span::styled("[c]", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(if app.compare_modes { " Single " } else { " Compare " }),
+            Span::styled("[j/k]", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" Scroll "),
+            Span::styled("[Tab]", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" Errors "),
+            Span::styled("[q]", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" Quit"),
+        ]),
+        View::Config => Line::from(vec![
+            Span::styled("[j/k]", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" Scroll "),
+            Span::styled("[i]", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" Back "),
+            Span::styled("[q]", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" Quit"),
+        ]),
+        View::Errors => Line::from(vec![
             Span::styled("[j/k]", Style::default().add_modifier(Modifier::BOLD)),
             Span::raw(" Scroll "),
             Span::styled("[Tab]", Style::default().add_modifier(Modifier::BOLD)),
             Span::raw(" Dashboard "),
+            Span::styled("[E]", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(" Back "),
             Span::styled("[q]", Style::default().add_modifier(Modifier::BOLD)),
             Span::raw(" Quit"),
         ]),
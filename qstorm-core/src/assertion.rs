@@ -0,0 +1,255 @@
+//! Pass/fail checks on a finished run.
+//!
+//! Assertions like `p99_ms < 150` or `recall@10 > 0.9` turn a headless run
+//! into a performance gate: each is checked against the run report once the
+//! run ends, and any that fail make the run fail.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::report::RunReport;
+
+/// A threshold on one run-level metric, e.g. `error_rate < 0.01`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assertion {
+    metric: Metric,
+    operator: Operator,
+    threshold: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Metric {
+    /// Latency percentile across every query, in milliseconds
+    Percentile(f64),
+    MeanMs,
+    MaxMs,
+    Qps,
+    ErrorRate,
+    TimeoutRate,
+    /// Mean recall across bursts, optionally pinned to a k
+    Recall(Option<usize>),
+    Ndcg(Option<usize>),
+    Mrr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+/// Outcome of checking one assertion against a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssertionResult {
+    /// The assertion, as `metric op threshold`
+    pub assertion: String,
+    /// The run's value for the metric, if it was measured
+    pub actual: Option<f64>,
+    pub passed: bool,
+    /// Why the metric has no value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl Assertion {
+    /// Check the assertion against a finished run. A metric the run didn't
+    /// measure (e.g. recall without ground truth) fails.
+    pub fn check(&self, report: &RunReport) -> AssertionResult {
+        let (actual, note) = match self.value(report) {
+            Ok(value) => (Some(value), None),
+            Err(note) => (None, Some(note)),
+        };
+        AssertionResult {
+            assertion: self.to_string(),
+            actual,
+            passed: actual.is_some_and(|value| self.operator.holds(value, self.threshold)),
+            note,
+        }
+    }
+
+    fn value(&self, report: &RunReport) -> std::result::Result<f64, String> {
+        let latency = &report.latency;
+        let ms = |us: u64| us as f64 / 1000.0;
+        let top_k = report.config.benchmark.top_k;
+        let at_top_k = |k: Option<usize>| match k {
+            Some(k) if k != top_k => Err(format!(
+                "quality is measured at k={top_k} (benchmark.top_k)"
+            )),
+            _ => Ok(()),
+        };
+        match self.metric {
+            Metric::Percentile(50.0) => Ok(ms(latency.p50_us)),
+            Metric::Percentile(90.0) => Ok(ms(latency.p90_us)),
+            Metric::Percentile(95.0) => Ok(ms(latency.p95_us)),
+            Metric::Percentile(99.0) => Ok(ms(latency.p99_us)),
+            Metric::Percentile(p) => latency
+                .percentile(p)
+                .map(ms)
+                .ok_or_else(|| format!("p{p} isn't measured; add it to benchmark.percentiles")),
+            Metric::MeanMs => Ok(latency.mean_us / 1000.0),
+            Metric::MaxMs => Ok(ms(latency.max_us)),
+            Metric::Qps => Ok(report.qps),
+            Metric::ErrorRate => Ok(report.errors.error_rate),
+            Metric::TimeoutRate => Ok(if report.total_queries == 0 {
+                0.0
+            } else {
+                report.errors.timeouts as f64 / report.total_queries as f64
+            }),
+            Metric::Recall(k) => {
+                at_top_k(k)?;
+                report
+                    .recall_at_k
+                    .as_ref()
+                    .map(|stats| stats.mean)
+                    .ok_or_else(|| "recall needs ground truth".to_string())
+            }
+            Metric::Ndcg(k) => {
+                at_top_k(k)?;
+                report
+                    .ndcg_at_k
+                    .as_ref()
+                    .map(|stats| stats.mean)
+                    .ok_or_else(|| "NDCG needs ground truth".to_string())
+            }
+            Metric::Mrr => report
+                .mrr
+                .as_ref()
+                .map(|stats| stats.mean)
+                .ok_or_else(|| "MRR needs ground truth".to_string()),
+        }
+    }
+}
+
+impl Operator {
+    fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Self::Less => value < threshold,
+            Self::LessOrEqual => value <= threshold,
+            Self::Greater => value > threshold,
+            Self::GreaterOrEqual => value >= threshold,
+        }
+    }
+}
+
+impl FromStr for Assertion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |why: &str| Error::Config(format!("Invalid assertion '{s}': {why}"));
+
+        // Two-character operators first, so `<=` isn't read as `<` then `=`
+        let (lhs, operator, rhs) = [
+            ("<=", Operator::LessOrEqual),
+            (">=", Operator::GreaterOrEqual),
+            ("<", Operator::Less),
+            (">", Operator::Greater),
+        ]
+        .into_iter()
+        .find_map(|(symbol, operator)| {
+            s.split_once(symbol)
+                .map(|(lhs, rhs)| (lhs.trim(), operator, rhs.trim()))
+        })
+        .ok_or_else(|| invalid("expected `metric < value` with <, <=, >, or >="))?;
+
+        let metric = parse_metric(&lhs.to_ascii_lowercase()).ok_or_else(|| {
+            invalid(
+                "unknown metric; expected pNN_ms, mean_ms, max_ms, qps, error_rate, \
+                 timeout_rate, recall[@k], ndcg[@k], or mrr",
+            )
+        })?;
+        let threshold = rhs
+            .parse()
+            .map_err(|_| invalid(&format!("'{rhs}' is not a number")))?;
+
+        Ok(Self {
+            metric,
+            operator,
+            threshold,
+        })
+    }
+}
+
+fn parse_metric(name: &str) -> Option<Metric> {
+    let with_k = |k: Option<&str>| -> Option<Option<usize>> {
+        match k {
+            Some(k) => k.parse().ok().filter(|&k| k > 0).map(Some),
+            None => Some(None),
+        }
+    };
+    let (base, k) = match name.split_once('@') {
+        Some((base, k)) => (base, Some(k)),
+        None => (name, None),
+    };
+
+    match base {
+        "recall" => return with_k(k).map(Metric::Recall),
+        "ndcg" => return with_k(k).map(Metric::Ndcg),
+        _ if k.is_some() => return None,
+        "mean_ms" => return Some(Metric::MeanMs),
+        "max_ms" => return Some(Metric::MaxMs),
+        "qps" => return Some(Metric::Qps),
+        "error_rate" => return Some(Metric::ErrorRate),
+        "timeout_rate" => return Some(Metric::TimeoutRate),
+        "mrr" => return Some(Metric::Mrr),
+        _ => {}
+    }
+
+    let percentile: f64 = name.strip_prefix('p')?.strip_suffix("_ms")?.parse().ok()?;
+    (percentile > 0.0 && percentile < 100.0).then_some(Metric::Percentile(percentile))
+}
+
+impl fmt::Display for Assertion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let at = |k: Option<usize>| k.map(|k| format!("@{k}")).unwrap_or_default();
+        match self.metric {
+            Metric::Percentile(p) => write!(f, "p{p}_ms")?,
+            Metric::MeanMs => f.write_str("mean_ms")?,
+            Metric::MaxMs => f.write_str("max_ms")?,
+            Metric::Qps => f.write_str("qps")?,
+            Metric::ErrorRate => f.write_str("error_rate")?,
+            Metric::TimeoutRate => f.write_str("timeout_rate")?,
+            Metric::Recall(k) => write!(f, "recall{}", at(k))?,
+            Metric::Ndcg(k) => write!(f, "ndcg{}", at(k))?,
+            Metric::Mrr => f.write_str("mrr")?,
+        }
+        let operator = match self.operator {
+            Operator::Less => "<",
+            Operator::LessOrEqual => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterOrEqual => ">=",
+        };
+        write!(f, " {operator} {}", self.threshold)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_assertions() {
+        let parsed = |s: &str| s.parse::<Assertion>().unwrap().to_string();
+        assert_eq!(parsed("p99_ms < 150"), "p99_ms < 150");
+        assert_eq!(parsed("p99.9_ms<=400"), "p99.9_ms <= 400");
+        assert_eq!(parsed("error_rate < 0.01"), "error_rate < 0.01");
+        assert_eq!(parsed("Recall@10 > 0.9"), "recall@10 > 0.9");
+        assert_eq!(parsed("ndcg >= 0.5"), "ndcg >= 0.5");
+
+        for invalid in [
+            "p99_ms = 150",
+            "latency < 3",
+            "qps > fast",
+            "qps@10 > 1",
+            "p100_ms < 1",
+        ] {
+            assert!(invalid.parse::<Assertion>().is_err(), "{invalid}");
+        }
+        assert!(Operator::LessOrEqual.holds(1.0, 1.0));
+        assert!(!Operator::Less.holds(1.0, 1.0));
+    }
+}
@@ -0,0 +1,185 @@
+//! Search for the maximum request rate a provider sustains within an SLO.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::CapacityConfig;
+use crate::error::{Error, Result};
+use crate::metrics::{LatencyMetrics, Metrics};
+
+/// Results at one offered rate of a capacity search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapacityStep {
+    /// Rate requests were sent at
+    pub offered_qps: f64,
+    /// Rate queries actually completed at
+    pub qps: f64,
+    pub bursts: usize,
+    pub query_count: usize,
+    pub failure_count: usize,
+    /// Share of queries that failed or timed out
+    pub error_rate: f64,
+    /// Latency percentiles across every query at this rate
+    pub latency: LatencyMetrics,
+    /// Mean Recall@k if ground truth was provided
+    pub recall_at_k: Option<f64>,
+    /// Whether the step met the SLO
+    pub passed: bool,
+}
+
+/// Outcome of a capacity search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapacityReport {
+    pub provider: String,
+    /// p99 latency target, in milliseconds
+    pub p99_ms: f64,
+    pub max_error_rate: f64,
+    /// Highest offered rate that met the SLO, if any did
+    pub max_sustainable_qps: Option<f64>,
+    /// Every step, in the order it ran
+    pub steps: Vec<CapacityStep>,
+}
+
+/// Chooses the next rate to try from the results so far
+#[derive(Debug, Clone)]
+pub struct CapacitySearch {
+    config: CapacityConfig,
+    /// Highest rate that passed
+    passed: Option<f64>,
+    /// Lowest rate that failed
+    failed: Option<f64>,
+    next: Option<f64>,
+    steps: usize,
+}
+
+impl CapacitySearch {
+    pub fn new(config: CapacityConfig) -> Result<Self> {
+        if !(config.start_qps.is_finite() && config.start_qps > 0.0) {
+            return Err(Error::Config(
+                "capacity.start_qps must be a positive number".into(),
+            ));
+        }
+        if !(config.growth.is_finite() && config.growth > 1.0) {
+            return Err(Error::Config(
+                "capacity.growth must be greater than 1".into(),
+            ));
+        }
+        if !(config.precision > 0.0 && config.precision < 1.0) {
+            return Err(Error::Config(
+                "capacity.precision must be between 0 and 1".into(),
+            ));
+        }
+        if config.bursts_per_step == 0 {
+            return Err(Error::Config(
+                "capacity.bursts_per_step must be at least 1".into(),
+            ));
+        }
+
+        let start = match config.max_qps {
+            Some(max) => config.start_qps.min(max),
+            None => config.start_qps,
+        };
+        Ok(Self {
+            config,
+            passed: None,
+            failed: None,
+            next: Some(start),
+            steps: 0,
+        })
+    }
+
+    pub fn config(&self) -> &CapacityConfig {
+        &self.config
+    }
+
+    /// Rate to try next, or `None` once the search is over
+    pub fn next_rate(&self) -> Option<f64> {
+        self.next
+    }
+
+    /// Highest rate found to meet the SLO so far
+    pub fn max_sustainable_qps(&self) -> Option<f64> {
+        self.passed
+    }
+
+    /// Judge the metrics from a step at `offered_qps` against the SLO and
+    /// pick the next rate
+    pub fn record(&mut self, offered_qps: f64, metrics: &Metrics) -> CapacityStep {
+        let query_count = metrics.total_queries();
+        let failure_count = query_count - metrics.total_successes();
+        let error_rate = if query_count == 0 {
+            1.0
+        } else {
+            failure_count as f64 / query_count as f64
+        };
+        let latency = metrics.aggregate_latency();
+        let passed = query_count > 0
+            && error_rate <= self.config.max_error_rate
+            && latency.p99_us as f64 / 1000.0 <= self.config.p99_ms;
+
+        self.advance(offered_qps, passed);
+        CapacityStep {
+            offered_qps,
+            qps: metrics.average_qps(),
+            bursts: metrics.bursts().len(),
+            query_count,
+            failure_count,
+            error_rate,
+            latency,
+            recall_at_k: metrics.average_recall(),
+            passed,
+        }
+    }
+
+    fn advance(&mut self, rate: f64, passed: bool) {
+        self.steps += 1;
+        if passed {
+            self.passed = Some(self.passed.map_or(rate, |best| best.max(rate)));
+        } else {
+            self.failed = Some(self.failed.map_or(rate, |worst| worst.min(rate)));
+        }
+
+        self.next = match self.failed {
+            // Still ramping up: grow until something fails or the cap is reached
+            None => match self.config.max_qps {
+                Some(max) if rate >= max => None,
+                Some(max) => Some((rate * self.config.growth).min(max)),
+                None => Some(rate * self.config.growth),
+            },
+            // Bisect between the best pass (or zero) and the lowest failure
+            Some(failed) => {
+                let passed = self.passed.unwrap_or(0.0);
+                let converged = failed - passed <= self.config.precision * failed;
+                (!converged).then_some((passed + failed) / 2.0)
+            }
+        };
+        if self.steps >= self.config.max_steps {
+            self.next = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capacity_search_brackets_the_knee() {
+        let mut search = CapacitySearch::new(CapacityConfig {
+            start_qps: 100.0,
+            precision: 0.1,
+            ..Default::default()
+        })
+        .unwrap();
+
+        // The provider keeps up with anything below 700 QPS
+        let mut tried = Vec::new();
+        while let Some(rate) = search.next_rate() {
+            tried.push(rate);
+            search.advance(rate, rate < 700.0);
+        }
+
+        assert_eq!(&tried[..4], [100.0, 200.0, 400.0, 800.0]);
+        let best = search.max_sustainable_qps().unwrap();
+        assert!((630.0..700.0).contains(&best), "best = {best}");
+    }
+}
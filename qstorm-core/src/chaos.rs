@@ -0,0 +1,257 @@
+//! Degraded backends on demand.
+//!
+//! [`ChaosProvider`] wraps a real provider and makes its searches slower or
+//! less reliable as `chaos` in the config says: a fixed added latency,
+//! random jitter, dropped requests that hang until their timeout, and
+//! periodic stalls. Injected time is part of each search's measured latency,
+//! so SLOs, stop conditions, and reports see it like a slow engine. Only
+//! searches are affected; scrolls, writes, and admin calls pass straight
+//! through.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use rand::Rng;
+
+use crate::config::{ChaosConfig, ChaosStall, DistanceMetric};
+use crate::error::{Error, Result};
+use crate::provider::{Capabilities, ProviderFactory, SearchProvider};
+use crate::types::{Document, ScrollPage, ScrollParams, SearchParams, SearchResults};
+
+impl ChaosConfig {
+    /// Check every setting is in range
+    pub fn validate(&self) -> Result<()> {
+        let time = |ms: f64| ms.is_finite() && ms >= 0.0;
+        if !time(self.latency_ms) || !time(self.jitter_ms) {
+            return Err(Error::Config(format!(
+                "chaos.latency_ms and chaos.jitter_ms can't be negative, got {} and {}",
+                self.latency_ms, self.jitter_ms
+            )));
+        }
+        if !(0.0..=1.0).contains(&self.drop_rate) {
+            return Err(Error::Config(format!(
+                "chaos.drop_rate must be between 0 and 1, got {}",
+                self.drop_rate
+            )));
+        }
+        if let Some(stall) = &self.stall
+            && !(stall.duration_secs > 0.0 && stall.duration_secs < stall.every_secs)
+        {
+            return Err(Error::Config(format!(
+                "chaos.stall.duration_secs must be positive and less than every_secs, got {} and {}",
+                stall.duration_secs, stall.every_secs
+            )));
+        }
+        Ok(())
+    }
+
+    /// Extra time a search sent at `now` waits before going out
+    fn delay(&self, now: SystemTime, rng: &mut impl Rng) -> Duration {
+        let mut ms = self.latency_ms;
+        if self.jitter_ms > 0.0 {
+            ms += rng.random_range(0.0..self.jitter_ms);
+        }
+        let stalled = self.stall.map(|stall| stall.remaining(now));
+        Duration::from_secs_f64(ms / 1000.0) + stalled.unwrap_or_default()
+    }
+}
+
+impl ChaosStall {
+    /// Time left in the stall under way at `now`, if any. Stalls are lined
+    /// up with the Unix epoch, so every client thread and worker stalls at
+    /// once.
+    fn remaining(&self, now: SystemTime) -> Duration {
+        let since_epoch = now.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let phase = since_epoch.as_secs_f64() % self.every_secs;
+        if phase < self.duration_secs {
+            Duration::from_secs_f64(self.duration_secs - phase)
+        } else {
+            Duration::ZERO
+        }
+    }
+}
+
+/// A provider whose searches suffer the faults in a [`ChaosConfig`]
+pub struct ChaosProvider {
+    inner: Box<dyn SearchProvider>,
+    config: ChaosConfig,
+}
+
+impl ChaosProvider {
+    pub fn new(inner: Box<dyn SearchProvider>, config: ChaosConfig) -> Self {
+        Self { inner, config }
+    }
+
+    /// Wrap every provider `factory` builds, e.g. for client threads
+    pub fn factory(factory: ProviderFactory, config: ChaosConfig) -> ProviderFactory {
+        Arc::new(move || {
+            let provider: Box<dyn SearchProvider> =
+                Box::new(ChaosProvider::new(factory()?, config.clone()));
+            Ok(provider)
+        })
+    }
+
+    /// Wait out the injected delay, or the whole timeout if the search is
+    /// dropped. Returns `params` with the time spent taken off the timeout.
+    async fn inject(&self, params: &SearchParams) -> Result<SearchParams> {
+        let (delay, dropped) = {
+            let mut rng = rand::rng();
+            (
+                self.config.delay(SystemTime::now(), &mut rng),
+                rng.random_bool(self.config.drop_rate),
+            )
+        };
+        let timeout = Duration::from_millis(params.timeout_ms);
+        if dropped || delay >= timeout {
+            tokio::time::sleep(timeout).await;
+            return Err(Error::Timeout(params.timeout_ms));
+        }
+        tokio::time::sleep(delay).await;
+        Ok(SearchParams {
+            timeout_ms: ((timeout - delay).as_millis() as u64).max(1),
+            ..params.clone()
+        })
+    }
+}
+
+#[async_trait]
+impl SearchProvider for ChaosProvider {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.inner.capabilities()
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        self.config.validate()?;
+        self.inner.connect().await
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.inner.disconnect().await
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        self.inner.health_check().await
+    }
+
+    async fn vector_search(&self, vector: &[f32], params: &SearchParams) -> Result<SearchResults> {
+        let params = self.inject(params).await?;
+        self.inner.vector_search(vector, &params).await
+    }
+
+    async fn hybrid_search(
+        &self,
+        text: &str,
+        vector: &[f32],
+        params: &SearchParams,
+    ) -> Result<SearchResults> {
+        let params = self.inject(params).await?;
+        self.inner.hybrid_search(text, vector, &params).await
+    }
+
+    async fn batch_search(
+        &self,
+        vectors: &[&[f32]],
+        params: &SearchParams,
+    ) -> Result<Vec<SearchResults>> {
+        let params = self.inject(params).await?;
+        self.inner.batch_search(vectors, &params).await
+    }
+
+    async fn scroll(&self, cursor: Option<&str>, params: &ScrollParams) -> Result<ScrollPage> {
+        self.inner.scroll(cursor, params).await
+    }
+
+    async fn end_scroll(&self, cursor: &str) -> Result<()> {
+        self.inner.end_scroll(cursor).await
+    }
+
+    async fn upsert(&self, documents: &[Document]) -> Result<()> {
+        self.inner.upsert(documents).await
+    }
+
+    async fn existing_ids(&self, ids: &[String]) -> Result<HashSet<String>> {
+        self.inner.existing_ids(ids).await
+    }
+
+    async fn server_stats(&self) -> Result<serde_json::Value> {
+        self.inner.server_stats().await
+    }
+
+    async fn index_exists(&self) -> Result<bool> {
+        self.inner.index_exists().await
+    }
+
+    async fn create_index(&self, dimension: usize, metric: DistanceMetric) -> Result<()> {
+        self.inner.create_index(dimension, metric).await
+    }
+
+    async fn vector_dimension(&self, vector_name: Option<&str>) -> Result<Option<usize>> {
+        self.inner.vector_dimension(vector_name).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::Instant;
+
+    use crate::config::{MockConfig, MockLatency};
+    use crate::providers::MockProvider;
+
+    use super::*;
+
+    fn chaos(latency_ms: f64, drop_rate: f64) -> ChaosProvider {
+        let mock = MockProvider::new(
+            "mock".into(),
+            MockConfig {
+                latency: MockLatency::Fixed { ms: 0.0 },
+                error_rate: 0.0,
+                dimension: None,
+                documents: 10,
+            },
+        );
+        let config = ChaosConfig {
+            latency_ms,
+            jitter_ms: 0.0,
+            drop_rate,
+            stall: None,
+        };
+        ChaosProvider::new(Box::new(mock), config)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_latency_and_drops() {
+        let params = SearchParams {
+            timeout_ms: 1000,
+            ..Default::default()
+        };
+        let mut slow = chaos(200.0, 0.0);
+        slow.connect().await.unwrap();
+        let start = Instant::now();
+        slow.vector_search(&[0.1], &params).await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_millis(200));
+
+        let mut dropping = chaos(0.0, 1.0);
+        dropping.connect().await.unwrap();
+        let start = Instant::now();
+        let result = dropping.vector_search(&[0.1], &params).await;
+        assert!(matches!(result, Err(Error::Timeout(1000))));
+        assert_eq!(start.elapsed(), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_stalls_line_up_with_the_epoch() {
+        let stall = ChaosStall {
+            every_secs: 60.0,
+            duration_secs: 5.0,
+        };
+        let at = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+        assert_eq!(stall.remaining(at(602)), Duration::from_secs(3));
+        assert_eq!(stall.remaining(at(610)), Duration::ZERO);
+    }
+}
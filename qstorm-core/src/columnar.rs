@@ -0,0 +1,324 @@
+//! Parquet output for samples and burst metrics, and input for corpora.
+//!
+//! A long run can log millions of samples. As Parquet they load into pandas,
+//! polars, or DuckDB as typed columns instead of being parsed line by line.
+//! Nested metrics are flattened into one column each, e.g. `latency_p99_us`.
+
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, Float64Array, StringArray, TimestampMicrosecondArray, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use arrow::json::ArrayWriter;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tracing::warn;
+
+use crate::error::{Error, Result};
+use crate::metrics::BurstMetrics;
+use crate::samples::{RequestKind, Sample, SampleStatus};
+
+/// Rows that can be written as a Parquet record batch
+pub(crate) trait Columnar: Serialize + Sized {
+    fn schema() -> SchemaRef;
+    fn batch(rows: &[Self]) -> Result<RecordBatch>;
+}
+
+/// A Parquet file written one batch at a time. Rows are buffered into row
+/// groups and the footer is written when the file is dropped, so the file
+/// is only readable once the run ends.
+pub(crate) struct ParquetFile {
+    writer: Option<ArrowWriter<File>>,
+}
+
+impl ParquetFile {
+    pub(crate) fn create(path: &Path, schema: SchemaRef) -> Result<Self> {
+        let props = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let writer = ArrowWriter::try_new(File::create(path)?, schema, Some(props))?;
+        Ok(Self {
+            writer: Some(writer),
+        })
+    }
+
+    pub(crate) fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        match &mut self.writer {
+            Some(writer) => Ok(writer.write(batch)?),
+            None => Err(Error::Config("Parquet file already closed".into())),
+        }
+    }
+
+    /// Bytes written so far plus those buffered for the current row group
+    pub(crate) fn size(&self) -> u64 {
+        self.writer
+            .as_ref()
+            .map_or(0, |w| (w.bytes_written() + w.in_progress_size()) as u64)
+    }
+
+    /// Write the footer now, reporting failure instead of only logging it
+    pub(crate) fn close(mut self) -> Result<()> {
+        if let Some(writer) = self.writer.take() {
+            writer.close()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ParquetFile {
+    fn drop(&mut self) {
+        if let Some(writer) = self.writer.take()
+            && let Err(e) = writer.close()
+        {
+            warn!(error = %e, "Failed to finish Parquet file");
+        }
+    }
+}
+
+impl Columnar for Sample {
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            timestamp_field(),
+            Field::new("burst", DataType::UInt64, false),
+            Field::new("kind", DataType::Utf8, false),
+            Field::new("status", DataType::Utf8, false),
+            Field::new("latency_us", DataType::UInt64, false),
+            Field::new("took_us", DataType::UInt64, true),
+            Field::new("query", DataType::Utf8, true),
+            Field::new("text", DataType::Utf8, true),
+            Field::new("documents", DataType::UInt64, true),
+            Field::new("rerank_us", DataType::UInt64, true),
+            Field::new("pages", DataType::UInt64, true),
+            Field::new("error", DataType::Utf8, true),
+        ]))
+    }
+
+    fn batch(rows: &[Self]) -> Result<RecordBatch> {
+        let columns: Vec<ArrayRef> = vec![
+            timestamps(rows.iter().map(|s| s.timestamp.timestamp_micros())),
+            Arc::new(UInt64Array::from_iter_values(
+                rows.iter().map(|s| s.burst as u64),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                rows.iter().map(|s| kind_name(s.kind)),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                rows.iter().map(|s| status_name(s.status)),
+            )),
+            Arc::new(UInt64Array::from_iter_values(
+                rows.iter().map(|s| s.latency_us),
+            )),
+            Arc::new(rows.iter().map(|s| s.took_us).collect::<UInt64Array>()),
+            Arc::new(
+                rows.iter()
+                    .map(|s| s.query.as_deref())
+                    .collect::<StringArray>(),
+            ),
+            Arc::new(
+                rows.iter()
+                    .map(|s| s.text.as_deref())
+                    .collect::<StringArray>(),
+            ),
+            Arc::new(
+                rows.iter()
+                    .map(|s| s.documents.map(|n| n as u64))
+                    .collect::<UInt64Array>(),
+            ),
+            Arc::new(rows.iter().map(|s| s.rerank_us).collect::<UInt64Array>()),
+            Arc::new(
+                rows.iter()
+                    .map(|s| s.pages.map(|n| n as u64))
+                    .collect::<UInt64Array>(),
+            ),
+            Arc::new(
+                rows.iter()
+                    .map(|s| s.error.as_deref())
+                    .collect::<StringArray>(),
+            ),
+        ];
+        Ok(RecordBatch::try_new(Self::schema(), columns)?)
+    }
+}
+
+/// A burst column: its name, whether it can be null, and how to read it
+type Column<T> = (&'static str, bool, fn(&BurstMetrics) -> Option<T>);
+
+/// Burst columns holding a count
+const BURST_COUNTS: [Column<u64>; 12] = [
+    ("duration_ms", false, |b| Some(b.duration_ms)),
+    ("query_count", false, |b| Some(b.query_count as u64)),
+    ("success_count", false, |b| Some(b.success_count as u64)),
+    ("failure_count", false, |b| Some(b.failure_count as u64)),
+    ("timeout_count", false, |b| Some(b.timeout_count as u64)),
+    ("latency_min_us", false, |b| Some(b.latency.min_us)),
+    ("latency_p50_us", false, |b| Some(b.latency.p50_us)),
+    ("latency_p90_us", false, |b| Some(b.latency.p90_us)),
+    ("latency_p95_us", false, |b| Some(b.latency.p95_us)),
+    ("latency_p99_us", false, |b| Some(b.latency.p99_us)),
+    ("latency_max_us", false, |b| Some(b.latency.max_us)),
+    ("peak_in_flight", true, |b| {
+        b.peak_in_flight.map(|n| n as u64)
+    }),
+];
+
+/// Burst columns holding a measurement
+const BURST_VALUES: [Column<f64>; 13] = [
+    ("qps", false, |b| Some(b.qps)),
+    ("latency_mean_us", false, |b| Some(b.latency.mean_us)),
+    ("latency_stddev_us", false, |b| Some(b.latency.stddev_us)),
+    ("server_p50_us", true, |b| {
+        b.server.as_ref().map(|s| s.latency.p50_us as f64)
+    }),
+    ("server_p99_us", true, |b| {
+        b.server.as_ref().map(|s| s.latency.p99_us as f64)
+    }),
+    ("recall_at_k", true, |b| b.recall_at_k),
+    ("ndcg_at_k", true, |b| b.ndcg_at_k),
+    ("mrr", true, |b| b.mrr),
+    ("probe_recall", true, |b| b.probe_recall),
+    ("stability", true, |b| b.stability),
+    ("empty_rate", true, |b| {
+        b.results.as_ref().map(|r| r.empty_rate)
+    }),
+    ("docs_per_sec", true, |b| {
+        b.writes.as_ref().map(|w| w.docs_per_sec)
+    }),
+    ("client_cpu_percent", true, |b| {
+        b.client.as_ref().map(|c| c.cpu_percent)
+    }),
+];
+
+impl Columnar for BurstMetrics {
+    fn schema() -> SchemaRef {
+        let mut fields = vec![timestamp_field(), Field::new("phase", DataType::Utf8, true)];
+        fields.extend(
+            BURST_COUNTS
+                .iter()
+                .map(|(name, nullable, _)| Field::new(*name, DataType::UInt64, *nullable)),
+        );
+        fields.extend(
+            BURST_VALUES
+                .iter()
+                .map(|(name, nullable, _)| Field::new(*name, DataType::Float64, *nullable)),
+        );
+        Arc::new(Schema::new(fields))
+    }
+
+    fn batch(rows: &[Self]) -> Result<RecordBatch> {
+        let mut columns: Vec<ArrayRef> = vec![
+            timestamps(rows.iter().map(|b| b.timestamp.timestamp_micros())),
+            Arc::new(
+                rows.iter()
+                    .map(|b| b.phase.as_deref())
+                    .collect::<StringArray>(),
+            ),
+        ];
+        columns.extend(BURST_COUNTS.iter().map(|(_, _, get)| {
+            Arc::new(rows.iter().map(get).collect::<UInt64Array>()) as ArrayRef
+        }));
+        columns.extend(BURST_VALUES.iter().map(|(_, _, get)| {
+            Arc::new(rows.iter().map(get).collect::<Float64Array>()) as ArrayRef
+        }));
+        Ok(RecordBatch::try_new(Self::schema(), columns)?)
+    }
+}
+
+/// Rows of a Parquet file as JSON objects, read one record batch at a time.
+/// Null values are left out of their row.
+pub(crate) fn read_rows(
+    path: &Path,
+) -> Result<impl Iterator<Item = Result<Map<String, Value>>> + Send + use<>> {
+    let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)?.build()?;
+    Ok(reader.flat_map(|batch| {
+        match batch
+            .map_err(Error::from)
+            .and_then(|batch| json_rows(&batch))
+        {
+            Ok(rows) => rows.into_iter().map(Ok).collect(),
+            Err(e) => vec![Err(e)],
+        }
+    }))
+}
+
+fn json_rows(batch: &RecordBatch) -> Result<Vec<Map<String, Value>>> {
+    let mut writer = ArrayWriter::new(Vec::new());
+    writer.write(batch)?;
+    writer.finish()?;
+    Ok(serde_json::from_slice(&writer.into_inner())?)
+}
+
+fn timestamp_field() -> Field {
+    Field::new(
+        "timestamp",
+        DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+        false,
+    )
+}
+
+fn timestamps(micros: impl Iterator<Item = i64>) -> ArrayRef {
+    Arc::new(TimestampMicrosecondArray::from_iter_values(micros).with_timezone("UTC"))
+}
+
+fn kind_name(kind: RequestKind) -> &'static str {
+    match kind {
+        RequestKind::Search => "search",
+        RequestKind::BatchSearch => "batch_search",
+        RequestKind::Upsert => "upsert",
+    }
+}
+
+fn status_name(status: SampleStatus) -> &'static str {
+    match status {
+        SampleStatus::Ok => "ok",
+        SampleStatus::Timeout => "timeout",
+        SampleStatus::Error => "error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    #[test]
+    fn test_samples_round_trip_through_parquet() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("samples.parquet");
+        let sample = |latency_us, error: Option<&str>| Sample {
+            timestamp: chrono::Utc::now(),
+            burst: 1,
+            kind: RequestKind::Search,
+            status: if error.is_some() {
+                SampleStatus::Error
+            } else {
+                SampleStatus::Ok
+            },
+            latency_us,
+            took_us: None,
+            query: Some("q-1".into()),
+            text: None,
+            documents: None,
+            rerank_us: None,
+            pages: None,
+            error: error.map(String::from),
+        };
+
+        let mut file = ParquetFile::create(&path, Sample::schema()).unwrap();
+        let batch = Sample::batch(&[sample(1200, None), sample(900, Some("boom"))]).unwrap();
+        file.write(&batch).unwrap();
+        file.write(&Sample::batch(&[sample(700, None)]).unwrap())
+            .unwrap();
+        drop(file);
+
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), 3);
+        assert_eq!(metadata.schema_descr().num_columns(), 12);
+    }
+}
@@ -0,0 +1,133 @@
+//! Compare two run reports and flag regressions.
+
+use serde::{Deserialize, Serialize};
+
+use crate::report::RunReport;
+
+/// How much worse a candidate may be than its baseline before it regresses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegressionThresholds {
+    /// Allowed p50 latency increase, in percent
+    pub p50_increase_pct: f64,
+    /// Allowed p99 latency increase, in percent
+    pub p99_increase_pct: f64,
+    /// Allowed QPS decrease, in percent
+    pub qps_decrease_pct: f64,
+    /// Allowed drop in mean Recall@k, as an absolute difference (0.01 = one point)
+    pub recall_decrease: f64,
+}
+
+impl Default for RegressionThresholds {
+    fn default() -> Self {
+        Self {
+            p50_increase_pct: 10.0,
+            p99_increase_pct: 10.0,
+            qps_decrease_pct: 10.0,
+            recall_decrease: 0.01,
+        }
+    }
+}
+
+/// One metric of a comparison
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricDelta {
+    pub name: String,
+    pub baseline: f64,
+    pub candidate: f64,
+    /// Change relative to the baseline, in percent (`None` for a zero baseline)
+    pub change_pct: Option<f64>,
+    pub regressed: bool,
+}
+
+/// Differences between a baseline and a candidate run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Comparison {
+    pub metrics: Vec<MetricDelta>,
+}
+
+impl Comparison {
+    pub fn new(
+        baseline: &RunReport,
+        candidate: &RunReport,
+        thresholds: &RegressionThresholds,
+    ) -> Self {
+        let ms = |us: u64| us as f64 / 1000.0;
+        let mut metrics = vec![
+            increase(
+                "p50_ms",
+                ms(baseline.latency.p50_us),
+                ms(candidate.latency.p50_us),
+                thresholds.p50_increase_pct,
+            ),
+            increase(
+                "p99_ms",
+                ms(baseline.latency.p99_us),
+                ms(candidate.latency.p99_us),
+                thresholds.p99_increase_pct,
+            ),
+        ];
+
+        let qps = delta("qps", baseline.qps, candidate.qps);
+        metrics.push(MetricDelta {
+            regressed: qps
+                .change_pct
+                .is_some_and(|change| -change > thresholds.qps_decrease_pct),
+            ..qps
+        });
+
+        // Recall is only compared when both runs measured it
+        if let (Some(base), Some(cand)) = (&baseline.recall_at_k, &candidate.recall_at_k) {
+            let recall = delta("recall_at_k", base.mean, cand.mean);
+            metrics.push(MetricDelta {
+                regressed: base.mean - cand.mean > thresholds.recall_decrease,
+                ..recall
+            });
+        }
+
+        metrics.push(delta(
+            "error_rate",
+            baseline.errors.error_rate,
+            candidate.errors.error_rate,
+        ));
+        Self { metrics }
+    }
+
+    pub fn regressed(&self) -> bool {
+        self.metrics.iter().any(|m| m.regressed)
+    }
+}
+
+/// A lower-is-better metric that regresses when it grows by more than `max_pct`
+fn increase(name: &str, baseline: f64, candidate: f64, max_pct: f64) -> MetricDelta {
+    let metric = delta(name, baseline, candidate);
+    MetricDelta {
+        regressed: metric.change_pct.is_some_and(|change| change > max_pct),
+        ..metric
+    }
+}
+
+fn delta(name: &str, baseline: f64, candidate: f64) -> MetricDelta {
+    MetricDelta {
+        name: name.to_string(),
+        baseline,
+        candidate,
+        change_pct: (baseline != 0.0).then(|| (candidate - baseline) / baseline * 100.0),
+        regressed: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thresholds_flag_only_large_changes() {
+        let within = increase("p99_ms", 10.0, 10.9, 10.0);
+        assert!(!within.regressed);
+        let over = increase("p99_ms", 10.0, 11.5, 10.0);
+        assert!(over.regressed);
+        assert!((over.change_pct.unwrap() - 15.0).abs() < 1e-9);
+        // A faster candidate never regresses
+        assert!(!increase("p50_ms", 10.0, 5.0, 10.0).regressed);
+    }
+}
@@ -0,0 +1,116 @@
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::error::{Error, Result};
+use crate::types::Document;
+use crate::vecs;
+
+type Rows = Box<dyn Iterator<Item = Result<Document>> + Send + Sync>;
+
+#[derive(Deserialize)]
+struct CorpusRecord {
+    id: Value,
+    vector: Vec<f32>,
+    #[serde(flatten)]
+    payload: Map<String, Value>,
+}
+
+/// Stream documents from a corpus vector file.
+///
+/// `.fvecs` files (SIFT/GIST format) use the row index as document ID;
+/// anything else is read as JSON lines of `{"id": ..., "vector": [...]}`,
+/// with any other fields kept as the payload.
+pub(crate) fn open(path: &Path) -> Result<Rows> {
+    if path.extension().is_some_and(|ext| ext == "fvecs") {
+        let rows = vecs::open::<f32>(path)?.enumerate().map(|(row, vector)| {
+            Ok(Document {
+                id: row.to_string(),
+                vector: Some(vector?),
+                payload: None,
+            })
+        });
+        return Ok(Box::new(rows));
+    }
+
+    let reader = BufReader::new(std::fs::File::open(path)?);
+    let rows = reader
+        .lines()
+        .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|line| {
+            let record: CorpusRecord = serde_json::from_str(&line?)?;
+            Ok(Document {
+                id: match record.id {
+                    Value::String(s) => s,
+                    other => other.to_string(),
+                },
+                vector: Some(record.vector),
+                payload: (!record.payload.is_empty()).then_some(Value::Object(record.payload)),
+            })
+        });
+    Ok(Box::new(rows))
+}
+
+/// Reads a corpus in batches, starting over from the top once it runs out
+pub(crate) struct CorpusCursor {
+    path: PathBuf,
+    rows: Rows,
+}
+
+impl CorpusCursor {
+    pub(crate) fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let rows = open(&path)?;
+        Ok(Self { path, rows })
+    }
+
+    /// The next `n` documents, wrapping around at the end of the file
+    pub(crate) fn take(&mut self, n: usize) -> Result<Vec<Document>> {
+        let mut documents = Vec::with_capacity(n);
+        let mut restarted = false;
+        while documents.len() < n {
+            match self.rows.next() {
+                Some(document) => {
+                    documents.push(document?);
+                    restarted = false;
+                }
+                None if restarted => {
+                    return Err(Error::Config(format!(
+                        "Corpus {} has no documents",
+                        self.path.display()
+                    )));
+                }
+                None => {
+                    self.rows = open(&self.path)?;
+                    restarted = true;
+                }
+            }
+        }
+        Ok(documents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_wraps_around() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("corpus.jsonl");
+        std::fs::write(
+            &path,
+            "{\"id\": 1, \"vector\": [1.0, 0.0]}\n\n{\"id\": \"b\", \"vector\": [0.0, 1.0], \"text\": \"hi\"}\n",
+        )
+        .unwrap();
+
+        let mut cursor = CorpusCursor::open(&path).unwrap();
+        let ids: Vec<String> = cursor.take(5).unwrap().into_iter().map(|d| d.id).collect();
+        assert_eq!(ids, ["1", "b", "1", "b", "1"]);
+
+        let next = cursor.take(1).unwrap();
+        assert_eq!(next[0].payload, Some(serde_json::json!({"text": "hi"})));
+    }
+}
@@ -0,0 +1,123 @@
+//! Throughput-latency curves from sweeps and capacity searches.
+//!
+//! Both kinds of run step through increasing load. A curve keeps just the
+//! numbers needed to plot achieved throughput and latency against offered
+//! load, so the knee can be read off directly.
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::capacity::CapacityReport;
+use crate::error::Result;
+use crate::metrics::SweepReport;
+
+/// What the offered load of a curve is measured in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadAxis {
+    /// Max concurrent requests (concurrency sweep)
+    Concurrency,
+    /// Requests sent per second (capacity search)
+    OfferedQps,
+}
+
+/// Results at one load level
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurvePoint {
+    /// Load applied, in the curve's `load` unit
+    pub offered: f64,
+    /// Rate queries actually completed at
+    pub qps: f64,
+    pub p50_ms: f64,
+    pub p99_ms: f64,
+    /// Share of queries that failed or timed out
+    pub error_rate: f64,
+}
+
+/// Offered load against achieved QPS and latency, in the order levels ran
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Curve {
+    pub provider: String,
+    pub load: LoadAxis,
+    pub points: Vec<CurvePoint>,
+}
+
+impl Curve {
+    pub fn from_sweep(report: &SweepReport) -> Self {
+        let points = report
+            .points
+            .iter()
+            .map(|p| CurvePoint {
+                offered: p.concurrency as f64,
+                qps: p.qps,
+                p50_ms: p.latency.p50_us as f64 / 1000.0,
+                p99_ms: p.latency.p99_us as f64 / 1000.0,
+                error_rate: if p.query_count == 0 {
+                    0.0
+                } else {
+                    p.failure_count as f64 / p.query_count as f64
+                },
+            })
+            .collect();
+        Self {
+            provider: report.provider.clone(),
+            load: LoadAxis::Concurrency,
+            points,
+        }
+    }
+
+    /// Capacity steps are kept in the order they ran, so a bisection goes
+    /// back and forth; sort by `offered` to plot it as a line
+    pub fn from_capacity(report: &CapacityReport) -> Self {
+        let points = report
+            .steps
+            .iter()
+            .map(|s| CurvePoint {
+                offered: s.offered_qps,
+                qps: s.qps,
+                p50_ms: s.latency.p50_us as f64 / 1000.0,
+                p99_ms: s.latency.p99_us as f64 / 1000.0,
+                error_rate: s.error_rate,
+            })
+            .collect();
+        Self {
+            provider: report.provider.clone(),
+            load: LoadAxis::OfferedQps,
+            points,
+        }
+    }
+
+    /// One row per point, headed by the load unit (`concurrency` or `offered_qps`)
+    pub fn to_csv(&self) -> String {
+        let load = match self.load {
+            LoadAxis::Concurrency => "concurrency",
+            LoadAxis::OfferedQps => "offered_qps",
+        };
+        let mut out = format!("{load},qps,p50_ms,p99_ms,error_rate\n");
+        for p in &self.points {
+            let _ = writeln!(
+                out,
+                "{},{:.2},{:.2},{:.2},{:.4}",
+                p.offered, p.qps, p.p50_ms, p.p99_ms, p.error_rate
+            );
+        }
+        out
+    }
+
+    /// Write the curve as CSV if `path` ends in `.csv`, JSON otherwise
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let is_csv = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        let contents = if is_csv {
+            self.to_csv()
+        } else {
+            serde_json::to_string_pretty(self)? + "\n"
+        };
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,223 @@
+//! Live web dashboard for watching a run from a browser.
+//!
+//! Serves a page at `/` that charts QPS, latency, search quality, and error
+//! rate as bursts finish, fed by a Server-Sent Events stream at `/events`.
+//! The page is built into the binary and draws on a canvas, so browsers need
+//! nothing beyond the dashboard itself. Each new stream starts with the most
+//! recent bursts, so a page opened mid-run has history to show.
+
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::sync::broadcast;
+use tracing::{debug, info};
+
+use crate::error::Result;
+use crate::html::escape;
+use crate::http::{self, Request};
+use crate::metrics::BurstMetrics;
+
+const PAGE: &str = include_str!("dashboard.html");
+
+/// Bursts a new stream starts with
+const BACKLOG: usize = 500;
+
+/// Largest request head read before answering
+const MAX_REQUEST_BYTES: usize = 8192;
+
+/// How often an idle stream gets a comment, so proxies keep it open and
+/// closed browsers are noticed
+const KEEPALIVE: Duration = Duration::from_secs(15);
+
+/// Serves the dashboard over HTTP until dropped
+pub struct Dashboard {
+    history: Arc<Mutex<History>>,
+    addr: SocketAddr,
+    server: tokio::task::JoinHandle<()>,
+}
+
+/// One burst as an SSE event: its sequence number and its JSON
+type Event = Arc<(u64, String)>;
+
+struct History {
+    provider: String,
+    /// Most recent bursts, oldest first
+    recent: VecDeque<Event>,
+    bursts: u64,
+    /// Reaches every open stream. Sent to while `History` is locked, so a
+    /// stream that copies `recent` and subscribes under the same lock sees
+    /// every burst exactly once.
+    events: broadcast::Sender<Event>,
+}
+
+impl Dashboard {
+    /// Start serving on `addr`
+    pub async fn bind(addr: impl ToSocketAddrs, provider: &str) -> Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        info!(%addr, "Serving the dashboard");
+
+        let history = Arc::new(Mutex::new(History {
+            provider: provider.to_string(),
+            recent: VecDeque::new(),
+            bursts: 0,
+            events: broadcast::channel(BACKLOG).0,
+        }));
+        let server = tokio::spawn(serve(listener, Arc::clone(&history)));
+        Ok(Self {
+            history,
+            addr,
+            server,
+        })
+    }
+
+    /// Address the dashboard is listening on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Send a finished burst to every open page
+    pub fn record(&self, burst: &BurstMetrics) -> Result<()> {
+        let json = serde_json::to_string(burst)?;
+        let mut history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        history.bursts += 1;
+        let event = Arc::new((history.bursts, json));
+        if history.recent.len() == BACKLOG {
+            history.recent.pop_front();
+        }
+        history.recent.push_back(Arc::clone(&event));
+        // No receivers just means no page is open
+        let _ = history.events.send(event);
+        Ok(())
+    }
+}
+
+impl Drop for Dashboard {
+    fn drop(&mut self) {
+        self.server.abort();
+        // Dropping the only sender ends the open streams
+        let mut history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        history.events = broadcast::channel(1).0;
+    }
+}
+
+async fn serve(listener: TcpListener, history: Arc<Mutex<History>>) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                debug!("Dashboard accept failed: {e}");
+                continue;
+            }
+        };
+        let history = Arc::clone(&history);
+        tokio::spawn(async move {
+            if let Err(e) = respond(stream, &history).await {
+                debug!(%peer, "Dashboard request failed: {e}");
+            }
+        });
+    }
+}
+
+/// Answer a single HTTP/1.1 request, streaming bursts until the browser
+/// goes away if it asked for `/events`
+async fn respond(mut stream: TcpStream, history: &Mutex<History>) -> std::io::Result<()> {
+    let text = "text/plain; charset=utf-8";
+    let Some(Request { method, path, .. }) =
+        http::read_request(&mut stream, MAX_REQUEST_BYTES).await?
+    else {
+        return http::respond(&mut stream, 400, text, "Malformed request\n").await;
+    };
+
+    match (method.as_str(), path.as_str()) {
+        ("GET", "/") => {
+            let provider = history
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .provider
+                .clone();
+            let page = PAGE.replace("{{provider}}", &escape(&provider));
+            http::respond(&mut stream, 200, "text/html; charset=utf-8", &page).await
+        }
+        ("GET", "/events") => stream_events(stream, history).await,
+        ("GET", _) => http::respond(&mut stream, 404, text, "Not found\n").await,
+        _ => http::respond(&mut stream, 405, text, "Method not allowed\n").await,
+    }
+}
+
+/// Send the recent bursts, then each new one as it finishes
+async fn stream_events(mut stream: TcpStream, history: &Mutex<History>) -> std::io::Result<()> {
+    let (backlog, mut events) = {
+        let history = history.lock().unwrap_or_else(|e| e.into_inner());
+        (
+            history.recent.iter().cloned().collect::<Vec<_>>(),
+            history.events.subscribe(),
+        )
+    };
+    stream
+        .write_all(
+            b"HTTP/1.1 200 OK\r\n\
+              Content-Type: text/event-stream\r\n\
+              Cache-Control: no-cache\r\n\
+              Connection: close\r\n\r\n",
+        )
+        .await?;
+    for event in backlog {
+        stream.write_all(format_event(&event).as_bytes()).await?;
+    }
+
+    loop {
+        match tokio::time::timeout(KEEPALIVE, events.recv()).await {
+            Ok(Ok(event)) => stream.write_all(format_event(&event).as_bytes()).await?,
+            // A page too slow to keep up just misses some bursts
+            Ok(Err(broadcast::error::RecvError::Lagged(_))) => continue,
+            Ok(Err(broadcast::error::RecvError::Closed)) => break,
+            Err(_) => stream.write_all(b": keepalive\n\n").await?,
+        }
+    }
+    stream.shutdown().await
+}
+
+fn format_event(event: &(u64, String)) -> String {
+    let (id, json) = event;
+    format!("id: {id}\ndata: {json}\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt;
+
+    use crate::metrics::Metrics;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stream_starts_with_recent_bursts() {
+        let dashboard = Dashboard::bind("127.0.0.1:0", "local").await.unwrap();
+        let mut metrics = Metrics::new();
+        for _ in 0..2 {
+            metrics.start_burst();
+            metrics.record_success(Duration::from_millis(3), None);
+            dashboard.record(&metrics.finish_burst().unwrap()).unwrap();
+        }
+
+        let mut stream = TcpStream::connect(dashboard.local_addr()).await.unwrap();
+        stream
+            .write_all(b"GET /events HTTP/1.1\r\n\r\n")
+            .await
+            .unwrap();
+        let mut received = String::new();
+        let mut buf = [0u8; 4096];
+        while !received.contains("id: 2\n") {
+            let n = stream.read(&mut buf).await.unwrap();
+            assert!(n > 0, "stream closed early: {received}");
+            received.push_str(&String::from_utf8_lossy(&buf[..n]));
+        }
+        assert!(received.contains("text/event-stream"));
+        assert!(received.contains("id: 1\ndata: {"));
+    }
+}
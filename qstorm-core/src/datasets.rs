@@ -0,0 +1,368 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use flate2::read::GzDecoder;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tracing::info;
+
+use crate::error::{Error, Result};
+use crate::queries::{Query, QueryFile};
+use crate::vecs;
+
+const BEIR_BASE_URL: &str = "https://public.ukp.informatik.tu-darmstadt.de/thakur/BEIR/datasets";
+/// TEXMEX archives are only served over FTP, which qstorm doesn't speak
+const TEXMEX_FTP_URL: &str = "ftp://ftp.irisa.fr/local/texmex/corpus";
+
+/// A standard benchmark dataset that can be fetched and converted to qstorm files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Dataset {
+    /// A BEIR dataset (e.g. `scifact`, `nfcorpus`) using the given qrels split
+    Beir { name: String, split: String },
+    /// TEXMEX SIFT1M or GIST1M vectors with exact ground truth
+    Texmex(TexmexSet),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TexmexSet {
+    Sift,
+    Gist,
+}
+
+impl TexmexSet {
+    fn name(&self) -> &'static str {
+        match self {
+            TexmexSet::Sift => "sift",
+            TexmexSet::Gist => "gist",
+        }
+    }
+}
+
+/// Parses `msmarco`, `sift`, `gist`, or `beir/<name>[:<split>]` (split defaults to `test`)
+impl FromStr for Dataset {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            // BEIR redistributes the MS MARCO passage corpus with the dev queries/qrels
+            "msmarco" => Ok(Dataset::Beir {
+                name: "msmarco".into(),
+                split: "dev".into(),
+            }),
+            "sift" | "sift1m" => Ok(Dataset::Texmex(TexmexSet::Sift)),
+            "gist" | "gist1m" => Ok(Dataset::Texmex(TexmexSet::Gist)),
+            other => {
+                let spec = other.strip_prefix("beir/").ok_or_else(|| {
+                    Error::Config(format!(
+                        "Unknown dataset '{s}'. Expected msmarco, sift, gist, or beir/<name>[:<split>]"
+                    ))
+                })?;
+                let (name, split) = spec.split_once(':').unwrap_or((spec, "test"));
+                Ok(Dataset::Beir {
+                    name: name.into(),
+                    split: split.into(),
+                })
+            }
+        }
+    }
+}
+
+impl Dataset {
+    /// Directory name the dataset is extracted to
+    pub fn name(&self) -> &str {
+        match self {
+            Dataset::Beir { name, .. } => name,
+            Dataset::Texmex(set) => set.name(),
+        }
+    }
+
+    /// Where qstorm downloads the source archive from, if it can fetch it itself
+    pub fn url(&self) -> Option<String> {
+        match self {
+            Dataset::Beir { name, .. } => Some(format!("{BEIR_BASE_URL}/{name}.zip")),
+            Dataset::Texmex(_) => None,
+        }
+    }
+
+    /// File name of the source archive
+    fn archive_name(&self) -> String {
+        match self {
+            Dataset::Beir { name, .. } => format!("{name}.zip"),
+            Dataset::Texmex(set) => format!("{}.tar.gz", set.name()),
+        }
+    }
+}
+
+/// Files produced by [`fetch`]
+#[derive(Debug, Clone)]
+pub struct FetchedDataset {
+    /// qstorm query file, referencing the ground truth (and vectors, for TEXMEX)
+    pub queries: PathBuf,
+    /// Query id -> expected document IDs, or graded relevance for BEIR
+    pub ground_truth: PathBuf,
+    /// Raw corpus: BEIR `corpus.jsonl` or TEXMEX `*_base.fvecs`
+    pub corpus: PathBuf,
+}
+
+/// Download (unless `archive` is given or already cached in `root`), extract, and convert a dataset
+pub async fn fetch(
+    dataset: &Dataset,
+    root: &Path,
+    archive: Option<&Path>,
+) -> Result<FetchedDataset> {
+    std::fs::create_dir_all(root)?;
+
+    let archive = match archive {
+        Some(path) => path.to_path_buf(),
+        None => {
+            let path = root.join(dataset.archive_name());
+            if !path.exists() {
+                let url = dataset.url().ok_or_else(|| {
+                    Error::Dataset(format!(
+                        "{} is only published over FTP; download {TEXMEX_FTP_URL}/{} yourself and pass it with --archive",
+                        dataset.name(),
+                        dataset.archive_name()
+                    ))
+                })?;
+                download(&url, &path).await?;
+            }
+            path
+        }
+    };
+
+    let dataset = dataset.clone();
+    let root = root.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        info!(archive = %archive.display(), "Extracting dataset");
+        match &dataset {
+            Dataset::Beir { name, split } => {
+                extract_zip(&archive, &root)?;
+                convert_beir(&root.join(name), split)
+            }
+            Dataset::Texmex(set) => {
+                extract_tar_gz(&archive, &root)?;
+                convert_texmex(&root.join(set.name()), set.name())
+            }
+        }
+    })
+    .await
+    .map_err(|e| Error::Dataset(format!("Conversion task failed: {e}")))?
+}
+
+/// Whether `download` can fetch `url`
+fn check_scheme(url: &str) -> Result<()> {
+    if url.starts_with("https://") || url.starts_with("http://") {
+        Ok(())
+    } else {
+        Err(Error::Dataset(format!(
+            "{url} isn't an HTTP URL; download it manually and pass the archive path"
+        )))
+    }
+}
+
+async fn download(url: &str, dest: &Path) -> Result<()> {
+    check_scheme(url)?;
+
+    info!(url, "Downloading dataset");
+    let mut response = reqwest::get(url).await.map_err(|e| Error::Connection {
+        message: format!("Failed to download {url}: {e}"),
+        source: Some(e.into()),
+    })?;
+    if !response.status().is_success() {
+        return Err(Error::connection_message(format!(
+            "Failed to download {url}: HTTP {}",
+            response.status()
+        )));
+    }
+
+    let pb = ProgressBar::new(response.content_length().unwrap_or(0));
+    pb.set_style(
+        ProgressStyle::with_template(
+            "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})",
+        )
+        .unwrap()
+        .progress_chars("#>-"),
+    );
+
+    // Write to a temporary name so an interrupted download isn't mistaken for a cached archive
+    let partial = dest.with_extension("part");
+    let mut file = tokio::fs::File::create(&partial).await?;
+    while let Some(chunk) = response.chunk().await.map_err(|e| Error::Connection {
+        message: format!("Failed to download {url}: {e}"),
+        source: Some(e.into()),
+    })? {
+        file.write_all(&chunk).await?;
+        pb.inc(chunk.len() as u64);
+    }
+    file.flush().await?;
+    tokio::fs::rename(&partial, dest).await?;
+
+    pb.finish_and_clear();
+    Ok(())
+}
+
+fn extract_zip(archive: &Path, dest: &Path) -> Result<()> {
+    zip::ZipArchive::new(File::open(archive)?)
+        .and_then(|mut zip| zip.extract(dest))
+        .map_err(|e| Error::Dataset(format!("Failed to extract {}: {e}", archive.display())))
+}
+
+fn extract_tar_gz(archive: &Path, dest: &Path) -> Result<()> {
+    tar::Archive::new(GzDecoder::new(File::open(archive)?)).unpack(dest)?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct BeirQuery {
+    #[serde(rename = "_id")]
+    id: String,
+    text: String,
+}
+
+/// Convert BEIR `queries.jsonl` + `qrels/<split>.tsv` into `queries.yaml` + `qrels.yaml`
+fn convert_beir(dir: &Path, split: &str) -> Result<FetchedDataset> {
+    let qrels_path = dir.join("qrels").join(format!("{split}.tsv"));
+    let mut qrels: HashMap<String, Vec<(String, f64)>> = HashMap::new();
+
+    for line in BufReader::new(File::open(&qrels_path)?).lines().skip(1) {
+        let line = line?;
+        let mut fields = line.split('\t');
+        let (Some(query_id), Some(doc_id), Some(score)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let score: f64 = score.trim().parse().unwrap_or(0.0);
+        if score > 0.0 {
+            qrels
+                .entry(query_id.to_string())
+                .or_default()
+                .push((doc_id.to_string(), score));
+        }
+    }
+
+    let mut queries = Vec::new();
+    let mut ground_truth = BTreeMap::new();
+    for line in BufReader::new(File::open(dir.join("queries.jsonl"))?).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let query: BeirQuery = serde_json::from_str(&line)?;
+        let Some(relevant) = qrels.remove(&query.id) else {
+            continue;
+        };
+
+        // Keep the grades so NDCG can weigh the strongest judgements
+        ground_truth.insert(
+            query.id.clone(),
+            relevant.into_iter().collect::<BTreeMap<_, _>>(),
+        );
+
+        let mut entry = Query::new(query.text);
+        entry.id = Some(query.id);
+        queries.push(entry);
+    }
+
+    info!(queries = queries.len(), split, "Converted BEIR dataset");
+    write_files(dir, queries, ground_truth, None, dir.join("corpus.jsonl"))
+}
+
+/// Convert TEXMEX `<name>_query.fvecs` + `<name>_groundtruth.ivecs` into qstorm files
+fn convert_texmex(dir: &Path, name: &str) -> Result<FetchedDataset> {
+    let vectors = format!("{name}_query.fvecs");
+    let mut queries = Vec::new();
+    let mut ground_truth = BTreeMap::new();
+
+    for (row, neighbors) in
+        vecs::open::<i32>(dir.join(format!("{name}_groundtruth.ivecs")))?.enumerate()
+    {
+        let id = row.to_string();
+        ground_truth.insert(
+            id.clone(),
+            neighbors?
+                .into_iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>(),
+        );
+
+        let mut query = Query::new(format!("{name}-query-{row}"));
+        query.id = Some(id);
+        queries.push(query);
+    }
+
+    info!(queries = queries.len(), "Converted {name} dataset");
+    write_files(
+        dir,
+        queries,
+        ground_truth,
+        Some(vectors),
+        dir.join(format!("{name}_base.fvecs")),
+    )
+}
+
+fn write_files(
+    dir: &Path,
+    queries: Vec<Query>,
+    ground_truth: BTreeMap<String, impl Serialize>,
+    vectors: Option<String>,
+    corpus: PathBuf,
+) -> Result<FetchedDataset> {
+    let query_file = QueryFile {
+        queries,
+        templates: Vec::new(),
+        ground_truth: Some("qrels.yaml".into()),
+        vectors,
+    };
+
+    let queries_path = dir.join("queries.yaml");
+    let ground_truth_path = dir.join("qrels.yaml");
+    std::fs::write(&queries_path, serde_yaml::to_string(&query_file)?)?;
+    std::fs::write(&ground_truth_path, serde_yaml::to_string(&ground_truth)?)?;
+
+    Ok(FetchedDataset {
+        queries: queries_path,
+        ground_truth: ground_truth_path,
+        corpus,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dataset() {
+        assert_eq!(
+            "beir/scifact".parse::<Dataset>().unwrap(),
+            Dataset::Beir {
+                name: "scifact".into(),
+                split: "test".into()
+            }
+        );
+        assert_eq!(
+            "msmarco".parse::<Dataset>().unwrap().url(),
+            Some(format!("{BEIR_BASE_URL}/msmarco.zip"))
+        );
+        assert_eq!(
+            "SIFT".parse::<Dataset>().unwrap(),
+            Dataset::Texmex(TexmexSet::Sift)
+        );
+        assert!("imagenet".parse::<Dataset>().is_err());
+    }
+
+    #[test]
+    fn test_catalogue_urls_can_be_downloaded() {
+        for name in ["msmarco", "beir/scifact", "sift", "gist"] {
+            let dataset: Dataset = name.parse().unwrap();
+            if let Some(url) = dataset.url() {
+                check_scheme(&url).unwrap();
+            }
+        }
+        assert!(check_scheme(&format!("{TEXMEX_FTP_URL}/sift.tar.gz")).is_err());
+    }
+}
@@ -0,0 +1,291 @@
+//! Coordinator/worker protocol for spreading load over several machines.
+//!
+//! Workers connect to the coordinator, receive the provider settings and the
+//! embedded query set, then run bursts in lockstep on request and send back
+//! their metrics, which the coordinator merges into one burst.
+//! Messages are newline-delimited JSON over TCP.
+//!
+//! A worker's first message must carry the run's shared token, or the
+//! coordinator hangs up on it. The link itself isn't encrypted, so provider
+//! settings go out with their secrets masked and each worker fills them in
+//! from its own config.
+
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tracing::{info, warn};
+
+use crate::config::{BenchmarkConfig, ChaosConfig, ProviderConfig};
+use crate::error::{Error, Result};
+use crate::metrics::{BurstMetrics, BurstSamples, merge_bursts};
+use crate::queries::EmbeddedQuery;
+
+/// Address the coordinator listens on when none is given
+pub const DEFAULT_LISTEN_ADDR: &str = "127.0.0.1:7700";
+
+/// How long a new connection has to present the token
+const JOIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sent from the coordinator to a worker
+// Messages are serialized straight away, so variant sizes don't matter
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CoordinatorMessage {
+    /// Everything a worker needs to run its share of the load
+    Assign {
+        worker: usize,
+        /// Redacted; see [`ProviderConfig::with_secrets_from`]
+        provider: ProviderConfig,
+        benchmark: BenchmarkConfig,
+        seed: Option<u64>,
+        queries: Vec<EmbeddedQuery>,
+        /// Faults the worker injects into its searches
+        #[serde(default)]
+        chaos: Option<ChaosConfig>,
+    },
+    /// Run one burst and report back
+    Burst { index: usize },
+    /// The run is over; disconnect and exit
+    Done,
+    /// The worker's token was wrong
+    Rejected { reason: String },
+}
+
+/// Sent from a worker to the coordinator
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorkerMessage {
+    /// First message on a new link, proving the worker belongs to the run
+    Join { token: String },
+    /// Connected to the provider and warmed up
+    Ready,
+    /// Result of a requested burst
+    Burst {
+        index: usize,
+        metrics: BurstMetrics,
+        samples: BurstSamples,
+    },
+    /// The worker could not carry out the last request
+    Failed { error: String },
+}
+
+/// One end of a coordinator/worker link
+pub struct Connection {
+    reader: BufReader<OwnedReadHalf>,
+    writer: OwnedWriteHalf,
+    line: String,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> Self {
+        let (reader, writer) = stream.into_split();
+        Self {
+            reader: BufReader::new(reader),
+            writer,
+            line: String::new(),
+        }
+    }
+
+    /// Connect to a coordinator and present `token`
+    pub async fn join(addr: impl ToSocketAddrs, token: &str) -> Result<Self> {
+        let stream = TcpStream::connect(addr)
+            .await
+            .map_err(|e| Error::Connection {
+                message: format!("could not reach coordinator: {e}"),
+                source: Some(e.into()),
+            })?;
+        stream.set_nodelay(true)?;
+        let mut connection = Self::new(stream);
+        connection
+            .send(&WorkerMessage::Join {
+                token: token.to_string(),
+            })
+            .await?;
+        Ok(connection)
+    }
+
+    pub async fn send<T: Serialize>(&mut self, message: &T) -> Result<()> {
+        let mut line = serde_json::to_vec(message)?;
+        line.push(b'\n');
+        self.writer.write_all(&line).await?;
+        Ok(())
+    }
+
+    /// Next message, or `None` once the other side hangs up
+    pub async fn recv<T: DeserializeOwned>(&mut self) -> Result<Option<T>> {
+        self.line.clear();
+        if self.reader.read_line(&mut self.line).await? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&self.line)?))
+    }
+}
+
+/// Drives a fixed set of workers through a benchmark
+pub struct Coordinator {
+    workers: Vec<Connection>,
+    bursts: usize,
+}
+
+impl Coordinator {
+    /// Listen on `addr` until `workers` workers have joined with `token`.
+    /// Connections without it are turned away.
+    pub async fn listen(addr: impl ToSocketAddrs, workers: usize, token: &str) -> Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        info!(addr = %listener.local_addr()?, workers, "Waiting for workers");
+
+        let mut connections = Vec::with_capacity(workers);
+        while connections.len() < workers {
+            let (stream, peer) = listener.accept().await?;
+            stream.set_nodelay(true)?;
+            let mut connection = Connection::new(stream);
+            let joined = tokio::time::timeout(JOIN_TIMEOUT, connection.recv()).await;
+            match joined {
+                Ok(Ok(Some(WorkerMessage::Join { token: got }))) if same_token(&got, token) => {
+                    connections.push(connection);
+                    info!(%peer, joined = connections.len(), workers, "Worker joined");
+                }
+                _ => {
+                    warn!(%peer, "Turned away a connection without the run's token");
+                    let _ = connection
+                        .send(&CoordinatorMessage::Rejected {
+                            reason: "wrong or missing token".into(),
+                        })
+                        .await;
+                }
+            }
+        }
+
+        Ok(Self {
+            workers: connections,
+            bursts: 0,
+        })
+    }
+
+    pub fn worker_count(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Hand every worker the provider, settings, and queries, then wait until
+    /// all of them have connected and warmed up. Each worker gets its own seed
+    /// derived from `seed` so randomized scheduling differs between them.
+    /// The provider is sent redacted.
+    pub async fn assign(
+        &mut self,
+        provider: &ProviderConfig,
+        benchmark: &BenchmarkConfig,
+        seed: Option<u64>,
+        queries: &[EmbeddedQuery],
+        chaos: Option<&ChaosConfig>,
+    ) -> Result<()> {
+        let provider = provider.redacted();
+        for (worker, connection) in self.workers.iter_mut().enumerate() {
+            connection
+                .send(&CoordinatorMessage::Assign {
+                    worker,
+                    provider: provider.clone(),
+                    benchmark: benchmark.clone(),
+                    seed: seed.map(|seed| seed.wrapping_add(worker as u64)),
+                    queries: queries.to_vec(),
+                    chaos: chaos.cloned(),
+                })
+                .await?;
+        }
+
+        for worker in 0..self.workers.len() {
+            match self.recv(worker).await? {
+                WorkerMessage::Ready => {}
+                _ => return Err(unexpected(worker)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Run one burst on every worker at once and merge their results
+    pub async fn run_burst(&mut self) -> Result<BurstMetrics> {
+        let index = self.bursts;
+        self.bursts += 1;
+
+        for connection in &mut self.workers {
+            connection
+                .send(&CoordinatorMessage::Burst { index })
+                .await?;
+        }
+
+        let mut parts = Vec::with_capacity(self.workers.len());
+        for worker in 0..self.workers.len() {
+            match self.recv(worker).await? {
+                WorkerMessage::Burst {
+                    index: got,
+                    metrics,
+                    samples,
+                } if got == index => parts.push((metrics, samples)),
+                _ => return Err(unexpected(worker)),
+            }
+        }
+
+        merge_bursts(&parts).ok_or_else(|| Error::Config("No workers connected".into()))
+    }
+
+    /// Tell every worker the run is over
+    pub async fn finish(mut self) -> Result<()> {
+        for connection in &mut self.workers {
+            connection.send(&CoordinatorMessage::Done).await?;
+        }
+        Ok(())
+    }
+
+    async fn recv(&mut self, worker: usize) -> Result<WorkerMessage> {
+        match self.workers[worker].recv().await? {
+            Some(WorkerMessage::Failed { error }) => {
+                Err(Error::query_message(format!("worker {worker}: {error}")))
+            }
+            Some(message) => Ok(message),
+            None => Err(Error::connection_message(format!(
+                "worker {worker} disconnected"
+            ))),
+        }
+    }
+}
+
+/// Compare tokens in time that doesn't depend on where they differ
+fn same_token(got: &str, expected: &str) -> bool {
+    got.len() == expected.len()
+        && got
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn unexpected(worker: usize) -> Error {
+    Error::InvalidResponse(format!("unexpected message from worker {worker}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_workers_need_the_token() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let coordinator = tokio::spawn(Coordinator::listen(addr, 1, "s3cret"));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let mut intruder = Connection::join(addr, "guess").await.unwrap();
+        let reply = intruder.recv::<CoordinatorMessage>().await.unwrap();
+        assert!(matches!(reply, Some(CoordinatorMessage::Rejected { .. })));
+
+        Connection::join(addr, "s3cret").await.unwrap();
+        let coordinator = coordinator.await.unwrap().unwrap();
+        assert_eq!(coordinator.worker_count(), 1);
+    }
+}
@@ -1,13 +1,22 @@
+use std::sync::Arc;
+
 use ::fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
 use async_trait::async_trait;
+use tracing::debug;
 
 use super::EmbeddingProvider;
 use crate::error::{Error, Result};
 use crate::queries::EmbeddedQuery;
 
+/// Texts embedded per blocking task. Progress is logged after each, and
+/// dropping the future stops after the chunk in progress.
+const CHUNK_SIZE: usize = 256;
+
 /// Fastembed-based local embedding provider
 pub struct FastEmbedProvider {
-    model: TextEmbedding,
+    model: Arc<TextEmbedding>,
+    /// Output dimension, measured once when the model loads
+    dimension: usize,
 }
 
 impl FastEmbedProvider {
@@ -16,35 +25,47 @@ impl FastEmbedProvider {
         let embedding =
             TextEmbedding::try_new(InitOptions::new(model).with_show_download_progress(true))
                 .map_err(|e| Error::Config(format!("Failed to load embedding model: {}", e)))?;
-        Ok(Self { model: embedding })
+        let dimension = embedding
+            .embed(vec!["test"], None)
+            .map(|v| v.first().map(|e| e.len()).unwrap_or(0))
+            .unwrap_or(0);
+        Ok(Self {
+            model: Arc::new(embedding),
+            dimension,
+        })
     }
 }
 
 #[async_trait]
 impl EmbeddingProvider for FastEmbedProvider {
     async fn embed_queries(&self, texts: &[String]) -> Result<Vec<EmbeddedQuery>> {
-        let embeddings = self
-            .model
-            .embed(texts.to_vec(), None)
-            .map_err(|e| Error::Config(format!("Embedding failed: {}", e)))?;
+        let mut queries = Vec::with_capacity(texts.len());
+
+        for chunk in texts.chunks(CHUNK_SIZE) {
+            let model = Arc::clone(&self.model);
+            let batch = chunk.to_vec();
 
-        let queries = texts
-            .iter()
-            .zip(embeddings)
-            .map(|(text, vector)| EmbeddedQuery {
-                text: text.clone(),
-                vector,
-            })
-            .collect();
+            // Inference is CPU-bound; keep it off the async workers so the
+            // TUI and in-flight requests keep running meanwhile
+            let embeddings = tokio::task::spawn_blocking(move || model.embed(batch, None))
+                .await
+                .map_err(|e| Error::Config(format!("Embedding task failed: {}", e)))?
+                .map_err(|e| Error::Config(format!("Embedding failed: {}", e)))?;
+
+            queries.extend(
+                chunk
+                    .iter()
+                    .zip(embeddings)
+                    .map(|(text, vector)| EmbeddedQuery::new(text.clone(), vector)),
+            );
+            debug!(embedded = queries.len(), total = texts.len(), "Embedding queries");
+        }
 
         Ok(queries)
     }
 
     fn dimension(&self) -> usize {
-        self.model
-            .embed(vec!["test"], None)
-            .map(|v| v.first().map(|e| e.len()).unwrap_or(0))
-            .unwrap_or(0)
+        self.dimension
     }
 }
 
@@ -8,11 +8,19 @@ pub use fastembed::FastEmbedProvider;
 #[cfg(feature = "openai-embeddings")]
 pub use openai::OpenAIProvider;
 
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
 use async_trait::async_trait;
+use tracing::warn;
+
+use crate::config::{Config, EmbeddingConfig};
+use crate::error::{Error, Result};
+use crate::queries::{EmbeddedQuery, NamedVector, QueryFile};
 
-use crate::config::EmbeddingConfig;
-use crate::error::Result;
-use crate::queries::EmbeddedQuery;
+/// Queries embedded per call by [`QueryEmbedder::embed_query_file`], bounding
+/// how many text copies exist at once for very large query files
+const EMBED_CHUNK_SIZE: usize = 10_000;
 
 /// Trait for embedding text into vectors
 #[async_trait]
@@ -24,23 +32,51 @@ pub trait EmbeddingProvider: Send + Sync {
     fn dimension(&self) -> usize;
 }
 
-/// Unified embedder dispatching to the configured backend
-pub enum Embedder {
-    #[cfg(feature = "embeddings")]
-    FastEmbed(FastEmbedProvider),
-    #[cfg(feature = "openai-embeddings")]
-    OpenAI(OpenAIProvider),
-    #[cfg(not(any(feature = "embeddings", feature = "openai-embeddings")))]
-    #[doc(hidden)]
-    _Disabled(std::convert::Infallible),
-}
+/// Builds an embedding backend from its config, with the backend's
+/// namespace already stripped from the model name
+pub type EmbedderFactory =
+    Arc<dyn Fn(&EmbeddingConfig) -> Result<Box<dyn EmbeddingProvider>> + Send + Sync>;
+
+static BACKENDS: RwLock<BTreeMap<String, EmbedderFactory>> = RwLock::new(BTreeMap::new());
+
+/// Embedder wrapping whichever backend the config picked, built in or not
+pub struct Embedder(Box<dyn EmbeddingProvider>);
 
 impl Embedder {
+    /// Wrap any embedding backend, e.g. one calling an internal inference service
+    pub fn new(provider: impl EmbeddingProvider + 'static) -> Self {
+        Self(Box::new(provider))
+    }
+
+    /// Dispatch models namespaced with `namespace/` to the backend built by
+    /// `factory`, taking precedence over the built-in backends
+    pub fn register(
+        namespace: impl Into<String>,
+        factory: impl Fn(&EmbeddingConfig) -> Result<Box<dyn EmbeddingProvider>> + Send + Sync + 'static,
+    ) {
+        BACKENDS
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(namespace.into(), Arc::new(factory));
+    }
+
     /// Create an embedder from configuration.
     ///
-    /// Models namespaced with `openai/` (e.g. `openai/text-embedding-3-small`)
-    /// dispatch to OpenAI; all others dispatch to fastembed.
+    /// Models namespaced with a registered backend's name go to that
+    /// backend, and those namespaced with `openai/` (e.g.
+    /// `openai/text-embedding-3-small`) to OpenAI; all others dispatch to
+    /// fastembed.
     pub fn from_config(config: &EmbeddingConfig) -> Result<Self> {
+        let registered = config.model.split_once('/').and_then(|(namespace, model)| {
+            let backends = BACKENDS.read().unwrap_or_else(|e| e.into_inner());
+            Some((backends.get(namespace)?.clone(), model))
+        });
+        if let Some((factory, model)) = registered {
+            let mut config = config.clone();
+            config.model = model.to_owned();
+            return Ok(Self(factory(&config)?));
+        }
+
         if let Some(model) = config.model.strip_prefix("openai/") {
             let mut config = config.clone();
             config.model = model.to_owned();
@@ -52,12 +88,12 @@ impl Embedder {
 
     #[cfg(feature = "openai-embeddings")]
     fn new_openai(config: &EmbeddingConfig) -> Result<Self> {
-        Ok(Self::OpenAI(OpenAIProvider::new(config)?))
+        Ok(Self::new(OpenAIProvider::new(config)?))
     }
 
     #[cfg(not(feature = "openai-embeddings"))]
     fn new_openai(config: &EmbeddingConfig) -> Result<Self> {
-        Err(crate::error::Error::Config(format!(
+        Err(Error::Config(format!(
             "Model '{}' requires the 'openai-embeddings' feature. \
              Rebuild with --features openai-embeddings",
             config.model
@@ -66,12 +102,12 @@ impl Embedder {
 
     #[cfg(feature = "embeddings")]
     fn new_fastembed(config: &EmbeddingConfig) -> Result<Self> {
-        Ok(Self::FastEmbed(FastEmbedProvider::new(&config.model)?))
+        Ok(Self::new(FastEmbedProvider::new(&config.model)?))
     }
 
     #[cfg(not(feature = "embeddings"))]
     fn new_fastembed(config: &EmbeddingConfig) -> Result<Self> {
-        Err(crate::error::Error::Config(format!(
+        Err(Error::Config(format!(
             "Model '{}' requires the 'embeddings' feature. \
              Rebuild with --features embeddings",
             config.model
@@ -79,27 +115,205 @@ impl Embedder {
     }
 
     /// Embed a batch of text queries
-    #[allow(unused_variables)]
     pub async fn embed_queries(&self, texts: &[String]) -> Result<Vec<EmbeddedQuery>> {
-        match self {
-            #[cfg(feature = "embeddings")]
-            Self::FastEmbed(p) => p.embed_queries(texts).await,
-            #[cfg(feature = "openai-embeddings")]
-            Self::OpenAI(p) => p.embed_queries(texts).await,
-            #[cfg(not(any(feature = "embeddings", feature = "openai-embeddings")))]
-            Self::_Disabled(never) => match *never {},
-        }
+        self.0.embed_queries(texts).await
     }
 
     /// Get the embedding dimension
     pub fn dimension(&self) -> usize {
-        match self {
-            #[cfg(feature = "embeddings")]
-            Self::FastEmbed(p) => p.dimension(),
-            #[cfg(feature = "openai-embeddings")]
-            Self::OpenAI(p) => p.dimension(),
-            #[cfg(not(any(feature = "embeddings", feature = "openai-embeddings")))]
-            Self::_Disabled(never) => match *never {},
+        self.0.dimension()
+    }
+}
+
+impl From<Box<dyn EmbeddingProvider>> for Embedder {
+    fn from(provider: Box<dyn EmbeddingProvider>) -> Self {
+        Self(provider)
+    }
+}
+
+/// Embeds queries with the default model plus one model per named vector
+pub struct QueryEmbedder {
+    default: Option<Embedder>,
+    named: Vec<(String, Embedder)>,
+}
+
+impl QueryEmbedder {
+    /// Embed queries with `default` alone; add named vectors with
+    /// [`with_vector`](Self::with_vector)
+    pub fn new(default: Embedder) -> Self {
+        Self {
+            default: Some(default),
+            named: Vec::new(),
+        }
+    }
+
+    /// Embed every query into the named vector `name` with `embedder`.
+    ///
+    /// Bursts only search named vectors once there are any, so the default
+    /// model is dropped and the first named vector doubles as the primary one.
+    pub fn with_vector(mut self, name: impl Into<String>, embedder: Embedder) -> Self {
+        self.default = None;
+        self.named.push((name.into(), embedder));
+        self
+    }
+
+    /// Build embedders for `embedding` or, if set, every entry in `vectors`.
+    ///
+    /// Without named vectors the default model is always used. With named
+    /// vectors, the first one doubles as the primary one and `embedding` is
+    /// ignored, since its vectors would never be sent.
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let named = config
+            .vectors
+            .iter()
+            .map(|v| Ok((v.name.clone(), Embedder::from_config(&v.embedding)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let default = match (&config.embedding, named.is_empty()) {
+            (Some(embedding), true) => Some(Embedder::from_config(embedding)?),
+            (None, true) => Some(Embedder::from_config(&EmbeddingConfig::default())?),
+            (Some(_), false) => {
+                warn!("Ignoring embedding: queries are embedded per entry in vectors");
+                None
+            }
+            (None, false) => None,
+        };
+
+        Ok(Self { default, named })
+    }
+
+    /// Output dimension of each model: `None` for the default embedding, then one per named vector
+    pub fn dimensions(&self) -> Vec<(Option<&str>, usize)> {
+        self.default
+            .iter()
+            .map(|embedder| (None, embedder.dimension()))
+            .chain(
+                self.named
+                    .iter()
+                    .map(|(name, embedder)| (Some(name.as_str()), embedder.dimension())),
+            )
+            .collect()
+    }
+
+    /// Embed every query in a file chunk by chunk, consuming it so texts and
+    /// ground truth are moved rather than copied
+    pub async fn embed_query_file(&self, query_file: QueryFile) -> Result<Vec<EmbeddedQuery>> {
+        let mut embedded = Vec::with_capacity(query_file.queries.len());
+        let mut queries = query_file.queries.into_iter().peekable();
+
+        while queries.peek().is_some() {
+            let (texts, labels): (Vec<String>, Vec<_>) = queries
+                .by_ref()
+                .take(EMBED_CHUNK_SIZE)
+                .map(|q| (q.text, (q.id, q.expected_ids, q.relevance, q.tags)))
+                .unzip();
+
+            let mut chunk = self.embed_queries(&texts).await?;
+            for (query, (id, expected_ids, relevance, tags)) in chunk.iter_mut().zip(labels) {
+                query.id = id.map(Into::into);
+                query.expected_ids = expected_ids;
+                query.relevance = relevance;
+                query.tags = tags;
+            }
+            embedded.append(&mut chunk);
+        }
+
+        Ok(embedded)
+    }
+
+    /// Embed a batch of text queries with every configured model
+    pub async fn embed_queries(&self, texts: &[String]) -> Result<Vec<EmbeddedQuery>> {
+        let mut named = Vec::with_capacity(self.named.len());
+        for (name, embedder) in &self.named {
+            let embedded = embedder.embed_queries(texts).await?;
+            if embedded.len() != texts.len() {
+                return Err(Error::InvalidResponse(format!(
+                    "Embedder for vector '{}' returned {} embeddings for {} queries",
+                    name,
+                    embedded.len(),
+                    texts.len()
+                )));
+            }
+            named.push((name, embedded));
+        }
+
+        let mut queries = match &self.default {
+            Some(embedder) => embedder.embed_queries(texts).await?,
+            None => named[0]
+                .1
+                .iter()
+                .map(|q| EmbeddedQuery {
+                    text: q.text.clone(),
+                    ..EmbeddedQuery::new(String::new(), q.vector.clone())
+                })
+                .collect(),
+        };
+
+        for (name, embedded) in named {
+            for (query, named_query) in queries.iter_mut().zip(embedded) {
+                query.named_vectors.push(NamedVector {
+                    name: name.clone(),
+                    vector: named_query.vector,
+                });
+            }
         }
+
+        Ok(queries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed(usize);
+
+    #[async_trait]
+    impl EmbeddingProvider for Fixed {
+        async fn embed_queries(&self, texts: &[String]) -> Result<Vec<EmbeddedQuery>> {
+            Ok(texts
+                .iter()
+                .map(|text| EmbeddedQuery::new(text.clone(), vec![0.0; self.0]))
+                .collect())
+        }
+
+        fn dimension(&self) -> usize {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_registered_backend_gets_its_namespaced_models() {
+        Embedder::register("fixed", |config| {
+            let dimension = config
+                .model
+                .parse()
+                .map_err(|_| Error::Config("bad".into()))?;
+            Ok(Box::new(Fixed(dimension)))
+        });
+        let config = |model: &str| EmbeddingConfig {
+            model: model.into(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Embedder::from_config(&config("fixed/12"))
+                .unwrap()
+                .dimension(),
+            12
+        );
+        // fastembed model names have a slash too
+        assert!(Embedder::from_config(&config("unregistered/12")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_named_vectors_replace_the_default_model() {
+        let fixed = |dimension| Embedder::new(Fixed(dimension));
+        let embedder = QueryEmbedder::new(fixed(3)).with_vector("title", fixed(2));
+        assert_eq!(embedder.dimensions(), [(Some("title"), 2)]);
+
+        let queries = embedder.embed_queries(&["chair".into()]).await.unwrap();
+        assert_eq!(queries[0].vector.len(), 2);
+        assert_eq!(queries[0].named_vectors[0].name, "title");
     }
 }
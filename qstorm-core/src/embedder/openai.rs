@@ -1,20 +1,48 @@
-use async_openai::config::OpenAIConfig;
-use async_openai::types::{CreateEmbeddingRequestArgs, EmbeddingInput};
-use async_openai::Client as OpenAiClient;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
 use async_trait::async_trait;
 use indicatif::{ProgressBar, ProgressStyle};
-use tracing::{debug, info};
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{debug, info, warn};
 
 use super::EmbeddingProvider;
 use crate::config::EmbeddingConfig;
 use crate::error::{Error, Result};
 use crate::queries::EmbeddedQuery;
 
+const EMBEDDINGS_URL: &str = "https://api.openai.com/v1/embeddings";
+
+/// Base delay for exponential backoff between retries
+const BACKOFF_BASE: Duration = Duration::from_millis(500);
+/// Upper bound on a single backoff delay
+const BACKOFF_MAX: Duration = Duration::from_secs(60);
+
 /// OpenAI API-based embedding provider
 pub struct OpenAIProvider {
     model: String,
     dimensions: u32,
-    client: OpenAiClient<OpenAIConfig>,
+    api_key: String,
+    client: reqwest::Client,
+    max_retries: u32,
+    /// Minimum spacing between requests when a requests-per-minute limit is set
+    min_interval: Option<Duration>,
+    /// Earliest instant the next request may be sent
+    next_slot: Mutex<Instant>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
 }
 
 impl OpenAIProvider {
@@ -33,15 +61,138 @@ impl OpenAIProvider {
 
         let dimensions = config.dimensions.unwrap_or(1536);
 
-        let oai_config = OpenAIConfig::new().with_api_key(&api_key);
-        let client = OpenAiClient::with_config(oai_config);
+        let min_interval = match config.requests_per_minute {
+            Some(0) => {
+                return Err(Error::Config(
+                    "'requests_per_minute' must be greater than zero".into(),
+                ));
+            }
+            Some(rpm) => Some(Duration::from_secs(60) / rpm),
+            None => None,
+        };
 
         Ok(Self {
             model: config.model.clone(),
             dimensions,
-            client,
+            api_key,
+            client: reqwest::Client::new(),
+            max_retries: config.max_retries,
+            min_interval,
+            next_slot: Mutex::new(Instant::now()),
         })
     }
+
+    /// Wait until the requests-per-minute throttle allows another request
+    async fn throttle(&self) {
+        let Some(interval) = self.min_interval else {
+            return;
+        };
+
+        let wait = {
+            let mut next = self.next_slot.lock().unwrap();
+            let now = Instant::now();
+            let slot = (*next).max(now);
+            *next = slot + interval;
+            slot - now
+        };
+
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Send one embedding request, retrying on rate limits and server errors
+    async fn embed_batch(&self, batch: &[String]) -> Result<Vec<Vec<f32>>> {
+        let body = json!({
+            "model": self.model,
+            "input": batch,
+            "dimensions": self.dimensions,
+        });
+
+        let mut attempt = 0;
+        loop {
+            self.throttle().await;
+
+            let response = self
+                .client
+                .post(EMBEDDINGS_URL)
+                .bearer_auth(&self.api_key)
+                .json(&body)
+                .send()
+                .await;
+
+            let (delay, error) = match response {
+                Ok(response) if response.status().is_success() => {
+                    let mut parsed: EmbeddingResponse = response.json().await.map_err(|e| {
+                        Error::InvalidResponse(format!("Malformed embedding response: {e}"))
+                    })?;
+                    parsed.data.sort_by_key(|d| d.index);
+                    return Ok(parsed.data.into_iter().map(|d| d.embedding).collect());
+                }
+                Ok(response) => {
+                    let status = response.status();
+                    let delay =
+                        retry_after(response.headers()).unwrap_or_else(|| backoff_delay(attempt));
+                    let error = Error::HttpStatus {
+                        status: status.as_u16(),
+                        message: format!(
+                            "OpenAI embedding request failed: {}",
+                            response.text().await.unwrap_or_default()
+                        ),
+                    };
+                    if !is_retryable(status) {
+                        return Err(error);
+                    }
+                    (delay, error)
+                }
+                Err(e) if e.is_timeout() || e.is_connect() => {
+                    (backoff_delay(attempt), Error::connection(e))
+                }
+                Err(e) => return Err(Error::connection(e)),
+            };
+
+            if attempt >= self.max_retries {
+                warn!(
+                    retries = self.max_retries,
+                    "Giving up on OpenAI embedding request"
+                );
+                return Err(error);
+            }
+
+            attempt += 1;
+            warn!(
+                attempt,
+                delay_ms = delay.as_millis() as u64,
+                reason = %error,
+                "Retrying OpenAI embedding request"
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Parse the server-requested delay from `retry-after-ms` or `retry-after` (seconds)
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name)?.to_str().ok()?.trim().parse::<f64>().ok();
+
+    let delay = if let Some(ms) = header("retry-after-ms") {
+        Duration::from_secs_f64(ms.max(0.0) / 1000.0)
+    } else {
+        Duration::from_secs_f64(header("retry-after")?.max(0.0))
+    };
+
+    Some(delay.min(BACKOFF_MAX))
+}
+
+/// Exponential backoff: 0.5s, 1s, 2s, ... capped at `BACKOFF_MAX`
+fn backoff_delay(attempt: u32) -> Duration {
+    BACKOFF_BASE
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(BACKOFF_MAX)
 }
 
 #[async_trait]
@@ -72,28 +223,17 @@ impl EmbeddingProvider for OpenAIProvider {
         for (batch_idx, batch) in texts.chunks(batch_size).enumerate() {
             debug!("Embedding batch of {} queries", batch.len());
 
-            let mut builder = CreateEmbeddingRequestArgs::default();
-            builder
-                .model(&self.model)
-                .input(EmbeddingInput::StringArray(batch.to_vec()))
-                .dimensions(self.dimensions);
-
-            let request = builder
-                .build()
-                .map_err(|e| Error::Config(format!("Failed to build embedding request: {e}")))?;
+            let embeddings = self.embed_batch(batch).await?;
+            if embeddings.len() != batch.len() {
+                return Err(Error::InvalidResponse(format!(
+                    "OpenAI returned {} embeddings for {} queries",
+                    embeddings.len(),
+                    batch.len()
+                )));
+            }
 
-            let response = self
-                .client
-                .embeddings()
-                .create(request)
-                .await
-                .map_err(|e| Error::Config(format!("OpenAI embedding request failed: {e}")))?;
-
-            for (i, embedding) in response.data.iter().enumerate() {
-                queries.push(EmbeddedQuery {
-                    text: batch[i].clone(),
-                    vector: embedding.embedding.to_vec(),
-                });
+            for (text, vector) in batch.iter().zip(embeddings) {
+                queries.push(EmbeddedQuery::new(text.clone(), vector));
             }
 
             pb.set_message(format!("{} embedded", queries.len()));
@@ -108,4 +248,50 @@ impl EmbeddingProvider for OpenAIProvider {
     fn dimension(&self) -> usize {
         self.dimensions as usize
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|&(name, value)| (name.parse().unwrap(), HeaderValue::from_static(value)))
+            .collect()
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let delay = |pairs| retry_after(&headers(pairs));
+        assert_eq!(delay(&[("retry-after", "2")]), Some(Duration::from_secs(2)));
+        assert_eq!(
+            delay(&[("retry-after", " 1.5 ")]),
+            Some(Duration::from_millis(1500))
+        );
+        // The millisecond header wins over the seconds one
+        assert_eq!(
+            delay(&[("retry-after-ms", "250"), ("retry-after", "9")]),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(delay(&[("retry-after", "-3")]), Some(Duration::ZERO));
+        assert_eq!(delay(&[("retry-after", "3600")]), Some(BACKOFF_MAX));
+        // HTTP dates and garbage fall back to backoff
+        assert_eq!(
+            delay(&[("retry-after", "Wed, 21 Oct 2026 07:28:00 GMT")]),
+            None
+        );
+        assert_eq!(delay(&[]), None);
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        assert_eq!(backoff_delay(0), Duration::from_millis(500));
+        assert_eq!(backoff_delay(1), Duration::from_secs(1));
+        assert_eq!(backoff_delay(6), Duration::from_secs(32));
+        assert_eq!(backoff_delay(7), BACKOFF_MAX);
+        assert_eq!(backoff_delay(u32::MAX), BACKOFF_MAX);
+    }
+}
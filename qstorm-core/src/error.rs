@@ -2,16 +2,43 @@ use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// The underlying error a provider or client library failed with
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// gRPC status codes worth retrying: the server is overloaded, restarting,
+/// or gave up on the request
+const RETRYABLE_CODES: [&str; 4] = [
+    "Unavailable",
+    "ResourceExhausted",
+    "DeadlineExceeded",
+    "Aborted",
+];
+
 #[derive(Debug, Error)]
 pub enum Error {
-    #[error("Connection failed: {0}")]
-    Connection(String),
+    #[error("Connection failed: {message}")]
+    Connection {
+        message: String,
+        #[source]
+        source: Option<BoxError>,
+    },
 
     #[error("Authentication failed: {0}")]
     Authentication(String),
 
-    #[error("Query execution failed: {0}")]
-    QueryExecution(String),
+    #[error("Query execution failed: {message}")]
+    QueryExecution {
+        message: String,
+        /// gRPC status code name (e.g. `Unavailable`), when the engine
+        /// answered with one
+        code: Option<String>,
+        #[source]
+        source: Option<BoxError>,
+    },
+
+    /// An HTTP request the server answered with a non-success status
+    #[error("Query execution failed with HTTP {status}: {message}")]
+    HttpStatus { status: u16, message: String },
 
     #[error("Provider not connected")]
     NotConnected,
@@ -25,9 +52,16 @@ pub enum Error {
     #[error("Unsupported operation: {0}")]
     Unsupported(String),
 
+    /// `max_total_queries` has been spent; holds the requests sent
+    #[error("Query budget exhausted after {0} requests")]
+    BudgetExhausted(usize),
+
     #[error("Invalid response: {0}")]
     InvalidResponse(String),
 
+    #[error("Dataset error: {0}")]
+    Dataset(String),
+
     #[error(transparent)]
     Io(#[from] std::io::Error),
 
@@ -36,4 +70,161 @@ pub enum Error {
 
     #[error(transparent)]
     SerdeYaml(#[from] serde_yaml::Error),
+
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+
+    #[cfg(feature = "parquet")]
+    #[error(transparent)]
+    Arrow(#[from] arrow::error::ArrowError),
+
+    #[cfg(feature = "parquet")]
+    #[error(transparent)]
+    Parquet(#[from] parquet::errors::ParquetError),
+}
+
+impl Error {
+    /// A failed connection, keeping what it failed with as the source
+    pub fn connection(source: impl Into<BoxError>) -> Self {
+        let source = source.into();
+        Error::Connection {
+            message: source.to_string(),
+            source: Some(source),
+        }
+    }
+
+    /// A failed connection described by `message` alone
+    pub fn connection_message(message: impl Into<String>) -> Self {
+        Error::Connection {
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// A failed request, keeping what it failed with as the source
+    pub fn query(source: impl Into<BoxError>) -> Self {
+        let source = source.into();
+        Error::QueryExecution {
+            message: source.to_string(),
+            code: None,
+            source: Some(source),
+        }
+    }
+
+    /// A failed request described by `message` alone
+    pub fn query_message(message: impl Into<String>) -> Self {
+        Error::QueryExecution {
+            message: message.into(),
+            code: None,
+            source: None,
+        }
+    }
+
+    /// Attach the engine's status code to a failed request
+    pub fn with_code(mut self, status: impl Into<String>) -> Self {
+        if let Error::QueryExecution { code, .. } = &mut self {
+            *code = Some(status.into());
+        }
+        self
+    }
+
+    /// HTTP status the server answered with, if it answered over HTTP
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Error::HttpStatus { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// Whether the same request may succeed if sent again: connection
+    /// failures, timeouts, throttling, and server-side errors
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Connection { .. } | Error::Timeout(_) => true,
+            Error::HttpStatus { status, .. } => {
+                matches!(status, 408 | 429) || (*status >= 500 && *status != 501)
+            }
+            Error::QueryExecution {
+                code: Some(code), ..
+            } => RETRYABLE_CODES.contains(&code.as_str()),
+            Error::Io(e) => matches!(
+                e.kind(),
+                std::io::ErrorKind::ConnectionRefused
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::BrokenPipe
+                    | std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::Interrupted
+            ),
+            _ => false,
+        }
+    }
+
+    /// Whether the server turned the request away to shed load
+    pub fn is_rate_limited(&self) -> bool {
+        match self {
+            Error::HttpStatus { status, .. } => *status == 429,
+            Error::QueryExecution {
+                code: Some(code), ..
+            } => code == "ResourceExhausted",
+            _ => false,
+        }
+    }
+
+    /// Short name for the kind of failure, for grouping errors by cause
+    pub fn class(&self) -> String {
+        match self {
+            Error::Connection { .. } => "connection".into(),
+            Error::Authentication(_) => "authentication".into(),
+            Error::QueryExecution {
+                code: Some(code), ..
+            } => format!("query ({code})"),
+            Error::QueryExecution { .. } => "query".into(),
+            Error::HttpStatus { status, .. } => format!("HTTP {status}"),
+            Error::NotConnected => "not connected".into(),
+            Error::Timeout(_) => "timeout".into(),
+            Error::Config(_) => "config".into(),
+            Error::Unsupported(_) => "unsupported".into(),
+            Error::BudgetExhausted(_) => "budget".into(),
+            Error::InvalidResponse(_) => "invalid response".into(),
+            Error::Dataset(_) => "dataset".into(),
+            Error::Io(_) => "io".into(),
+            Error::SerdeJson(_) | Error::SerdeYaml(_) => "serialization".into(),
+            Error::Csv(_) => "csv".into(),
+            #[cfg(feature = "parquet")]
+            Error::Arrow(_) | Error::Parquet(_) => "parquet".into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retryable_and_rate_limited() {
+        let throttled = Error::HttpStatus {
+            status: 429,
+            message: "slow down".into(),
+        };
+        assert!(throttled.is_retryable() && throttled.is_rate_limited());
+
+        let unimplemented = Error::HttpStatus {
+            status: 501,
+            message: String::new(),
+        };
+        assert!(!unimplemented.is_retryable());
+
+        let exhausted = Error::query_message("busy").with_code("ResourceExhausted");
+        assert!(exhausted.is_retryable() && exhausted.is_rate_limited());
+        assert_eq!(exhausted.class(), "query (ResourceExhausted)");
+
+        let invalid = Error::query_message("bad vector").with_code("InvalidArgument");
+        assert!(!invalid.is_retryable() && !invalid.is_rate_limited());
+
+        let refused =
+            Error::connection(std::io::Error::from(std::io::ErrorKind::ConnectionRefused));
+        assert!(refused.is_retryable());
+        assert!(std::error::Error::source(&refused).is_some());
+    }
 }
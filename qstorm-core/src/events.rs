@@ -0,0 +1,134 @@
+//! Live events from a running benchmark.
+//!
+//! [`BenchmarkRunner::events`](crate::BenchmarkRunner::events) hands out a
+//! stream of what the runner is doing as it happens: bursts starting and
+//! finishing, every request completing, errors, and the provider's health
+//! changing. Any number of streams can be open at once, and a runner nobody
+//! listens to doesn't build events at all.
+//!
+//! Events are broadcast, never waited on: a listener that falls behind by more
+//! than [`CAPACITY`] events gets a [`RunnerEvent::Lagged`] in place of the
+//! ones it missed rather than slowing the benchmark down.
+//!
+//! That makes the stream for watching a run, not for keeping its books:
+//! [`RunnerEvent::BurstFinished`] can be among the events missed, so
+//! anything that must see every burst should use what
+//! [`BenchmarkRunner::run_burst`](crate::BenchmarkRunner::run_burst)
+//! returns. qstorm's own TUI and headless output do the same, and watch the
+//! stream for burst progress and the provider's health.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream};
+use tokio::sync::broadcast;
+
+use crate::metrics::BurstMetrics;
+use crate::samples::{RequestKind, SampleStatus};
+
+/// Events buffered per listener before the oldest are dropped
+pub const CAPACITY: usize = 4096;
+
+/// Something a runner did
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum RunnerEvent {
+    /// A burst is about to send its first request
+    BurstStarted {
+        /// Run-wide index of the burst
+        index: usize,
+        /// Scenario phase the burst belongs to
+        phase: Option<String>,
+        at: DateTime<Utc>,
+    },
+    /// A burst finished, with the metrics `run_burst` returned for it
+    BurstFinished(Arc<BurstMetrics>),
+    /// A request answered, failed, or timed out. Batched searches give one
+    /// event per query, like samples do.
+    RequestCompleted {
+        kind: RequestKind,
+        status: SampleStatus,
+        latency: Duration,
+        /// Why the request failed, if it did and didn't time out
+        error: Option<String>,
+    },
+    /// A burst, or connecting, failed outright
+    Error { message: String },
+    /// The provider became reachable, or stopped being so
+    HealthChanged {
+        healthy: bool,
+        /// What made it unhealthy
+        reason: Option<String>,
+    },
+    /// This listener fell behind and missed some events
+    Lagged { missed: u64 },
+}
+
+/// Sends a runner's events to whoever is listening
+#[derive(Clone)]
+pub(crate) struct EventBus {
+    sender: broadcast::Sender<RunnerEvent>,
+}
+
+impl EventBus {
+    pub(crate) fn new() -> Self {
+        Self {
+            sender: broadcast::channel(CAPACITY).0,
+        }
+    }
+
+    /// Send the event built by `event`, only building it if anyone listens
+    pub(crate) fn emit(&self, event: impl FnOnce() -> RunnerEvent) {
+        if self.listening() {
+            // Listeners may all have gone between the check and the send
+            let _ = self.sender.send(event());
+        }
+    }
+
+    pub(crate) fn listening(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    /// A stream of every event sent from now on
+    pub(crate) fn subscribe(&self) -> impl Stream<Item = RunnerEvent> + Send + Unpin + 'static {
+        Box::pin(stream::unfold(
+            self.sender.subscribe(),
+            |mut receiver| async move {
+                let event = match receiver.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        RunnerEvent::Lagged { missed }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                };
+                Some((event, receiver))
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_lagging_listener_is_told_what_it_missed() {
+        let bus = EventBus::new();
+        let mut events = bus.subscribe();
+        for _ in 0..CAPACITY + 3 {
+            bus.emit(|| RunnerEvent::Error {
+                message: "down".into(),
+            });
+        }
+        drop(bus);
+
+        assert!(matches!(
+            events.next().await,
+            Some(RunnerEvent::Lagged { missed: 3 })
+        ));
+        assert_eq!(events.count().await, CAPACITY);
+    }
+}
@@ -0,0 +1,293 @@
+//! Prometheus exporter for watching a run from an existing dashboard.
+//!
+//! Serves `/metrics` in the Prometheus text exposition format while the
+//! benchmark runs. Counters and the latency histogram accumulate over the
+//! whole run; gauges describe the most recent burst.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tracing::{debug, info};
+
+use crate::error::Result;
+use crate::http::{self, Request};
+use crate::metrics::{BurstMetrics, BurstSamples};
+
+/// Upper bounds of the latency histogram buckets, in seconds
+const LATENCY_BUCKETS: [f64; 14] = [
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Largest request head read before answering
+const MAX_REQUEST_BYTES: usize = 8192;
+
+/// Serves the run's metrics over HTTP until dropped
+pub struct PrometheusExporter {
+    registry: Arc<Mutex<Registry>>,
+    addr: SocketAddr,
+    server: tokio::task::JoinHandle<()>,
+}
+
+#[derive(Default)]
+struct Registry {
+    provider: String,
+    bursts: u64,
+    queries: u64,
+    failures: u64,
+    timeouts: u64,
+    /// Searches per HTTP response status
+    responses: BTreeMap<u16, u64>,
+    /// Cumulative count per bucket in `LATENCY_BUCKETS`
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    latency_count: u64,
+    latency_sum_us: u64,
+    qps: f64,
+    p50_us: u64,
+    p99_us: u64,
+    recall: Option<f64>,
+    ndcg: Option<f64>,
+    mrr: Option<f64>,
+}
+
+impl PrometheusExporter {
+    /// Start serving on `addr`
+    pub async fn bind(addr: impl ToSocketAddrs, provider: &str) -> Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        info!(%addr, "Serving Prometheus metrics");
+
+        let registry = Arc::new(Mutex::new(Registry {
+            provider: provider.to_string(),
+            ..Default::default()
+        }));
+        let server = tokio::spawn(serve(listener, Arc::clone(&registry)));
+        Ok(Self {
+            registry,
+            addr,
+            server,
+        })
+    }
+
+    /// Address the endpoint is listening on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Fold a finished burst into the exported metrics
+    pub fn record(&self, burst: &BurstMetrics, samples: &BurstSamples) {
+        let mut registry = self.registry.lock().unwrap_or_else(|e| e.into_inner());
+        registry.bursts += 1;
+        registry.queries += burst.query_count as u64;
+        registry.failures += burst.failure_count as u64;
+        registry.timeouts += burst.timeout_count as u64;
+        for (&status, &count) in &burst.status_codes {
+            *registry.responses.entry(status).or_default() += count as u64;
+        }
+        for &latency_us in &samples.latencies_us {
+            registry.observe_latency(latency_us);
+        }
+        registry.qps = burst.qps;
+        registry.p50_us = burst.latency.p50_us;
+        registry.p99_us = burst.latency.p99_us;
+        registry.recall = burst.recall_at_k;
+        registry.ndcg = burst.ndcg_at_k;
+        registry.mrr = burst.mrr;
+    }
+
+    /// Current metrics in the Prometheus text format
+    pub fn render(&self) -> String {
+        self.registry
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .render()
+    }
+}
+
+impl Drop for PrometheusExporter {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+impl Registry {
+    fn observe_latency(&mut self, latency_us: u64) {
+        let seconds = latency_us as f64 / 1_000_000.0;
+        for (count, bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                *count += 1;
+            }
+        }
+        self.latency_count += 1;
+        self.latency_sum_us += latency_us;
+    }
+
+    fn render(&self) -> String {
+        let labels = format!("provider=\"{}\"", escape_label(&self.provider));
+        let mut out = String::new();
+
+        let mut metric = |name: &str, kind: &str, help: &str, value: f64| {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} {kind}");
+            let _ = writeln!(out, "{name}{{{labels}}} {value}");
+        };
+        metric(
+            "qstorm_bursts_total",
+            "counter",
+            "Bursts completed.",
+            self.bursts as f64,
+        );
+        metric(
+            "qstorm_queries_total",
+            "counter",
+            "Queries sent.",
+            self.queries as f64,
+        );
+        metric(
+            "qstorm_query_failures_total",
+            "counter",
+            "Queries that failed, including timeouts.",
+            self.failures as f64,
+        );
+        metric(
+            "qstorm_query_timeouts_total",
+            "counter",
+            "Queries that hit the client-side timeout.",
+            self.timeouts as f64,
+        );
+        metric(
+            "qstorm_qps",
+            "gauge",
+            "Queries per second achieved by the last burst.",
+            self.qps,
+        );
+        metric(
+            "qstorm_latency_p50_seconds",
+            "gauge",
+            "Median latency of the last burst.",
+            self.p50_us as f64 / 1_000_000.0,
+        );
+        metric(
+            "qstorm_latency_p99_seconds",
+            "gauge",
+            "p99 latency of the last burst.",
+            self.p99_us as f64 / 1_000_000.0,
+        );
+        // Quality gauges only appear once ground truth has been scored
+        if let Some(recall) = self.recall {
+            metric(
+                "qstorm_recall",
+                "gauge",
+                "Recall@k of the last burst.",
+                recall,
+            );
+        }
+        if let Some(ndcg) = self.ndcg {
+            metric("qstorm_ndcg", "gauge", "NDCG@k of the last burst.", ndcg);
+        }
+        if let Some(mrr) = self.mrr {
+            metric("qstorm_mrr", "gauge", "MRR of the last burst.", mrr);
+        }
+
+        if !self.responses.is_empty() {
+            let name = "qstorm_responses_total";
+            let _ = writeln!(out, "# HELP {name} Searches by HTTP response status.");
+            let _ = writeln!(out, "# TYPE {name} counter");
+            for (status, count) in &self.responses {
+                let _ = writeln!(out, "{name}{{{labels},status=\"{status}\"}} {count}");
+            }
+        }
+
+        let name = "qstorm_query_latency_seconds";
+        let _ = writeln!(out, "# HELP {name} Client-observed query latency.");
+        let _ = writeln!(out, "# TYPE {name} histogram");
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(self.buckets) {
+            let _ = writeln!(out, "{name}_bucket{{{labels},le=\"{bound}\"}} {count}");
+        }
+        let _ = writeln!(
+            out,
+            "{name}_bucket{{{labels},le=\"+Inf\"}} {}",
+            self.latency_count
+        );
+        let _ = writeln!(
+            out,
+            "{name}_sum{{{labels}}} {}",
+            self.latency_sum_us as f64 / 1_000_000.0
+        );
+        let _ = writeln!(out, "{name}_count{{{labels}}} {}", self.latency_count);
+        out
+    }
+}
+
+async fn serve(listener: TcpListener, registry: Arc<Mutex<Registry>>) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                debug!("Metrics endpoint accept failed: {e}");
+                continue;
+            }
+        };
+        let registry = Arc::clone(&registry);
+        tokio::spawn(async move {
+            if let Err(e) = respond(stream, &registry).await {
+                debug!(%peer, "Metrics request failed: {e}");
+            }
+        });
+    }
+}
+
+/// Answer a single HTTP/1.1 request and close the connection
+async fn respond(mut stream: TcpStream, registry: &Mutex<Registry>) -> std::io::Result<()> {
+    let request = http::read_request(&mut stream, MAX_REQUEST_BYTES).await?;
+    let (status, body) = match request {
+        Some(Request { method, path, .. }) => match (method.as_str(), path.as_str()) {
+            ("GET", "/metrics") => (
+                200,
+                registry.lock().unwrap_or_else(|e| e.into_inner()).render(),
+            ),
+            ("GET", _) => (404, "Not found\n".to_string()),
+            _ => (405, "Method not allowed\n".to_string()),
+        },
+        None => (400, "Malformed request\n".to_string()),
+    };
+    let content_type = "text/plain; version=0.0.4; charset=utf-8";
+    http::respond(&mut stream, status, content_type, &body).await
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let mut registry = Registry {
+            provider: "local".into(),
+            ..Default::default()
+        };
+        for latency_us in [800, 3_000, 2_000_000] {
+            registry.observe_latency(latency_us);
+        }
+
+        let text = registry.render();
+        assert!(
+            text.contains("qstorm_query_latency_seconds_bucket{provider=\"local\",le=\"0.001\"} 1")
+        );
+        assert!(
+            text.contains("qstorm_query_latency_seconds_bucket{provider=\"local\",le=\"0.005\"} 2")
+        );
+        assert!(
+            text.contains("qstorm_query_latency_seconds_bucket{provider=\"local\",le=\"+Inf\"} 3")
+        );
+        assert!(!text.contains("qstorm_recall"));
+    }
+}
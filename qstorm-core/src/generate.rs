@@ -0,0 +1,360 @@
+use std::collections::HashSet;
+
+use rand::Rng;
+use rand::seq::IndexedRandom;
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::config::seeded_rng;
+use crate::error::{Error, Result};
+use crate::provider::SearchProvider;
+use crate::queries::{Query, QueryFile};
+use crate::types::{Document, ScrollParams};
+
+/// Options controlling synthetic query generation
+#[derive(Debug, Clone)]
+pub struct GenerateOptions {
+    /// Number of queries to produce
+    pub count: usize,
+    /// Payload field holding document text (dotted path). Defaults to the longest string field.
+    pub text_field: Option<String>,
+    /// Stop scrolling after this many documents; the sample is drawn from these
+    pub max_documents: usize,
+    /// Documents fetched per scroll request
+    pub batch_size: usize,
+    /// Shortest extracted query, in words
+    pub min_words: usize,
+    /// Longest extracted query, in words
+    pub max_words: usize,
+    /// Record the source document ID as each query's expected result
+    pub with_source_ids: bool,
+    /// Seed for document sampling and query extraction
+    pub seed: Option<u64>,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        Self {
+            count: 100,
+            text_field: None,
+            max_documents: 10_000,
+            batch_size: 1000,
+            min_words: 3,
+            max_words: 12,
+            with_source_ids: false,
+            seed: None,
+        }
+    }
+}
+
+/// Derives a query file from documents sampled out of the target index
+pub struct QueryGenerator {
+    options: GenerateOptions,
+    #[cfg(feature = "openai-embeddings")]
+    llm: Option<llm::QueryWriter>,
+}
+
+impl QueryGenerator {
+    pub fn new(options: GenerateOptions) -> Self {
+        Self {
+            options,
+            #[cfg(feature = "openai-embeddings")]
+            llm: None,
+        }
+    }
+
+    /// Ask an OpenAI chat model to write each query instead of extracting sentences
+    #[cfg(feature = "openai-embeddings")]
+    pub fn with_llm(mut self, model: impl Into<String>, api_key: impl Into<String>) -> Self {
+        self.llm = Some(llm::QueryWriter::new(model.into(), api_key.into()));
+        self
+    }
+
+    /// Sample documents from the provider and turn them into queries
+    pub async fn generate(&self, provider: &dyn SearchProvider) -> Result<QueryFile> {
+        let documents = self.sample_documents(provider).await?;
+        let mut rng = seeded_rng(self.options.seed, "generate-queries");
+        let mut seen = HashSet::new();
+        let mut queries = Vec::with_capacity(documents.len());
+
+        for doc in &documents {
+            let Some(text) = doc
+                .payload
+                .as_ref()
+                .and_then(|p| extract_text(p, self.options.text_field.as_deref()))
+            else {
+                continue;
+            };
+
+            let query = match self.write_query(&text).await? {
+                Some(query) => Some(query),
+                None => extract_query(
+                    &text,
+                    self.options.min_words,
+                    self.options.max_words,
+                    &mut rng,
+                ),
+            };
+
+            if let Some(text) = query.filter(|q| seen.insert(q.clone())) {
+                let mut query = Query::new(text);
+                if self.options.with_source_ids {
+                    query.expected_ids = Some(vec![doc.id.clone()]);
+                }
+                queries.push(query);
+            }
+        }
+
+        if queries.is_empty() {
+            return Err(Error::InvalidResponse(
+                "No query text could be derived from sampled documents; \
+                 check that payloads contain text or set a text field"
+                    .into(),
+            ));
+        }
+        if queries.len() < self.options.count {
+            warn!(
+                requested = self.options.count,
+                generated = queries.len(),
+                "Generated fewer queries than requested"
+            );
+        }
+
+        Ok(QueryFile {
+            queries,
+            templates: Vec::new(),
+            ground_truth: None,
+            vectors: None,
+        })
+    }
+
+    /// Reservoir-sample `count` documents from the first `max_documents` in the index
+    async fn sample_documents(&self, provider: &dyn SearchProvider) -> Result<Vec<Document>> {
+        let params = ScrollParams {
+            batch_size: self.options.batch_size,
+            with_vectors: false,
+            with_payload: true,
+            vector_name: None,
+        };
+        let mut rng = seeded_rng(self.options.seed, "document-sampling");
+        let mut reservoir: Vec<Document> = Vec::with_capacity(self.options.count);
+        let mut scanned = 0usize;
+        let mut cursor: Option<String> = None;
+
+        'scroll: loop {
+            let page = provider.scroll(cursor.as_deref(), &params).await?;
+            let exhausted = page.documents.is_empty();
+
+            for doc in page.documents {
+                if scanned >= self.options.max_documents {
+                    if let Some(next) = &page.next_cursor
+                        && let Err(e) = provider.end_scroll(next).await
+                    {
+                        warn!(error = %e, "Failed to end scroll");
+                    }
+                    break 'scroll;
+                }
+                scanned += 1;
+                if reservoir.len() < self.options.count {
+                    reservoir.push(doc);
+                } else {
+                    let slot = rng.random_range(0..scanned);
+                    if slot < self.options.count {
+                        reservoir[slot] = doc;
+                    }
+                }
+            }
+
+            match page.next_cursor {
+                Some(next) if !exhausted => cursor = Some(next),
+                _ => break,
+            }
+        }
+
+        info!(
+            scanned,
+            sampled = reservoir.len(),
+            "Sampled documents for query generation"
+        );
+        Ok(reservoir)
+    }
+
+    #[cfg(feature = "openai-embeddings")]
+    async fn write_query(&self, text: &str) -> Result<Option<String>> {
+        match &self.llm {
+            Some(llm) => llm.write_query(text).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    #[cfg(not(feature = "openai-embeddings"))]
+    async fn write_query(&self, _text: &str) -> Result<Option<String>> {
+        Ok(None)
+    }
+}
+
+/// Pull document text from a payload: the given dotted path, or the longest string anywhere
+pub fn extract_text(payload: &Value, field: Option<&str>) -> Option<String> {
+    match field {
+        Some(path) => path
+            .split('.')
+            .try_fold(payload, |value, key| value.get(key))
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        None => longest_string(payload).map(str::to_string),
+    }
+}
+
+fn longest_string(value: &Value) -> Option<&str> {
+    match value {
+        Value::String(s) => Some(s.as_str()),
+        Value::Array(items) => items
+            .iter()
+            .filter_map(longest_string)
+            .max_by_key(|s| s.len()),
+        Value::Object(map) => map
+            .values()
+            .filter_map(longest_string)
+            .max_by_key(|s| s.len()),
+        _ => None,
+    }
+}
+
+/// Pick a random sentence and cut a window of `min_words..=max_words` words from it
+pub fn extract_query(
+    text: &str,
+    min_words: usize,
+    max_words: usize,
+    rng: &mut impl Rng,
+) -> Option<String> {
+    let sentences: Vec<Vec<&str>> = text
+        .split(['.', '!', '?', '\n'])
+        .map(|s| s.split_whitespace().collect::<Vec<_>>())
+        .filter(|words| words.len() >= min_words)
+        .collect();
+
+    let words = sentences.choose(rng)?;
+    let len = rng.random_range(min_words..=max_words.max(min_words).min(words.len()));
+    let start = rng.random_range(0..=words.len() - len);
+
+    let query = words[start..start + len]
+        .join(" ")
+        .trim_matches(|c: char| c.is_ascii_punctuation())
+        .to_string();
+    Some(query).filter(|q| !q.is_empty())
+}
+
+#[cfg(feature = "openai-embeddings")]
+mod llm {
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use crate::error::{Error, Result};
+
+    const CHAT_URL: &str = "https://api.openai.com/v1/chat/completions";
+
+    /// Longest passage excerpt sent to the model
+    const MAX_PASSAGE_CHARS: usize = 2000;
+
+    const PROMPT: &str = "Write one short search query a user might type to find the \
+                          following document. Reply with the query only, no quotes.";
+
+    pub struct QueryWriter {
+        model: String,
+        api_key: String,
+        client: reqwest::Client,
+    }
+
+    #[derive(Deserialize)]
+    struct ChatResponse {
+        choices: Vec<Choice>,
+    }
+
+    #[derive(Deserialize)]
+    struct Choice {
+        message: Message,
+    }
+
+    #[derive(Deserialize)]
+    struct Message {
+        content: String,
+    }
+
+    impl QueryWriter {
+        pub fn new(model: String, api_key: String) -> Self {
+            Self {
+                model,
+                api_key,
+                client: reqwest::Client::new(),
+            }
+        }
+
+        pub async fn write_query(&self, passage: &str) -> Result<String> {
+            let passage: String = passage.chars().take(MAX_PASSAGE_CHARS).collect();
+            let body = json!({
+                "model": self.model,
+                "messages": [
+                    {"role": "system", "content": PROMPT},
+                    {"role": "user", "content": passage},
+                ],
+                "temperature": 0.7,
+            });
+
+            let response = self
+                .client
+                .post(CHAT_URL)
+                .bearer_auth(&self.api_key)
+                .json(&body)
+                .send()
+                .await
+                .map_err(|e| Error::Connection {
+                    message: format!("OpenAI chat request failed: {e}"),
+                    source: Some(e.into()),
+                })?;
+
+            let status = response.status();
+            if !status.is_success() {
+                let text = response.text().await.unwrap_or_default();
+                return Err(Error::Config(format!(
+                    "OpenAI chat request failed ({status}): {text}"
+                )));
+            }
+
+            let parsed: ChatResponse = response.json().await.map_err(|e| {
+                Error::InvalidResponse(format!("Malformed chat completion response: {e}"))
+            })?;
+
+            parsed
+                .choices
+                .into_iter()
+                .next()
+                .map(|c| c.message.content.trim().trim_matches('"').to_string())
+                .filter(|q| !q.is_empty())
+                .ok_or_else(|| Error::InvalidResponse("Chat completion returned no text".into()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_text_and_query() {
+        let payload = serde_json::json!({
+            "title": "Short",
+            "meta": {"body": "Trail shoes need grip. Road shoes need cushioning and bounce!"},
+        });
+        let text = extract_text(&payload, None).unwrap();
+        assert!(text.starts_with("Trail shoes"));
+        assert_eq!(
+            extract_text(&payload, Some("title")).as_deref(),
+            Some("Short")
+        );
+
+        let query = extract_query(&text, 3, 4, &mut rand::rng()).unwrap();
+        let words = query.split_whitespace().count();
+        assert!((3..=4).contains(&words));
+        assert!(text.contains(&query));
+    }
+}
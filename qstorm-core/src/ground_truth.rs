@@ -0,0 +1,263 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::path::Path;
+
+use tracing::info;
+
+use crate::config::DistanceMetric;
+use crate::corpus;
+use crate::error::{Error, Result};
+use crate::provider::SearchProvider;
+use crate::queries::EmbeddedQuery;
+use crate::types::ScrollParams;
+
+/// Exact top-k neighbors for each query, computed by brute force
+#[derive(Debug, Clone)]
+pub struct GroundTruth {
+    /// Neighbors computed per query
+    pub k: usize,
+    /// Query text -> neighbor IDs ordered from nearest to farthest
+    pub neighbors: HashMap<String, Vec<String>>,
+}
+
+impl GroundTruth {
+    /// Compute ground truth from an in-memory corpus of `(id, vector)` pairs
+    pub fn compute(
+        queries: &[EmbeddedQuery],
+        corpus: impl IntoIterator<Item = (String, Vec<f32>)>,
+        k: usize,
+        metric: DistanceMetric,
+    ) -> Self {
+        let mut search = NeighborSearch::new(queries, k, metric);
+        for (id, vector) in corpus {
+            search.add(&id, &vector);
+        }
+        search.finish(queries)
+    }
+
+    /// Compute ground truth from a corpus vector file.
+    ///
+    /// `.fvecs` files (SIFT/GIST format) use the row index as document ID;
+    /// anything else is read as JSON lines of `{"id": ..., "vector": [...]}`.
+    pub fn from_vector_file(
+        path: impl AsRef<Path>,
+        queries: &[EmbeddedQuery],
+        k: usize,
+        metric: DistanceMetric,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        let mut search = NeighborSearch::new(queries, k, metric);
+
+        for document in corpus::open(path)? {
+            let document = document?;
+            if let Some(vector) = &document.vector {
+                search.add(&document.id, vector);
+            }
+        }
+
+        info!(
+            documents = search.scanned,
+            "Computed ground truth from corpus file"
+        );
+        Ok(search.finish(queries))
+    }
+
+    /// Compute ground truth by scrolling every stored vector out of the provider
+    pub async fn from_provider(
+        provider: &dyn SearchProvider,
+        queries: &[EmbeddedQuery],
+        k: usize,
+        metric: DistanceMetric,
+        params: &ScrollParams,
+    ) -> Result<Self> {
+        let params = ScrollParams {
+            with_vectors: true,
+            with_payload: false,
+            ..params.clone()
+        };
+        let mut search = NeighborSearch::new(queries, k, metric);
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let page = provider.scroll(cursor.as_deref(), &params).await?;
+            for doc in &page.documents {
+                let vector = doc.vector.as_ref().ok_or_else(|| {
+                    Error::InvalidResponse(format!("Document '{}' has no stored vector", doc.id))
+                })?;
+                search.add(&doc.id, vector);
+            }
+
+            match page.next_cursor {
+                Some(next) if !page.documents.is_empty() => cursor = Some(next),
+                _ => break,
+            }
+        }
+
+        info!(
+            documents = search.scanned,
+            "Computed ground truth from provider"
+        );
+        Ok(search.finish(queries))
+    }
+
+    /// Load ground truth previously written with [`GroundTruth::save`]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let neighbors: HashMap<String, Vec<String>> = serde_yaml::from_str(&contents)?;
+        let k = neighbors.values().map(Vec::len).max().unwrap_or(0);
+        Ok(Self { k, neighbors })
+    }
+
+    /// Write ground truth as a query-text -> IDs mapping, usable as a query file `ground_truth`
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, serde_yaml::to_string(&self.neighbors)?)?;
+        Ok(())
+    }
+
+    /// Set expected IDs on queries that don't already carry ground truth
+    pub fn apply(&self, queries: &mut [EmbeddedQuery]) {
+        for query in queries.iter_mut().filter(|q| q.expected_ids.is_none()) {
+            if let Some(ids) = self.neighbors.get(&*query.text) {
+                query.expected_ids = Some(ids.clone());
+            }
+        }
+    }
+}
+
+/// Streaming exact k-NN: keeps a bounded min-heap of the best candidates per query
+struct NeighborSearch {
+    k: usize,
+    metric: DistanceMetric,
+    queries: Vec<Vec<f32>>,
+    heaps: Vec<BinaryHeap<Candidate>>,
+    scanned: usize,
+}
+
+impl NeighborSearch {
+    fn new(queries: &[EmbeddedQuery], k: usize, metric: DistanceMetric) -> Self {
+        Self {
+            k,
+            metric,
+            queries: queries.iter().map(|q| metric.prepare(&q.vector)).collect(),
+            heaps: queries
+                .iter()
+                .map(|_| BinaryHeap::with_capacity(k + 1))
+                .collect(),
+            scanned: 0,
+        }
+    }
+
+    fn add(&mut self, id: &str, vector: &[f32]) {
+        self.scanned += 1;
+        let vector = self.metric.prepare(vector);
+
+        for (query, heap) in self.queries.iter().zip(&mut self.heaps) {
+            let score = self.metric.similarity(query, &vector);
+            if heap.len() < self.k {
+                heap.push(Candidate {
+                    score,
+                    id: id.to_string(),
+                });
+            } else if heap.peek().is_some_and(|worst| score > worst.score) {
+                heap.pop();
+                heap.push(Candidate {
+                    score,
+                    id: id.to_string(),
+                });
+            }
+        }
+    }
+
+    fn finish(self, queries: &[EmbeddedQuery]) -> GroundTruth {
+        let neighbors = queries
+            .iter()
+            .zip(self.heaps)
+            .map(|(query, heap)| {
+                // into_sorted_vec is ascending by Ord, i.e. best score first
+                let ids = heap.into_sorted_vec().into_iter().map(|c| c.id).collect();
+                (query.text.to_string(), ids)
+            })
+            .collect();
+
+        GroundTruth {
+            k: self.k,
+            neighbors,
+        }
+    }
+}
+
+/// Heap entry ordered so the *worst* score sits at the top of a `BinaryHeap`
+struct Candidate {
+    score: f32,
+    id: String,
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.score.total_cmp(&self.score)
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl DistanceMetric {
+    /// Normalize vectors up front for cosine so scoring is a plain dot product
+    fn prepare(&self, vector: &[f32]) -> Vec<f32> {
+        match self {
+            DistanceMetric::Cosine => {
+                let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+                if norm > 0.0 {
+                    vector.iter().map(|x| x / norm).collect()
+                } else {
+                    vector.to_vec()
+                }
+            }
+            DistanceMetric::Dot | DistanceMetric::Euclidean => vector.to_vec(),
+        }
+    }
+
+    /// Similarity where higher means closer
+    fn similarity(&self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            DistanceMetric::Cosine | DistanceMetric::Dot => {
+                a.iter().zip(b).map(|(x, y)| x * y).sum()
+            }
+            DistanceMetric::Euclidean => {
+                -a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum::<f32>()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_neighbors() {
+        let queries = vec![EmbeddedQuery::new("q".into(), vec![1.0, 0.0])];
+        let corpus = vec![
+            ("far".to_string(), vec![-1.0, 0.0]),
+            ("near".to_string(), vec![0.9, 0.1]),
+            ("mid".to_string(), vec![0.5, 0.5]),
+        ];
+
+        let cosine = GroundTruth::compute(&queries, corpus.clone(), 2, DistanceMetric::Cosine);
+        assert_eq!(cosine.neighbors["q"], vec!["near", "mid"]);
+
+        let euclidean = GroundTruth::compute(&queries, corpus, 3, DistanceMetric::Euclidean);
+        assert_eq!(euclidean.neighbors["q"], vec!["near", "mid", "far"]);
+    }
+}
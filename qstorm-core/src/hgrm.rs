@@ -0,0 +1,99 @@
+//! HdrHistogram percentile distribution export.
+//!
+//! Writes the classic `.hgrm` text format produced by HdrHistogram's
+//! `outputPercentileDistribution`, so qstorm latencies can be plotted with
+//! the HdrHistogram plotter and compared against other tools' output.
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+use hdrhistogram::Histogram;
+
+use crate::error::Result;
+
+/// Reporting steps per half-distance to 100%, as in HdrHistogram's default output
+const TICKS_PER_HALF_DISTANCE: u32 = 5;
+
+/// Recorded values are microseconds; distributions are reported in milliseconds
+const UNIT_RATIO: f64 = 1000.0;
+
+/// Render a latency histogram as a `.hgrm` percentile distribution
+pub fn percentile_distribution(histogram: &Histogram<u64>) -> String {
+    let mut out = format!(
+        "{:>12} {:>14} {:>10} {:>14}\n\n",
+        "Value", "Percentile", "TotalCount", "1/(1-Percentile)"
+    );
+
+    let mut total = 0;
+    if !histogram.is_empty() {
+        for step in histogram.iter_quantiles(TICKS_PER_HALF_DISTANCE) {
+            total += step.count_since_last_iteration();
+            let value = step.value_iterated_to() as f64 / UNIT_RATIO;
+            let quantile = step.quantile_iterated_to();
+            if quantile < 1.0 {
+                let _ = writeln!(
+                    out,
+                    "{value:12.3} {quantile:2.12} {total:10} {:14.2}",
+                    1.0 / (1.0 - quantile)
+                );
+            } else {
+                let _ = writeln!(out, "{value:12.3} {quantile:2.12} {total:10}");
+            }
+        }
+    }
+
+    let (mean, stdev, max) = if histogram.is_empty() {
+        (0.0, 0.0, 0.0)
+    } else {
+        (
+            histogram.mean() / UNIT_RATIO,
+            histogram.stdev() / UNIT_RATIO,
+            histogram.max() as f64 / UNIT_RATIO,
+        )
+    };
+    // Sub-buckets cover 2 * 10^sigfig values at unit resolution, rounded up to a power of two
+    let sub_buckets = (2 * 10u64.pow(histogram.sigfig() as u32)).next_power_of_two();
+    let _ = writeln!(
+        out,
+        "#[Mean    = {mean:12.3}, StdDeviation   = {stdev:12.3}]"
+    );
+    let _ = writeln!(
+        out,
+        "#[Max     = {max:12.3}, Total count    = {:12}]",
+        histogram.len()
+    );
+    let _ = writeln!(
+        out,
+        "#[Buckets = {:12}, SubBuckets     = {sub_buckets:12}]",
+        histogram.buckets()
+    );
+    out
+}
+
+/// Write a latency histogram's percentile distribution to `path`
+pub fn save(path: impl AsRef<Path>, histogram: &Histogram<u64>) -> Result<()> {
+    std::fs::write(path, percentile_distribution(histogram))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile_distribution_ends_at_total() {
+        let mut histogram = Histogram::<u64>::new_with_bounds(1, 60_000_000, 3).unwrap();
+        for latency_us in 1..=1000 {
+            histogram.record(latency_us * 10).unwrap();
+        }
+
+        let text = percentile_distribution(&histogram);
+        let last = text
+            .lines()
+            .rfind(|line| !line.starts_with('#') && !line.trim().is_empty())
+            .unwrap();
+        let columns: Vec<&str> = last.split_whitespace().collect();
+        assert_eq!(columns, ["10.007", "1.000000000000", "1000"]);
+        assert!(text.contains("Total count    =         1000]"));
+    }
+}
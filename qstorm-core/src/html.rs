@@ -0,0 +1,309 @@
+//! Standalone HTML rendering of a run report.
+//!
+//! Charts are inline SVG and styles are embedded, so the file opens anywhere
+//! without scripts or network access.
+
+use std::fmt::Write as _;
+
+use crate::metrics::{BurstMetrics, LatencyMetrics};
+use crate::report::{QualityStats, RunReport};
+
+const CHART_WIDTH: f64 = 720.0;
+const CHART_HEIGHT: f64 = 220.0;
+const MARGIN_LEFT: f64 = 56.0;
+const MARGIN_BOTTOM: f64 = 28.0;
+const MARGIN_TOP: f64 = 10.0;
+const MARGIN_RIGHT: f64 = 12.0;
+
+const STYLE: &str = "\
+body{font-family:system-ui,sans-serif;margin:2rem auto;max-width:780px;color:#222}\
+h1{font-size:1.4rem}h2{font-size:1.1rem;margin-top:2rem}\
+table{border-collapse:collapse}td,th{padding:.25rem .75rem;border-bottom:1px solid #ddd;text-align:left}\
+td.n{text-align:right;font-variant-numeric:tabular-nums}\
+.legend span{margin-right:1rem}.legend i{display:inline-block;width:.8rem;height:.8rem;margin-right:.3rem}\
+svg text{font-size:11px;fill:#555}";
+
+/// One line on a chart, valued per burst (`None` where it wasn't measured)
+struct Series<'a> {
+    name: &'a str,
+    color: &'a str,
+    values: Vec<Option<f64>>,
+}
+
+/// Render `report` and the bursts behind it as a complete HTML document
+pub fn render(report: &RunReport, bursts: &[BurstMetrics]) -> String {
+    let mut out = String::new();
+    let title = format!("qstorm report: {}", escape(&report.provider));
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{title}</title>\
+         <style>{STYLE}</style></head><body>\n<h1>{title}</h1>\n"
+    );
+
+    out.push_str(&summary_table(report));
+    if !report.tags.is_empty() {
+        out.push_str(&tag_table(report));
+    }
+
+    // A report loaded on its own has no bursts to chart
+    if bursts.is_empty() {
+        out.push_str("</body></html>\n");
+        return out;
+    }
+
+    let ms = |us: u64| us as f64 / 1000.0;
+    out.push_str(&chart(
+        "Throughput",
+        "QPS",
+        &[Series {
+            name: "qps",
+            color: "#2a7ab9",
+            values: bursts.iter().map(|b| Some(b.qps)).collect(),
+        }],
+    ));
+    out.push_str(&chart(
+        "Latency",
+        "ms",
+        &[
+            Series {
+                name: "p50",
+                color: "#2f9e44",
+                values: bursts.iter().map(|b| Some(ms(b.latency.p50_us))).collect(),
+            },
+            Series {
+                name: "p99",
+                color: "#e8590c",
+                values: bursts.iter().map(|b| Some(ms(b.latency.p99_us))).collect(),
+            },
+        ],
+    ));
+
+    let quality = [
+        Series {
+            name: "recall",
+            color: "#7048e8",
+            values: bursts.iter().map(|b| b.recall_at_k).collect(),
+        },
+        Series {
+            name: "ndcg",
+            color: "#1098ad",
+            values: bursts.iter().map(|b| b.ndcg_at_k).collect(),
+        },
+        Series {
+            name: "mrr",
+            color: "#d6336c",
+            values: bursts.iter().map(|b| b.mrr).collect(),
+        },
+    ];
+    let quality: Vec<Series> = quality
+        .into_iter()
+        .filter(|s| s.values.iter().any(Option::is_some))
+        .collect();
+    if !quality.is_empty() {
+        out.push_str(&chart("Search quality", "score", &quality));
+    }
+
+    out.push_str("</body></html>\n");
+    out
+}
+
+fn summary_table(report: &RunReport) -> String {
+    let mut rows: Vec<(String, String)> = [
+        ("Provider", escape(&report.provider)),
+        ("Started", report.started_at.to_rfc3339()),
+        ("Duration", format!("{:.1} s", report.duration_secs)),
+        ("Bursts", report.bursts.to_string()),
+        ("Queries", report.total_queries.to_string()),
+        ("Mean QPS", format!("{:.1}", report.qps)),
+    ]
+    .map(|(name, value)| (name.to_string(), value))
+    .into();
+    rows.extend(latency_rows(&report.latency));
+    rows.push((
+        "Failures".into(),
+        format!(
+            "{} ({:.2}%; {} timeouts)",
+            report.errors.failures,
+            report.errors.error_rate * 100.0,
+            report.errors.timeouts
+        ),
+    ));
+    let quality = [
+        ("Recall@k", &report.recall_at_k),
+        ("NDCG@k", &report.ndcg_at_k),
+        ("MRR", &report.mrr),
+    ];
+    for (name, stats) in quality {
+        if let Some(QualityStats { mean, min, max }) = stats {
+            rows.push((
+                name.into(),
+                format!("{mean:.3} (min {min:.3}, max {max:.3})"),
+            ));
+        }
+    }
+    if let Some(reason) = &report.stop_reason {
+        rows.push(("Stopped early".into(), escape(reason)));
+    }
+    for result in &report.assertions {
+        let verdict = if result.passed { "pass" } else { "FAIL" };
+        let actual = result
+            .actual
+            .map_or_else(|| "-".to_string(), |actual| format!("{actual:.4}"));
+        rows.push((escape(&result.assertion), format!("{verdict} ({actual})")));
+    }
+    if let Some(seed) = report.seed {
+        rows.push(("Seed".into(), seed.to_string()));
+    }
+    let version = match &report.git_commit {
+        Some(commit) => format!("{} (commit {})", report.version, escape(commit)),
+        None => report.version.clone(),
+    };
+    rows.push(("qstorm".into(), version));
+
+    let mut out = String::from("<h2>Summary</h2>\n<table>\n");
+    for (name, value) in rows {
+        let _ = writeln!(out, "<tr><th>{name}</th><td class=\"n\">{value}</td></tr>");
+    }
+    out.push_str("</table>\n");
+    out
+}
+
+/// One row per query tag
+fn tag_table(report: &RunReport) -> String {
+    let mut out = String::from(
+        "<h2>By tag</h2>\n<table>\n<tr><th>Tag</th><th>Queries</th><th>Failures</th>\
+         <th>p50</th><th>p99</th><th>Recall@k</th></tr>\n",
+    );
+    let ms = |us: u64| format!("{:.2} ms", us as f64 / 1000.0);
+    for (tag, metrics) in &report.tags {
+        let recall = metrics
+            .recall_at_k
+            .map_or_else(|| "-".to_string(), |r| format!("{r:.3}"));
+        let _ = writeln!(
+            out,
+            "<tr><th>{}</th><td class=\"n\">{}</td><td class=\"n\">{}</td>\
+             <td class=\"n\">{}</td><td class=\"n\">{}</td><td class=\"n\">{recall}</td></tr>",
+            escape(tag),
+            metrics.query_count,
+            metrics.failure_count,
+            ms(metrics.latency.p50_us),
+            ms(metrics.latency.p99_us),
+        );
+    }
+    out.push_str("</table>\n");
+    out
+}
+
+fn latency_rows(latency: &LatencyMetrics) -> Vec<(String, String)> {
+    let ms = |us: u64| format!("{:.2} ms", us as f64 / 1000.0);
+    let mut rows = vec![
+        ("Latency p50".into(), ms(latency.p50_us)),
+        ("Latency p90".into(), ms(latency.p90_us)),
+        ("Latency p99".into(), ms(latency.p99_us)),
+    ];
+    for p in &latency.percentiles {
+        rows.push((format!("Latency p{}", p.percentile), ms(p.latency_us)));
+    }
+    rows.push(("Latency max".into(), ms(latency.max_us)));
+    rows.push((
+        "Latency stddev".into(),
+        format!(
+            "{:.2} ms (CV {:.2})",
+            latency.stddev_us / 1000.0,
+            latency.cv
+        ),
+    ));
+    rows.push(("Latency IQR".into(), ms(latency.iqr_us)));
+    rows
+}
+
+/// A line chart of per-burst values, with bursts along the x axis
+fn chart(title: &str, unit: &str, series: &[Series]) -> String {
+    let mut out = format!("<h2>{title}</h2>\n<div class=\"legend\">");
+    for s in series {
+        let _ = write!(
+            out,
+            "<span><i style=\"background:{}\"></i>{}</span>",
+            s.color, s.name
+        );
+    }
+    out.push_str("</div>\n");
+
+    let points = series.iter().map(|s| s.values.len()).max().unwrap_or(0);
+    let top = series
+        .iter()
+        .flat_map(|s| s.values.iter().flatten())
+        .fold(0.0_f64, |max, &v| max.max(v));
+    let top = if top > 0.0 { top * 1.1 } else { 1.0 };
+
+    let plot_width = CHART_WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+    let plot_height = CHART_HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+    let x = |i: usize| MARGIN_LEFT + plot_width * i as f64 / points.saturating_sub(1).max(1) as f64;
+    let y = |v: f64| MARGIN_TOP + plot_height * (1.0 - v / top);
+
+    let _ = writeln!(
+        out,
+        "<svg viewBox=\"0 0 {CHART_WIDTH} {CHART_HEIGHT}\" width=\"100%\" role=\"img\">"
+    );
+    for tick in 0..=4 {
+        let value = top * tick as f64 / 4.0;
+        let _ = writeln!(
+            out,
+            "<line x1=\"{MARGIN_LEFT}\" x2=\"{}\" y1=\"{y:.1}\" y2=\"{y:.1}\" stroke=\"#eee\"/>\
+             <text x=\"{}\" y=\"{:.1}\" text-anchor=\"end\">{value:.1}</text>",
+            CHART_WIDTH - MARGIN_RIGHT,
+            MARGIN_LEFT - 6.0,
+            y(value) + 4.0,
+            y = y(value),
+        );
+    }
+    let _ = writeln!(
+        out,
+        "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">burst</text>\
+         <text x=\"12\" y=\"{}\" transform=\"rotate(-90 12 {})\" text-anchor=\"middle\">{unit}</text>",
+        MARGIN_LEFT + plot_width / 2.0,
+        CHART_HEIGHT - 6.0,
+        MARGIN_TOP + plot_height / 2.0,
+        MARGIN_TOP + plot_height / 2.0,
+    );
+
+    for s in series {
+        // Bursts without a value break the line rather than dropping to zero
+        let mut segments: Vec<Vec<String>> = vec![Vec::new()];
+        for (i, value) in s.values.iter().enumerate() {
+            match value {
+                Some(v) => segments
+                    .last_mut()
+                    .unwrap()
+                    .push(format!("{:.1},{:.1}", x(i), y(*v))),
+                None => segments.push(Vec::new()),
+            }
+        }
+        for segment in segments.iter().filter(|segment| !segment.is_empty()) {
+            if let [point] = segment.as_slice() {
+                let (cx, cy) = point.split_once(',').unwrap_or_default();
+                let _ = writeln!(
+                    out,
+                    "<circle cx=\"{cx}\" cy=\"{cy}\" r=\"2.5\" fill=\"{}\"/>",
+                    s.color
+                );
+                continue;
+            }
+            let _ = writeln!(
+                out,
+                "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\" points=\"{}\"/>",
+                s.color,
+                segment.join(" ")
+            );
+        }
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,148 @@
+//! Just enough HTTP/1.1 for qstorm's own small servers: the Prometheus
+//! exporter, the live dashboard, and the `qstorm serve` control API.
+//!
+//! Every connection carries one request and is closed after the response.
+//! A client gets [`READ_TIMEOUT`] to send the whole request, so connections
+//! that go quiet or trickle bytes are dropped instead of piling up.
+
+use std::io;
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// How long a client has to send its request, head and body together
+pub const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A request as read by [`read_request`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    /// Path without its query string
+    pub path: String,
+    pub body: Vec<u8>,
+}
+
+/// Read one request of at most `max_bytes`, head and body together.
+/// Returns `None` for a malformed or oversized request, or one cut short,
+/// and a `TimedOut` error if it isn't all there within [`READ_TIMEOUT`].
+pub async fn read_request(
+    stream: &mut (impl AsyncRead + Unpin),
+    max_bytes: usize,
+) -> io::Result<Option<Request>> {
+    tokio::time::timeout(READ_TIMEOUT, read(stream, max_bytes))
+        .await
+        .unwrap_or_else(|_| {
+            Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "request not received in time",
+            ))
+        })
+}
+
+async fn read(
+    stream: &mut (impl AsyncRead + Unpin),
+    max_bytes: usize,
+) -> io::Result<Option<Request>> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 4096];
+    let head_end = loop {
+        if let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+        let n = stream.read(&mut buf).await?;
+        if n == 0 || data.len() >= max_bytes {
+            return Ok(None);
+        }
+        data.extend_from_slice(&buf[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&data[..head_end]).into_owned();
+    let mut lines = head.lines();
+    let mut parts = lines.next().unwrap_or_default().split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Ok(None);
+    };
+    let path = path.split('?').next().unwrap_or(path).to_string();
+    let length = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    if head_end + length > max_bytes {
+        return Ok(None);
+    }
+
+    while data.len() < head_end + length {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        data.extend_from_slice(&buf[..n]);
+    }
+    data.truncate(head_end + length);
+    Ok(Some(Request {
+        method: method.to_string(),
+        path,
+        body: data.split_off(head_end),
+    }))
+}
+
+/// Send a complete response and close the connection
+pub async fn respond(
+    stream: &mut (impl AsyncWrite + Unpin),
+    status: u16,
+    content_type: &str,
+    body: &str,
+) -> io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {status} {}\r\n\
+         Content-Type: {content_type}\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        reason(status),
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        503 => "Service Unavailable",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_read_request() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        client
+            .write_all(b"POST /load?x=1 HTTP/1.1\r\nContent-Length: 4\r\n\r\n{}\r\n")
+            .await
+            .unwrap();
+        let request = read_request(&mut server, 1024).await.unwrap().unwrap();
+        assert_eq!(
+            (request.method.as_str(), request.path.as_str()),
+            ("POST", "/load")
+        );
+        assert_eq!(request.body, b"{}\r\n");
+
+        // A client that stops partway is cut off
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        client
+            .write_all(b"GET /metrics HTTP/1.1\r\n")
+            .await
+            .unwrap();
+        let err = read_request(&mut server, 1024).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+}
@@ -0,0 +1,268 @@
+//! Loading a document corpus into a provider.
+//!
+//! Comparing engines means loading the same documents into each. A corpus is
+//! read as JSON lines, CSV, or Parquet; documents without a vector are
+//! embedded from their text, and everything is upserted in batches, creating
+//! the index first if it doesn't exist yet.
+
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use serde_json::{Map, Value};
+
+use crate::config::{DistanceMetric, EmbeddingConfig};
+use crate::embedder::Embedder;
+use crate::error::{Error, Result};
+use crate::provider::SearchProvider;
+use crate::types::Document;
+
+/// One corpus record, field name to value
+pub type Row = Map<String, Value>;
+
+/// Corpus records in file order
+pub type Rows = Box<dyn Iterator<Item = Result<Row>> + Send>;
+
+/// Which corpus fields hold a document's ID, text, and vector
+#[derive(Debug, Clone)]
+pub struct CorpusFields {
+    /// Document ID; the row number is used for rows without one
+    pub id: String,
+    /// Text to embed, stored as the document's text
+    pub text: String,
+    /// Precomputed vector; documents with one aren't embedded
+    pub vector: String,
+}
+
+impl Default for CorpusFields {
+    fn default() -> Self {
+        Self {
+            id: "id".into(),
+            text: "text".into(),
+            vector: "vector".into(),
+        }
+    }
+}
+
+/// How a corpus is loaded
+#[derive(Debug, Clone)]
+pub struct IngestOptions {
+    pub fields: CorpusFields,
+    /// Documents embedded and upserted per request
+    pub batch_size: usize,
+    /// Similarity the index is created with, if it doesn't exist yet
+    pub metric: DistanceMetric,
+    /// Model for documents without a vector, loaded when the first one is seen
+    pub embedding: EmbeddingConfig,
+}
+
+/// How far a corpus load has got
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IngestProgress {
+    /// Documents upserted so far
+    pub documents: usize,
+    /// Of `documents`, those embedded here rather than read with a vector
+    pub embedded: usize,
+    pub batches: usize,
+    pub elapsed: Duration,
+    /// Whether the index was created because it didn't exist
+    pub created_index: bool,
+}
+
+impl IngestProgress {
+    pub fn docs_per_sec(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            self.documents as f64 / secs
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Read a corpus: `.csv` (with a header row) and `.parquet` by extension,
+/// anything else as JSON lines
+pub fn read_rows(path: impl AsRef<Path>) -> Result<Rows> {
+    let path = path.as_ref();
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("csv") => {
+            let mut reader = csv::Reader::from_path(path)?;
+            let headers = reader.headers()?.clone();
+            let rows = reader.into_records().map(move |record| {
+                let record = record?;
+                Ok(headers
+                    .iter()
+                    .zip(record.iter())
+                    .map(|(name, value)| (name.to_string(), Value::String(value.to_string())))
+                    .collect())
+            });
+            Ok(Box::new(rows))
+        }
+        #[cfg(feature = "parquet")]
+        Some("parquet") => Ok(Box::new(crate::columnar::read_rows(path)?)),
+        #[cfg(not(feature = "parquet"))]
+        Some("parquet") => Err(Error::Config(format!(
+            "{} needs qstorm built with the `parquet` feature",
+            path.display()
+        ))),
+        _ => {
+            let reader = BufReader::new(std::fs::File::open(path)?);
+            let rows = reader
+                .lines()
+                .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+                .map(|line| Ok(serde_json::from_str(&line?)?));
+            Ok(Box::new(rows))
+        }
+    }
+}
+
+/// Load every row of `rows` into `provider`, calling `on_batch` after each
+/// upsert. The index is created from the first batch's vector dimension if
+/// it doesn't exist.
+pub async fn ingest(
+    provider: &dyn SearchProvider,
+    rows: Rows,
+    options: &IngestOptions,
+    mut on_batch: impl FnMut(&IngestProgress),
+) -> Result<IngestProgress> {
+    if options.batch_size == 0 {
+        return Err(Error::Config("batch size must be at least 1".into()));
+    }
+
+    let start = Instant::now();
+    let mut progress = IngestProgress::default();
+    let mut embedder = None;
+    let mut rows = rows.enumerate();
+    loop {
+        let mut batch = rows
+            .by_ref()
+            .take(options.batch_size)
+            .map(|(row_number, row)| to_document(row_number, row?, &options.fields))
+            .collect::<Result<Vec<_>>>()?;
+        if batch.is_empty() {
+            break;
+        }
+
+        progress.embedded += embed_missing(&mut batch, &mut embedder, options).await?;
+        if progress.batches == 0 && !provider.index_exists().await? {
+            let dimension = batch[0].vector.as_ref().map_or(0, Vec::len);
+            provider.create_index(dimension, options.metric).await?;
+            progress.created_index = true;
+        }
+        provider.upsert(&batch).await?;
+
+        progress.documents += batch.len();
+        progress.batches += 1;
+        progress.elapsed = start.elapsed();
+        on_batch(&progress);
+    }
+
+    Ok(progress)
+}
+
+/// A row as a document, with its text in the `text` payload field where
+/// providers look for it and every other field kept alongside
+fn to_document(row_number: usize, mut row: Row, fields: &CorpusFields) -> Result<Document> {
+    let id = match row.remove(&fields.id) {
+        Some(Value::String(id)) => id,
+        Some(Value::Null) | None => row_number.to_string(),
+        Some(other) => other.to_string(),
+    };
+    let vector = match row.remove(&fields.vector) {
+        Some(Value::Null) | None => None,
+        // CSV cells hold the vector as a JSON array string
+        Some(Value::String(s)) if s.trim().is_empty() => None,
+        Some(Value::String(s)) => Some(serde_json::from_str(&s)?),
+        Some(value) => Some(serde_json::from_value(value)?),
+    };
+    if let Some(text) = row.remove(&fields.text) {
+        row.insert("text".into(), text);
+    }
+
+    Ok(Document {
+        id,
+        vector,
+        payload: (!row.is_empty()).then_some(Value::Object(row)),
+    })
+}
+
+/// Embed the text of documents without a vector, returning how many there were
+async fn embed_missing(
+    batch: &mut [Document],
+    embedder: &mut Option<Embedder>,
+    options: &IngestOptions,
+) -> Result<usize> {
+    let mut missing = Vec::new();
+    let mut texts = Vec::new();
+    for (i, document) in batch.iter().enumerate() {
+        if document.vector.is_some() {
+            continue;
+        }
+        let text = document
+            .payload
+            .as_ref()
+            .and_then(|payload| payload["text"].as_str())
+            .ok_or_else(|| {
+                Error::Config(format!(
+                    "Document '{}' has neither a '{}' to embed nor a '{}'",
+                    document.id, options.fields.text, options.fields.vector
+                ))
+            })?;
+        missing.push(i);
+        texts.push(text.to_string());
+    }
+    if missing.is_empty() {
+        return Ok(0);
+    }
+
+    let embedder = match embedder {
+        Some(embedder) => embedder,
+        None => embedder.insert(Embedder::from_config(&options.embedding)?),
+    };
+    let embedded = embedder.embed_queries(&texts).await?;
+    if embedded.len() != missing.len() {
+        return Err(Error::InvalidResponse(format!(
+            "Embedder returned {} embeddings for {} documents",
+            embedded.len(),
+            missing.len()
+        )));
+    }
+    for (i, query) in missing.iter().zip(embedded) {
+        batch[*i].vector = Some(query.vector);
+    }
+    Ok(missing.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rows_become_documents_with_text_payload() {
+        let fields = CorpusFields {
+            id: "doc_id".into(),
+            text: "body".into(),
+            ..Default::default()
+        };
+        let row = |value: Value| value.as_object().cloned().unwrap();
+
+        let document = to_document(
+            0,
+            row(json!({"doc_id": 42, "body": "hello", "lang": "en", "vector": "[0.5, 1]"})),
+            &fields,
+        )
+        .unwrap();
+        assert_eq!(document.id, "42");
+        assert_eq!(document.vector, Some(vec![0.5, 1.0]));
+        assert_eq!(
+            document.payload,
+            Some(json!({"text": "hello", "lang": "en"}))
+        );
+
+        let document =
+            to_document(7, row(json!({"body": "no id", "vector": null})), &fields).unwrap();
+        assert_eq!(document.id, "7");
+        assert!(document.vector.is_none());
+    }
+}
@@ -1,19 +1,92 @@
+pub mod assertion;
+pub mod capacity;
+pub mod chaos;
+#[cfg(feature = "parquet")]
+mod columnar;
+pub mod compare;
 pub mod config;
+pub mod curve;
+mod corpus;
+pub mod dashboard;
+#[cfg(feature = "datasets")]
+pub mod datasets;
+pub mod distributed;
 pub mod embedder;
 pub mod error;
+pub mod events;
+pub mod exporter;
+pub mod generate;
+pub mod ground_truth;
+pub mod hgrm;
+mod html;
+pub mod http;
+pub mod ingest;
 pub mod metrics;
+pub mod notify;
+mod perturb;
+pub mod pipeline;
 pub mod provider;
 pub mod providers;
 pub mod queries;
+pub mod report;
+pub mod rerank;
+pub mod resources;
 pub mod runner;
+pub mod samples;
+mod scenario;
+mod shard;
+mod slo;
+pub mod soak;
+pub mod state;
+pub mod statsd;
+mod stop;
+pub mod stream;
+mod synthetic;
+pub mod template;
 pub mod types;
+pub mod validate;
+mod vecs;
+pub mod window;
 
 // re-exports
+pub use assertion::{Assertion, AssertionResult};
+pub use capacity::{CapacityReport, CapacitySearch, CapacityStep};
+pub use chaos::ChaosProvider;
+pub use compare::{Comparison, RegressionThresholds};
 pub use config::{Config, SearchMode};
-pub use embedder::{Embedder, EmbeddingProvider};
+pub use curve::Curve;
+pub use dashboard::Dashboard;
+pub use embedder::{Embedder, EmbedderFactory, EmbeddingProvider, QueryEmbedder};
 pub use error::{Error, Result};
-pub use metrics::{BurstMetrics, Metrics};
-pub use provider::{Capabilities, SearchProvider};
-pub use queries::{EmbeddedQuery, QueryFile};
+pub use events::RunnerEvent;
+pub use exporter::PrometheusExporter;
+pub use generate::{GenerateOptions, QueryGenerator};
+pub use ground_truth::GroundTruth;
+pub use metrics::{
+    BurstMetrics, BurstSamples, Judgment, Metrics, PageMetrics, PaginationMetrics, Recorder,
+    RerankMetrics, ResultMetrics, ScoreStats, ServerMetrics, SweepPoint, SweepReport, TagMetrics,
+    WriteMetrics,
+};
+#[cfg(feature = "notifications")]
+pub use notify::Notifier;
+pub use notify::Notification;
+pub use pipeline::{LoadProfile, MetricSink, QueryFeed, RunnerBuilder};
+pub use provider::{
+    Capabilities, CustomProviderFactory, ProviderFactory, ProviderRegistry, SearchProvider,
+};
+pub use queries::{EmbeddedQuery, NamedVector, QueryFile, QuerySource};
+pub use report::RunReport;
+pub use rerank::{RerankStage, Reranker};
+pub use resources::ClientResources;
 pub use runner::BenchmarkRunner;
-pub use types::{SearchParams, SearchResult, SearchResults};
+pub use samples::{
+    BurstLog, Failure, FailureLog, Rotation, Sample, SampleLog, SlowQueryLog, close_outputs,
+};
+pub use soak::{Checkpoint, Resumed, SoakRecorder};
+pub use state::RunState;
+pub use statsd::StatsdSink;
+pub use stream::{Record, RunHeader};
+pub use template::QueryTemplate;
+pub use types::{Document, ScrollPage, ScrollParams, SearchParams, SearchResult, SearchResults};
+pub use validate::ValidationReport;
+pub use window::WindowMetrics;
@@ -1,8 +1,17 @@
+use std::collections::{BTreeMap, HashSet};
+use std::hash::Hash;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::time::{Duration, Instant};
 
 use hdrhistogram::Histogram;
 use serde::{Deserialize, Serialize};
 
+use crate::resources::{ClientResources, ResourceSampler};
+use crate::types::SearchResult;
+use crate::window::{SlidingWindow, WindowMetrics};
+
 /// Metrics collected from a single burst of queries
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BurstMetrics {
@@ -16,16 +25,245 @@ pub struct BurstMetrics {
     pub success_count: usize,
     /// Number of failed queries
     pub failure_count: usize,
+    /// Failed queries that hit the client-side timeout (included in `failure_count`)
+    #[serde(default)]
+    pub timeout_count: usize,
     /// Latency percentiles in microseconds
     pub latency: LatencyMetrics,
+    /// Processing time reported by the engine, for providers that report it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<ServerMetrics>,
     /// Queries per second achieved
     pub qps: f64,
     /// Recall@k if ground truth was provided
     pub recall_at_k: Option<f64>,
+    /// NDCG@k if ground truth was provided, using graded relevance when given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ndcg_at_k: Option<f64>,
+    /// Mean reciprocal rank of the first relevant result within the top k
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mrr: Option<f64>,
+    /// Recall@k of the recall probe, if one ran after this burst
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub probe_recall: Option<f64>,
+    /// Most requests outstanding at once (open-loop load only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peak_in_flight: Option<usize>,
+    /// Upserts sent during the burst (mixed and ingest workloads only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub writes: Option<WriteMetrics>,
+    /// Scenario phase the burst ran in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phase: Option<String>,
+    /// Reranking stage timings; `latency` then covers retrieval plus rerank
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rerank: Option<RerankMetrics>,
+    /// Per-page latency of paginated searches; `latency` then covers every page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<PaginationMetrics>,
+    /// Mean Jaccard overlap between each query's results and the results it
+    /// returned last time (1.0 means identical), for queries seen before
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stability: Option<f64>,
+    /// Rolling windows over the most recent requests as of the end of the
+    /// burst, spanning earlier bursts too
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub windows: Vec<WindowMetrics>,
+    /// How many results successful searches returned, and their scores
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub results: Option<ResultMetrics>,
+    /// CPU and memory use of qstorm itself during the burst (Linux only);
+    /// for a distributed run, the busiest worker's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client: Option<ClientResources>,
+    /// Engine-native stats polled right after the burst, as the provider reports them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_stats: Option<serde_json::Value>,
+    /// Searches by HTTP response status, for HTTP-based providers. Client-side
+    /// timeouts and connection errors got no response and aren't counted.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub status_codes: BTreeMap<u16, usize>,
+    /// Searches broken down by the tags of their queries. A query with
+    /// several tags counts towards each of them.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, TagMetrics>,
 }
 
-/// Latency percentiles
+/// Searches for the queries carrying one tag
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagMetrics {
+    pub query_count: usize,
+    pub failure_count: usize,
+    pub latency: LatencyMetrics,
+    /// Mean Recall@k of the tag's successful queries with ground truth
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recall_at_k: Option<f64>,
+}
+
+/// Raw latencies behind a burst, kept so bursts run on several workers can
+/// be merged without approximating percentiles
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BurstSamples {
+    pub latencies_us: Vec<u64>,
+    pub write_latencies_us: Vec<u64>,
+    #[serde(default)]
+    pub retrieval_latencies_us: Vec<u64>,
+    #[serde(default)]
+    pub rerank_latencies_us: Vec<u64>,
+    /// Page request latencies, indexed by page
+    #[serde(default)]
+    pub page_latencies_us: Vec<Vec<u64>>,
+    /// Result overlaps behind `stability`
+    #[serde(default)]
+    pub overlaps: Vec<f64>,
+    #[serde(default)]
+    pub server_latencies_us: Vec<u64>,
+    #[serde(default)]
+    pub overhead_latencies_us: Vec<u64>,
+    /// Query latencies per tag
+    #[serde(default)]
+    pub tag_latencies_us: BTreeMap<String, Vec<u64>>,
+}
+
+impl BurstSamples {
+    /// Query latencies of the burst, in microseconds
+    pub fn latency_histogram(&self) -> Histogram<u64> {
+        let mut histogram = new_latency_histogram();
+        for &latency_us in &self.latencies_us {
+            let _ = histogram.record(latency_us);
+        }
+        histogram
+    }
+}
+
+/// Metrics for document upserts within a burst
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WriteMetrics {
+    /// Upsert requests sent
+    pub request_count: usize,
+    /// Upsert requests that failed
+    pub failure_count: usize,
+    /// Documents written successfully
+    pub document_count: usize,
+    /// Documents written per second over the burst
+    pub docs_per_sec: f64,
+    /// Upsert request latency percentiles in microseconds
+    pub latency: LatencyMetrics,
+}
+
+/// Engine-reported processing time next to what the client observed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServerMetrics {
+    /// Successful searches that reported a processing time
+    pub request_count: usize,
+    /// Engine-reported processing time percentiles in microseconds
+    pub latency: LatencyMetrics,
+    /// Client-observed latency minus processing time (network, queueing, and
+    /// client overhead) percentiles in microseconds
+    pub overhead: LatencyMetrics,
+}
+
+/// What successful searches returned. A provider under pressure can answer
+/// quickly with fewer or worse matches, which latency alone doesn't show.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResultMetrics {
+    /// Successful searches
+    pub query_count: usize,
+    /// Searches that returned no results
+    pub empty_count: usize,
+    /// Share of searches that returned no results
+    pub empty_rate: f64,
+    /// Mean results returned per search
+    pub mean_hits: f64,
+    /// Scores of every returned result, if any were returned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<ScoreStats>,
+}
+
+/// Spread of result scores
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ScoreStats {
+    /// Scores seen
+    pub count: usize,
+    pub min: f64,
+    pub mean: f64,
+    pub max: f64,
+}
+
+impl ScoreStats {
+    fn of(results: &[SearchResult]) -> Option<Self> {
+        let scores: Vec<f64> = results
+            .iter()
+            .map(|r| r.score as f64)
+            .filter(|s| s.is_finite())
+            .collect();
+        (!scores.is_empty()).then(|| Self {
+            count: scores.len(),
+            min: scores.iter().copied().fold(f64::INFINITY, f64::min),
+            mean: scores.iter().sum::<f64>() / scores.len() as f64,
+            max: scores.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        })
+    }
+
+    fn merge(a: Option<Self>, b: Option<Self>) -> Option<Self> {
+        match (a, b) {
+            (Some(a), Some(b)) => {
+                let count = a.count + b.count;
+                Some(Self {
+                    count,
+                    min: a.min.min(b.min),
+                    mean: (a.mean * a.count as f64 + b.mean * b.count as f64) / count as f64,
+                    max: a.max.max(b.max),
+                })
+            }
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+/// Retrieval and rerank latency for searches that went through a reranker
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RerankMetrics {
+    /// Searches whose results were passed to the reranker
+    pub request_count: usize,
+    /// Rerank calls that failed
+    pub failure_count: usize,
+    /// Provider search alone, in microseconds
+    pub retrieval_latency: LatencyMetrics,
+    /// Rerank call alone, in microseconds
+    pub latency: LatencyMetrics,
+}
+
+/// Latency of each page request for searches paged through their results
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PaginationMetrics {
+    /// Results requested per page
+    pub page_size: usize,
+    /// First page first
+    pub pages: Vec<PageMetrics>,
+}
+
+/// Requests for one page depth
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PageMetrics {
+    /// Results skipped before this page
+    pub offset: usize,
+    /// Searches that reached this page
+    pub request_count: usize,
+    /// Page request latency percentiles in microseconds
+    pub latency: LatencyMetrics,
+}
+
+/// How well one query's results match its ground truth
+#[derive(Debug, Clone, Copy)]
+pub struct Judgment {
+    pub recall: f64,
+    pub ndcg: f64,
+    pub reciprocal_rank: f64,
+}
+
+/// Latency percentiles
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LatencyMetrics {
     pub min_us: u64,
     pub max_us: u64,
@@ -34,6 +272,61 @@ pub struct LatencyMetrics {
     pub p90_us: u64,
     pub p95_us: u64,
     pub p99_us: u64,
+    /// Standard deviation of latency
+    #[serde(default)]
+    pub stddev_us: f64,
+    /// Coefficient of variation: standard deviation over the mean (0 with no samples)
+    #[serde(default)]
+    pub cv: f64,
+    /// Interquartile range: p75 minus p25
+    #[serde(default)]
+    pub iqr_us: u64,
+    /// Extra percentiles from `benchmark.percentiles`, in the order configured
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub percentiles: Vec<PercentileLatency>,
+}
+
+impl LatencyMetrics {
+    /// Latency at one of the extra configured percentiles
+    pub fn percentile(&self, percentile: f64) -> Option<u64> {
+        self.percentiles
+            .iter()
+            .find(|p| p.percentile == percentile)
+            .map(|p| p.latency_us)
+    }
+}
+
+/// Latency at a configured percentile
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PercentileLatency {
+    /// Percentile between 0 and 100 (e.g. 99.9)
+    pub percentile: f64,
+    pub latency_us: u64,
+}
+
+/// Aggregate results for one concurrency level of a sweep
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SweepPoint {
+    /// Max concurrent requests at this level
+    pub concurrency: usize,
+    /// Bursts run at this level
+    pub bursts: usize,
+    pub query_count: usize,
+    pub success_count: usize,
+    pub failure_count: usize,
+    /// Average queries per second across the bursts
+    pub qps: f64,
+    /// Latency percentiles across every query at this level
+    pub latency: LatencyMetrics,
+    /// Mean Recall@k if ground truth was provided
+    pub recall_at_k: Option<f64>,
+}
+
+/// Throughput-vs-latency curve from a concurrency sweep
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SweepReport {
+    pub provider: String,
+    pub points: Vec<SweepPoint>,
 }
 
 /// Tracks metrics across multiple bursts
@@ -44,59 +337,434 @@ pub struct Metrics {
     bursts: Vec<BurstMetrics>,
     /// Current burst state
     current_burst: Option<BurstState>,
+    /// Raw latencies from the most recently finished burst
+    last_samples: BurstSamples,
+    /// Extra percentiles reported for query latency
+    percentiles: Vec<f64>,
+    windows: Vec<SlidingWindow>,
+    resources: ResourceSampler,
+    /// Latency per query tag across all bursts
+    tag_histograms: BTreeMap<String, Histogram<u64>>,
 }
 
 struct BurstState {
     start_time: Instant,
     start_timestamp: chrono::DateTime<chrono::Utc>,
-    latencies_us: Vec<u64>,
+    peak_in_flight: Option<usize>,
+    phase: Option<String>,
+    recorder: Recorder,
+}
+
+/// Requests recorded into one shard of a burst
+#[derive(Default)]
+struct Tally {
+    /// When each query completed, its latency, and whether it succeeded
+    completions: Vec<(Instant, u64, bool)>,
     successes: usize,
     failures: usize,
+    timeouts: usize,
     recalls: Vec<f64>,
+    ndcgs: Vec<f64>,
+    reciprocal_ranks: Vec<f64>,
+    write_latencies_us: Vec<u64>,
+    write_failures: usize,
+    documents_written: usize,
+    retrieval_latencies_us: Vec<u64>,
+    rerank_latencies_us: Vec<u64>,
+    rerank_failures: usize,
+    page_size: usize,
+    page_latencies_us: Vec<Vec<u64>>,
+    overlaps: Vec<f64>,
+    server_latencies_us: Vec<u64>,
+    overhead_latencies_us: Vec<u64>,
+    hit_counts: Vec<usize>,
+    scores: Option<ScoreStats>,
+    status_codes: BTreeMap<u16, usize>,
+    tags: BTreeMap<String, TagState>,
+}
+
+#[derive(Default)]
+struct TagState {
+    latencies_us: Vec<u64>,
+    failures: usize,
+    recalls: Vec<f64>,
+}
+
+impl Tally {
+    fn query(&mut self, latency: Duration, success: bool) {
+        self.completions
+            .push((Instant::now(), latency.as_micros() as u64, success));
+        if success {
+            self.successes += 1;
+        } else {
+            self.failures += 1;
+        }
+    }
+
+    fn merge(&mut self, other: Tally) {
+        self.completions.extend(other.completions);
+        self.successes += other.successes;
+        self.failures += other.failures;
+        self.timeouts += other.timeouts;
+        self.recalls.extend(other.recalls);
+        self.ndcgs.extend(other.ndcgs);
+        self.reciprocal_ranks.extend(other.reciprocal_ranks);
+        self.write_latencies_us.extend(other.write_latencies_us);
+        self.write_failures += other.write_failures;
+        self.documents_written += other.documents_written;
+        self.retrieval_latencies_us
+            .extend(other.retrieval_latencies_us);
+        self.rerank_latencies_us.extend(other.rerank_latencies_us);
+        self.rerank_failures += other.rerank_failures;
+        self.page_size = self.page_size.max(other.page_size);
+        if self.page_latencies_us.len() < other.page_latencies_us.len() {
+            self.page_latencies_us
+                .resize_with(other.page_latencies_us.len(), Vec::new);
+        }
+        for (latencies, page) in self
+            .page_latencies_us
+            .iter_mut()
+            .zip(other.page_latencies_us)
+        {
+            latencies.extend(page);
+        }
+        self.overlaps.extend(other.overlaps);
+        self.server_latencies_us.extend(other.server_latencies_us);
+        self.overhead_latencies_us
+            .extend(other.overhead_latencies_us);
+        self.hit_counts.extend(other.hit_counts);
+        self.scores = ScoreStats::merge(self.scores, other.scores);
+        for (status, count) in other.status_codes {
+            *self.status_codes.entry(status).or_default() += count;
+        }
+        for (tag, state) in other.tags {
+            let merged = self.tags.entry(tag).or_default();
+            merged.latencies_us.extend(state.latencies_us);
+            merged.failures += state.failures;
+            merged.recalls.extend(state.recalls);
+        }
+    }
+}
+
+/// Records requests into a burst while it runs.
+///
+/// Clones share the burst and can record from any task or thread. Each clone
+/// writes to its own shard of the burst, so requests finishing at the same
+/// time rarely wait on one another; the shards are combined when the burst
+/// finishes.
+pub struct Recorder {
+    shards: Arc<[Mutex<Tally>]>,
+    /// Shard the next clone writes to
+    next: Arc<AtomicUsize>,
+    shard: usize,
+}
+
+impl Clone for Recorder {
+    fn clone(&self) -> Self {
+        Self {
+            shards: self.shards.clone(),
+            next: self.next.clone(),
+            shard: self.next.fetch_add(1, Ordering::Relaxed) % self.shards.len(),
+        }
+    }
+}
+
+impl Recorder {
+    fn new() -> Self {
+        let shards = std::thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        Self {
+            shards: (0..shards).map(|_| Mutex::default()).collect(),
+            next: Arc::new(AtomicUsize::new(1)),
+            shard: 0,
+        }
+    }
+
+    fn tally(&self) -> MutexGuard<'_, Tally> {
+        self.shards[self.shard]
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Everything recorded so far, emptying the shards
+    fn take(&self) -> Tally {
+        let mut tally = Tally::default();
+        for shard in self.shards.iter() {
+            let shard = std::mem::take(&mut *shard.lock().unwrap_or_else(|e| e.into_inner()));
+            tally.merge(shard);
+        }
+        tally
+    }
+
+    /// Record a successful query execution
+    pub fn record_success(&self, latency: Duration, recall: Option<f64>) {
+        let mut tally = self.tally();
+        tally.query(latency, true);
+        tally.recalls.extend(recall);
+    }
+
+    /// Record the ranking quality of a successful query's results, alongside
+    /// the recall passed to `record_success`
+    pub fn record_ranking(&self, ndcg: f64, reciprocal_rank: f64) {
+        let mut tally = self.tally();
+        tally.ndcgs.push(ndcg);
+        tally.reciprocal_ranks.push(reciprocal_rank);
+    }
+
+    /// Record a failed query execution
+    pub fn record_failure(&self, latency: Duration) {
+        self.tally().query(latency, false);
+    }
+
+    /// Record a query abandoned after exceeding its timeout
+    pub fn record_timeout(&self, latency: Duration) {
+        let mut tally = self.tally();
+        tally.timeouts += 1;
+        tally.query(latency, false);
+    }
+
+    /// Record an upsert request of `documents` documents. Kept apart from
+    /// search latency so writes don't skew query percentiles.
+    pub fn record_write(&self, latency: Duration, documents: usize, success: bool) {
+        let mut tally = self.tally();
+        tally.write_latencies_us.push(latency.as_micros() as u64);
+        if success {
+            tally.documents_written += documents;
+        } else {
+            tally.write_failures += 1;
+        }
+    }
+
+    /// Record the two stages of a reranked search. The query itself is
+    /// recorded separately with its end-to-end latency.
+    pub fn record_rerank(&self, retrieval: Duration, rerank: Duration, success: bool) {
+        let mut tally = self.tally();
+        tally
+            .retrieval_latencies_us
+            .push(retrieval.as_micros() as u64);
+        tally.rerank_latencies_us.push(rerank.as_micros() as u64);
+        if !success {
+            tally.rerank_failures += 1;
+        }
+    }
+
+    /// Record the page requests of a paginated search, `page_size` results
+    /// at a time. The query itself is recorded separately with the latency
+    /// of all its pages.
+    pub fn record_pages(&self, page_size: usize, pages: &[Duration]) {
+        let mut tally = self.tally();
+        tally.page_size = page_size;
+        if tally.page_latencies_us.len() < pages.len() {
+            tally.page_latencies_us.resize_with(pages.len(), Vec::new);
+        }
+        for (latencies, page) in tally.page_latencies_us.iter_mut().zip(pages) {
+            latencies.push(page.as_micros() as u64);
+        }
+    }
+
+    /// Record the processing time an engine reported for a request the
+    /// client saw take `latency`
+    pub fn record_server_time(&self, latency: Duration, took_us: u64) {
+        let mut tally = self.tally();
+        tally.server_latencies_us.push(took_us);
+        tally
+            .overhead_latencies_us
+            .push((latency.as_micros() as u64).saturating_sub(took_us));
+    }
+
+    /// Record how much a query's results overlap with the ones it returned
+    /// last time
+    pub fn record_stability(&self, overlap: f64) {
+        self.tally().overlaps.push(overlap);
+    }
+
+    /// Record the results a successful query returned
+    pub fn record_results(&self, results: &[SearchResult]) {
+        let mut tally = self.tally();
+        tally.hit_counts.push(results.len());
+        tally.scores = ScoreStats::merge(tally.scores, ScoreStats::of(results));
+    }
+
+    /// Record a search for a query carrying `tags`, alongside its
+    /// `record_success` or `record_failure`
+    pub fn record_tags(
+        &self,
+        tags: &[String],
+        latency: Duration,
+        success: bool,
+        recall: Option<f64>,
+    ) {
+        let mut tally = self.tally();
+        for tag in tags {
+            // Only a tag's first request pays for a copy of its name
+            if !tally.tags.contains_key(tag) {
+                tally.tags.insert(tag.clone(), TagState::default());
+            }
+            let state = tally.tags.get_mut(tag).expect("just inserted");
+            state.latencies_us.push(latency.as_micros() as u64);
+            if !success {
+                state.failures += 1;
+            }
+            state.recalls.extend(recall);
+        }
+    }
+
+    /// Record the HTTP status a search was answered with
+    pub fn record_status(&self, status: u16) {
+        *self.tally().status_codes.entry(status).or_default() += 1;
+    }
 }
 
 impl Metrics {
     pub fn new() -> Self {
         Self {
-            // Track latencies from 1us to 60 seconds with 3 significant figures
-            latency_histogram: Histogram::new_with_bounds(1, 60_000_000, 3).unwrap(),
+            latency_histogram: new_latency_histogram(),
             bursts: Vec::new(),
             current_burst: None,
+            last_samples: BurstSamples::default(),
+            percentiles: Vec::new(),
+            windows: Vec::new(),
+            resources: ResourceSampler::default(),
+            tag_histograms: BTreeMap::new(),
         }
     }
 
+    /// Also report query latency at these percentiles (between 0 and 100)
+    pub fn with_percentiles(mut self, percentiles: Vec<f64>) -> Self {
+        self.percentiles = percentiles;
+        self
+    }
+
+    /// Keep rolling windows of these lengths, reported with every burst
+    pub fn with_windows(mut self, windows: &[Duration]) -> Self {
+        self.windows = windows.iter().map(|&w| SlidingWindow::new(w)).collect();
+        self
+    }
+
     /// Start tracking a new burst
     pub fn start_burst(&mut self) {
+        self.resources.start();
         self.current_burst = Some(BurstState {
             start_time: Instant::now(),
             start_timestamp: chrono::Utc::now(),
-            latencies_us: Vec::new(),
-            successes: 0,
-            failures: 0,
-            recalls: Vec::new(),
+            peak_in_flight: None,
+            phase: None,
+            recorder: Recorder::new(),
         });
     }
 
-    /// Record a successful query execution
-    pub fn record_success(&mut self, latency: Duration, recall: Option<f64>) {
+    /// A handle requests can record into the current burst with as they
+    /// complete, if a burst is in progress
+    pub fn recorder(&self) -> Option<Recorder> {
+        self.current_burst.as_ref().map(|b| b.recorder.clone())
+    }
+
+    fn current(&self) -> Option<&Recorder> {
+        self.current_burst.as_ref().map(|b| &b.recorder)
+    }
+
+    /// Note the number of requests currently outstanding
+    pub fn record_in_flight(&mut self, in_flight: usize) {
         if let Some(burst) = &mut self.current_burst {
-            let latency_us = latency.as_micros() as u64;
-            burst.latencies_us.push(latency_us);
-            burst.successes += 1;
-            if let Some(r) = recall {
-                burst.recalls.push(r);
-            }
-            let _ = self.latency_histogram.record(latency_us);
+            burst.peak_in_flight = Some(burst.peak_in_flight.unwrap_or(0).max(in_flight));
         }
     }
 
-    /// Record a failed query execution
-    pub fn record_failure(&mut self, latency: Duration) {
+    /// Label the current burst with the scenario phase it belongs to
+    pub fn record_phase(&mut self, phase: String) {
         if let Some(burst) = &mut self.current_burst {
-            let latency_us = latency.as_micros() as u64;
-            burst.latencies_us.push(latency_us);
-            burst.failures += 1;
-            let _ = self.latency_histogram.record(latency_us);
+            burst.phase = Some(phase);
+        }
+    }
+
+    /// Record a successful query execution
+    pub fn record_success(&self, latency: Duration, recall: Option<f64>) {
+        if let Some(recorder) = self.current() {
+            recorder.record_success(latency, recall);
+        }
+    }
+
+    /// Record the ranking quality of a successful query's results, alongside
+    /// the recall passed to `record_success`
+    pub fn record_ranking(&self, ndcg: f64, reciprocal_rank: f64) {
+        if let Some(recorder) = self.current() {
+            recorder.record_ranking(ndcg, reciprocal_rank);
+        }
+    }
+
+    /// Record a failed query execution
+    pub fn record_failure(&self, latency: Duration) {
+        if let Some(recorder) = self.current() {
+            recorder.record_failure(latency);
+        }
+    }
+
+    /// Record an upsert request of `documents` documents
+    pub fn record_write(&self, latency: Duration, documents: usize, success: bool) {
+        if let Some(recorder) = self.current() {
+            recorder.record_write(latency, documents, success);
+        }
+    }
+
+    /// Record the two stages of a reranked search
+    pub fn record_rerank(&self, retrieval: Duration, rerank: Duration, success: bool) {
+        if let Some(recorder) = self.current() {
+            recorder.record_rerank(retrieval, rerank, success);
+        }
+    }
+
+    /// Record the page requests of a paginated search
+    pub fn record_pages(&self, page_size: usize, pages: &[Duration]) {
+        if let Some(recorder) = self.current() {
+            recorder.record_pages(page_size, pages);
+        }
+    }
+
+    /// Record the processing time an engine reported for a request
+    pub fn record_server_time(&self, latency: Duration, took_us: u64) {
+        if let Some(recorder) = self.current() {
+            recorder.record_server_time(latency, took_us);
+        }
+    }
+
+    /// Record how much a query's results overlap with the ones it returned
+    /// last time
+    pub fn record_stability(&self, overlap: f64) {
+        if let Some(recorder) = self.current() {
+            recorder.record_stability(overlap);
+        }
+    }
+
+    /// Record the results a successful query returned
+    pub fn record_results(&self, results: &[SearchResult]) {
+        if let Some(recorder) = self.current() {
+            recorder.record_results(results);
+        }
+    }
+
+    /// Record a search for a query carrying `tags`
+    pub fn record_tags(
+        &self,
+        tags: &[String],
+        latency: Duration,
+        success: bool,
+        recall: Option<f64>,
+    ) {
+        if let Some(recorder) = self.current() {
+            recorder.record_tags(tags, latency, success, recall);
+        }
+    }
+
+    /// Record the HTTP status a search was answered with
+    pub fn record_status(&self, status: u16) {
+        if let Some(recorder) = self.current() {
+            recorder.record_status(status);
+        }
+    }
+
+    /// Record a query abandoned after exceeding its timeout
+    pub fn record_timeout(&self, latency: Duration) {
+        if let Some(recorder) = self.current() {
+            recorder.record_timeout(latency);
         }
     }
 
@@ -105,37 +773,199 @@ impl Metrics {
         let burst = self.current_burst.take()?;
         let duration = burst.start_time.elapsed();
         let duration_ms = duration.as_millis() as u64;
+        let mut tally = burst.recorder.take();
+
+        // Shards interleave in time; windows want requests in the order
+        // they completed
+        tally.completions.sort_by_key(|&(at, _, _)| at);
+        for &(at, latency_us, success) in &tally.completions {
+            let _ = self.latency_histogram.record(latency_us);
+            for window in &mut self.windows {
+                window.record(at, latency_us, success);
+            }
+        }
+        for (tag, state) in &tally.tags {
+            let histogram = self
+                .tag_histograms
+                .entry(tag.clone())
+                .or_insert_with(new_latency_histogram);
+            for &latency_us in &state.latencies_us {
+                let _ = histogram.record(latency_us);
+            }
+        }
+        let latencies_us: Vec<u64> = tally.completions.iter().map(|&(_, us, _)| us).collect();
 
-        let query_count = burst.successes + burst.failures;
+        let query_count = tally.successes + tally.failures;
         let qps = if duration_ms > 0 {
             (query_count as f64) / (duration_ms as f64 / 1000.0)
         } else {
             0.0
         };
 
-        let latency = compute_latency_metrics(&burst.latencies_us);
+        let mut latency = compute_latency_metrics(&latencies_us);
+        latency.percentiles = compute_percentiles(&latencies_us, &self.percentiles);
 
-        let recall_at_k = if burst.recalls.is_empty() {
-            None
-        } else {
-            Some(burst.recalls.iter().sum::<f64>() / burst.recalls.len() as f64)
-        };
+        let writes = (!tally.write_latencies_us.is_empty()).then(|| WriteMetrics {
+            request_count: tally.write_latencies_us.len(),
+            failure_count: tally.write_failures,
+            document_count: tally.documents_written,
+            docs_per_sec: if duration_ms > 0 {
+                tally.documents_written as f64 / (duration_ms as f64 / 1000.0)
+            } else {
+                0.0
+            },
+            latency: compute_latency_metrics(&tally.write_latencies_us),
+        });
+
+        let rerank = (!tally.rerank_latencies_us.is_empty()).then(|| RerankMetrics {
+            request_count: tally.rerank_latencies_us.len(),
+            failure_count: tally.rerank_failures,
+            retrieval_latency: compute_latency_metrics(&tally.retrieval_latencies_us),
+            latency: compute_latency_metrics(&tally.rerank_latencies_us),
+        });
+
+        let pagination = page_metrics(tally.page_size, &tally.page_latencies_us);
+        let server = server_metrics(&tally.server_latencies_us, &tally.overhead_latencies_us);
 
         let metrics = BurstMetrics {
             timestamp: burst.start_timestamp,
             duration_ms,
             query_count,
-            success_count: burst.successes,
-            failure_count: burst.failures,
+            success_count: tally.successes,
+            failure_count: tally.failures,
+            timeout_count: tally.timeouts,
             latency,
+            server,
             qps,
-            recall_at_k,
+            recall_at_k: mean(&tally.recalls),
+            ndcg_at_k: mean(&tally.ndcgs),
+            mrr: mean(&tally.reciprocal_ranks),
+            probe_recall: None,
+            peak_in_flight: burst.peak_in_flight,
+            writes,
+            phase: burst.phase,
+            rerank,
+            pagination,
+            stability: mean(&tally.overlaps),
+            windows: self.window_metrics(),
+            results: result_metrics(&tally.hit_counts, tally.scores),
+            client: self.resources.sample(),
+            server_stats: None,
+            status_codes: tally.status_codes,
+            tags: tally
+                .tags
+                .iter()
+                .map(|(tag, state)| {
+                    let metrics = TagMetrics {
+                        query_count: state.latencies_us.len(),
+                        failure_count: state.failures,
+                        latency: compute_latency_metrics(&state.latencies_us),
+                        recall_at_k: mean(&state.recalls),
+                    };
+                    (tag.clone(), metrics)
+                })
+                .collect(),
         };
 
+        self.last_samples = BurstSamples {
+            latencies_us,
+            write_latencies_us: tally.write_latencies_us,
+            retrieval_latencies_us: tally.retrieval_latencies_us,
+            rerank_latencies_us: tally.rerank_latencies_us,
+            page_latencies_us: tally.page_latencies_us,
+            overlaps: tally.overlaps,
+            server_latencies_us: tally.server_latencies_us,
+            overhead_latencies_us: tally.overhead_latencies_us,
+            tag_latencies_us: tally
+                .tags
+                .into_iter()
+                .map(|(tag, state)| (tag, state.latencies_us))
+                .collect(),
+        };
         self.bursts.push(metrics.clone());
         Some(metrics)
     }
 
+    /// Add a burst measured elsewhere (e.g. merged from several client
+    /// threads) as if it had been recorded here, filling in its windows
+    pub fn push_burst(&mut self, mut burst: BurstMetrics, samples: BurstSamples) -> BurstMetrics {
+        for (i, &latency_us) in samples.latencies_us.iter().enumerate() {
+            let _ = self.latency_histogram.record(latency_us);
+            // Completion times aren't sent back, so the burst's requests all
+            // land in the windows as it finishes; only the failure count is kept
+            self.record_window(latency_us, i >= burst.failure_count);
+        }
+        for (tag, latencies) in &samples.tag_latencies_us {
+            let histogram = self
+                .tag_histograms
+                .entry(tag.clone())
+                .or_insert_with(new_latency_histogram);
+            for &latency_us in latencies {
+                let _ = histogram.record(latency_us);
+            }
+        }
+        burst.windows = self.window_metrics();
+        if let Some(client) = self.resources.sample() {
+            burst.client = Some(client);
+        }
+        self.last_samples = samples;
+        self.bursts.push(burst.clone());
+        burst
+    }
+
+    /// Attach engine stats polled after the most recent burst
+    pub fn record_server_stats(&mut self, stats: serde_json::Value) {
+        if let Some(burst) = self.bursts.last_mut() {
+            burst.server_stats = Some(stats);
+        }
+    }
+
+    /// Start measuring process resources for a burst that will be added
+    /// with `push_burst`
+    pub(crate) fn start_pushed_burst(&mut self) {
+        self.resources.start();
+    }
+
+    fn record_window(&mut self, latency_us: u64, success: bool) {
+        let now = Instant::now();
+        for window in &mut self.windows {
+            window.record(now, latency_us, success);
+        }
+    }
+
+    fn window_metrics(&mut self) -> Vec<WindowMetrics> {
+        let now = Instant::now();
+        self.windows.iter_mut().map(|w| w.snapshot(now)).collect()
+    }
+
+    /// Latencies of every query so far, in microseconds
+    pub fn latency_histogram(&self) -> &Histogram<u64> {
+        &self.latency_histogram
+    }
+
+    /// Latencies of every query so far per query tag, in microseconds
+    pub fn tag_histograms(&self) -> &BTreeMap<String, Histogram<u64>> {
+        &self.tag_histograms
+    }
+
+    /// Carry on from an earlier run: its bursts become this run's first ones,
+    /// and its latencies count towards the aggregate percentiles
+    pub fn restore(
+        &mut self,
+        bursts: Vec<BurstMetrics>,
+        latency: Histogram<u64>,
+        tags: BTreeMap<String, Histogram<u64>>,
+    ) {
+        self.bursts = bursts;
+        self.latency_histogram = latency;
+        self.tag_histograms = tags;
+    }
+
+    /// Raw latencies from the most recently finished burst
+    pub fn last_burst_samples(&self) -> &BurstSamples {
+        &self.last_samples
+    }
+
     /// Get all burst metrics
     pub fn bursts(&self) -> &[BurstMetrics] {
         &self.bursts
@@ -148,15 +978,25 @@ impl Metrics {
 
     /// Compute aggregate latency metrics across all bursts
     pub fn aggregate_latency(&self) -> LatencyMetrics {
-        LatencyMetrics {
-            min_us: self.latency_histogram.min(),
-            max_us: self.latency_histogram.max(),
-            mean_us: self.latency_histogram.mean(),
-            p50_us: self.latency_histogram.value_at_quantile(0.50),
-            p90_us: self.latency_histogram.value_at_quantile(0.90),
-            p95_us: self.latency_histogram.value_at_quantile(0.95),
-            p99_us: self.latency_histogram.value_at_quantile(0.99),
-        }
+        histogram_latency(&self.latency_histogram, &self.percentiles)
+    }
+
+    /// Per-tag counts, latency, and recall across all bursts
+    pub fn aggregate_tags(&self) -> BTreeMap<String, TagMetrics> {
+        self.tag_histograms
+            .iter()
+            .map(|(tag, histogram)| {
+                let bursts: Vec<&TagMetrics> =
+                    self.bursts.iter().filter_map(|b| b.tags.get(tag)).collect();
+                let metrics = TagMetrics {
+                    query_count: bursts.iter().map(|t| t.query_count).sum(),
+                    failure_count: bursts.iter().map(|t| t.failure_count).sum(),
+                    latency: histogram_latency(histogram, &self.percentiles),
+                    recall_at_k: weighted_recall(&bursts),
+                };
+                (tag.clone(), metrics)
+            })
+            .collect()
     }
 
     /// Total queries executed across all bursts
@@ -164,6 +1004,32 @@ impl Metrics {
         self.bursts.iter().map(|b| b.query_count).sum()
     }
 
+    /// Total successful queries across all bursts
+    pub fn total_successes(&self) -> usize {
+        self.bursts.iter().map(|b| b.success_count).sum()
+    }
+
+    /// Mean Recall@k across bursts that measured it
+    pub fn average_recall(&self) -> Option<f64> {
+        let recalls: Vec<f64> = self.bursts.iter().filter_map(|b| b.recall_at_k).collect();
+        if recalls.is_empty() {
+            return None;
+        }
+        Some(recalls.iter().sum::<f64>() / recalls.len() as f64)
+    }
+
+    /// Mean NDCG@k across bursts that measured it
+    pub fn average_ndcg(&self) -> Option<f64> {
+        let ndcgs: Vec<f64> = self.bursts.iter().filter_map(|b| b.ndcg_at_k).collect();
+        mean(&ndcgs)
+    }
+
+    /// Mean MRR across bursts that measured it
+    pub fn average_mrr(&self) -> Option<f64> {
+        let mrrs: Vec<f64> = self.bursts.iter().filter_map(|b| b.mrr).collect();
+        mean(&mrrs)
+    }
+
     /// Average QPS across all bursts
     pub fn average_qps(&self) -> f64 {
         if self.bursts.is_empty() {
@@ -179,7 +1045,278 @@ impl Default for Metrics {
     }
 }
 
-fn compute_latency_metrics(latencies_us: &[u64]) -> LatencyMetrics {
+/// Combine bursts that ran at the same time (e.g. on separate workers) into one.
+///
+/// Counts and throughput add up, percentiles are recomputed from the pooled
+/// samples, and recall is weighted by each burst's successful queries.
+pub fn merge_bursts(parts: &[(BurstMetrics, BurstSamples)]) -> Option<BurstMetrics> {
+    let (first, _) = parts.first()?;
+    let timestamp = parts.iter().map(|(b, _)| b.timestamp).min()?;
+    let duration_ms = parts.iter().map(|(b, _)| b.duration_ms).max()?;
+    let per_second = |count: usize| {
+        if duration_ms > 0 {
+            count as f64 / (duration_ms as f64 / 1000.0)
+        } else {
+            0.0
+        }
+    };
+
+    let sum = |f: fn(&BurstMetrics) -> usize| parts.iter().map(|(b, _)| f(b)).sum::<usize>();
+    let query_count = sum(|b| b.query_count);
+
+    let latencies: Vec<u64> = parts
+        .iter()
+        .flat_map(|(_, s)| s.latencies_us.iter().copied())
+        .collect();
+    // Every part reports the same configured percentiles
+    let percentiles: Vec<f64> = first
+        .latency
+        .percentiles
+        .iter()
+        .map(|p| p.percentile)
+        .collect();
+
+    let weighted = |f: fn(&BurstMetrics) -> Option<f64>| {
+        let (sum, weight) = parts
+            .iter()
+            .filter_map(|(b, _)| f(b).map(|r| (r, b.success_count)))
+            .fold((0.0, 0), |(sum, n), (r, w)| (sum + r * w as f64, n + w));
+        (weight > 0).then(|| sum / weight as f64)
+    };
+
+    let peaks: Vec<usize> = parts.iter().filter_map(|(b, _)| b.peak_in_flight).collect();
+
+    let writes: Vec<&WriteMetrics> = parts
+        .iter()
+        .filter_map(|(b, _)| b.writes.as_ref())
+        .collect();
+    let writes = (!writes.is_empty()).then(|| {
+        let document_count = writes.iter().map(|w| w.document_count).sum();
+        let write_latencies: Vec<u64> = parts
+            .iter()
+            .flat_map(|(_, s)| s.write_latencies_us.iter().copied())
+            .collect();
+        WriteMetrics {
+            request_count: writes.iter().map(|w| w.request_count).sum(),
+            failure_count: writes.iter().map(|w| w.failure_count).sum(),
+            document_count,
+            docs_per_sec: per_second(document_count),
+            latency: compute_latency_metrics(&write_latencies),
+        }
+    });
+
+    let pooled = |f: fn(&BurstSamples) -> &Vec<u64>| -> Vec<u64> {
+        parts
+            .iter()
+            .flat_map(|(_, s)| f(s).iter().copied())
+            .collect()
+    };
+
+    let reranks: Vec<&RerankMetrics> = parts
+        .iter()
+        .filter_map(|(b, _)| b.rerank.as_ref())
+        .collect();
+    let rerank = (!reranks.is_empty()).then(|| RerankMetrics {
+        request_count: reranks.iter().map(|r| r.request_count).sum(),
+        failure_count: reranks.iter().map(|r| r.failure_count).sum(),
+        retrieval_latency: compute_latency_metrics(&pooled(|s| &s.retrieval_latencies_us)),
+        latency: compute_latency_metrics(&pooled(|s| &s.rerank_latencies_us)),
+    });
+
+    let overlaps: Vec<f64> = parts
+        .iter()
+        .flat_map(|(_, s)| s.overlaps.iter().copied())
+        .collect();
+
+    // Pool each page depth's samples across the parts
+    let mut page_latencies: Vec<Vec<u64>> = Vec::new();
+    for (_, samples) in parts {
+        if page_latencies.len() < samples.page_latencies_us.len() {
+            page_latencies.resize_with(samples.page_latencies_us.len(), Vec::new);
+        }
+        for (pooled, part) in page_latencies.iter_mut().zip(&samples.page_latencies_us) {
+            pooled.extend_from_slice(part);
+        }
+    }
+    let page_size = parts
+        .iter()
+        .find_map(|(b, _)| b.pagination.as_ref())
+        .map_or(0, |p| p.page_size);
+
+    Some(BurstMetrics {
+        timestamp,
+        duration_ms,
+        query_count,
+        success_count: sum(|b| b.success_count),
+        failure_count: sum(|b| b.failure_count),
+        timeout_count: sum(|b| b.timeout_count),
+        latency: LatencyMetrics {
+            percentiles: compute_percentiles(&latencies, &percentiles),
+            ..compute_latency_metrics(&latencies)
+        },
+        server: server_metrics(
+            &pooled(|s| &s.server_latencies_us),
+            &pooled(|s| &s.overhead_latencies_us),
+        ),
+        qps: per_second(query_count),
+        recall_at_k: weighted(|b| b.recall_at_k),
+        ndcg_at_k: weighted(|b| b.ndcg_at_k),
+        mrr: weighted(|b| b.mrr),
+        probe_recall: None,
+        peak_in_flight: (!peaks.is_empty()).then(|| peaks.iter().sum()),
+        writes,
+        phase: first.phase.clone(),
+        rerank,
+        pagination: page_metrics(page_size, &page_latencies),
+        stability: mean(&overlaps),
+        windows: Vec::new(),
+        results: merge_results(parts.iter().filter_map(|(b, _)| b.results.as_ref())),
+        client: parts
+            .iter()
+            .filter_map(|(b, _)| b.client.clone())
+            .max_by(|a, b| a.cpu_percent.total_cmp(&b.cpu_percent)),
+        // Every part polled the same engine, so any one of them will do
+        server_stats: parts.iter().find_map(|(b, _)| b.server_stats.clone()),
+        tags: merge_tags(parts, &percentiles),
+        status_codes: parts.iter().flat_map(|(b, _)| &b.status_codes).fold(
+            BTreeMap::new(),
+            |mut total, (&status, &count)| {
+                *total.entry(status).or_default() += count;
+                total
+            },
+        ),
+    })
+}
+
+fn merge_tags(
+    parts: &[(BurstMetrics, BurstSamples)],
+    percentiles: &[f64],
+) -> BTreeMap<String, TagMetrics> {
+    let mut tags: BTreeMap<&str, Vec<&TagMetrics>> = BTreeMap::new();
+    for (burst, _) in parts {
+        for (tag, metrics) in &burst.tags {
+            tags.entry(tag).or_default().push(metrics);
+        }
+    }
+    tags.into_iter()
+        .map(|(tag, metrics)| {
+            let latencies: Vec<u64> = parts
+                .iter()
+                .filter_map(|(_, s)| s.tag_latencies_us.get(tag))
+                .flatten()
+                .copied()
+                .collect();
+            let merged = TagMetrics {
+                query_count: metrics.iter().map(|t| t.query_count).sum(),
+                failure_count: metrics.iter().map(|t| t.failure_count).sum(),
+                latency: LatencyMetrics {
+                    percentiles: compute_percentiles(&latencies, percentiles),
+                    ..compute_latency_metrics(&latencies)
+                },
+                recall_at_k: weighted_recall(&metrics),
+            };
+            (tag.to_string(), merged)
+        })
+        .collect()
+}
+
+fn result_metrics(hit_counts: &[usize], score: Option<ScoreStats>) -> Option<ResultMetrics> {
+    let query_count = hit_counts.len();
+    let empty_count = hit_counts.iter().filter(|&&hits| hits == 0).count();
+    (query_count > 0).then(|| ResultMetrics {
+        query_count,
+        empty_count,
+        empty_rate: empty_count as f64 / query_count as f64,
+        mean_hits: hit_counts.iter().sum::<usize>() as f64 / query_count as f64,
+        score,
+    })
+}
+
+fn merge_results<'a>(parts: impl Iterator<Item = &'a ResultMetrics>) -> Option<ResultMetrics> {
+    let mut merged: Option<ResultMetrics> = None;
+    for part in parts {
+        let total = merged.get_or_insert_with(ResultMetrics::default);
+        let query_count = total.query_count + part.query_count;
+        if query_count == 0 {
+            continue;
+        }
+        let hits =
+            total.mean_hits * total.query_count as f64 + part.mean_hits * part.query_count as f64;
+        total.empty_count += part.empty_count;
+        total.empty_rate = total.empty_count as f64 / query_count as f64;
+        total.mean_hits = hits / query_count as f64;
+        total.query_count = query_count;
+        total.score = ScoreStats::merge(total.score, part.score);
+    }
+    merged
+}
+
+fn server_metrics(server_us: &[u64], overhead_us: &[u64]) -> Option<ServerMetrics> {
+    (!server_us.is_empty()).then(|| ServerMetrics {
+        request_count: server_us.len(),
+        latency: compute_latency_metrics(server_us),
+        overhead: compute_latency_metrics(overhead_us),
+    })
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+fn page_metrics(page_size: usize, page_latencies_us: &[Vec<u64>]) -> Option<PaginationMetrics> {
+    (!page_latencies_us.is_empty()).then(|| PaginationMetrics {
+        page_size,
+        pages: page_latencies_us
+            .iter()
+            .enumerate()
+            .map(|(i, latencies)| PageMetrics {
+                offset: i * page_size,
+                request_count: latencies.len(),
+                latency: compute_latency_metrics(latencies),
+            })
+            .collect(),
+    })
+}
+
+fn new_latency_histogram() -> Histogram<u64> {
+    // Track latencies from 1us to 60 seconds with 3 significant figures
+    Histogram::new_with_bounds(1, 60_000_000, 3).unwrap()
+}
+
+fn histogram_latency(histogram: &Histogram<u64>, percentiles: &[f64]) -> LatencyMetrics {
+    let mean_us = histogram.mean();
+    let stddev_us = histogram.stdev();
+    LatencyMetrics {
+        min_us: histogram.min(),
+        max_us: histogram.max(),
+        mean_us,
+        p50_us: histogram.value_at_quantile(0.50),
+        p90_us: histogram.value_at_quantile(0.90),
+        p95_us: histogram.value_at_quantile(0.95),
+        p99_us: histogram.value_at_quantile(0.99),
+        stddev_us,
+        cv: coefficient_of_variation(stddev_us, mean_us),
+        iqr_us: histogram.value_at_quantile(0.75) - histogram.value_at_quantile(0.25),
+        percentiles: percentiles
+            .iter()
+            .map(|&percentile| PercentileLatency {
+                percentile,
+                latency_us: histogram.value_at_quantile(percentile / 100.0),
+            })
+            .collect(),
+    }
+}
+
+/// Mean recall over several measurements of a tag, weighted by successful queries
+fn weighted_recall(parts: &[&TagMetrics]) -> Option<f64> {
+    let (sum, weight) = parts
+        .iter()
+        .filter_map(|t| t.recall_at_k.map(|r| (r, t.query_count - t.failure_count)))
+        .fold((0.0, 0), |(sum, n), (r, w)| (sum + r * w as f64, n + w));
+    (weight > 0).then(|| sum / weight as f64)
+}
+
+pub(crate) fn compute_latency_metrics(latencies_us: &[u64]) -> LatencyMetrics {
     if latencies_us.is_empty() {
         return LatencyMetrics {
             min_us: 0,
@@ -189,6 +1326,10 @@ fn compute_latency_metrics(latencies_us: &[u64]) -> LatencyMetrics {
             p90_us: 0,
             p95_us: 0,
             p99_us: 0,
+            stddev_us: 0.0,
+            cv: 0.0,
+            iqr_us: 0,
+            percentiles: Vec::new(),
         };
     }
 
@@ -198,11 +1339,14 @@ fn compute_latency_metrics(latencies_us: &[u64]) -> LatencyMetrics {
     let min_us = sorted[0];
     let max_us = sorted[sorted.len() - 1];
     let mean_us = sorted.iter().sum::<u64>() as f64 / sorted.len() as f64;
+    let variance = sorted
+        .iter()
+        .map(|&us| (us as f64 - mean_us).powi(2))
+        .sum::<f64>()
+        / sorted.len() as f64;
+    let stddev_us = variance.sqrt();
 
-    let percentile = |p: f64| -> u64 {
-        let idx = ((p / 100.0) * (sorted.len() - 1) as f64).round() as usize;
-        sorted[idx]
-    };
+    let percentile = |p: f64| percentile_of(&sorted, p);
 
     LatencyMetrics {
         min_us,
@@ -212,16 +1356,47 @@ fn compute_latency_metrics(latencies_us: &[u64]) -> LatencyMetrics {
         p90_us: percentile(90.0),
         p95_us: percentile(95.0),
         p99_us: percentile(99.0),
+        stddev_us,
+        cv: coefficient_of_variation(stddev_us, mean_us),
+        iqr_us: percentile(75.0) - percentile(25.0),
+        percentiles: Vec::new(),
     }
 }
 
+fn coefficient_of_variation(stddev: f64, mean: f64) -> f64 {
+    if mean > 0.0 { stddev / mean } else { 0.0 }
+}
+
+/// Latency at each of `percentiles`, in the order given
+fn compute_percentiles(latencies_us: &[u64], percentiles: &[f64]) -> Vec<PercentileLatency> {
+    if latencies_us.is_empty() || percentiles.is_empty() {
+        return Vec::new();
+    }
+
+    let mut sorted = latencies_us.to_vec();
+    sorted.sort_unstable();
+    percentiles
+        .iter()
+        .map(|&percentile| PercentileLatency {
+            percentile,
+            latency_us: percentile_of(&sorted, percentile),
+        })
+        .collect()
+}
+
+/// Nearest-rank percentile of already sorted, non-empty latencies
+fn percentile_of(sorted: &[u64], percentile: f64) -> u64 {
+    let idx = ((percentile / 100.0) * (sorted.len() - 1) as f64).round() as usize;
+    sorted[idx]
+}
+
 /// Calculate recall@k given returned IDs and expected IDs
 pub fn recall_at_k(returned: &[&str], expected: &[String], k: usize) -> f64 {
     if expected.is_empty() {
         return 0.0;
     }
 
-    let k = k.min(expected.len());
+    // Only the true top k count, however deep the ground truth goes
     let expected_set: std::collections::HashSet<&str> =
         expected.iter().take(k).map(|s| s.as_str()).collect();
 
@@ -231,7 +1406,53 @@ pub fn recall_at_k(returned: &[&str], expected: &[String], k: usize) -> f64 {
         .filter(|id| expected_set.contains(*id))
         .count();
 
-    hits as f64 / k as f64
+    hits as f64 / expected_set.len() as f64
+}
+
+/// NDCG@k with linear gains: each of the top `k` results gains its `grade`,
+/// discounted by log2 of its rank plus one, relative to the best ordering of
+/// the `ideal` grades
+pub fn ndcg_at_k(returned: &[&str], grade: impl Fn(&str) -> f64, ideal: &[f64], k: usize) -> f64 {
+    let discount = |rank: usize| (rank as f64 + 2.0).log2();
+    let dcg: f64 = returned
+        .iter()
+        .take(k)
+        .enumerate()
+        .map(|(rank, id)| grade(id) / discount(rank))
+        .sum();
+
+    let mut ideal = ideal.to_vec();
+    ideal.sort_by(|a, b| b.total_cmp(a));
+    let idcg: f64 = ideal
+        .iter()
+        .take(k)
+        .enumerate()
+        .map(|(rank, g)| g / discount(rank))
+        .sum();
+
+    if idcg > 0.0 { dcg / idcg } else { 0.0 }
+}
+
+/// Reciprocal of the rank of the first relevant result within the top `k`,
+/// or 0 if none is relevant
+pub fn reciprocal_rank(returned: &[&str], relevant: impl Fn(&str) -> bool, k: usize) -> f64 {
+    returned
+        .iter()
+        .take(k)
+        .position(|id| relevant(id))
+        .map_or(0.0, |rank| 1.0 / (rank as f64 + 1.0))
+}
+
+/// Jaccard similarity of two result ID sets: shared IDs over distinct IDs.
+/// Two empty result sets count as identical.
+pub fn jaccard<T: Eq + Hash>(a: &[T], b: &[T]) -> f64 {
+    let a: HashSet<&T> = a.iter().collect();
+    let b: HashSet<&T> = b.iter().collect();
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
 }
 
 #[cfg(test)]
@@ -248,5 +1469,89 @@ mod tests {
 
         // 2 of 3 expected in top 3 (a, c)
         assert!((recall_at_k(&returned, &expected, 3) - (2.0 / 3.0)).abs() < 0.001);
+
+        // Ground truth deeper than k is cut to its top k (a, c)
+        assert!((recall_at_k(&returned, &expected, 2) - 0.5).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_jaccard() {
+        let ids = |s: &[&str]| s.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        assert_eq!(jaccard(&ids(&["a", "b"]), &ids(&["b", "a"])), 1.0);
+        assert_eq!(jaccard(&ids(&["a", "b", "c"]), &ids(&["b", "c", "d"])), 0.5);
+        assert_eq!(jaccard(&ids(&["a"]), &ids(&["b"])), 0.0);
+        assert_eq!(jaccard::<String>(&[], &[]), 1.0);
+    }
+
+    #[test]
+    fn test_latency_dispersion() {
+        let latency = compute_latency_metrics(&[1000, 2000, 3000, 4000, 5000]);
+        assert!((latency.stddev_us - 2_000_000f64.sqrt()).abs() < 1e-6);
+        assert!((latency.cv - latency.stddev_us / 3000.0).abs() < 1e-9);
+        // p25 and p75 land on the second and fourth samples
+        assert_eq!(latency.iqr_us, 2000);
+    }
+
+    #[test]
+    fn test_merge_bursts() {
+        let mut parts = Vec::new();
+        for (latency_ms, recall) in [(10, 1.0), (30, 0.5)] {
+            let mut metrics = Metrics::new();
+            metrics.start_burst();
+            let tags = ["short".to_string()];
+            for _ in 0..4 {
+                let latency = Duration::from_millis(latency_ms);
+                metrics.record_success(latency, Some(recall));
+                metrics.record_tags(&tags, latency, true, Some(recall));
+            }
+            metrics.record_failure(Duration::from_millis(latency_ms));
+            metrics.record_tags(&tags, Duration::from_millis(latency_ms), false, None);
+            let burst = metrics.finish_burst().unwrap();
+            parts.push((burst, metrics.last_burst_samples().clone()));
+        }
+
+        let merged = merge_bursts(&parts).unwrap();
+        assert_eq!(merged.query_count, 10);
+        assert_eq!(merged.success_count, 8);
+        assert_eq!(merged.failure_count, 2);
+        assert_eq!(merged.latency.min_us, 10_000);
+        assert_eq!(merged.latency.max_us, 30_000);
+        assert!((merged.recall_at_k.unwrap() - 0.75).abs() < 0.001);
+        let short = &merged.tags["short"];
+        assert_eq!(short.query_count, 10);
+        assert_eq!(short.failure_count, 2);
+        assert_eq!(short.latency.max_us, 30_000);
+        assert!((short.recall_at_k.unwrap() - 0.75).abs() < 0.001);
+        assert!(merge_bursts(&[]).is_none());
+    }
+
+    #[test]
+    fn test_recorder_shared_across_threads() {
+        let mut metrics = Metrics::new().with_windows(&[Duration::from_secs(60)]);
+        metrics.start_burst();
+        let recorder = metrics.recorder().unwrap();
+        std::thread::scope(|scope| {
+            for latency_ms in 1..=4 {
+                let recorder = recorder.clone();
+                scope.spawn(move || {
+                    let latency = Duration::from_millis(latency_ms);
+                    recorder.record_success(latency, Some(1.0));
+                    recorder.record_status(200);
+                    recorder.record_timeout(latency);
+                });
+            }
+        });
+
+        let burst = metrics.finish_burst().unwrap();
+        assert_eq!(burst.query_count, 8);
+        assert_eq!(burst.success_count, 4);
+        assert_eq!(burst.timeout_count, 4);
+        assert_eq!(burst.status_codes[&200], 4);
+        assert_eq!(burst.latency.max_us, 4_000);
+        assert_eq!(burst.windows[0].query_count, 8);
+        assert_eq!(metrics.latency_histogram().len(), 8);
+        // Recording after the burst finished goes nowhere
+        recorder.record_failure(Duration::from_millis(1));
+        assert!(metrics.recorder().is_none());
     }
 }
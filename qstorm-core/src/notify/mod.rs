@@ -0,0 +1,50 @@
+//! Webhook notifications for run events.
+//!
+//! Posts to `notify.webhook_url` when a run finishes, a stop condition ends
+//! it, it fails, or a burst breaks the SLOs, so a long soak run can say how
+//! it went without anyone watching it. The `json` format sends the event
+//! with the run report; `slack` sends a short message with the headline
+//! numbers. Posting needs the `notifications` feature.
+
+#[cfg(feature = "notifications")]
+mod webhook;
+
+#[cfg(feature = "notifications")]
+pub use webhook::Notifier;
+
+use crate::config::NotifyEvent;
+use crate::metrics::BurstMetrics;
+use crate::report::RunReport;
+
+/// A run event to post
+#[derive(Debug, Clone, Copy)]
+pub enum Notification<'a> {
+    /// The run ended normally
+    Finished(&'a RunReport),
+    /// A stop condition ended the run; the report's `stop_reason` says which
+    Stopped(&'a RunReport),
+    /// `burst` broke the objectives described by `breaches`
+    SloBreach {
+        provider: &'a str,
+        breaches: &'a [String],
+        burst: &'a BurstMetrics,
+    },
+    /// The run ended with `error`, after the bursts in `report` if any ran
+    Failed {
+        provider: &'a str,
+        error: &'a str,
+        report: Option<&'a RunReport>,
+    },
+}
+
+impl Notification<'_> {
+    /// Which of the `notify.on` events this is
+    pub fn event(&self) -> NotifyEvent {
+        match self {
+            Notification::Finished(_) => NotifyEvent::Finished,
+            Notification::Stopped(_) => NotifyEvent::Stopped,
+            Notification::SloBreach { .. } => NotifyEvent::SloBreach,
+            Notification::Failed { .. } => NotifyEvent::Failed,
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use std::future::Future;
+use std::time::Duration;
+
+use serde_json::{Value, json};
+
+use crate::config::{NotifyConfig, NotifyEvent, WebhookFormat};
+use crate::error::{Error, Result};
+use crate::metrics::BurstMetrics;
+use crate::report::RunReport;
+
+use super::Notification;
+
+/// Posts run events to a webhook
+#[derive(Debug, Clone)]
+pub struct Notifier {
+    client: reqwest::Client,
+    url: String,
+    format: WebhookFormat,
+    on: Vec<NotifyEvent>,
+}
+
+impl Notifier {
+    pub fn new(config: &NotifyConfig) -> Result<Self> {
+        // The URL holds the webhook's token, so it stays out of the error
+        if !["http://", "https://"]
+            .iter()
+            .any(|scheme| config.webhook_url.starts_with(scheme))
+        {
+            return Err(Error::Config(
+                "notify.webhook_url must be an http:// or https:// URL".into(),
+            ));
+        }
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .map_err(Error::connection)?;
+        Ok(Self {
+            client,
+            url: config.webhook_url.clone(),
+            format: config.format,
+            on: config.on.clone(),
+        })
+    }
+
+    /// Whether `event` is one the config asks to be told about
+    pub fn wants(&self, event: NotifyEvent) -> bool {
+        self.on.is_empty() || self.on.contains(&event)
+    }
+
+    /// Post `notification`, or do nothing if its event isn't wanted. The
+    /// request is built before returning, so the future borrows nothing and
+    /// can be spawned to deliver in the background.
+    pub fn send(
+        &self,
+        notification: Notification<'_>,
+    ) -> impl Future<Output = Result<()>> + Send + 'static {
+        let request = self
+            .wants(notification.event())
+            .then(|| self.client.post(&self.url).json(&self.body(&notification)));
+        async move {
+            let Some(request) = request else {
+                return Ok(());
+            };
+            // reqwest errors name the URL, which holds the webhook's token
+            let response = request
+                .send()
+                .await
+                .map_err(|e| Error::connection(e.without_url()))?;
+            let status = response.status();
+            if !status.is_success() {
+                return Err(Error::HttpStatus {
+                    status: status.as_u16(),
+                    message: response.text().await.unwrap_or_default(),
+                });
+            }
+            Ok(())
+        }
+    }
+
+    fn body(&self, notification: &Notification<'_>) -> Value {
+        match self.format {
+            WebhookFormat::Json => json_body(notification),
+            WebhookFormat::Slack => json!({ "text": slack_text(notification) }),
+        }
+    }
+}
+
+fn json_body(notification: &Notification<'_>) -> Value {
+    let event = notification.event();
+    match *notification {
+        Notification::Finished(report) | Notification::Stopped(report) => json!({
+            "event": event,
+            "provider": report.provider,
+            "reason": report.stop_reason,
+            "report": report,
+        }),
+        Notification::SloBreach {
+            provider,
+            breaches,
+            burst,
+        } => json!({
+            "event": event,
+            "provider": provider,
+            "breaches": breaches,
+            "burst": burst,
+        }),
+        Notification::Failed {
+            provider,
+            error,
+            report,
+        } => json!({
+            "event": event,
+            "provider": provider,
+            "error": error,
+            "report": report,
+        }),
+    }
+}
+
+fn slack_text(notification: &Notification<'_>) -> String {
+    match *notification {
+        Notification::Finished(report) => format!(
+            ":white_check_mark: qstorm run against *{}* finished\n{}",
+            report.provider,
+            report_line(report)
+        ),
+        Notification::Stopped(report) => format!(
+            ":octagonal_sign: qstorm run against *{}* stopped early: {}\n{}",
+            report.provider,
+            report
+                .stop_reason
+                .as_deref()
+                .unwrap_or("stop condition met"),
+            report_line(report)
+        ),
+        Notification::SloBreach {
+            provider,
+            breaches,
+            burst,
+        } => format!(
+            ":warning: qstorm run against *{provider}* broke its SLOs: {}\nLast burst: {}",
+            breaches.join(", "),
+            burst_line(burst)
+        ),
+        Notification::Failed {
+            provider,
+            error,
+            report,
+        } => {
+            let mut text = format!(":x: qstorm run against *{provider}* failed: {error}");
+            if let Some(report) = report {
+                text.push('\n');
+                text.push_str(&report_line(report));
+            }
+            text
+        }
+    }
+}
+
+/// Headline numbers of a whole run
+fn report_line(report: &RunReport) -> String {
+    let mut line = format!(
+        "{} bursts, {} queries in {:.0}s · QPS {:.1} · p50 {:.2}ms · p99 {:.2}ms · errors {:.2}%",
+        report.bursts,
+        report.total_queries,
+        report.duration_secs,
+        report.qps,
+        report.latency.p50_us as f64 / 1000.0,
+        report.latency.p99_us as f64 / 1000.0,
+        report.errors.error_rate * 100.0
+    );
+    if let Some(recall) = &report.recall_at_k {
+        line.push_str(&format!(" · recall@k {:.3}", recall.mean));
+    }
+    line
+}
+
+/// Headline numbers of one burst
+fn burst_line(burst: &BurstMetrics) -> String {
+    let mut line = format!(
+        "QPS {:.1} · p50 {:.2}ms · p99 {:.2}ms · {} of {} queries failed",
+        burst.qps,
+        burst.latency.p50_us as f64 / 1000.0,
+        burst.latency.p99_us as f64 / 1000.0,
+        burst.failure_count,
+        burst.query_count
+    );
+    if let Some(recall) = burst.recall_at_k {
+        line.push_str(&format!(" · recall@k {recall:.3}"));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metrics::Metrics;
+
+    use super::*;
+
+    #[test]
+    fn test_slack_text() {
+        let mut metrics = Metrics::new();
+        metrics.start_burst();
+        metrics.record_success(Duration::from_millis(4), None);
+        let burst = metrics.finish_burst().unwrap();
+
+        let breaches = ["p99 4.0ms > 2ms".to_string()];
+        let text = slack_text(&Notification::SloBreach {
+            provider: "local",
+            breaches: &breaches,
+            burst: &burst,
+        });
+        assert!(text.starts_with(":warning: qstorm run against *local* broke its SLOs: p99"));
+        assert!(text.contains("0 of 1 queries failed"));
+    }
+}
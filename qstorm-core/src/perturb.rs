@@ -0,0 +1,136 @@
+use rand::Rng;
+use rand::seq::IndexedRandom;
+
+use crate::config::{PerturbationConfig, PerturbationKind};
+use crate::error::{Error, Result};
+use crate::queries::Query;
+
+impl PerturbationConfig {
+    /// Mutate roughly `rate` of the queries in place and return how many changed.
+    ///
+    /// Queries with precomputed vectors are left alone since their text is not embedded.
+    pub fn apply(&self, queries: &mut [Query], rng: &mut impl Rng) -> Result<usize> {
+        if !(0.0..=1.0).contains(&self.rate) {
+            return Err(Error::Config(format!(
+                "perturbation rate must be between 0 and 1, got {}",
+                self.rate
+            )));
+        }
+
+        let mut mutated = 0;
+        for query in queries.iter_mut().filter(|q| q.vector.is_none()) {
+            if !rng.random_bool(self.rate) {
+                continue;
+            }
+            let Some(kind) = self.kinds.choose(rng) else {
+                break;
+            };
+
+            let text = kind.apply(&query.text, rng);
+            if text != query.text {
+                query.text = text;
+                mutated += 1;
+            }
+        }
+        Ok(mutated)
+    }
+}
+
+impl PerturbationKind {
+    fn apply(&self, text: &str, rng: &mut impl Rng) -> String {
+        match self {
+            PerturbationKind::Typo => typo(text, rng),
+            PerturbationKind::Case => recase(text, rng),
+            PerturbationKind::Truncate => truncate(text, rng),
+        }
+    }
+}
+
+fn typo(text: &str, rng: &mut impl Rng) -> String {
+    let mut chars: Vec<char> = text.chars().collect();
+    let letters: Vec<usize> = (0..chars.len())
+        .filter(|&i| chars[i].is_alphabetic())
+        .collect();
+    let Some(&i) = letters.choose(rng) else {
+        return text.to_string();
+    };
+
+    match rng.random_range(0..4) {
+        // transpose with the next character
+        0 if i + 1 < chars.len() => chars.swap(i, i + 1),
+        // dropped keystroke
+        1 => {
+            chars.remove(i);
+        }
+        // doubled keystroke
+        2 => chars.insert(i, chars[i]),
+        // fat-fingered substitution
+        _ => {
+            let c = rng.random_range(b'a'..=b'z') as char;
+            chars[i] = if chars[i].is_uppercase() {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            };
+        }
+    }
+    chars.into_iter().collect()
+}
+
+fn recase(text: &str, rng: &mut impl Rng) -> String {
+    match rng.random_range(0..3) {
+        0 => text.to_lowercase(),
+        1 => text.to_uppercase(),
+        _ => text
+            .split(' ')
+            .map(|word| {
+                if rng.random_bool(0.5) {
+                    word.to_uppercase()
+                } else {
+                    word.to_lowercase()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+/// Keep 40-90% of the characters, always at least one
+fn truncate(text: &str, rng: &mut impl Rng) -> String {
+    let len = text.chars().count();
+    let keep = ((len as f64 * rng.random_range(0.4..0.9)).ceil() as usize).clamp(1, len.max(1));
+    text.chars()
+        .take(keep)
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_perturbation_rate() {
+        let mut queries: Vec<Query> = (0..50)
+            .map(|i| Query::new(format!("Ergonomic office chair number {i}")))
+            .collect();
+        let original: Vec<String> = queries.iter().map(|q| q.text.clone()).collect();
+
+        let none = PerturbationConfig {
+            rate: 0.0,
+            kinds: vec![PerturbationKind::Typo],
+        };
+        assert_eq!(none.apply(&mut queries, &mut rand::rng()).unwrap(), 0);
+
+        let all = PerturbationConfig {
+            rate: 1.0,
+            kinds: vec![PerturbationKind::Truncate],
+        };
+        let mutated = all.apply(&mut queries, &mut rand::rng()).unwrap();
+        assert_eq!(mutated, 50);
+        for (query, original) in queries.iter().zip(&original) {
+            assert!(original.starts_with(&query.text));
+        }
+    }
+}
@@ -0,0 +1,356 @@
+//! Pieces a runner is assembled from.
+//!
+//! [`RunnerBuilder`] puts a [`BenchmarkRunner`] together from a provider,
+//! where its queries come from ([`QueryFeed`]), how each burst's requests
+//! are sent ([`LoadProfile`]), and where finished bursts go
+//! ([`MetricSink`]). Each is a trait, so a new load pattern or metrics
+//! backend is a type implemented outside the runner rather than a change to
+//! it. Without a load profile, a runner follows `benchmark.load` from its
+//! config, including any changes a scenario phase or the TUI makes to it.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use rand::Rng;
+use rand::rngs::StdRng;
+
+use crate::config::{ArrivalProcess, BenchmarkConfig, LoadConfig, ScenarioConfig};
+use crate::dashboard::Dashboard;
+use crate::error::{Error, Result};
+use crate::exporter::PrometheusExporter;
+use crate::metrics::{BurstMetrics, BurstSamples};
+use crate::provider::{ProviderFactory, SearchProvider};
+use crate::queries::EmbeddedQuery;
+use crate::rerank::RerankStage;
+use crate::runner::BenchmarkRunner;
+use crate::samples::{BurstLog, FailureLog, SampleLog, SlowQueryLog};
+use crate::statsd::StatsdSink;
+
+/// How the requests of one burst are sent
+#[derive(Debug, Clone, PartialEq)]
+pub enum Dispatch {
+    /// Keep up to this many requests in flight, sending the next as one
+    /// completes
+    Concurrent(usize),
+    /// Send each request at its offset from the start of the burst, without
+    /// waiting for earlier ones to finish. Latency is measured from the
+    /// scheduled time.
+    Scheduled(Vec<Duration>),
+}
+
+/// Decides how each burst's requests are sent
+pub trait LoadProfile: Send {
+    /// How to send the next burst's `requests` requests. `rng` is the
+    /// runner's, so seeded runs schedule the same way every time.
+    fn dispatch(&mut self, requests: usize, rng: &mut StdRng) -> Result<Dispatch>;
+}
+
+/// Up to `concurrency` requests in flight at once
+#[derive(Debug, Clone, Copy)]
+pub struct ClosedLoop {
+    pub concurrency: usize,
+}
+
+impl LoadProfile for ClosedLoop {
+    fn dispatch(&mut self, _requests: usize, _rng: &mut StdRng) -> Result<Dispatch> {
+        if self.concurrency == 0 {
+            return Err(Error::Config("concurrency must be at least 1".into()));
+        }
+        Ok(Dispatch::Concurrent(self.concurrency))
+    }
+}
+
+/// Requests sent at `qps` regardless of how fast they complete
+#[derive(Debug, Clone, Copy)]
+pub struct ConstantRate {
+    pub qps: f64,
+    pub arrival: ArrivalProcess,
+}
+
+impl LoadProfile for ConstantRate {
+    fn dispatch(&mut self, requests: usize, rng: &mut StdRng) -> Result<Dispatch> {
+        if !(self.qps.is_finite() && self.qps > 0.0) {
+            return Err(Error::Config(format!(
+                "load.qps must be a positive number, got {}",
+                self.qps
+            )));
+        }
+        Ok(Dispatch::Scheduled(send_offsets(
+            self.arrival,
+            requests,
+            self.qps,
+            rng,
+        )))
+    }
+}
+
+/// A constant rate that moves from `from_qps` to `to_qps` in equal steps
+/// over `bursts` bursts, then holds at `to_qps`
+#[derive(Debug, Clone, Copy)]
+pub struct Ramp {
+    pub from_qps: f64,
+    pub to_qps: f64,
+    pub bursts: usize,
+    pub arrival: ArrivalProcess,
+    /// Bursts dispatched so far
+    step: usize,
+}
+
+impl Ramp {
+    pub fn new(from_qps: f64, to_qps: f64, bursts: usize) -> Self {
+        Self {
+            from_qps,
+            to_qps,
+            bursts,
+            arrival: ArrivalProcess::Uniform,
+            step: 0,
+        }
+    }
+
+    pub fn with_arrival(mut self, arrival: ArrivalProcess) -> Self {
+        self.arrival = arrival;
+        self
+    }
+
+    /// Rate of the next burst
+    pub fn qps(&self) -> f64 {
+        if self.bursts <= 1 {
+            return self.to_qps;
+        }
+        let progress = (self.step as f64 / (self.bursts - 1) as f64).min(1.0);
+        self.from_qps + (self.to_qps - self.from_qps) * progress
+    }
+}
+
+impl LoadProfile for Ramp {
+    fn dispatch(&mut self, requests: usize, rng: &mut StdRng) -> Result<Dispatch> {
+        let mut rate = ConstantRate {
+            qps: self.qps(),
+            arrival: self.arrival,
+        };
+        self.step += 1;
+        rate.dispatch(requests, rng)
+    }
+}
+
+/// How `config` says to send a burst's requests
+pub(crate) fn configured(
+    config: &BenchmarkConfig,
+    requests: usize,
+    rng: &mut StdRng,
+) -> Result<Dispatch> {
+    match config.load {
+        LoadConfig::ClosedLoop => ClosedLoop {
+            concurrency: config.concurrency,
+        }
+        .dispatch(requests, rng),
+        LoadConfig::ConstantRate { qps, arrival } => {
+            ConstantRate { qps, arrival }.dispatch(requests, rng)
+        }
+    }
+}
+
+/// Send time of each of `n` requests relative to the start of the burst
+fn send_offsets(arrival: ArrivalProcess, n: usize, qps: f64, rng: &mut impl Rng) -> Vec<Duration> {
+    match arrival {
+        ArrivalProcess::Uniform => (0..n)
+            .map(|i| Duration::from_secs_f64(i as f64 / qps))
+            .collect(),
+        ArrivalProcess::Poisson => {
+            // First request goes out immediately, then exponential gaps
+            let mut elapsed = 0.0;
+            (0..n)
+                .map(|i| {
+                    if i > 0 {
+                        let u: f64 = rng.random();
+                        elapsed += -(1.0 - u).ln() / qps;
+                    }
+                    Duration::from_secs_f64(elapsed)
+                })
+                .collect()
+        }
+    }
+}
+
+/// Where a runner's queries come from
+pub trait QueryFeed: Send {
+    /// The embedded queries to benchmark with, read once when the runner is
+    /// built
+    fn load(&mut self) -> Result<Arc<[EmbeddedQuery]>>;
+}
+
+impl QueryFeed for Vec<EmbeddedQuery> {
+    fn load(&mut self) -> Result<Arc<[EmbeddedQuery]>> {
+        Ok(std::mem::take(self).into())
+    }
+}
+
+impl QueryFeed for Arc<[EmbeddedQuery]> {
+    fn load(&mut self) -> Result<Arc<[EmbeddedQuery]>> {
+        Ok(self.clone())
+    }
+}
+
+/// Receives every burst a runner finishes
+pub trait MetricSink: Send {
+    fn record(&mut self, burst: &BurstMetrics, samples: &BurstSamples) -> Result<()>;
+}
+
+impl<F> MetricSink for F
+where
+    F: FnMut(&BurstMetrics, &BurstSamples) -> Result<()> + Send,
+{
+    fn record(&mut self, burst: &BurstMetrics, samples: &BurstSamples) -> Result<()> {
+        self(burst, samples)
+    }
+}
+
+impl MetricSink for PrometheusExporter {
+    fn record(&mut self, burst: &BurstMetrics, samples: &BurstSamples) -> Result<()> {
+        PrometheusExporter::record(self, burst, samples);
+        Ok(())
+    }
+}
+
+impl MetricSink for Dashboard {
+    fn record(&mut self, burst: &BurstMetrics, _samples: &BurstSamples) -> Result<()> {
+        Dashboard::record(self, burst)
+    }
+}
+
+impl MetricSink for StatsdSink {
+    fn record(&mut self, burst: &BurstMetrics, _samples: &BurstSamples) -> Result<()> {
+        StatsdSink::record(self, burst);
+        Ok(())
+    }
+}
+
+impl MetricSink for BurstLog {
+    fn record(&mut self, burst: &BurstMetrics, _samples: &BurstSamples) -> Result<()> {
+        self.write(burst)
+    }
+}
+
+/// Assembles a [`BenchmarkRunner`]
+pub struct RunnerBuilder {
+    runner: BenchmarkRunner,
+    queries: Option<Box<dyn QueryFeed>>,
+    scenario: Option<ScenarioConfig>,
+}
+
+impl RunnerBuilder {
+    pub fn new(provider: Box<dyn SearchProvider>, config: BenchmarkConfig) -> Self {
+        Self {
+            runner: BenchmarkRunner::new(provider, config),
+            queries: None,
+            scenario: None,
+        }
+    }
+
+    /// Where to read the queries from
+    pub fn queries(mut self, feed: impl QueryFeed + 'static) -> Self {
+        self.queries = Some(Box::new(feed));
+        self
+    }
+
+    /// Send bursts with `profile` instead of following `benchmark.load`
+    pub fn load(mut self, profile: impl LoadProfile + 'static) -> Self {
+        self.runner = self.runner.with_load_profile(profile);
+        self
+    }
+
+    /// Also pass every finished burst to `sink`
+    pub fn sink(mut self, sink: impl MetricSink + 'static) -> Self {
+        self.runner = self.runner.with_sink(sink);
+        self
+    }
+
+    /// Run the phases of `scenario` in order
+    pub fn scenario(mut self, scenario: ScenarioConfig) -> Self {
+        self.scenario = Some(scenario);
+        self
+    }
+
+    /// Make randomized scheduling and query order reproducible
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.runner = self.runner.with_seed(seed);
+        self
+    }
+
+    /// How to open the extra provider connections used by `client_threads`
+    pub fn provider_factory(mut self, factory: ProviderFactory) -> Self {
+        self.runner = self.runner.with_provider_factory(factory);
+        self
+    }
+
+    /// Log every request sent during bursts as a raw sample
+    pub fn sample_log(mut self, log: SampleLog) -> Self {
+        self.runner = self.runner.with_sample_log(log);
+        self
+    }
+
+    /// Log the slowest searches of each burst with their query text
+    pub fn slow_query_log(mut self, log: SlowQueryLog) -> Self {
+        self.runner = self.runner.with_slow_query_log(log);
+        self
+    }
+
+    /// Keep failed requests with their error and query in `log`
+    pub fn failure_log(mut self, log: FailureLog) -> Self {
+        self.runner = self.runner.with_failure_log(log);
+        self
+    }
+
+    /// Pass each search's results through a second-stage reranker
+    pub fn rerank(mut self, stage: RerankStage) -> Self {
+        self.runner = self.runner.with_rerank(stage);
+        self
+    }
+
+    /// Load the queries and check the scenario
+    pub fn build(self) -> Result<BenchmarkRunner> {
+        let mut runner = self.runner;
+        if let Some(mut feed) = self.queries {
+            runner = runner.with_queries(feed.load()?);
+        }
+        if let Some(scenario) = self.scenario {
+            scenario.validate()?;
+            runner = runner.with_scenario(scenario);
+        }
+        Ok(runner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::seeded_rng;
+
+    use super::*;
+
+    #[test]
+    fn test_send_offsets() {
+        let uniform = send_offsets(ArrivalProcess::Uniform, 5, 10.0, &mut rand::rng());
+        assert_eq!(uniform[0], Duration::ZERO);
+        assert_eq!(uniform[4], Duration::from_millis(400));
+
+        // 10k arrivals at 100 qps should span roughly 100 seconds
+        let poisson = send_offsets(ArrivalProcess::Poisson, 10_000, 100.0, &mut rand::rng());
+        assert!(poisson.windows(2).all(|w| w[0] <= w[1]));
+        let span = poisson.last().unwrap().as_secs_f64();
+        assert!((90.0..110.0).contains(&span), "span was {span}");
+    }
+
+    #[test]
+    fn test_ramp() {
+        let mut ramp = Ramp::new(10.0, 40.0, 4);
+        let mut rng = seeded_rng(Some(1), "test");
+        let mut gaps = Vec::new();
+        for _ in 0..5 {
+            let Dispatch::Scheduled(offsets) = ramp.dispatch(2, &mut rng).unwrap() else {
+                panic!("a ramp schedules its requests");
+            };
+            gaps.push(offsets[1].as_millis());
+        }
+        assert_eq!(gaps, [100, 50, 33, 25, 25]);
+    }
+}
@@ -1,7 +1,11 @@
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Arc, RwLock};
+
 use async_trait::async_trait;
 
+use crate::config::{DistanceMetric, ProviderConfig, ProviderKind};
 use crate::error::{Error, Result};
-use crate::types::{SearchParams, SearchResults};
+use crate::types::{Document, ScrollPage, ScrollParams, SearchParams, SearchResults};
 
 /// Capabilities advertised by a search provider
 #[derive(Debug, Clone, Default)]
@@ -9,6 +13,115 @@ pub struct Capabilities {
     pub vector_search: bool,
     pub native_hybrid: bool,
     pub vector_dimension: Option<usize>,
+    pub upsert: bool,
+    pub batch_search: bool,
+    /// Honors `SearchParams::offset` to page through results
+    pub pagination: bool,
+}
+
+/// Builds a new, unconnected provider instance, e.g. one per client thread
+pub type ProviderFactory = Arc<dyn Fn() -> Result<Box<dyn SearchProvider>> + Send + Sync>;
+
+/// Builds an unconnected custom provider from its display name and the
+/// `options` of its config
+pub type CustomProviderFactory =
+    Arc<dyn Fn(&str, &serde_yaml::Value) -> Result<Box<dyn SearchProvider>> + Send + Sync>;
+
+static CUSTOM_PROVIDERS: RwLock<BTreeMap<String, CustomProviderFactory>> =
+    RwLock::new(BTreeMap::new());
+
+/// Creates providers from their config: the built-in ones, and any a program
+/// using qstorm-core registered for `type: custom` configs
+pub struct ProviderRegistry;
+
+impl ProviderRegistry {
+    /// Make `kind` available to configs with `type: custom` and `kind: <kind>`,
+    /// replacing any provider registered under the same name
+    pub fn register(
+        kind: impl Into<String>,
+        factory: impl Fn(&str, &serde_yaml::Value) -> Result<Box<dyn SearchProvider>>
+        + Send
+        + Sync
+        + 'static,
+    ) {
+        CUSTOM_PROVIDERS
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(kind.into(), Arc::new(factory));
+    }
+
+    /// Names of the registered custom providers, sorted
+    pub fn registered() -> Vec<String> {
+        CUSTOM_PROVIDERS
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    /// Build an unconnected provider for `config`
+    pub fn create(config: &ProviderConfig) -> Result<Box<dyn SearchProvider>> {
+        let name = config.name.clone();
+        match &config.provider {
+            #[cfg(feature = "elasticsearch")]
+            ProviderKind::Elasticsearch(c) => Ok(Box::new(
+                crate::providers::ElasticsearchProvider::new(name, c.clone()),
+            )),
+
+            #[cfg(feature = "qdrant")]
+            ProviderKind::Qdrant(c) => Ok(Box::new(crate::providers::QdrantProvider::new(
+                name,
+                c.clone(),
+            ))),
+
+            #[cfg(feature = "pgvector")]
+            ProviderKind::Pgvector(c) => Ok(Box::new(crate::providers::PgvectorProvider::new(
+                name,
+                c.clone(),
+            ))),
+
+            #[cfg(feature = "wasm")]
+            ProviderKind::Wasm(c) => Ok(Box::new(crate::providers::WasmProvider::new(
+                name,
+                c.clone(),
+            ))),
+
+            ProviderKind::Mock(c) => Ok(Box::new(crate::providers::MockProvider::new(
+                name,
+                c.clone(),
+            ))),
+
+            ProviderKind::Custom(custom) => {
+                let factory = CUSTOM_PROVIDERS
+                    .read()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .get(&custom.kind)
+                    .cloned();
+                match factory {
+                    Some(factory) => factory(&name, &custom.options),
+                    None => {
+                        let registered = Self::registered();
+                        let expected = if registered.is_empty() {
+                            "none are registered in this build".to_string()
+                        } else {
+                            format!("expected one of: {}", registered.join(", "))
+                        };
+                        Err(Error::Config(format!(
+                            "provider.kind: no custom provider registered as '{}'; {expected}",
+                            custom.kind
+                        )))
+                    }
+                }
+            }
+        }
+    }
+
+    /// Builds fresh providers for `config`, e.g. for each client thread
+    pub fn factory(config: &ProviderConfig) -> ProviderFactory {
+        let config = config.clone();
+        Arc::new(move || Self::create(&config))
+    }
 }
 
 /// Trait for search providers
@@ -44,4 +157,135 @@ pub trait SearchProvider: Send + Sync {
             self.name()
         )))
     }
+
+    /// Run several vector searches in one request, returning results in input order
+    async fn batch_search(
+        &self,
+        _vectors: &[&[f32]],
+        _params: &SearchParams,
+    ) -> Result<Vec<SearchResults>> {
+        Err(Error::Unsupported(format!(
+            "Provider '{}' does not support batch search",
+            self.name()
+        )))
+    }
+
+    /// Fetch a page of stored documents, resuming from `cursor` (`None` starts from the beginning)
+    async fn scroll(&self, _cursor: Option<&str>, _params: &ScrollParams) -> Result<ScrollPage> {
+        Err(Error::Unsupported(format!(
+            "Provider '{}' does not support scrolling documents",
+            self.name()
+        )))
+    }
+
+    /// Release a scroll abandoned before its last page. Providers that hold
+    /// server-side state for a scroll free it here; the rest need not.
+    async fn end_scroll(&self, _cursor: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Insert or overwrite documents, each carrying a vector for the configured field
+    async fn upsert(&self, _documents: &[Document]) -> Result<()> {
+        Err(Error::Unsupported(format!(
+            "Provider '{}' does not support writing documents",
+            self.name()
+        )))
+    }
+
+    /// Return the subset of `ids` that exist in the index
+    async fn existing_ids(&self, _ids: &[String]) -> Result<HashSet<String>> {
+        Err(Error::Unsupported(format!(
+            "Provider '{}' does not support looking up documents by ID",
+            self.name()
+        )))
+    }
+
+    /// Engine-native statistics for the target index, polled between bursts
+    async fn server_stats(&self) -> Result<serde_json::Value> {
+        Err(Error::Unsupported(format!(
+            "Provider '{}' does not report server stats",
+            self.name()
+        )))
+    }
+
+    /// Whether the configured index, collection, or table exists
+    async fn index_exists(&self) -> Result<bool> {
+        Err(Error::Unsupported(format!(
+            "Provider '{}' cannot check whether its index exists",
+            self.name()
+        )))
+    }
+
+    /// Create the configured index, collection, or table for vectors of
+    /// `dimension` compared by `metric`, with a text field if one is configured
+    async fn create_index(&self, _dimension: usize, _metric: DistanceMetric) -> Result<()> {
+        Err(Error::Unsupported(format!(
+            "Provider '{}' cannot create its index",
+            self.name()
+        )))
+    }
+
+    /// Dimension of the stored vectors in `vector_name` (or the configured field), if known
+    async fn vector_dimension(&self, _vector_name: Option<&str>) -> Result<Option<usize>> {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CustomProviderConfig;
+
+    struct Named(String);
+
+    #[async_trait]
+    impl SearchProvider for Named {
+        fn name(&self) -> &str {
+            &self.0
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            Capabilities::default()
+        }
+
+        async fn connect(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn disconnect(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn health_check(&self) -> Result<bool> {
+            Ok(true)
+        }
+
+        async fn vector_search(&self, _: &[f32], _: &SearchParams) -> Result<SearchResults> {
+            Err(Error::NotConnected)
+        }
+    }
+
+    #[test]
+    fn test_registered_provider_is_created_from_config() {
+        ProviderRegistry::register("named", |name, options| {
+            let suffix = options["suffix"].as_str().unwrap_or_default();
+            Ok(Box::new(Named(format!("{name}{suffix}"))))
+        });
+        let config = |kind: &str| ProviderConfig {
+            name: "engine".into(),
+            provider: ProviderKind::Custom(CustomProviderConfig {
+                kind: kind.into(),
+                options: serde_yaml::from_str("suffix: _1").unwrap(),
+            }),
+        };
+
+        let provider = ProviderRegistry::factory(&config("named"))().unwrap();
+        assert_eq!(provider.name(), "engine_1");
+
+        let err = ProviderRegistry::create(&config("nameless"))
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("'nameless'; expected one of: named"), "{err}");
+    }
 }
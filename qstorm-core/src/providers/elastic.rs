@@ -1,52 +1,149 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
 use async_trait::async_trait;
 use elasticsearch::{
-    Elasticsearch, SearchParts,
+    BulkOperation, BulkParts, ClearScrollParts, Elasticsearch, MsearchParts, ScrollParts,
+    SearchParts,
     auth::Credentials as EsCredentials,
-    http::transport::{SingleNodeConnectionPool, TransportBuilder},
+    http::headers::{CONNECTION, HeaderValue},
+    http::request::JsonBody,
+    http::transport::{SingleNodeConnectionPool, Transport, TransportBuilder},
+    indices::{IndicesCreateParts, IndicesExistsParts, IndicesGetMappingParts},
+    nodes::NodesStatsParts,
 };
 use serde_json::json;
-use tracing::debug;
+use serde_json::value::{RawValue, to_raw_value};
+use tracing::{debug, warn};
 
-use crate::config::{ElasticsearchConfig, ElasticsearchCredentials};
+use crate::config::{DistanceMetric, ElasticsearchConfig, ElasticsearchCredentials};
 use crate::error::{Error, Result};
 use crate::provider::{Capabilities, SearchProvider};
-use crate::types::{SearchParams, SearchResult, SearchResults};
+use crate::types::{
+    Document, ScrollPage, ScrollParams, SearchParams, SearchResult, SearchResults, merge_json,
+};
+
+/// How long Elasticsearch keeps a scroll context alive between pages
+const SCROLL_KEEP_ALIVE: &str = "1m";
+
+/// Max document IDs per lookup request (well under the default result window)
+const ID_LOOKUP_CHUNK: usize = 1000;
 
 pub struct ElasticsearchProvider {
     name: String,
     config: ElasticsearchConfig,
-    client: Option<Elasticsearch>,
+    clients: Vec<Elasticsearch>,
+    next_client: AtomicUsize,
+    /// Set when `cache_bodies` is on
+    bodies: Option<BodyCache>,
+    /// The configured `extra`, checked on connect
+    extra: Extra,
 }
 
-impl ElasticsearchProvider {
-    pub fn new(name: String, config: ElasticsearchConfig) -> Self {
+/// `extra` split into the `preference` URL parameter and what's merged into
+/// the search body
+#[derive(Debug, Clone, Default)]
+struct Extra {
+    preference: Option<String>,
+    body: serde_json::Value,
+}
+
+impl Extra {
+    fn parse(extra: &serde_json::Value) -> Result<Self> {
+        let mut body = extra.clone();
+        if !(body.is_null() || body.is_object()) {
+            return Err(Error::Config(
+                "provider.extra must be a mapping merged into the search body".into(),
+            ));
+        }
+        let preference = body
+            .as_object_mut()
+            .and_then(|body| body.remove("preference"))
+            .map(|preference| match preference {
+                serde_json::Value::String(preference) => preference,
+                other => other.to_string(),
+            });
+        Ok(Self { preference, body })
+    }
+}
+
+/// Most search bodies kept by `cache_bodies`; later ones are serialized on
+/// every request
+const MAX_CACHED_BODIES: usize = 10_000;
+
+/// Search bodies already serialized, keyed by what they were built from
+#[derive(Default)]
+struct BodyCache {
+    bodies: Mutex<HashMap<BodyKey, Arc<RawValue>>>,
+}
+
+/// The query text, vector and params a search body was built from
+#[derive(PartialEq, Eq, Hash)]
+struct BodyKey {
+    text: Option<String>,
+    /// The vector's bits, so it can be hashed
+    vector: Vec<u32>,
+    top_k: usize,
+    offset: usize,
+    vector_name: Option<String>,
+    extra: Option<String>,
+}
+
+impl BodyKey {
+    fn new(text: Option<&str>, vector: &[f32], params: &SearchParams) -> Self {
         Self {
-            name,
-            config,
-            client: None,
+            text: text.map(str::to_string),
+            vector: vector.iter().map(|x| x.to_bits()).collect(),
+            top_k: params.top_k,
+            offset: params.offset,
+            vector_name: params.vector_name.clone(),
+            extra: (!params.extra.is_null()).then(|| params.extra.to_string()),
         }
     }
+}
 
-    fn client(&self) -> Result<&Elasticsearch> {
-        self.client.as_ref().ok_or(Error::NotConnected)
+impl BodyCache {
+    fn get(&self, key: &BodyKey) -> Option<Arc<RawValue>> {
+        let bodies = self.bodies.lock().unwrap_or_else(|e| e.into_inner());
+        bodies.get(key).cloned()
+    }
+
+    /// Keep `body` for `key`, unless the cache is full
+    fn insert(&self, key: BodyKey, body: Arc<RawValue>) {
+        let mut bodies = self.bodies.lock().unwrap_or_else(|e| e.into_inner());
+        if bodies.len() < MAX_CACHED_BODIES {
+            bodies.insert(key, body);
+        }
     }
 }
 
-#[async_trait]
-impl SearchProvider for ElasticsearchProvider {
-    fn name(&self) -> &str {
-        &self.name
+impl ElasticsearchProvider {
+    pub fn new(name: String, config: ElasticsearchConfig) -> Self {
+        let bodies = config.cache_bodies.then(BodyCache::default);
+        Self {
+            name,
+            config,
+            clients: Vec::new(),
+            next_client: AtomicUsize::new(0),
+            bodies,
+            extra: Extra::default(),
+        }
     }
 
-    fn capabilities(&self) -> Capabilities {
-        Capabilities {
-            vector_search: true,
-            native_hybrid: true,
-            vector_dimension: None,
+    /// Next client from the pool, taken in turn
+    fn client(&self) -> Result<&Elasticsearch> {
+        if self.clients.is_empty() {
+            return Err(Error::NotConnected);
         }
+        let i = self.next_client.fetch_add(1, Ordering::Relaxed) % self.clients.len();
+        Ok(&self.clients[i])
     }
 
-    async fn connect(&mut self) -> Result<()> {
+    /// A new transport with its own HTTP connection pool
+    fn transport(&self) -> Result<Transport> {
         let url = self
             .config
             .url
@@ -55,6 +152,9 @@ impl SearchProvider for ElasticsearchProvider {
 
         let pool = SingleNodeConnectionPool::new(url);
         let mut builder = TransportBuilder::new(pool);
+        if !self.config.http.keep_alive {
+            builder = builder.header(CONNECTION, HeaderValue::from_static("close"));
+        }
 
         if let Some(creds) = &self.config.credentials {
             builder = match creds {
@@ -70,11 +170,170 @@ impl SearchProvider for ElasticsearchProvider {
             };
         }
 
-        let transport = builder
+        builder
             .build()
-            .map_err(|e| Error::Connection(e.to_string()))?;
+            .map_err(Error::connection)
+    }
+
+    /// The configured `extra`, with the request's own merged over it
+    fn extra(&self, params: &SearchParams) -> Result<Cow<'_, Extra>> {
+        if params.extra.is_null() {
+            return Ok(Cow::Borrowed(&self.extra));
+        }
+        Extra::parse(&params.extra_over(&self.config.extra)).map(Cow::Owned)
+    }
 
-        let client = Elasticsearch::new(transport);
+    fn knn_body(
+        &self,
+        vector: &[f32],
+        params: &SearchParams,
+        extra: &serde_json::Value,
+    ) -> serde_json::Value {
+        let vector_field = params
+            .vector_field(self.config.vector_field.as_deref())
+            .unwrap_or("vector");
+
+        // kNN only ranks its `k` nearest hits, so they must cover every skipped result
+        let k = params.offset + params.top_k;
+        let mut body = json!({
+            "from": params.offset,
+            "size": params.top_k,
+            "knn": {
+                "field": vector_field,
+                "query_vector": vector,
+                "k": k,
+                "num_candidates": k * 10
+            }
+        });
+        merge_json(&mut body, extra);
+        body
+    }
+
+    /// kNN with a BM25 match on `text`, fused by RRF
+    fn hybrid_body(
+        &self,
+        text: &str,
+        vector: &[f32],
+        params: &SearchParams,
+        extra: &serde_json::Value,
+    ) -> serde_json::Value {
+        let text_field = self.config.text_field.as_deref().unwrap_or("text");
+        let vector_field = params
+            .vector_field(self.config.vector_field.as_deref())
+            .unwrap_or("vector");
+
+        let k = params.offset + params.top_k;
+        let mut body = json!({
+            "from": params.offset,
+            "size": params.top_k,
+            "query": {
+                "match": {
+                    text_field: text
+                }
+            },
+            "knn": {
+                "field": vector_field,
+                "query_vector": vector,
+                "k": k,
+                "num_candidates": k * 10
+            },
+            "rank": {
+                "rrf": {}
+            }
+        });
+        merge_json(&mut body, extra);
+        body
+    }
+
+    /// The serialized search body for `vector`, and `text` if hybrid. With
+    /// `cache_bodies` on, the first [`MAX_CACHED_BODIES`] are serialized the
+    /// first time they're sent (usually during warmup) and reused after that.
+    fn search_body(
+        &self,
+        text: Option<&str>,
+        vector: &[f32],
+        params: &SearchParams,
+        extra: &serde_json::Value,
+    ) -> Result<Arc<RawValue>> {
+        let serialize = || -> Result<Arc<RawValue>> {
+            let body = match text {
+                Some(text) => self.hybrid_body(text, vector, params, extra),
+                None => self.knn_body(vector, params, extra),
+            };
+            Ok(to_raw_value(&body)?.into())
+        };
+        let Some(cache) = &self.bodies else {
+            return serialize();
+        };
+
+        let key = BodyKey::new(text, vector, params);
+        if let Some(body) = cache.get(&key) {
+            return Ok(body);
+        }
+        let body = serialize()?;
+        cache.insert(key, body.clone());
+        Ok(body)
+    }
+}
+
+/// Convert a search response body into results
+fn parse_hits(body: &serde_json::Value, params: &SearchParams) -> Result<SearchResults> {
+    let took_ms = body["took"].as_u64();
+    let total_hits = body["hits"]["total"]["value"].as_u64();
+
+    let hits = body["hits"]["hits"]
+        .as_array()
+        .ok_or_else(|| Error::InvalidResponse("Missing hits array".into()))?;
+
+    let results: Vec<SearchResult> = hits
+        .iter()
+        .filter_map(|hit| {
+            let id = hit["_id"].as_str()?.to_string();
+            let score = hit["_score"].as_f64().unwrap_or(0.0) as f32;
+            let payload = if params.include_payload {
+                hit.get("_source").cloned()
+            } else {
+                None
+            };
+            Some(SearchResult { id, score, payload })
+        })
+        .collect();
+
+    let mut search_results = SearchResults::new(results);
+    if let Some(took) = took_ms {
+        search_results = search_results.with_took(Duration::from_millis(took));
+    }
+    if let Some(total) = total_hits {
+        search_results = search_results.with_total_hits(total);
+    }
+
+    Ok(search_results)
+}
+
+#[async_trait]
+impl SearchProvider for ElasticsearchProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            vector_search: true,
+            native_hybrid: true,
+            vector_dimension: None,
+            upsert: true,
+            batch_search: true,
+            pagination: true,
+        }
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        if self.config.pool_size == 0 {
+            return Err(Error::Config("pool_size must be at least 1".into()));
+        }
+        self.extra = Extra::parse(&self.config.extra)?;
+
+        let client = Elasticsearch::new(self.transport()?);
 
         // Verify connection
         let response = client
@@ -82,19 +341,28 @@ impl SearchProvider for ElasticsearchProvider {
             .health()
             .send()
             .await
-            .map_err(|e| Error::Connection(e.to_string()))?;
+            .map_err(Error::connection)?;
 
         if !response.status_code().is_success() {
-            return Err(Error::Connection("Health check failed".into()));
+            return Err(Error::connection_message("Health check failed"));
+        }
+
+        let mut clients = vec![client];
+        for _ in 1..self.config.pool_size {
+            clients.push(Elasticsearch::new(self.transport()?));
         }
 
-        debug!(index = %self.config.index_name, "Connected to Elasticsearch");
-        self.client = Some(client);
+        debug!(
+            index = %self.config.index_name,
+            pool_size = clients.len(),
+            "Connected to Elasticsearch"
+        );
+        self.clients = clients;
         Ok(())
     }
 
     async fn disconnect(&mut self) -> Result<()> {
-        self.client = None;
+        self.clients.clear();
         Ok(())
     }
 
@@ -105,37 +373,135 @@ impl SearchProvider for ElasticsearchProvider {
             .health()
             .send()
             .await
-            .map_err(|e| Error::Connection(e.to_string()))?;
+            .map_err(Error::connection)?;
         Ok(response.status_code().is_success())
     }
 
     async fn vector_search(&self, vector: &[f32], params: &SearchParams) -> Result<SearchResults> {
         let client = self.client()?;
-        let vector_field = self.config.vector_field.as_deref().unwrap_or("vector");
+        let extra = self.extra(params)?;
+        let body = self.search_body(None, vector, params, &extra.body)?;
 
-        let body = json!({
-            "size": params.top_k,
-            "knn": {
-                "field": vector_field,
-                "query_vector": vector,
-                "k": params.top_k,
-                "num_candidates": params.top_k * 10
-            }
-        });
+        let index = [self.config.index_name.as_str()];
+        let mut search = client.search(SearchParts::Index(&index)).body(body);
+        if let Some(preference) = &extra.preference {
+            search = search.preference(preference);
+        }
+        let response = search
+            .send()
+            .await
+            .map_err(Error::query)?;
+
+        let status = response.status_code().as_u16();
+        if !response.status_code().is_success() {
+            let error_body = response.text().await.unwrap_or_default();
+            return Err(Error::HttpStatus {
+                status,
+                message: format!("Search failed: {}", error_body),
+            });
+        }
+
+        let response_body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| Error::InvalidResponse(e.to_string()))?;
+
+        Ok(parse_hits(&response_body, params)?.with_status(status))
+    }
+
+    async fn batch_search(
+        &self,
+        vectors: &[&[f32]],
+        params: &SearchParams,
+    ) -> Result<Vec<SearchResults>> {
+        let client = self.client()?;
+
+        // _msearch takes alternating header and body lines
+        let extra = self.extra(params)?;
+        let header: Arc<RawValue> = match &extra.preference {
+            Some(preference) => to_raw_value(&json!({ "preference": preference }))?,
+            None => to_raw_value(&json!({}))?,
+        }
+        .into();
+        let mut body: Vec<JsonBody<Arc<RawValue>>> = Vec::with_capacity(vectors.len() * 2);
+        for vector in vectors {
+            body.push(header.clone().into());
+            body.push(self.search_body(None, vector, params, &extra.body)?.into());
+        }
 
         let response = client
-            .search(SearchParts::Index(&[&self.config.index_name]))
+            .msearch(MsearchParts::Index(&[&self.config.index_name]))
             .body(body)
             .send()
             .await
-            .map_err(|e| Error::QueryExecution(e.to_string()))?;
+            .map_err(Error::query)?;
 
         if !response.status_code().is_success() {
+            let status = response.status_code().as_u16();
             let error_body = response.text().await.unwrap_or_default();
-            return Err(Error::QueryExecution(format!(
-                "Search failed: {}",
-                error_body
-            )));
+            return Err(Error::HttpStatus {
+                status,
+                message: format!("Multi-search failed: {}", error_body),
+            });
+        }
+
+        let response_body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| Error::InvalidResponse(e.to_string()))?;
+
+        let responses = response_body["responses"]
+            .as_array()
+            .ok_or_else(|| Error::InvalidResponse("Missing responses array".into()))?;
+
+        responses
+            .iter()
+            .map(|item| {
+                // Each search succeeds or fails on its own, with its own status
+                let status = item["status"].as_u64().map(|status| status as u16);
+                if let Some(error) = item.get("error") {
+                    let message = format!("Search failed: {}", error);
+                    return Err(match status {
+                        Some(status) => Error::HttpStatus { status, message },
+                        None => Error::query_message(message),
+                    });
+                }
+                let results = parse_hits(item, params)?;
+                Ok(match status {
+                    Some(status) => results.with_status(status),
+                    None => results,
+                })
+            })
+            .collect()
+    }
+
+    async fn hybrid_search(
+        &self,
+        text: &str,
+        vector: &[f32],
+        params: &SearchParams,
+    ) -> Result<SearchResults> {
+        let client = self.client()?;
+        let extra = self.extra(params)?;
+        let body = self.search_body(Some(text), vector, params, &extra.body)?;
+
+        let index = [self.config.index_name.as_str()];
+        let mut search = client.search(SearchParts::Index(&index)).body(body);
+        if let Some(preference) = &extra.preference {
+            search = search.preference(preference);
+        }
+        let response = search
+            .send()
+            .await
+            .map_err(Error::query)?;
+
+        let status = response.status_code().as_u16();
+        if !response.status_code().is_success() {
+            let error_body = response.text().await.unwrap_or_default();
+            return Err(Error::HttpStatus {
+                status,
+                message: format!("Hybrid search failed: {}", error_body),
+            });
         }
 
         let response_body: serde_json::Value = response
@@ -164,9 +530,9 @@ impl SearchProvider for ElasticsearchProvider {
             })
             .collect();
 
-        let mut search_results = SearchResults::new(results);
+        let mut search_results = SearchResults::new(results).with_status(status);
         if let Some(took) = took_ms {
-            search_results = search_results.with_took(took);
+            search_results = search_results.with_took(Duration::from_millis(took));
         }
         if let Some(total) = total_hits {
             search_results = search_results.with_total_hits(total);
@@ -175,46 +541,156 @@ impl SearchProvider for ElasticsearchProvider {
         Ok(search_results)
     }
 
-    async fn hybrid_search(
-        &self,
-        text: &str,
-        vector: &[f32],
-        params: &SearchParams,
-    ) -> Result<SearchResults> {
+    async fn scroll(&self, cursor: Option<&str>, params: &ScrollParams) -> Result<ScrollPage> {
+        let client = self.client()?;
+        let vector_field = params
+            .vector_name
+            .as_deref()
+            .or(self.config.vector_field.as_deref())
+            .unwrap_or("vector");
+
+        let response = match cursor {
+            Some(scroll_id) => {
+                client
+                    .scroll(ScrollParts::None)
+                    .body(json!({
+                        "scroll": SCROLL_KEEP_ALIVE,
+                        "scroll_id": scroll_id
+                    }))
+                    .send()
+                    .await
+            }
+            None => {
+                let source = match (params.with_vectors, params.with_payload) {
+                    (true, true) => json!(true),
+                    (true, false) => json!([vector_field]),
+                    (false, true) => json!({ "excludes": [vector_field] }),
+                    (false, false) => json!(false),
+                };
+                client
+                    .search(SearchParts::Index(&[&self.config.index_name]))
+                    .scroll(SCROLL_KEEP_ALIVE)
+                    .body(json!({
+                        "size": params.batch_size,
+                        "query": { "match_all": {} },
+                        "_source": source
+                    }))
+                    .send()
+                    .await
+            }
+        }
+        .map_err(Error::query)?;
+
+        if !response.status_code().is_success() {
+            let error_body = response.text().await.unwrap_or_default();
+            return Err(Error::query_message(format!(
+                "Scroll failed: {}",
+                error_body
+            )));
+        }
+
+        let response_body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| Error::InvalidResponse(e.to_string()))?;
+
+        let hits = response_body["hits"]["hits"]
+            .as_array()
+            .ok_or_else(|| Error::InvalidResponse("Missing hits array".into()))?;
+
+        let documents: Vec<Document> = hits
+            .iter()
+            .filter_map(|hit| {
+                let id = hit["_id"].as_str()?.to_string();
+                let mut source = hit.get("_source").cloned().unwrap_or_default();
+                let vector = source
+                    .as_object_mut()
+                    .and_then(|s| s.remove(vector_field))
+                    .and_then(|v| serde_json::from_value::<Vec<f32>>(v).ok())
+                    .filter(|_| params.with_vectors);
+                let payload = params.with_payload.then_some(source);
+                Some(Document {
+                    id,
+                    vector,
+                    payload,
+                })
+            })
+            .collect();
+
+        let scroll_id = response_body["_scroll_id"].as_str();
+        let next_cursor = if hits.len() < params.batch_size {
+            // The last page; free the context rather than wait out its keep-alive
+            if let Some(scroll_id) = scroll_id
+                && let Err(e) = self.end_scroll(scroll_id).await
+            {
+                warn!(error = %e, "Failed to clear scroll context");
+            }
+            None
+        } else {
+            scroll_id.map(str::to_string)
+        };
+
+        Ok(ScrollPage {
+            documents,
+            next_cursor,
+        })
+    }
+
+    async fn end_scroll(&self, cursor: &str) -> Result<()> {
+        let response = self
+            .client()?
+            .clear_scroll(ClearScrollParts::None)
+            .body(json!({ "scroll_id": [cursor] }))
+            .send()
+            .await
+            .map_err(Error::query)?;
+        // 404 means the context already expired
+        let status = response.status_code().as_u16();
+        if !response.status_code().is_success() && status != 404 {
+            let error_body = response.text().await.unwrap_or_default();
+            return Err(Error::query_message(format!(
+                "Clearing scroll failed: {error_body}"
+            )));
+        }
+        Ok(())
+    }
+
+    async fn upsert(&self, documents: &[Document]) -> Result<()> {
         let client = self.client()?;
-        let text_field = self.config.text_field.as_deref().unwrap_or("text");
         let vector_field = self.config.vector_field.as_deref().unwrap_or("vector");
+        let text_field = self.config.text_field.as_deref().unwrap_or("text");
 
-        // kNN + BM25 match query - Elasticsearch fuses via RRF by default
-        let body = json!({
-            "size": params.top_k,
-            "query": {
-                "match": {
-                    text_field: text
-                }
-            },
-            "knn": {
-                "field": vector_field,
-                "query_vector": vector,
-                "k": params.top_k,
-                "num_candidates": params.top_k * 10
-            },
-            "rank": {
-                "rrf": {}
+        let mut ops: Vec<BulkOperation<serde_json::Value>> = Vec::with_capacity(documents.len());
+        for doc in documents {
+            let vector = doc.vector.as_ref().ok_or_else(|| {
+                Error::Config(format!("Document '{}' has no vector to upsert", doc.id))
+            })?;
+            let mut source = match &doc.payload {
+                Some(serde_json::Value::Object(map)) => map.clone(),
+                _ => serde_json::Map::new(),
+            };
+            if let Some(text) = source.remove("text") {
+                source.insert(text_field.to_string(), text);
             }
-        });
+            source.insert(vector_field.to_string(), json!(vector));
+            ops.push(
+                BulkOperation::index(serde_json::Value::Object(source))
+                    .id(doc.id.as_str())
+                    .into(),
+            );
+        }
 
         let response = client
-            .search(SearchParts::Index(&[&self.config.index_name]))
-            .body(body)
+            .bulk(BulkParts::Index(&self.config.index_name))
+            .body(ops)
             .send()
             .await
-            .map_err(|e| Error::QueryExecution(e.to_string()))?;
+            .map_err(Error::query)?;
 
         if !response.status_code().is_success() {
             let error_body = response.text().await.unwrap_or_default();
-            return Err(Error::QueryExecution(format!(
-                "Hybrid search failed: {}",
+            return Err(Error::query_message(format!(
+                "Bulk upsert failed: {}",
                 error_body
             )));
         }
@@ -224,35 +700,265 @@ impl SearchProvider for ElasticsearchProvider {
             .await
             .map_err(|e| Error::InvalidResponse(e.to_string()))?;
 
-        let took_ms = response_body["took"].as_u64();
-        let total_hits = response_body["hits"]["total"]["value"].as_u64();
+        // Bulk requests succeed as a whole even when individual items fail
+        if response_body["errors"].as_bool() == Some(true) {
+            let reason = response_body["items"]
+                .as_array()
+                .and_then(|items| {
+                    items
+                        .iter()
+                        .find_map(|item| item["index"]["error"]["reason"].as_str())
+                })
+                .unwrap_or("unknown error");
+            return Err(Error::query_message(format!(
+                "Bulk upsert failed: {}",
+                reason
+            )));
+        }
 
-        let hits = response_body["hits"]["hits"]
-            .as_array()
-            .ok_or_else(|| Error::InvalidResponse("Missing hits array".into()))?;
+        Ok(())
+    }
 
-        let results: Vec<SearchResult> = hits
-            .iter()
-            .filter_map(|hit| {
-                let id = hit["_id"].as_str()?.to_string();
-                let score = hit["_score"].as_f64().unwrap_or(0.0) as f32;
-                let payload = if params.include_payload {
-                    hit.get("_source").cloned()
-                } else {
-                    None
-                };
-                Some(SearchResult { id, score, payload })
+    async fn existing_ids(&self, ids: &[String]) -> Result<HashSet<String>> {
+        let client = self.client()?;
+        let mut found = HashSet::with_capacity(ids.len());
+
+        for chunk in ids.chunks(ID_LOOKUP_CHUNK) {
+            let response = client
+                .search(SearchParts::Index(&[&self.config.index_name]))
+                .body(json!({
+                    "size": chunk.len(),
+                    "query": { "ids": { "values": chunk } },
+                    "_source": false
+                }))
+                .send()
+                .await
+                .map_err(Error::query)?;
+
+            if !response.status_code().is_success() {
+                let error_body = response.text().await.unwrap_or_default();
+                return Err(Error::query_message(format!(
+                    "ID lookup failed: {}",
+                    error_body
+                )));
+            }
+
+            let response_body: serde_json::Value = response
+                .json()
+                .await
+                .map_err(|e| Error::InvalidResponse(e.to_string()))?;
+
+            let hits = response_body["hits"]["hits"]
+                .as_array()
+                .ok_or_else(|| Error::InvalidResponse("Missing hits array".into()))?;
+            found.extend(
+                hits.iter()
+                    .filter_map(|hit| hit["_id"].as_str().map(str::to_string)),
+            );
+        }
+
+        Ok(found)
+    }
+
+    /// Search, JVM, OS, and search thread pool stats of every node
+    async fn server_stats(&self) -> Result<serde_json::Value> {
+        let client = self.client()?;
+        let response = client
+            .nodes()
+            .stats(NodesStatsParts::Metric(&["indices", "jvm", "os", "thread_pool"]))
+            .send()
+            .await
+            .map_err(Error::query)?;
+
+        if !response.status_code().is_success() {
+            let error_body = response.text().await.unwrap_or_default();
+            return Err(Error::query_message(format!(
+                "Node stats failed: {}",
+                error_body
+            )));
+        }
+
+        let response_body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| Error::InvalidResponse(e.to_string()))?;
+
+        // The full response runs to thousands of fields per node; keep the
+        // ones that explain search latency
+        let nodes: serde_json::Map<String, serde_json::Value> = response_body["nodes"]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .map(|(id, node)| {
+                let name = node["name"].as_str().unwrap_or(id).to_string();
+                let search = &node["indices"]["search"];
+                let pool = &node["thread_pool"]["search"];
+                let stats = json!({
+                    "search_query_total": search["query_total"],
+                    "search_query_time_ms": search["query_time_in_millis"],
+                    "search_query_current": search["query_current"],
+                    "heap_used_percent": node["jvm"]["mem"]["heap_used_percent"],
+                    "gc_old_collection_count": node["jvm"]["gc"]["collectors"]["old"]["collection_count"],
+                    "cpu_percent": node["os"]["cpu"]["percent"],
+                    "search_pool_active": pool["active"],
+                    "search_pool_queue": pool["queue"],
+                    "search_pool_rejected": pool["rejected"],
+                });
+                (name, stats)
             })
             .collect();
 
-        let mut search_results = SearchResults::new(results);
-        if let Some(took) = took_ms {
-            search_results = search_results.with_took(took);
+        Ok(json!({ "nodes": nodes }))
+    }
+
+    async fn index_exists(&self) -> Result<bool> {
+        let response = self
+            .client()?
+            .indices()
+            .exists(IndicesExistsParts::Index(&[&self.config.index_name]))
+            .send()
+            .await
+            .map_err(Error::query)?;
+
+        match response.status_code().as_u16() {
+            200 => Ok(true),
+            404 => Ok(false),
+            status => Err(Error::HttpStatus {
+                status,
+                message: response.text().await.unwrap_or_default(),
+            }),
         }
-        if let Some(total) = total_hits {
-            search_results = search_results.with_total_hits(total);
+    }
+
+    async fn create_index(&self, dimension: usize, metric: DistanceMetric) -> Result<()> {
+        let vector_field = self.config.vector_field.as_deref().unwrap_or("vector");
+        let text_field = self.config.text_field.as_deref().unwrap_or("text");
+        let similarity = match metric {
+            DistanceMetric::Cosine => "cosine",
+            // Elasticsearch requires unit-length vectors for dot_product
+            DistanceMetric::Dot => "dot_product",
+            DistanceMetric::Euclidean => "l2_norm",
+        };
+        let body = json!({
+            "mappings": {
+                "properties": {
+                    vector_field: {
+                        "type": "dense_vector",
+                        "dims": dimension,
+                        "index": true,
+                        "similarity": similarity,
+                    },
+                    text_field: { "type": "text" },
+                }
+            }
+        });
+
+        let response = self
+            .client()?
+            .indices()
+            .create(IndicesCreateParts::Index(&self.config.index_name))
+            .body(body)
+            .send()
+            .await
+            .map_err(Error::query)?;
+
+        let status = response.status_code().as_u16();
+        if !response.status_code().is_success() {
+            return Err(Error::HttpStatus {
+                status,
+                message: response.text().await.unwrap_or_default(),
+            });
         }
+        Ok(())
+    }
 
-        Ok(search_results)
+    async fn vector_dimension(&self, vector_name: Option<&str>) -> Result<Option<usize>> {
+        let client = self.client()?;
+        let vector_field = vector_name
+            .or(self.config.vector_field.as_deref())
+            .unwrap_or("vector");
+
+        let response = client
+            .indices()
+            .get_mapping(IndicesGetMappingParts::Index(&[&self.config.index_name]))
+            .send()
+            .await
+            .map_err(Error::query)?;
+
+        if !response.status_code().is_success() {
+            let error_body = response.text().await.unwrap_or_default();
+            return Err(Error::query_message(format!(
+                "Mapping lookup failed: {}",
+                error_body
+            )));
+        }
+
+        let response_body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| Error::InvalidResponse(e.to_string()))?;
+
+        // Response is keyed by concrete index name, which may differ from an alias
+        let dims = response_body
+            .as_object()
+            .and_then(|indices| indices.values().next())
+            .and_then(|index| index["mappings"]["properties"][vector_field]["dims"].as_u64());
+
+        Ok(dims.map(|dims| dims as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extra_lifts_preference_out_of_the_body() {
+        let extra =
+            Extra::parse(&json!({ "preference": "_local", "track_total_hits": false })).unwrap();
+        assert_eq!(extra.preference.as_deref(), Some("_local"));
+        assert_eq!(extra.body, json!({ "track_total_hits": false }));
+
+        let extra = Extra::parse(&json!({ "preference": 7 })).unwrap();
+        assert_eq!(extra.preference.as_deref(), Some("7"));
+        let extra = Extra::parse(&serde_json::Value::Null).unwrap();
+        assert_eq!((extra.preference, extra.body), (None, serde_json::Value::Null));
+        assert!(matches!(
+            Extra::parse(&json!(["size", 5])),
+            Err(Error::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_body_cache() {
+        let cache = BodyCache::default();
+        let params = SearchParams::default();
+        let body: Arc<RawValue> = to_raw_value(&json!({ "size": 10 })).unwrap().into();
+        cache.insert(BodyKey::new(Some("a"), &[0.5], &params), body.clone());
+
+        let cached = |text, vector: &[f32], params| {
+            let body = cache.get(&BodyKey::new(text, vector, params));
+            body.map(|body| body.get().to_string())
+        };
+        assert_eq!(
+            cached(Some("a"), &[0.5], &params).as_deref(),
+            Some(body.get())
+        );
+        assert_eq!(cached(None, &[0.5], &params), None);
+        assert_eq!(cached(Some("a"), &[0.5, 0.0], &params), None);
+        let paged = SearchParams {
+            offset: 10,
+            ..Default::default()
+        };
+        assert_eq!(cached(Some("a"), &[0.5], &paged), None);
+
+        // Once full, new bodies aren't kept
+        for i in 0..MAX_CACHED_BODIES {
+            cache.insert(BodyKey::new(None, &[i as f32], &params), body.clone());
+        }
+        assert_eq!(cache.bodies.lock().unwrap().len(), MAX_CACHED_BODIES);
+        assert!(cached(None, &[1.0], &params).is_some());
+        let last = MAX_CACHED_BODIES as f32 - 1.0;
+        assert_eq!(cached(None, &[last], &params), None);
     }
 }
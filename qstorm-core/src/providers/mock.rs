@@ -0,0 +1,252 @@
+//! A simulated engine, for working on qstorm without a real one.
+//!
+//! Each search waits out a latency drawn from the configured distribution and
+//! fails at the configured rate. The results depend only on the query, so
+//! ground truth recorded against the mock keeps scoring the same.
+
+use std::f64::consts::PI;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::json;
+
+use crate::config::{MockConfig, MockLatency};
+use crate::error::{Error, Result};
+use crate::provider::{Capabilities, SearchProvider};
+use crate::types::{SearchParams, SearchResult, SearchResults};
+
+pub struct MockProvider {
+    name: String,
+    config: MockConfig,
+    connected: bool,
+}
+
+impl MockProvider {
+    pub fn new(name: String, config: MockConfig) -> Self {
+        Self {
+            name,
+            config,
+            connected: false,
+        }
+    }
+
+    fn check_config(&self) -> Result<()> {
+        let rate = |p: f64| (0.0..=1.0).contains(&p);
+        let time = |ms: f64| ms.is_finite() && ms >= 0.0;
+        if !rate(self.config.error_rate) {
+            return Err(Error::Config(format!(
+                "provider.error_rate must be between 0 and 1, got {}",
+                self.config.error_rate
+            )));
+        }
+        let valid = match self.config.latency {
+            MockLatency::Fixed { ms } => time(ms),
+            MockLatency::Lognormal { median_ms, sigma } => {
+                time(median_ms) && sigma.is_finite() && sigma >= 0.0
+            }
+            MockLatency::Bimodal {
+                fast_ms,
+                slow_ms,
+                slow_fraction,
+            } => time(fast_ms) && time(slow_ms) && rate(slow_fraction),
+        };
+        if !valid {
+            return Err(Error::Config(format!(
+                "provider.latency: times and sigma can't be negative, and slow_fraction must be between 0 and 1; got {:?}",
+                self.config.latency
+            )));
+        }
+        Ok(())
+    }
+
+    async fn search(
+        &self,
+        text: Option<&str>,
+        vector: &[f32],
+        params: &SearchParams,
+    ) -> Result<SearchResults> {
+        if !self.connected {
+            return Err(Error::NotConnected);
+        }
+        let (latency, fail) = {
+            let mut rng = rand::rng();
+            (
+                sample_latency(self.config.latency, &mut rng),
+                rng.random_bool(self.config.error_rate),
+            )
+        };
+
+        let timeout = Duration::from_millis(params.timeout_ms);
+        if latency > timeout {
+            tokio::time::sleep(timeout).await;
+            return Err(Error::Timeout(params.timeout_ms));
+        }
+        tokio::time::sleep(latency).await;
+        if fail {
+            return Err(Error::query_message("mock provider: simulated failure"));
+        }
+        Ok(self.results(text, vector, params).with_took(latency))
+    }
+
+    /// The same results every time for the same query and params
+    fn results(&self, text: Option<&str>, vector: &[f32], params: &SearchParams) -> SearchResults {
+        let mut rng = StdRng::seed_from_u64(query_key(text, vector));
+        let documents = self.config.documents;
+        let n = (params.offset + params.top_k).min(documents);
+        let ids = rand::seq::index::sample(&mut rng, documents, n);
+        let mut scores: Vec<f32> = (0..n).map(|_| rng.random()).collect();
+        scores.sort_by(|a, b| b.total_cmp(a));
+
+        let results = ids
+            .iter()
+            .zip(scores)
+            .skip(params.offset)
+            .filter(|&(_, score)| params.min_score.is_none_or(|min| score >= min))
+            .map(|(id, score)| SearchResult {
+                id: format!("doc-{id}"),
+                score,
+                payload: params
+                    .include_payload
+                    .then(|| json!({ "text": format!("Document {id}") })),
+            })
+            .collect();
+        SearchResults::new(results).with_total_hits(documents as u64)
+    }
+}
+
+#[async_trait]
+impl SearchProvider for MockProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            vector_search: true,
+            native_hybrid: true,
+            vector_dimension: self.config.dimension,
+            pagination: true,
+            ..Default::default()
+        }
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        self.check_config()?;
+        self.connected = true;
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.connected = false;
+        Ok(())
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        Ok(self.connected)
+    }
+
+    async fn vector_search(&self, vector: &[f32], params: &SearchParams) -> Result<SearchResults> {
+        self.search(None, vector, params).await
+    }
+
+    async fn hybrid_search(
+        &self,
+        text: &str,
+        vector: &[f32],
+        params: &SearchParams,
+    ) -> Result<SearchResults> {
+        self.search(Some(text), vector, params).await
+    }
+
+    async fn vector_dimension(&self, _vector_name: Option<&str>) -> Result<Option<usize>> {
+        Ok(self.config.dimension)
+    }
+}
+
+fn sample_latency(latency: MockLatency, rng: &mut impl Rng) -> Duration {
+    let ms = match latency {
+        MockLatency::Fixed { ms } => ms,
+        MockLatency::Lognormal { median_ms, sigma } => {
+            // Box-Muller for a standard normal
+            let (u, v): (f64, f64) = (rng.random(), rng.random());
+            let z = (-2.0 * (1.0 - u).ln()).sqrt() * (2.0 * PI * v).cos();
+            median_ms * (sigma * z).exp()
+        }
+        MockLatency::Bimodal {
+            fast_ms,
+            slow_ms,
+            slow_fraction,
+        } => {
+            if rng.random_bool(slow_fraction) {
+                slow_ms
+            } else {
+                fast_ms
+            }
+        }
+    };
+    // A lognormal tail can overflow; that's just a timeout
+    Duration::try_from_secs_f64(ms / 1000.0).unwrap_or(Duration::MAX)
+}
+
+/// FNV-1a over the query, so results don't change across Rust versions
+fn query_key(text: Option<&str>, vector: &[f32]) -> u64 {
+    let text = text.unwrap_or_default().bytes();
+    let vector = vector.iter().flat_map(|x| x.to_bits().to_le_bytes());
+    text.chain(vector)
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider(error_rate: f64) -> MockProvider {
+        MockProvider::new(
+            "mock".into(),
+            MockConfig {
+                latency: MockLatency::Fixed { ms: 0.0 },
+                error_rate,
+                dimension: Some(2),
+                documents: 100,
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_results_depend_only_on_the_query() {
+        let mut mock = provider(0.0);
+        mock.connect().await.unwrap();
+        let top = SearchParams {
+            top_k: 20,
+            ..Default::default()
+        };
+        let page = SearchParams {
+            offset: 10,
+            ..Default::default()
+        };
+
+        let first = mock.vector_search(&[0.1, 0.2], &top).await.unwrap();
+        let again = mock.vector_search(&[0.1, 0.2], &top).await.unwrap();
+        let other = mock.vector_search(&[0.2, 0.1], &top).await.unwrap();
+        let second_page = mock.vector_search(&[0.1, 0.2], &page).await.unwrap();
+        assert_eq!(first.ids(), again.ids());
+        assert_ne!(first.ids(), other.ids());
+        assert_eq!(first.ids()[10..], second_page.ids());
+        assert!(first.results.is_sorted_by(|a, b| a.score >= b.score));
+    }
+
+    #[tokio::test]
+    async fn test_error_rate_and_config_checks() {
+        let mut mock = provider(1.0);
+        mock.connect().await.unwrap();
+        let params = SearchParams::default();
+        assert!(mock.vector_search(&[0.1, 0.2], &params).await.is_err());
+
+        let mut mock = provider(1.5);
+        assert!(matches!(mock.connect().await, Err(Error::Config(_))));
+    }
+}
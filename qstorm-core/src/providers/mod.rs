@@ -1,18 +1,28 @@
 #[cfg(feature = "elasticsearch")]
 pub mod elastic;
 
+pub mod mock;
+
 #[cfg(feature = "pgvector")]
 pub mod pgvector;
 
 #[cfg(feature = "qdrant")]
 pub mod qdrant;
 
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
 // re-export provider types when features are enabled
 #[cfg(feature = "elasticsearch")]
 pub use elastic::ElasticsearchProvider;
 
+pub use mock::MockProvider;
+
 #[cfg(feature = "pgvector")]
 pub use pgvector::PgvectorProvider;
 
 #[cfg(feature = "qdrant")]
 pub use qdrant::QdrantProvider;
+
+#[cfg(feature = "wasm")]
+pub use wasm::WasmProvider;
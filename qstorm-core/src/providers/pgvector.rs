@@ -1,18 +1,24 @@
+use std::collections::HashSet;
+
 use async_trait::async_trait;
 use pgvector::Vector;
-use sqlx::postgres::PgPoolOptions;
-use sqlx::{PgPool, Row};
+use sqlx::postgres::{PgArguments, PgPoolOptions, PgRow};
+use sqlx::query::Query;
+use sqlx::{PgPool, Postgres, Row};
+use tokio::sync::OnceCell;
 use tracing::debug;
 
-use crate::config::PgvectorConfig;
+use crate::config::{DistanceMetric, PgvectorConfig};
 use crate::error::{Error, Result};
 use crate::provider::{Capabilities, SearchProvider};
-use crate::types::{SearchParams, SearchResult, SearchResults};
+use crate::types::{Document, ScrollPage, ScrollParams, SearchParams, SearchResult, SearchResults};
 
 pub struct PgvectorProvider {
     name: String,
     config: PgvectorConfig,
     pool: Option<PgPool>,
+    /// SQL type of the `id` column, looked up on first upsert
+    id_type: OnceCell<String>,
 }
 
 impl PgvectorProvider {
@@ -21,12 +27,98 @@ impl PgvectorProvider {
             name,
             config,
             pool: None,
+            id_type: OnceCell::new(),
         }
     }
 
     fn pool(&self) -> Result<&PgPool> {
         self.pool.as_ref().ok_or(Error::NotConnected)
     }
+
+    /// Run a search, in a transaction with the request's own `extra`
+    /// settings applied when it has any
+    async fn fetch_all(
+        &self,
+        query: Query<'_, Postgres, PgArguments>,
+        params: &SearchParams,
+    ) -> Result<Vec<PgRow>> {
+        let pool = self.pool()?;
+        let settings = settings(&params.extra)?;
+        if settings.is_empty() {
+            return query
+                .fetch_all(pool)
+                .await
+                .map_err(Error::query);
+        }
+
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(Error::query)?;
+        for (name, value) in &settings {
+            sqlx::query("SELECT set_config($1, $2, true)")
+                .bind(name)
+                .bind(value)
+                .execute(&mut *tx)
+                .await
+                .map_err(Error::query)?;
+        }
+        let rows = query
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(Error::query)?;
+        // Nothing was written; ending the transaction drops the settings
+        tx.rollback()
+            .await
+            .map_err(Error::query)?;
+        Ok(rows)
+    }
+
+    async fn id_type(&self, pool: &PgPool) -> Result<&str> {
+        let id_type = self
+            .id_type
+            .get_or_try_init(|| async {
+                sqlx::query_scalar(
+                    "SELECT format_type(atttypid, atttypmod) FROM pg_attribute \
+                     WHERE attrelid = $1::regclass AND attname = 'id' AND NOT attisdropped",
+                )
+                .bind(&self.config.table_name)
+                .fetch_one(pool)
+                .await
+                .map_err(Error::query)
+            })
+            .await?;
+        Ok(id_type)
+    }
+}
+
+/// `extra` as Postgres settings: setting names mapped to strings, numbers,
+/// or booleans
+fn settings(extra: &serde_json::Value) -> Result<Vec<(String, String)>> {
+    let Some(extra) = extra.as_object() else {
+        if extra.is_null() {
+            return Ok(Vec::new());
+        }
+        return Err(Error::Config(
+            "provider.extra must map Postgres setting names to values".into(),
+        ));
+    };
+    extra
+        .iter()
+        .map(|(name, value)| {
+            let value = match value {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Number(n) => n.to_string(),
+                serde_json::Value::Bool(b) => b.to_string(),
+                _ => {
+                    return Err(Error::Config(format!(
+                        "provider.extra.{name}: expected a string, number, or boolean"
+                    )));
+                }
+            };
+            Ok((name.clone(), value))
+        })
+        .collect()
 }
 
 #[async_trait]
@@ -40,15 +132,37 @@ impl SearchProvider for PgvectorProvider {
             vector_search: true,
             native_hybrid: self.config.text_field.is_some(),
             vector_dimension: None,
+            upsert: true,
+            batch_search: false,
+            pagination: true,
         }
     }
 
     async fn connect(&mut self) -> Result<()> {
+        if self.config.pool_size == 0 {
+            return Err(Error::Config("pool_size must be at least 1".into()));
+        }
+
+        // Configured settings hold for every connection's whole session
+        let settings = settings(&self.config.extra)?;
         let pool = PgPoolOptions::new()
-            .max_connections(5)
+            .max_connections(self.config.pool_size as u32)
+            .after_connect(move |conn, _meta| {
+                let settings = settings.clone();
+                Box::pin(async move {
+                    for (name, value) in &settings {
+                        sqlx::query("SELECT set_config($1, $2, false)")
+                            .bind(name)
+                            .bind(value)
+                            .execute(&mut *conn)
+                            .await?;
+                    }
+                    Ok(())
+                })
+            })
             .connect(&self.config.url)
             .await
-            .map_err(|e| Error::Connection(e.to_string()))?;
+            .map_err(Error::connection)?;
 
         // Verify table exists
         let exists: bool = sqlx::query_scalar(
@@ -57,7 +171,7 @@ impl SearchProvider for PgvectorProvider {
         .bind(&self.config.table_name)
         .fetch_one(&pool)
         .await
-        .map_err(|e| Error::Connection(e.to_string()))?;
+        .map_err(Error::connection)?;
 
         if !exists {
             return Err(Error::Config(format!(
@@ -84,12 +198,13 @@ impl SearchProvider for PgvectorProvider {
             .execute(pool)
             .await
             .map(|_| true)
-            .map_err(|e| Error::Connection(e.to_string()))
+            .map_err(Error::connection)
     }
 
     async fn vector_search(&self, vector: &[f32], params: &SearchParams) -> Result<SearchResults> {
-        let pool = self.pool()?;
-        let vector_field = self.config.vector_field.as_deref().unwrap_or("embedding");
+        let vector_field = params
+            .vector_field(self.config.vector_field.as_deref())
+            .unwrap_or("embedding");
         let table = &self.config.table_name;
         let embedding = Vector::from(vector.to_vec());
 
@@ -99,23 +214,22 @@ impl SearchProvider for PgvectorProvider {
                  to_jsonb(t) - '{vector_field}' - 'id' as payload \
                  FROM {table} t \
                  ORDER BY {vector_field} <=> $1::vector \
-                 LIMIT $2"
+                 LIMIT $2 OFFSET $3"
             )
         } else {
             format!(
                 "SELECT id::text, 1 - ({vector_field} <=> $1::vector) as score \
                  FROM {table} \
                  ORDER BY {vector_field} <=> $1::vector \
-                 LIMIT $2"
+                 LIMIT $2 OFFSET $3"
             )
         };
 
-        let rows = sqlx::query(&query)
+        let search = sqlx::query(&query)
             .bind(&embedding)
             .bind(params.top_k as i64)
-            .fetch_all(pool)
-            .await
-            .map_err(|e| Error::QueryExecution(e.to_string()))?;
+            .bind(params.offset as i64);
+        let rows = self.fetch_all(search, params).await?;
 
         let results: Vec<SearchResult> = rows
             .iter()
@@ -144,17 +258,18 @@ impl SearchProvider for PgvectorProvider {
         vector: &[f32],
         params: &SearchParams,
     ) -> Result<SearchResults> {
-        let pool = self.pool()?;
-
         let text_field = self.config.text_field.as_deref().ok_or_else(|| {
             Error::Config(
                 "Hybrid search requires 'text_field' to be set in provider config".into(),
             )
         })?;
-        let vector_field = self.config.vector_field.as_deref().unwrap_or("embedding");
+        let vector_field = params
+            .vector_field(self.config.vector_field.as_deref())
+            .unwrap_or("embedding");
         let table = &self.config.table_name;
         let embedding = Vector::from(vector.to_vec());
-        let prefetch_limit = (params.top_k * 2) as i64;
+        // Each ranking has to reach past the skipped results too
+        let prefetch_limit = ((params.offset + params.top_k) * 2) as i64;
         let limit = params.top_k as i64;
 
         let query = format!(
@@ -177,17 +292,16 @@ impl SearchProvider for PgvectorProvider {
             FROM vector_results v \
             FULL OUTER JOIN text_results t ON v.id = t.id \
             ORDER BY score DESC \
-            LIMIT $4"
+            LIMIT $4 OFFSET $5"
         );
 
-        let rows = sqlx::query(&query)
+        let search = sqlx::query(&query)
             .bind(&embedding)
             .bind(text)
             .bind(prefetch_limit)
             .bind(limit)
-            .fetch_all(pool)
-            .await
-            .map_err(|e| Error::QueryExecution(e.to_string()))?;
+            .bind(params.offset as i64);
+        let rows = self.fetch_all(search, params).await?;
 
         let results: Vec<SearchResult> = rows
             .iter()
@@ -204,4 +318,291 @@ impl SearchProvider for PgvectorProvider {
 
         Ok(SearchResults::new(results))
     }
+
+    async fn scroll(&self, cursor: Option<&str>, params: &ScrollParams) -> Result<ScrollPage> {
+        let pool = self.pool()?;
+        let vector_field = params
+            .vector_name
+            .as_deref()
+            .or(self.config.vector_field.as_deref())
+            .unwrap_or("embedding");
+        let table = &self.config.table_name;
+
+        let mut columns = vec!["id::text AS id".to_string()];
+        if params.with_vectors {
+            columns.push(format!("{vector_field} AS vector"));
+        }
+        if params.with_payload {
+            columns.push(format!("to_jsonb(t) - '{vector_field}' - 'id' AS payload"));
+        }
+
+        // Keyset pagination on the text form of the id keeps pages stable
+        let query = format!(
+            "SELECT {} FROM {table} t \
+             WHERE $1::text IS NULL OR id::text > $1 \
+             ORDER BY id::text \
+             LIMIT $2",
+            columns.join(", ")
+        );
+
+        let rows = sqlx::query(&query)
+            .bind(cursor)
+            .bind(params.batch_size as i64)
+            .fetch_all(pool)
+            .await
+            .map_err(Error::query)?;
+
+        let documents: Vec<Document> = rows
+            .iter()
+            .filter_map(|row| {
+                let id: String = row.try_get("id").ok()?;
+                let vector = if params.with_vectors {
+                    row.try_get::<Vector, _>("vector").ok().map(|v| v.to_vec())
+                } else {
+                    None
+                };
+                let payload = if params.with_payload {
+                    row.try_get::<serde_json::Value, _>("payload").ok()
+                } else {
+                    None
+                };
+                Some(Document {
+                    id,
+                    vector,
+                    payload,
+                })
+            })
+            .collect();
+
+        let next_cursor = if rows.len() == params.batch_size {
+            documents.last().map(|d| d.id.clone())
+        } else {
+            None
+        };
+
+        Ok(ScrollPage {
+            documents,
+            next_cursor,
+        })
+    }
+
+    async fn upsert(&self, documents: &[Document]) -> Result<()> {
+        let pool = self.pool()?;
+        let vector_field = self.config.vector_field.as_deref().unwrap_or("embedding");
+        let table = &self.config.table_name;
+        let id_type = self.id_type(pool).await?;
+
+        let mut ids = Vec::with_capacity(documents.len());
+        let mut vectors = Vec::with_capacity(documents.len());
+        let mut texts = Vec::with_capacity(documents.len());
+        for doc in documents {
+            let vector = doc.vector.as_ref().ok_or_else(|| {
+                Error::Config(format!("Document '{}' has no vector to upsert", doc.id))
+            })?;
+            ids.push(doc.id.clone());
+            // pgvector parses its text form, which lets the whole batch bind as text[]
+            vectors.push(format!(
+                "[{}]",
+                vector
+                    .iter()
+                    .map(f32::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ));
+            texts.push(
+                doc.payload
+                    .as_ref()
+                    .and_then(|p| p["text"].as_str())
+                    .map(str::to_string),
+            );
+        }
+
+        // The id column's own type is cast to, since it may be text, uuid, or bigint
+        let query = match &self.config.text_field {
+            Some(text_field) => format!(
+                "INSERT INTO {table} (id, {vector_field}, {text_field}) \
+                 SELECT CAST(u.id AS {id_type}), u.v::vector, u.t \
+                 FROM UNNEST($1::text[], $2::text[], $3::text[]) AS u(id, v, t) \
+                 ON CONFLICT (id) DO UPDATE SET \
+                 {vector_field} = EXCLUDED.{vector_field}, {text_field} = EXCLUDED.{text_field}"
+            ),
+            None => format!(
+                "INSERT INTO {table} (id, {vector_field}) \
+                 SELECT CAST(u.id AS {id_type}), u.v::vector \
+                 FROM UNNEST($1::text[], $2::text[]) AS u(id, v) \
+                 ON CONFLICT (id) DO UPDATE SET {vector_field} = EXCLUDED.{vector_field}"
+            ),
+        };
+
+        let mut insert = sqlx::query(&query).bind(&ids).bind(&vectors);
+        if self.config.text_field.is_some() {
+            insert = insert.bind(&texts);
+        }
+        insert
+            .execute(pool)
+            .await
+            .map_err(Error::query)?;
+        Ok(())
+    }
+
+    async fn existing_ids(&self, ids: &[String]) -> Result<HashSet<String>> {
+        let pool = self.pool()?;
+        let table = &self.config.table_name;
+
+        let found: Vec<String> = sqlx::query_scalar(&format!(
+            "SELECT id::text FROM {table} WHERE id::text = ANY($1)"
+        ))
+        .bind(ids)
+        .fetch_all(pool)
+        .await
+        .map_err(Error::query)?;
+
+        Ok(found.into_iter().collect())
+    }
+
+    /// Scan and tuple counts for the table, plus the costliest statements
+    /// touching it when the `pg_stat_statements` extension is installed
+    async fn server_stats(&self) -> Result<serde_json::Value> {
+        let pool = self.pool()?;
+
+        // Rows come back as JSON text, as the json feature of sqlx isn't enabled
+        let table: Option<String> = sqlx::query_scalar(
+            "SELECT row_to_json(t)::text FROM ( \
+                 SELECT seq_scan, idx_scan, n_live_tup, n_dead_tup, \
+                        n_tup_ins, n_tup_upd, n_tup_del, last_autovacuum, last_autoanalyze \
+                 FROM pg_stat_user_tables WHERE relid = $1::regclass \
+             ) t",
+        )
+        .bind(&self.config.table_name)
+        .fetch_optional(pool)
+        .await
+        .map_err(Error::query)?;
+
+        let installed: bool = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'pg_stat_statements')",
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(Error::query)?;
+
+        let statements: Option<String> = if installed {
+            sqlx::query_scalar(
+                "SELECT coalesce(json_agg(s)::text, '[]') FROM ( \
+                     SELECT left(query, 200) AS query, calls, total_exec_time, \
+                            mean_exec_time, stddev_exec_time, rows, shared_blks_hit, shared_blks_read \
+                     FROM pg_stat_statements WHERE query ILIKE '%' || $1 || '%' \
+                     ORDER BY total_exec_time DESC LIMIT 5 \
+                 ) s",
+            )
+            .bind(&self.config.table_name)
+            .fetch_one(pool)
+            .await
+            .map_err(Error::query)?
+        } else {
+            None
+        };
+
+        let parse = |text: Option<String>| -> Result<serde_json::Value> {
+            text.map_or(Ok(serde_json::Value::Null), |text| {
+                Ok(serde_json::from_str(&text)?)
+            })
+        };
+        Ok(serde_json::json!({
+            "table": parse(table)?,
+            "statements": parse(statements)?,
+        }))
+    }
+
+    async fn index_exists(&self) -> Result<bool> {
+        sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+            .bind(&self.config.table_name)
+            .fetch_one(self.pool()?)
+            .await
+            .map_err(Error::query)
+    }
+
+    async fn create_index(&self, dimension: usize, metric: DistanceMetric) -> Result<()> {
+        // Searches order by `<=>`, which only an index built with cosine ops serves
+        if !matches!(metric, DistanceMetric::Cosine) {
+            return Err(Error::Unsupported(
+                "pgvector searches by cosine distance, so tables can only be created for the cosine metric".into(),
+            ));
+        }
+        let pool = self.pool()?;
+        let vector_field = self.config.vector_field.as_deref().unwrap_or("embedding");
+        let table = &self.config.table_name;
+
+        let mut statements = vec![
+            "CREATE EXTENSION IF NOT EXISTS vector".to_string(),
+            match &self.config.text_field {
+                Some(text_field) => format!(
+                    "CREATE TABLE {table} (id TEXT PRIMARY KEY, {vector_field} vector({dimension}), {text_field} TEXT)"
+                ),
+                None => format!(
+                    "CREATE TABLE {table} (id TEXT PRIMARY KEY, {vector_field} vector({dimension}))"
+                ),
+            },
+            format!("CREATE INDEX ON {table} USING hnsw ({vector_field} vector_cosine_ops)"),
+        ];
+        if let Some(text_field) = &self.config.text_field {
+            // Matches the expression hybrid search filters on
+            statements.push(format!(
+                "CREATE INDEX ON {table} USING gin (to_tsvector('english', {text_field}))"
+            ));
+        }
+
+        for statement in &statements {
+            sqlx::query(statement)
+                .execute(pool)
+                .await
+                .map_err(Error::query)?;
+        }
+        Ok(())
+    }
+
+    async fn vector_dimension(&self, vector_name: Option<&str>) -> Result<Option<usize>> {
+        let pool = self.pool()?;
+        let vector_field = vector_name
+            .or(self.config.vector_field.as_deref())
+            .unwrap_or("embedding");
+
+        // pgvector stores the declared dimension as the column's type modifier
+        let typmod: Option<i32> = sqlx::query_scalar(
+            "SELECT atttypmod FROM pg_attribute \
+             WHERE attrelid = $1::regclass AND attname = $2 AND NOT attisdropped",
+        )
+        .bind(&self.config.table_name)
+        .bind(vector_field)
+        .fetch_optional(pool)
+        .await
+        .map_err(Error::query)?;
+
+        Ok(typmod.filter(|&dim| dim > 0).map(|dim| dim as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_settings_take_scalar_values_only() {
+        let gucs = settings(&json!({ "hnsw.ef_search": 200, "enable_seqscan": false })).unwrap();
+        assert!(gucs.contains(&("hnsw.ef_search".to_string(), "200".to_string())));
+        assert!(gucs.contains(&("enable_seqscan".to_string(), "false".to_string())));
+        assert!(settings(&serde_json::Value::Null).unwrap().is_empty());
+
+        for invalid in [
+            json!({ "hnsw.ef_search": [200] }),
+            json!({ "a": { "b": 1 } }),
+            json!(5),
+        ] {
+            assert!(
+                matches!(settings(&invalid), Err(Error::Config(_))),
+                "{invalid}"
+            );
+        }
+    }
 }
@@ -1,20 +1,104 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
 use async_trait::async_trait;
-use qdrant_client::Qdrant;
+use qdrant_client::{Payload, Qdrant, QdrantError};
+use qdrant_client::qdrant::point_id::PointIdOptions;
+use qdrant_client::qdrant::vector_output::Vector;
+use qdrant_client::qdrant::vectors_config::Config as VectorsConfig;
 use qdrant_client::qdrant::{
-    Document, Fusion, PointId, PrefetchQueryBuilder, Query, QueryPointsBuilder,
-    SearchPointsBuilder,
+    CollectionStatus, CreateCollectionBuilder, Distance, Document, Fusion, GetPointsBuilder, PointId, PointStruct, PrefetchQueryBuilder, Query,
+    QueryPointsBuilder, ReadConsistencyType, ScoredPoint, ScrollPointsBuilder,
+    SearchBatchPointsBuilder, SearchParamsBuilder, SearchPoints, SearchPointsBuilder,
+    UpsertPointsBuilder, VectorParamsBuilder, Vectors, VectorsConfigBuilder, VectorsSelector,
+    read_consistency,
 };
+use serde::Deserialize;
+use serde_json::json;
 use tracing::debug;
 
-use crate::config::QdrantConfig;
+use crate::config::{DistanceMetric, QdrantConfig};
 use crate::error::{Error, Result};
 use crate::provider::{Capabilities, SearchProvider};
-use crate::types::{SearchParams, SearchResult, SearchResults};
+use crate::types::{self, ScrollPage, ScrollParams, SearchParams, SearchResult, SearchResults};
 
 pub struct QdrantProvider {
     name: String,
     config: QdrantConfig,
-    client: Option<Qdrant>,
+    clients: Vec<Qdrant>,
+    next_client: AtomicUsize,
+    /// The configured `extra`, checked on connect
+    extra: Extra,
+}
+
+/// Search knobs Qdrant takes through `extra`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Extra {
+    consistency: Option<Consistency>,
+    hnsw_ef: Option<u64>,
+    exact: Option<bool>,
+    indexed_only: Option<bool>,
+}
+
+/// Read consistency: a named level, or how many replicas must agree
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(untagged)]
+enum Consistency {
+    Factor(u64),
+    Level(ConsistencyLevel),
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ConsistencyLevel {
+    All,
+    Majority,
+    Quorum,
+}
+
+impl Extra {
+    fn parse(extra: &serde_json::Value) -> Result<Self> {
+        if extra.is_null() {
+            return Ok(Self::default());
+        }
+        serde_json::from_value(extra.clone()).map_err(|e| {
+            Error::Config(format!(
+                "provider.extra: {e}; Qdrant takes consistency, hnsw_ef, exact, and indexed_only"
+            ))
+        })
+    }
+
+    fn read_consistency(&self) -> Option<read_consistency::Value> {
+        self.consistency.map(|consistency| match consistency {
+            Consistency::Factor(replicas) => read_consistency::Value::Factor(replicas),
+            Consistency::Level(level) => read_consistency::Value::Type(match level {
+                ConsistencyLevel::All => ReadConsistencyType::All,
+                ConsistencyLevel::Majority => ReadConsistencyType::Majority,
+                ConsistencyLevel::Quorum => ReadConsistencyType::Quorum,
+            } as i32),
+        })
+    }
+
+    /// HNSW settings for the search, if any are set
+    fn search_params(&self) -> Option<SearchParamsBuilder> {
+        if self.hnsw_ef.is_none() && self.exact.is_none() && self.indexed_only.is_none() {
+            return None;
+        }
+        let mut params = SearchParamsBuilder::default();
+        if let Some(ef) = self.hnsw_ef {
+            params = params.hnsw_ef(ef);
+        }
+        if let Some(exact) = self.exact {
+            params = params.exact(exact);
+        }
+        if let Some(indexed_only) = self.indexed_only {
+            params = params.indexed_only(indexed_only);
+        }
+        Some(params)
+    }
 }
 
 impl QdrantProvider {
@@ -22,15 +106,141 @@ impl QdrantProvider {
         Self {
             name,
             config,
-            client: None,
+            clients: Vec::new(),
+            next_client: AtomicUsize::new(0),
+            extra: Extra::default(),
+        }
+    }
+
+    /// The configured `extra`, with the request's own merged over it
+    fn extra(&self, params: &SearchParams) -> Result<Cow<'_, Extra>> {
+        if params.extra.is_null() {
+            return Ok(Cow::Borrowed(&self.extra));
+        }
+        Extra::parse(&params.extra_over(&self.config.extra)).map(Cow::Owned)
+    }
+
+    fn search_request(&self, vector: &[f32], params: &SearchParams) -> Result<SearchPointsBuilder> {
+        let vector_field = params.vector_field(self.config.vector_field.as_deref());
+        let extra = self.extra(params)?;
+
+        let mut search = SearchPointsBuilder::new(
+            &self.config.collection_name,
+            vector.to_vec(),
+            params.top_k as u64,
+        );
+
+        if params.offset > 0 {
+            search = search.offset(params.offset as u64);
         }
+
+        if let Some(field) = vector_field {
+            search = search.vector_name(field.to_string());
+        }
+
+        if let Some(min_score) = params.min_score {
+            search = search.score_threshold(min_score);
+        }
+
+        if let Some(consistency) = extra.read_consistency() {
+            search = search.read_consistency(consistency);
+        }
+
+        if let Some(search_params) = extra.search_params() {
+            search = search.params(search_params);
+        }
+
+        // return payloads
+        Ok(search.with_payload(true))
     }
 
+    /// Next client from the pool, taken in turn
     fn client(&self) -> Result<&Qdrant> {
-        self.client.as_ref().ok_or(Error::NotConnected)
+        if self.clients.is_empty() {
+            return Err(Error::NotConnected);
+        }
+        let i = self.next_client.fetch_add(1, Ordering::Relaxed) % self.clients.len();
+        Ok(&self.clients[i])
+    }
+}
+
+fn scored_results(points: Vec<ScoredPoint>, params: &SearchParams) -> SearchResults {
+    let results: Vec<SearchResult> = points
+        .into_iter()
+        .map(|point| {
+            let id = point_id_to_string(point.id);
+
+            let payload = if params.include_payload {
+                Some(serde_json::to_value(&point.payload).unwrap_or_default())
+            } else {
+                None
+            };
+
+            SearchResult {
+                id,
+                score: point.score,
+                payload,
+            }
+        })
+        .collect();
+
+    SearchResults::new(results)
+}
+
+/// A failed request, with the gRPC status code Qdrant answered with so
+/// throttling and outages can be told apart from bad requests
+fn query_error(e: QdrantError) -> Error {
+    let code = match &e {
+        QdrantError::ResponseError { status }
+        | QdrantError::ResourceExhaustedError { status, .. } => {
+            Some(format!("{:?}", status.code()))
+        }
+        _ => None,
+    };
+    match code {
+        Some(code) => Error::query(e).with_code(code),
+        None => Error::query(e),
+    }
+}
+
+/// Qdrant reports processing time in fractional seconds
+fn server_time(seconds: f64) -> Option<Duration> {
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+fn point_id_to_string(id: Option<PointId>) -> String {
+    match id.and_then(|id| id.point_id_options) {
+        Some(PointIdOptions::Num(n)) => n.to_string(),
+        Some(PointIdOptions::Uuid(s)) => s,
+        None => "unknown".to_string(),
     }
 }
 
+/// Parse a string ID (or scroll cursor) back into a point ID. Qdrant only
+/// has unsigned integer and UUID IDs, so anything else can't name a point.
+fn parse_point_id(id: &str) -> Option<PointId> {
+    match id.parse::<u64>() {
+        Ok(n) => Some(PointId::from(n)),
+        Err(_) => is_uuid(id).then(|| PointId::from(id)),
+    }
+}
+
+/// Whether Qdrant reads `id` as a UUID: hyphenated, simple, braced, or URN
+fn is_uuid(id: &str) -> bool {
+    let id = id.strip_prefix("urn:uuid:").unwrap_or(id);
+    let id = id
+        .strip_prefix('{')
+        .and_then(|id| id.strip_suffix('}'))
+        .unwrap_or(id);
+    let hex = |part: &&str| part.bytes().all(|b| b.is_ascii_hexdigit());
+    let parts: Vec<&str> = id.split('-').collect();
+    let lengths: Vec<usize> = parts.iter().map(|part| part.len()).collect();
+    (lengths == [32] || lengths == [8, 4, 4, 4, 12]) && parts.iter().all(hex)
+}
+
+/// Max point IDs per retrieve request
+const GET_POINTS_CHUNK: usize = 1000;
+
 #[async_trait]
 impl SearchProvider for QdrantProvider {
     fn name(&self) -> &str {
@@ -42,10 +252,18 @@ impl SearchProvider for QdrantProvider {
             vector_search: true,
             native_hybrid: self.config.text_field.is_some(),
             vector_dimension: None,
+            upsert: true,
+            batch_search: true,
+            pagination: true,
         }
     }
 
     async fn connect(&mut self) -> Result<()> {
+        if self.config.pool_size == 0 {
+            return Err(Error::Config("pool_size must be at least 1".into()));
+        }
+        self.extra = Extra::parse(&self.config.extra)?;
+
         let mut builder = Qdrant::from_url(&self.config.url);
 
         if let Some(api_key) = &self.config.api_key {
@@ -53,14 +271,15 @@ impl SearchProvider for QdrantProvider {
         }
 
         let client = builder
+            .clone()
             .build()
-            .map_err(|e| Error::Connection(e.to_string()))?;
+            .map_err(Error::connection)?;
 
         // Verify connection by checking collection exists
         let collections = client
             .list_collections()
             .await
-            .map_err(|e| Error::Connection(e.to_string()))?;
+            .map_err(Error::connection)?;
 
         let exists = collections
             .collections
@@ -74,13 +293,28 @@ impl SearchProvider for QdrantProvider {
             )));
         }
 
-        debug!(collection = %self.config.collection_name, "Connected to Qdrant");
-        self.client = Some(client);
+        // Each client opens its own channel on first use
+        let mut clients = vec![client];
+        for _ in 1..self.config.pool_size {
+            clients.push(
+                builder
+                    .clone()
+                    .build()
+                    .map_err(Error::connection)?,
+            );
+        }
+
+        debug!(
+            collection = %self.config.collection_name,
+            pool_size = clients.len(),
+            "Connected to Qdrant"
+        );
+        self.clients = clients;
         Ok(())
     }
 
     async fn disconnect(&mut self) -> Result<()> {
-        self.client = None;
+        self.clients.clear();
         Ok(())
     }
 
@@ -90,71 +324,49 @@ impl SearchProvider for QdrantProvider {
             .health_check()
             .await
             .map(|_| true)
-            .map_err(|e| Error::Connection(e.to_string()))
+            .map_err(Error::connection)
     }
 
     async fn vector_search(&self, vector: &[f32], params: &SearchParams) -> Result<SearchResults> {
         let client = self.client()?;
-        let vector_field = self.config.vector_field.as_deref();
 
-        let mut search = SearchPointsBuilder::new(
-            &self.config.collection_name,
-            vector.to_vec(),
-            params.top_k as u64,
-        );
-
-        if let Some(field) = vector_field {
-            search = search.vector_name(field.to_string());
-        }
+        let response = client
+            .search_points(self.search_request(vector, params)?)
+            .await
+            .map_err(query_error)?;
 
-        if params.include_payload {
-            search = search.with_payload(true);
-        }
+        let results = scored_results(response.result, params);
+        Ok(match server_time(response.time) {
+            Some(took) => results.with_took(took),
+            None => results,
+        })
+    }
 
-        if let Some(min_score) = params.min_score {
-            search = search.score_threshold(min_score);
-        }
+    async fn batch_search(
+        &self,
+        vectors: &[&[f32]],
+        params: &SearchParams,
+    ) -> Result<Vec<SearchResults>> {
+        let client = self.client()?;
 
-        // return payloads
-        search = search.with_payload(true);
+        let searches: Vec<SearchPoints> = vectors
+            .iter()
+            .map(|vector| Ok(self.search_request(vector, params)?.build()))
+            .collect::<Result<_>>()?;
 
         let response = client
-            .search_points(search)
+            .search_batch_points(SearchBatchPointsBuilder::new(
+                &self.config.collection_name,
+                searches,
+            ))
             .await
-            .map_err(|e| Error::QueryExecution(e.to_string()))?;
+            .map_err(query_error)?;
 
-        let results: Vec<SearchResult> = response
+        Ok(response
             .result
             .into_iter()
-            .map(|point| {
-                let id = match point.id {
-                    Some(PointId {
-                        point_id_options: Some(id),
-                    }) => {
-                        use qdrant_client::qdrant::point_id::PointIdOptions;
-                        match id {
-                            PointIdOptions::Num(n) => n.to_string(),
-                            PointIdOptions::Uuid(s) => s,
-                        }
-                    }
-                    _ => "unknown".to_string(),
-                };
-
-                let payload = if params.include_payload {
-                    Some(serde_json::to_value(&point.payload).unwrap_or_default())
-                } else {
-                    None
-                };
-
-                SearchResult {
-                    id,
-                    score: point.score,
-                    payload,
-                }
-            })
-            .collect();
-
-        Ok(SearchResults::new(results))
+            .map(|batch| scored_results(batch.result, params))
+            .collect())
     }
 
     async fn hybrid_search(
@@ -170,7 +382,9 @@ impl SearchProvider for QdrantProvider {
         })?;
 
         let limit = params.top_k as u64;
-        let prefetch_limit = limit * 2;
+        let offset = params.offset as u64;
+        // Each prefetch has to reach past the skipped results too
+        let prefetch_limit = (offset + limit) * 2;
 
         // BM25 prefetch: Qdrant tokenizes and scores server-side
         let bm25_prefetch = PrefetchQueryBuilder::default()
@@ -183,39 +397,38 @@ impl SearchProvider for QdrantProvider {
             .query(Query::new_nearest(vector.to_vec()))
             .limit(prefetch_limit);
 
-        if let Some(field) = self.config.vector_field.as_deref() {
+        if let Some(field) = params.vector_field(self.config.vector_field.as_deref()) {
             dense_prefetch = dense_prefetch.using(field.to_string());
         }
 
+        let extra = self.extra(params)?;
+        if let Some(search_params) = extra.search_params() {
+            dense_prefetch = dense_prefetch.params(search_params);
+        }
+
         // Fuse with RRF
-        let query = QueryPointsBuilder::new(&self.config.collection_name)
+        let mut query = QueryPointsBuilder::new(&self.config.collection_name)
             .add_prefetch(bm25_prefetch)
             .add_prefetch(dense_prefetch)
             .query(Fusion::Rrf)
             .with_payload(true)
+            .offset(offset)
             .limit(limit);
 
+        if let Some(consistency) = extra.read_consistency() {
+            query = query.read_consistency(consistency);
+        }
+
         let response = client
             .query(query)
             .await
-            .map_err(|e| Error::QueryExecution(e.to_string()))?;
+            .map_err(query_error)?;
 
         let results: Vec<SearchResult> = response
             .result
             .into_iter()
             .map(|point| {
-                let id = match point.id {
-                    Some(PointId {
-                        point_id_options: Some(id),
-                    }) => {
-                        use qdrant_client::qdrant::point_id::PointIdOptions;
-                        match id {
-                            PointIdOptions::Num(n) => n.to_string(),
-                            PointIdOptions::Uuid(s) => s,
-                        }
-                    }
-                    _ => "unknown".to_string(),
-                };
+                let id = point_id_to_string(point.id);
 
                 let payload = if params.include_payload {
                     Some(serde_json::to_value(&point.payload).unwrap_or_default())
@@ -231,6 +444,288 @@ impl SearchProvider for QdrantProvider {
             })
             .collect();
 
-        Ok(SearchResults::new(results))
+        let results = SearchResults::new(results);
+        Ok(match server_time(response.time) {
+            Some(took) => results.with_took(took),
+            None => results,
+        })
+    }
+
+    async fn scroll(&self, cursor: Option<&str>, params: &ScrollParams) -> Result<ScrollPage> {
+        let client = self.client()?;
+        let vector_field = params
+            .vector_name
+            .as_deref()
+            .or(self.config.vector_field.as_deref());
+
+        let mut request = ScrollPointsBuilder::new(&self.config.collection_name)
+            .limit(params.batch_size as u32)
+            .with_payload(params.with_payload);
+
+        request = match (params.with_vectors, vector_field) {
+            (true, Some(field)) => {
+                request.with_vectors(VectorsSelector::from(vec![field.to_string()]))
+            }
+            (with_vectors, _) => request.with_vectors(with_vectors),
+        };
+
+        if let Some(cursor) = cursor {
+            let offset = parse_point_id(cursor).ok_or_else(|| {
+                Error::Config(format!("'{cursor}' is not a Qdrant scroll cursor"))
+            })?;
+            request = request.offset(offset);
+        }
+
+        let response = client
+            .scroll(request)
+            .await
+            .map_err(query_error)?;
+
+        let documents = response
+            .result
+            .into_iter()
+            .map(|point| {
+                let vector = point
+                    .vectors
+                    .as_ref()
+                    .and_then(|v| match vector_field {
+                        Some(field) => v.get_vector_by_name(field),
+                        None => v.get_vector(),
+                    })
+                    .and_then(|v| match v {
+                        Vector::Dense(dense) => Some(dense.data),
+                        _ => None,
+                    });
+
+                let payload = if params.with_payload {
+                    Some(serde_json::to_value(&point.payload).unwrap_or_default())
+                } else {
+                    None
+                };
+
+                types::Document {
+                    id: point_id_to_string(point.id),
+                    vector,
+                    payload,
+                }
+            })
+            .collect();
+
+        Ok(ScrollPage {
+            documents,
+            next_cursor: response
+                .next_page_offset
+                .map(|id| point_id_to_string(Some(id))),
+        })
+    }
+
+    async fn upsert(&self, documents: &[types::Document]) -> Result<()> {
+        let client = self.client()?;
+
+        let points = documents
+            .iter()
+            .map(|doc| {
+                let vector = doc.vector.clone().ok_or_else(|| {
+                    Error::Config(format!("Document '{}' has no vector to upsert", doc.id))
+                })?;
+                let vectors: Vectors = match &self.config.vector_field {
+                    Some(name) => HashMap::from([(name.clone(), vector)]).into(),
+                    None => vector.into(),
+                };
+                let payload = match &doc.payload {
+                    Some(serde_json::Value::Object(map)) => Payload::from(map.clone()),
+                    _ => Payload::new(),
+                };
+                let id = parse_point_id(&doc.id).ok_or_else(|| {
+                    Error::Config(format!(
+                        "Qdrant point IDs must be unsigned integers or UUIDs, got '{}'",
+                        doc.id
+                    ))
+                })?;
+                Ok(PointStruct::new(id, vectors, payload))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        client
+            .upsert_points(
+                UpsertPointsBuilder::new(&self.config.collection_name, points).wait(true),
+            )
+            .await
+            .map_err(query_error)?;
+        Ok(())
+    }
+
+    async fn existing_ids(&self, ids: &[String]) -> Result<HashSet<String>> {
+        let client = self.client()?;
+        let mut found = HashSet::with_capacity(ids.len());
+
+        for chunk in ids.chunks(GET_POINTS_CHUNK) {
+            // An ID Qdrant can't store can't exist, so it isn't looked up
+            let point_ids: Vec<PointId> =
+                chunk.iter().filter_map(|id| parse_point_id(id)).collect();
+            if point_ids.is_empty() {
+                continue;
+            }
+            let request = GetPointsBuilder::new(&self.config.collection_name, point_ids)
+                .with_payload(false)
+                .with_vectors(false);
+
+            let response = client
+                .get_points(request)
+                .await
+                .map_err(query_error)?;
+            found.extend(
+                response
+                    .result
+                    .into_iter()
+                    .map(|point| point_id_to_string(point.id)),
+            );
+        }
+
+        Ok(found)
+    }
+
+    /// Collection info; the telemetry endpoint isn't served over gRPC
+    async fn server_stats(&self) -> Result<serde_json::Value> {
+        let client = self.client()?;
+        let info = client
+            .collection_info(&self.config.collection_name)
+            .await
+            .map_err(query_error)?
+            .result
+            .ok_or_else(|| Error::InvalidResponse("Collection info missing".into()))?;
+
+        let status = CollectionStatus::try_from(info.status)
+            .map_or("unknown", |status| status.as_str_name());
+        let optimizer = info.optimizer_status.map(|optimizer| {
+            json!({
+                "ok": optimizer.ok,
+                "error": (!optimizer.error.is_empty()).then_some(optimizer.error),
+            })
+        });
+        Ok(json!({
+            "status": status,
+            "optimizer": optimizer,
+            "segments_count": info.segments_count,
+            "points_count": info.points_count,
+            "indexed_vectors_count": info.indexed_vectors_count,
+        }))
+    }
+
+    async fn index_exists(&self) -> Result<bool> {
+        self.client()?
+            .collection_exists(&self.config.collection_name)
+            .await
+            .map_err(query_error)
+    }
+
+    async fn create_index(&self, dimension: usize, metric: DistanceMetric) -> Result<()> {
+        let distance = match metric {
+            DistanceMetric::Cosine => Distance::Cosine,
+            DistanceMetric::Dot => Distance::Dot,
+            DistanceMetric::Euclidean => Distance::Euclid,
+        };
+        let params = VectorParamsBuilder::new(dimension as u64, distance);
+        let request = CreateCollectionBuilder::new(&self.config.collection_name);
+        let request = match &self.config.vector_field {
+            Some(name) => {
+                let mut vectors = VectorsConfigBuilder::default();
+                vectors.add_named_vector_params(name, params);
+                request.vectors_config(vectors)
+            }
+            None => request.vectors_config(params),
+        };
+
+        self.client()?
+            .create_collection(request)
+            .await
+            .map_err(query_error)?;
+        Ok(())
+    }
+
+    async fn vector_dimension(&self, vector_name: Option<&str>) -> Result<Option<usize>> {
+        let client = self.client()?;
+        let vector_name = vector_name.or(self.config.vector_field.as_deref());
+
+        let info = client
+            .collection_info(&self.config.collection_name)
+            .await
+            .map_err(query_error)?;
+
+        let vectors = info
+            .result
+            .and_then(|info| info.config)
+            .and_then(|config| config.params)
+            .and_then(|params| params.vectors_config)
+            .and_then(|vectors| vectors.config);
+
+        let size = match (vectors, vector_name) {
+            (Some(VectorsConfig::Params(params)), _) => Some(params.size),
+            (Some(VectorsConfig::ParamsMap(map)), Some(name)) => {
+                map.map.get(name).map(|params| params.size)
+            }
+            // a single named vector is unambiguous even without a configured field
+            (Some(VectorsConfig::ParamsMap(map)), None) if map.map.len() == 1 => {
+                map.map.values().next().map(|params| params.size)
+            }
+            _ => None,
+        };
+
+        Ok(size.map(|size| size as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_point_id() {
+        let id = |id| parse_point_id(id).and_then(|id| id.point_id_options);
+        assert_eq!(id("42"), Some(PointIdOptions::Num(42)));
+        let uuid = "550e8400-e29b-41d4-a716-446655440000";
+        assert_eq!(id(uuid), Some(PointIdOptions::Uuid(uuid.into())));
+        assert!(id("550e8400e29b41d4a716446655440000").is_some());
+        assert!(id("{550e8400-e29b-41d4-a716-446655440000}").is_some());
+
+        for invalid in ["doc-1", "-1", "550e8400-e29b-41d4-a716-44665544000g", ""] {
+            assert!(parse_point_id(invalid).is_none(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_extra_parse() {
+        let consistency = |value| {
+            Extra::parse(&serde_json::json!({ "consistency": value }))
+                .unwrap()
+                .read_consistency()
+        };
+        assert_eq!(
+            consistency(serde_json::json!(2)),
+            Some(read_consistency::Value::Factor(2))
+        );
+        assert_eq!(
+            consistency(serde_json::json!("majority")),
+            Some(read_consistency::Value::Type(
+                ReadConsistencyType::Majority as i32
+            ))
+        );
+
+        let extra = Extra::parse(&serde_json::json!({ "hnsw_ef": 128, "exact": false })).unwrap();
+        assert!(extra.search_params().is_some());
+        assert!(
+            Extra::parse(&serde_json::Value::Null)
+                .unwrap()
+                .search_params()
+                .is_none()
+        );
+
+        for invalid in [
+            serde_json::json!({ "hnsw": 128 }),
+            serde_json::json!({ "consistency": "most" }),
+        ] {
+            let err = Extra::parse(&invalid).unwrap_err();
+            assert!(err.to_string().contains("Qdrant takes"), "{err}");
+        }
     }
 }
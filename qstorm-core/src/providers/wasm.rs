@@ -0,0 +1,411 @@
+//! Engines driven by a WebAssembly plugin (experimental).
+//!
+//! The plugin never touches the network. For each search it builds the HTTP
+//! request, qstorm sends it, and the plugin turns the response into results.
+//! The search's latency covers all three, as a built-in provider's covers
+//! its own encoding and parsing. Plugin calls run on blocking threads, one
+//! at a time per provider, so a slow plugin queues its own searches but
+//! doesn't stall the async runtime. Every exchange is JSON passed through
+//! the plugin's memory:
+//!
+//! - `qstorm_alloc(len: i32) -> i32` returns where qstorm may write `len`
+//!   bytes of input
+//! - `qstorm_connect`, `qstorm_search`, and `qstorm_parse` each take
+//!   `(ptr: i32, len: i32)` and return their output as
+//!   `(ptr << 32) | len` in an `i64`
+//!
+//! Output is read before the next call, so a plugin may reuse one buffer.
+//! An output of `{"error": "..."}` fails the call.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tracing::debug;
+use wasmi::{Engine, Linker, Memory, Module, Store, TypedFunc};
+
+use crate::config::WasmConfig;
+use crate::error::{Error, Result};
+use crate::provider::{Capabilities, SearchProvider};
+use crate::types::{SearchParams, SearchResults};
+
+/// Instructions a plugin may run per call, so one stuck in a loop fails
+/// the search instead of hanging it
+const FUEL_PER_CALL: u64 = 100_000_000;
+
+pub struct WasmProvider {
+    name: String,
+    config: WasmConfig,
+    /// Calls into the plugin take turns; they only build and parse JSON, so
+    /// the HTTP requests themselves still overlap
+    plugin: Option<Arc<Mutex<Plugin>>>,
+    client: reqwest::Client,
+    /// What the plugin's `qstorm_connect` said it supports
+    info: PluginInfo,
+}
+
+/// Output of `qstorm_connect`
+#[derive(Debug, Clone, Default, Deserialize)]
+struct PluginInfo {
+    /// Dimension of the engine's vectors, if the plugin knows it
+    #[serde(default)]
+    dimension: Option<usize>,
+    /// `qstorm_search` uses the query text for native hybrid search
+    #[serde(default)]
+    hybrid: bool,
+    /// `qstorm_search` honors `params.offset`
+    #[serde(default)]
+    pagination: bool,
+}
+
+/// Output of `qstorm_search`
+#[derive(Debug, Deserialize)]
+struct PluginRequest {
+    #[serde(default = "default_method")]
+    method: String,
+    url: String,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    /// Sent as-is if a string, as JSON otherwise
+    #[serde(default)]
+    body: Option<Value>,
+}
+
+fn default_method() -> String {
+    "POST".to_string()
+}
+
+impl WasmProvider {
+    pub fn new(name: String, config: WasmConfig) -> Self {
+        Self {
+            name,
+            config,
+            plugin: None,
+            client: reqwest::Client::new(),
+            info: PluginInfo::default(),
+        }
+    }
+
+    /// Run one of the plugin's exports on `input`, off the async runtime
+    async fn call(&self, export: Export, input: Value) -> Result<Value> {
+        let plugin = Arc::clone(self.plugin.as_ref().ok_or(Error::NotConnected)?);
+        tokio::task::spawn_blocking(move || {
+            plugin
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .call(export, &input)
+        })
+        .await
+        .map_err(|e| Error::query_message(format!("{} panicked: {e}", export.name())))?
+    }
+
+    async fn search(
+        &self,
+        text: Option<&str>,
+        vector: &[f32],
+        params: &SearchParams,
+    ) -> Result<SearchResults> {
+        let request = self
+            .call(
+                Export::Search,
+                json!({ "vector": vector, "text": text, "params": params }),
+            )
+            .await?;
+        let request: PluginRequest = serde_json::from_value(request).map_err(|e| {
+            Error::InvalidResponse(format!("qstorm_search returned a malformed request: {e}"))
+        })?;
+
+        let method = reqwest::Method::from_bytes(request.method.as_bytes()).map_err(|_| {
+            Error::InvalidResponse(format!(
+                "qstorm_search returned an invalid method '{}'",
+                request.method
+            ))
+        })?;
+        let mut builder = self
+            .client
+            .request(method, &request.url)
+            .timeout(Duration::from_millis(params.timeout_ms));
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
+        }
+        builder = match request.body {
+            Some(Value::String(body)) => builder.body(body),
+            Some(body) => builder.json(&body),
+            None => builder,
+        };
+
+        let response = builder.send().await.map_err(|e| {
+            if e.is_timeout() {
+                Error::Timeout(params.timeout_ms)
+            } else {
+                Error::query(e)
+            }
+        })?;
+        let status = response.status().as_u16();
+        let body = response.text().await.map_err(Error::query)?;
+        if !(200..300).contains(&status) {
+            return Err(Error::HttpStatus {
+                status,
+                message: body,
+            });
+        }
+
+        let results = self
+            .call(Export::Parse, json!({ "status": status, "body": body }))
+            .await?;
+        let results: SearchResults = serde_json::from_value(results).map_err(|e| {
+            Error::InvalidResponse(format!("qstorm_parse returned malformed results: {e}"))
+        })?;
+        Ok(results.with_status(status))
+    }
+}
+
+#[async_trait]
+impl SearchProvider for WasmProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            vector_search: true,
+            native_hybrid: self.info.hybrid,
+            vector_dimension: self.info.dimension,
+            pagination: self.info.pagination,
+            ..Default::default()
+        }
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        let wasm = std::fs::read(&self.config.plugin).map_err(|e| {
+            Error::Config(format!("Failed to read plugin {}: {e}", self.config.plugin))
+        })?;
+        let mut plugin = Plugin::load(&wasm)?;
+        let options = serde_json::to_value(&self.config.options)?;
+        let info = plugin.call(Export::Connect, &options)?;
+        self.info = serde_json::from_value(info).map_err(|e| {
+            Error::InvalidResponse(format!("qstorm_connect returned malformed output: {e}"))
+        })?;
+        debug!(plugin = %self.config.plugin, "Loaded WASM plugin");
+        self.plugin = Some(Arc::new(Mutex::new(plugin)));
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.plugin = None;
+        Ok(())
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        Ok(self.plugin.is_some())
+    }
+
+    async fn vector_search(&self, vector: &[f32], params: &SearchParams) -> Result<SearchResults> {
+        self.search(None, vector, params).await
+    }
+
+    async fn hybrid_search(
+        &self,
+        text: &str,
+        vector: &[f32],
+        params: &SearchParams,
+    ) -> Result<SearchResults> {
+        if !self.info.hybrid {
+            return Err(Error::Unsupported(format!(
+                "Plugin for '{}' does not support native hybrid search",
+                self.name
+            )));
+        }
+        self.search(Some(text), vector, params).await
+    }
+
+    async fn vector_dimension(&self, _vector_name: Option<&str>) -> Result<Option<usize>> {
+        Ok(self.info.dimension)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Export {
+    Connect,
+    Search,
+    Parse,
+}
+
+impl Export {
+    fn name(self) -> &'static str {
+        match self {
+            Export::Connect => "qstorm_connect",
+            Export::Search => "qstorm_search",
+            Export::Parse => "qstorm_parse",
+        }
+    }
+}
+
+/// A loaded plugin instance
+struct Plugin {
+    store: Store<()>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    connect: TypedFunc<(i32, i32), i64>,
+    search: TypedFunc<(i32, i32), i64>,
+    parse: TypedFunc<(i32, i32), i64>,
+}
+
+impl Plugin {
+    fn load(wasm: &[u8]) -> Result<Self> {
+        let mut config = wasmi::Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, wasm)
+            .map_err(|e| Error::Config(format!("Invalid WASM plugin: {e}")))?;
+        let mut store = Store::new(&engine, ());
+        // Plugins get no imports: no clock, filesystem, or network
+        let instance = Linker::<()>::new(&engine)
+            .instantiate(&mut store, &module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(|e| Error::Config(format!("Failed to start WASM plugin: {e}")))?;
+
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| Error::Config("WASM plugin doesn't export its memory".into()))?;
+        let missing = |name: &str, e: wasmi::Error| {
+            Error::Config(format!(
+                "WASM plugin export '{name}' is missing or mistyped: {e}"
+            ))
+        };
+        let alloc = instance
+            .get_typed_func(&store, "qstorm_alloc")
+            .map_err(|e| missing("qstorm_alloc", e))?;
+        let export = |export: Export| {
+            instance
+                .get_typed_func(&store, export.name())
+                .map_err(|e| missing(export.name(), e))
+        };
+        let connect = export(Export::Connect)?;
+        let search = export(Export::Search)?;
+        let parse = export(Export::Parse)?;
+        Ok(Self {
+            store,
+            memory,
+            alloc,
+            connect,
+            search,
+            parse,
+        })
+    }
+
+    fn call(&mut self, export: Export, input: &Value) -> Result<Value> {
+        let name = export.name();
+        let failed =
+            |e: &dyn std::fmt::Display| Error::query_message(format!("{name} failed: {e}"));
+        self.store.set_fuel(FUEL_PER_CALL).map_err(|e| failed(&e))?;
+
+        let input = serde_json::to_vec(input)?;
+        let len = i32::try_from(input.len()).map_err(|_| failed(&"input too large"))?;
+        let ptr = self
+            .alloc
+            .call(&mut self.store, len)
+            .map_err(|e| failed(&e))?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, &input)
+            .map_err(|e| failed(&e))?;
+
+        let func = match export {
+            Export::Connect => &self.connect,
+            Export::Search => &self.search,
+            Export::Parse => &self.parse,
+        };
+        let packed = func
+            .call(&mut self.store, (ptr, len))
+            .map_err(|e| failed(&e))?;
+        let (ptr, len) = ((packed as u64 >> 32) as usize, packed as u32 as usize);
+        let output = ptr
+            .checked_add(len)
+            .and_then(|end| self.memory.data(&self.store).get(ptr..end))
+            .ok_or_else(|| failed(&format!("output of {len} bytes at {ptr} is out of bounds")))?;
+
+        let output: Value = serde_json::from_slice(output)
+            .map_err(|e| Error::InvalidResponse(format!("{name} returned invalid JSON: {e}")))?;
+        if let Some(error) = output.get("error") {
+            let message = error
+                .as_str()
+                .map_or_else(|| error.to_string(), str::to_string);
+            return Err(Error::query_message(format!("{name}: {message}")));
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answers every call with a fixed output from its data segment
+    const PLUGIN: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (data (i32.const 0) "{\"dimension\":3}")
+          (data (i32.const 64) "{\"url\":\"http://engine/search\",\"body\":{\"k\":2}}")
+          (data (i32.const 192) "{\"results\":[{\"id\":\"a\",\"score\":0.5}],\"total_hits\":7}")
+          (func (export "qstorm_alloc") (param i32) (result i32) i32.const 1024)
+          (func (export "qstorm_connect") (param i32 i32) (result i64) i64.const 15)
+          (func (export "qstorm_search") (param i32 i32) (result i64)
+            ;; (64 << 32) | 45
+            i64.const 274877906989)
+          (func (export "qstorm_parse") (param i32 i32) (result i64)
+            ;; (192 << 32) | 51
+            i64.const 824633720883))
+    "#;
+
+    #[test]
+    fn test_plugin_calls() {
+        let wasm = wat::parse_str(PLUGIN).unwrap();
+        let mut plugin = Plugin::load(&wasm).unwrap();
+
+        let info = plugin.call(Export::Connect, &json!({})).unwrap();
+        let info: PluginInfo = serde_json::from_value(info).unwrap();
+        assert_eq!(info.dimension, Some(3));
+        assert!(!info.pagination);
+
+        let request = plugin
+            .call(Export::Search, &json!({ "vector": [0.1] }))
+            .unwrap();
+        let request: PluginRequest = serde_json::from_value(request).unwrap();
+        assert_eq!(
+            (request.method.as_str(), request.url.as_str()),
+            ("POST", "http://engine/search")
+        );
+
+        let results = plugin.call(Export::Parse, &json!({ "body": "" })).unwrap();
+        let results: SearchResults = serde_json::from_value(results).unwrap();
+        assert_eq!(results.ids(), ["a"]);
+        assert_eq!(results.total_hits, Some(7));
+    }
+
+    #[test]
+    fn test_output_outside_memory_fails_the_call() {
+        let wasm = wat::parse_str(
+            r#"
+            (module
+              (memory (export "memory") 1)
+              (func (export "qstorm_alloc") (param i32) (result i32) i32.const 0)
+              ;; 4 GiB from the start of a 64 KiB memory
+              (func (export "qstorm_connect") (param i32 i32) (result i64)
+                i64.const 4294967295)
+              ;; (65000 << 32) | 1000, running past the end
+              (func (export "qstorm_search") (param i32 i32) (result i64)
+                i64.const 279172874241000)
+              (func (export "qstorm_parse") (param i32 i32) (result i64) i64.const 0))
+            "#,
+        )
+        .unwrap();
+        let mut plugin = Plugin::load(&wasm).unwrap();
+        for export in [Export::Connect, Export::Search] {
+            let err = plugin.call(export, &json!({})).unwrap_err();
+            assert!(err.to_string().contains("out of bounds"), "{err}");
+        }
+    }
+}
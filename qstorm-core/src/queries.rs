@@ -1,34 +1,838 @@
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
 
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
-use crate::error::Result;
+use crate::config::{PerturbationConfig, seeded_rng};
+use crate::error::{Error, Result};
+use crate::metrics::{Judgment, ndcg_at_k, recall_at_k, reciprocal_rank};
+use crate::template::QueryTemplate;
+use crate::vecs;
 
-/// Query file format - simple list of text queries to embed
+/// Query file format - list of text queries to embed, optionally with ground truth
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueryFile {
     /// Text queries to embed and search with
-    pub queries: Vec<String>,
+    #[serde(default)]
+    pub queries: Vec<Query>,
+    /// Templates expanded into additional queries at load time
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub templates: Vec<QueryTemplate>,
+    /// Path to a separate ground-truth file mapping query ids (or texts) to
+    /// expected document IDs. Relative paths resolve against the query file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ground_truth: Option<String>,
+    /// Path to an `.fvecs` file of precomputed query vectors, one row per query
+    /// in file order. Queries with vectors skip embedding.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vectors: Option<String>,
+}
+
+/// A single query entry: either a bare string or an object with ground truth
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "QueryEntry")]
+pub struct Query {
+    /// Identifier used to match entries in a ground-truth file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Query text to embed
+    pub text: String,
+    /// Document IDs a perfect search would return, in rank order
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_ids: Option<Vec<String>>,
+    /// Graded relevance judgments (document ID -> grade) for NDCG
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relevance: Option<HashMap<String, f64>>,
+    /// Labels that latency and recall are also broken down by (e.g. `long`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Precomputed query vector, loaded from the file-level `vectors` path
+    #[serde(skip)]
+    pub vector: Option<Vec<f32>>,
+}
+
+impl Query {
+    pub fn new(text: String) -> Self {
+        Self {
+            id: None,
+            text,
+            expected_ids: None,
+            relevance: None,
+            tags: Vec::new(),
+            vector: None,
+        }
+    }
+
+    /// Take ground truth from relevance judgments
+    fn set_judgments(&mut self, judgments: Judgments) {
+        let (expected_ids, relevance) = judgments.into_parts();
+        self.expected_ids = expected_ids;
+        self.relevance = relevance;
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum QueryEntry {
+    Text(String),
+    Detailed {
+        #[serde(default)]
+        id: Option<RawId>,
+        text: String,
+        #[serde(default)]
+        expected_ids: Vec<RawId>,
+        #[serde(default)]
+        relevance: HashMap<RawId, f64>,
+        #[serde(default)]
+        tags: Vec<String>,
+    },
+}
+
+impl From<QueryEntry> for Query {
+    fn from(entry: QueryEntry) -> Self {
+        match entry {
+            QueryEntry::Text(text) => Query::new(text),
+            QueryEntry::Detailed {
+                id,
+                text,
+                expected_ids,
+                relevance,
+                tags,
+            } => {
+                let mut query = Query::new(text);
+                query.id = id.map(String::from);
+                query.tags = tags;
+                if relevance.is_empty() {
+                    query.expected_ids = into_ids(expected_ids);
+                } else {
+                    query.set_judgments(Judgments::Graded(relevance));
+                    // An explicit ranking still decides the order for recall
+                    if !expected_ids.is_empty() {
+                        query.expected_ids = into_ids(expected_ids);
+                    }
+                }
+                query
+            }
+        }
+    }
+}
+
+/// Ground-truth file: query id (or text) -> expected document IDs
+type GroundTruthFile = HashMap<String, Judgments>;
+
+/// Ground truth for one query: either the expected IDs in rank order, or a
+/// map of document ID to relevance grade
+#[derive(Clone, Deserialize)]
+#[serde(untagged)]
+enum Judgments {
+    Ranked(Vec<RawId>),
+    Graded(HashMap<RawId, f64>),
+}
+
+impl Judgments {
+    /// Expected IDs plus graded relevance. Graded documents are expected
+    /// when their grade is positive, most relevant first.
+    fn into_parts(self) -> (Option<Vec<String>>, Option<HashMap<String, f64>>) {
+        match self {
+            Judgments::Ranked(ids) => (into_ids(ids), None),
+            Judgments::Graded(grades) => {
+                let grades: HashMap<String, f64> =
+                    grades.into_iter().map(|(id, g)| (id.into(), g)).collect();
+                let mut relevant: Vec<(&String, f64)> = grades
+                    .iter()
+                    .filter(|(_, g)| **g > 0.0)
+                    .map(|(id, g)| (id, *g))
+                    .collect();
+                relevant.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+                let expected: Vec<String> =
+                    relevant.into_iter().map(|(id, _)| id.clone()).collect();
+                (
+                    Some(expected).filter(|ids| !ids.is_empty()),
+                    Some(grades).filter(|grades| !grades.is_empty()),
+                )
+            }
+        }
+    }
 }
 
 impl QueryFile {
+    /// Load a query file.
+    ///
+    /// `.txt` (one query per line) and `.jsonl` (one query entry per line) files
+    /// are read a line at a time; anything else is parsed as YAML. Either way
+    /// every query ends up in memory.
     pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
-        let contents = std::fs::read_to_string(path)?;
-        let query_file: QueryFile = serde_yaml::from_str(&contents)?;
+        Self::from_file_seeded(path, None)
+    }
+
+    /// Load a query file, sampling template combinations with the given seed
+    pub fn from_file_seeded(path: impl AsRef<Path>, seed: Option<u64>) -> Result<Self> {
+        let path = path.as_ref();
+        let mut query_file = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("txt") => Self::from_lines(path, |line| Ok(Query::new(line.to_string())))?,
+            Some("jsonl" | "ndjson") => {
+                Self::from_lines(path, |line| Ok(serde_json::from_str(line)?))?
+            }
+            _ => {
+                let reader = BufReader::new(std::fs::File::open(path)?);
+                serde_yaml::from_reader(reader)?
+            }
+        };
+        query_file.expand_templates(&mut seeded_rng(seed, "templates"))?;
+
+        let resolve = |relative: &str| -> PathBuf {
+            path.parent()
+                .map(|dir| dir.join(relative))
+                .unwrap_or_else(|| relative.into())
+        };
+
+        if let Some(ground_truth) = &query_file.ground_truth {
+            query_file.load_ground_truth(resolve(ground_truth))?;
+        }
+        if let Some(vectors) = &query_file.vectors {
+            query_file.load_vectors(resolve(vectors))?;
+        }
+
         Ok(query_file)
     }
 
+    /// Load queries from several sources into one set.
+    ///
+    /// A single file loads exactly as [`QueryFile::from_file_seeded`]. With
+    /// more than one file, each query is tagged with its file's name (e.g.
+    /// `head` for `head.yaml`) so results break down per set. Without weights
+    /// the sets are concatenated in order; with any weight they're
+    /// interleaved so every stretch of the result mixes the sources in those
+    /// proportions, repeating a source's queries when its share is larger
+    /// than the source. Sources without a weight count as 1.
+    pub fn from_sources(sources: &[QuerySource], seed: Option<u64>) -> Result<Self> {
+        let files = sources
+            .iter()
+            .map(QuerySource::files)
+            .collect::<Result<Vec<_>>>()?;
+        if let [single] = files.as_slice()
+            && let [path] = single.as_slice()
+        {
+            return Self::from_file_seeded(path, seed);
+        }
+
+        let mut sets = Vec::with_capacity(sources.len());
+        for (source, paths) in sources.iter().zip(files) {
+            let mut queries = Vec::new();
+            for path in paths {
+                let set = path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned());
+                for mut query in Self::from_file_seeded(&path, seed)?.queries {
+                    if let Some(set) = &set
+                        && !query.tags.contains(set)
+                    {
+                        query.tags.push(set.clone());
+                    }
+                    queries.push(query);
+                }
+            }
+            sets.push((queries, source.weight));
+        }
+
+        let queries = if sets.iter().all(|(_, weight)| weight.is_none()) {
+            sets.into_iter().flat_map(|(queries, _)| queries).collect()
+        } else {
+            interleave(
+                sets.into_iter()
+                    .map(|(queries, weight)| (queries, weight.unwrap_or(1.0)))
+                    .collect(),
+            )
+        };
+
+        // Ground truth and vectors were attached as each file loaded
+        Ok(Self {
+            queries,
+            templates: Vec::new(),
+            ground_truth: None,
+            vectors: None,
+        })
+    }
+
+    /// Read one query per non-blank line without holding the whole file in memory
+    fn from_lines(path: &Path, parse: impl Fn(&str) -> Result<Query>) -> Result<Self> {
+        let reader = BufReader::new(std::fs::File::open(path)?);
+        let mut queries = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if !line.is_empty() {
+                queries.push(parse(line)?);
+            }
+        }
+
+        Ok(Self {
+            queries,
+            templates: Vec::new(),
+            ground_truth: None,
+            vectors: None,
+        })
+    }
+
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(yaml: &str) -> Result<Self> {
-        let query_file: QueryFile = serde_yaml::from_str(yaml)?;
+        Self::from_str_seeded(yaml, None)
+    }
+
+    /// Parse a YAML query set, sampling template combinations with the given
+    /// seed like [`QueryFile::from_file_seeded`]
+    pub fn from_str_seeded(yaml: &str, seed: Option<u64>) -> Result<Self> {
+        let mut query_file: QueryFile = serde_yaml::from_str(yaml)?;
+        query_file.expand_templates(&mut seeded_rng(seed, "templates"))?;
         Ok(query_file)
     }
+
+    /// Expand `templates` into plain queries appended after the literal ones
+    pub fn expand_templates(&mut self, rng: &mut impl Rng) -> Result<()> {
+        for template in std::mem::take(&mut self.templates) {
+            let texts = template.expand(rng)?;
+            self.queries.extend(texts.into_iter().map(Query::new));
+        }
+        Ok(())
+    }
+
+    /// Apply text perturbations to a fraction of queries, returning how many changed
+    pub fn perturb(&mut self, config: &PerturbationConfig, seed: Option<u64>) -> Result<usize> {
+        config.apply(&mut self.queries, &mut seeded_rng(seed, "perturbation"))
+    }
+
+    /// Merge expected IDs (or graded judgments) from a ground-truth file,
+    /// matching on query id then text. Inline ground truth takes precedence.
+    pub fn load_ground_truth(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let contents = std::fs::read_to_string(path)?;
+        let truth: GroundTruthFile = serde_yaml::from_str(&contents)?;
+
+        for query in &mut self.queries {
+            if query.expected_ids.is_some() || query.relevance.is_some() {
+                continue;
+            }
+            let key = query.id.as_deref().unwrap_or(&query.text);
+            // A query repeated in the file gets the same judgments each time
+            if let Some(judgments) = truth.get(key).cloned() {
+                query.set_judgments(judgments);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Assign precomputed vectors from an `.fvecs` file, row `i` to query `i`
+    pub fn load_vectors(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let mut rows = 0;
+        for (row, vector) in vecs::open::<f32>(path)?.enumerate() {
+            if let Some(query) = self.queries.get_mut(row) {
+                query.vector = Some(vector?);
+            }
+            rows += 1;
+        }
+
+        if rows != self.queries.len() {
+            return Err(Error::Config(format!(
+                "Vector file '{}' has {} rows but the query file has {} queries",
+                path.display(),
+                rows,
+                self.queries.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Convert into embedded queries without copying, if every query carries a
+    /// precomputed vector. Otherwise hands the file back unchanged.
+    pub fn into_precomputed(self) -> std::result::Result<Vec<EmbeddedQuery>, Self> {
+        if self.queries.iter().any(|q| q.vector.is_none()) {
+            return Err(self);
+        }
+
+        Ok(self
+            .queries
+            .into_iter()
+            .filter_map(|q| {
+                let mut embedded = EmbeddedQuery::new(q.text, q.vector?);
+                embedded.id = q.id.map(Into::into);
+                embedded.expected_ids = q.expected_ids;
+                embedded.relevance = q.relevance;
+                embedded.tags = q.tags;
+                Some(embedded)
+            })
+            .collect())
+    }
+
+    /// Query texts in file order
+    pub fn texts(&self) -> Vec<String> {
+        self.queries.iter().map(|q| q.text.clone()).collect()
+    }
+
+    /// Write `queries` as a YAML query file at `path`, with their vectors in
+    /// an `.fvecs` file beside it that the query file points to, so loading
+    /// it skips embedding. Returns the vector file's path.
+    pub fn save_embedded(queries: &[EmbeddedQuery], path: impl AsRef<Path>) -> Result<PathBuf> {
+        let path = path.as_ref();
+        if matches!(
+            path.extension().and_then(|ext| ext.to_str()),
+            Some("txt" | "jsonl" | "ndjson")
+        ) {
+            return Err(Error::Config(format!(
+                "'{}' would be read without its vectors; write embedded queries to a .yaml file",
+                path.display()
+            )));
+        }
+        if queries.iter().any(|q| !q.named_vectors.is_empty()) {
+            return Err(Error::Config(
+                "Precomputed vector files hold one vector per query, not named vectors".into(),
+            ));
+        }
+
+        let vectors = path.with_extension("fvecs");
+        let file = QueryFile {
+            queries: queries
+                .iter()
+                .map(|q| Query {
+                    id: q.id.as_deref().map(String::from),
+                    text: q.text.to_string(),
+                    expected_ids: q.expected_ids.clone(),
+                    relevance: q.relevance.clone(),
+                    tags: q.tags.clone(),
+                    vector: None,
+                })
+                .collect(),
+            templates: Vec::new(),
+            // Ground truth is written inline
+            ground_truth: None,
+            vectors: vectors
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned()),
+        };
+        vecs::write(&vectors, queries.iter().map(|q| q.vector.as_slice()))?;
+        std::fs::write(path, serde_yaml::to_string(&file)?)?;
+        Ok(vectors)
+    }
+}
+
+/// Where queries come from: a query file, a directory of them, or a glob
+/// such as `queries/*.yaml`, optionally weighted with a `:weight` suffix
+/// (`head.yaml:0.6`) when several sources are mixed
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuerySource {
+    pub path: PathBuf,
+    pub weight: Option<f64>,
+}
+
+/// Extensions picked up when a source is a directory
+const QUERY_EXTENSIONS: &[&str] = &["yaml", "yml", "txt", "jsonl", "ndjson"];
+
+impl QuerySource {
+    /// The query files this source names, in name order. Wildcards (`*` and
+    /// `?`) are matched in the file name only.
+    pub fn files(&self) -> Result<Vec<PathBuf>> {
+        let not_found = || {
+            Error::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("no query files at {}", self.path.display()),
+            ))
+        };
+
+        let name = self
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let (dir, pattern) = if name.contains(['*', '?']) {
+            let dir = match self.path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+                _ => PathBuf::from("."),
+            };
+            (dir, Some(name))
+        } else if self.path.is_dir() {
+            (self.path.clone(), None)
+        } else if self.path.exists() {
+            return Ok(vec![self.path.clone()]);
+        } else {
+            return Err(not_found());
+        };
+
+        let mut files: Vec<PathBuf> = std::fs::read_dir(&dir)
+            .map_err(|_| not_found())?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file())
+            .filter(|path| match &pattern {
+                Some(pattern) => path
+                    .file_name()
+                    .is_some_and(|name| wildcard_match(pattern, &name.to_string_lossy())),
+                None => path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| QUERY_EXTENSIONS.contains(&ext)),
+            })
+            .collect();
+        if files.is_empty() {
+            return Err(not_found());
+        }
+        files.sort();
+        Ok(files)
+    }
+}
+
+impl FromStr for QuerySource {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        // Only a trailing number is a weight, so paths with colons still work
+        if let Some((path, weight)) = s.rsplit_once(':')
+            && let Ok(weight) = weight.parse::<f64>()
+        {
+            if !(weight.is_finite() && weight > 0.0) {
+                return Err(Error::Config(format!(
+                    "Invalid query weight in '{s}': must be a positive number"
+                )));
+            }
+            return Ok(Self {
+                path: path.into(),
+                weight: Some(weight),
+            });
+        }
+        Ok(Self {
+            path: s.into(),
+            weight: None,
+        })
+    }
+}
+
+impl fmt::Display for QuerySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(weight) = self.weight {
+            write!(f, ":{weight}")?;
+        }
+        Ok(())
+    }
+}
+
+/// `*` matches any run of characters, `?` any single one
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Where the last `*` was and how much of the name it has taken
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Mix weighted query sets into one list as long as all of them together,
+/// using smooth weighted round-robin so the proportions hold throughout
+/// rather than only over the whole list. Sets are cycled when they run out.
+fn interleave(sets: Vec<(Vec<Query>, f64)>) -> Vec<Query> {
+    let sets: Vec<(Vec<Query>, f64)> = sets
+        .into_iter()
+        .filter(|(queries, _)| !queries.is_empty())
+        .collect();
+    let total: usize = sets.iter().map(|(queries, _)| queries.len()).sum();
+    let weight_sum: f64 = sets.iter().map(|(_, weight)| weight).sum();
+    let mut credit = vec![0.0; sets.len()];
+    let mut taken = vec![0; sets.len()];
+
+    let mut mixed = Vec::with_capacity(total);
+    for _ in 0..total {
+        for (credit, (_, weight)) in credit.iter_mut().zip(&sets) {
+            *credit += weight;
+        }
+        // Most credit goes next; ties go to the earlier set
+        let Some(next) =
+            (0..sets.len()).max_by(|&a, &b| credit[a].total_cmp(&credit[b]).then(b.cmp(&a)))
+        else {
+            break;
+        };
+        credit[next] -= weight_sum;
+        let queries = &sets[next].0;
+        mixed.push(queries[taken[next] % queries.len()].clone());
+        taken[next] += 1;
+    }
+    mixed
+}
+
+/// Identifier written as either a string or an integer
+#[derive(Clone, Deserialize, PartialEq, Eq, Hash)]
+#[serde(untagged)]
+enum RawId {
+    Str(String),
+    Int(i64),
+}
+
+impl From<RawId> for String {
+    fn from(id: RawId) -> Self {
+        match id {
+            RawId::Str(s) => s,
+            RawId::Int(n) => n.to_string(),
+        }
+    }
+}
+
+fn into_ids(ids: Vec<RawId>) -> Option<Vec<String>> {
+    Some(ids.into_iter().map(String::from).collect::<Vec<_>>()).filter(|ids| !ids.is_empty())
 }
 
 /// Embedded query ready for vector search
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmbeddedQuery {
-    /// Original text
-    pub text: String,
+    /// Identifier from the query file, if it gave one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Arc<str>>,
+    /// Original text, shared with the samples and failures that name it
+    pub text: Arc<str>,
     /// Embedding vector
     pub vector: Vec<f32>,
+    /// Additional embeddings for collections with several named vectors
+    pub named_vectors: Vec<NamedVector>,
+    /// Ground-truth document IDs for recall calculation
+    pub expected_ids: Option<Vec<String>>,
+    /// Graded relevance judgments (document ID -> grade) for NDCG
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relevance: Option<HashMap<String, f64>>,
+    /// Labels from the query file for per-tag metrics
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl EmbeddedQuery {
+    pub fn new(text: String, vector: Vec<f32>) -> Self {
+        Self {
+            id: None,
+            text: text.into(),
+            vector,
+            named_vectors: Vec::new(),
+            expected_ids: None,
+            relevance: None,
+            tags: Vec::new(),
+        }
+    }
+
+    /// Recall, NDCG, and reciprocal rank of `returned` within the top `k`,
+    /// if the query has ground truth. Without graded judgments every
+    /// expected ID counts as relevant with grade 1.
+    pub fn judge(&self, returned: &[&str], k: usize) -> Option<Judgment> {
+        let expected = self.expected_ids.as_deref().unwrap_or_default();
+        match &self.relevance {
+            Some(grades) => {
+                let grade = |id: &str| grades.get(id).copied().unwrap_or(0.0).max(0.0);
+                let ideal: Vec<f64> = grades.values().copied().filter(|g| *g > 0.0).collect();
+                Some(Judgment {
+                    recall: recall_at_k(returned, expected, k),
+                    ndcg: ndcg_at_k(returned, grade, &ideal, k),
+                    reciprocal_rank: reciprocal_rank(returned, |id| grade(id) > 0.0, k),
+                })
+            }
+            None if !expected.is_empty() => {
+                let expected_set: HashSet<&str> = expected.iter().map(String::as_str).collect();
+                let grade = |id: &str| if expected_set.contains(id) { 1.0 } else { 0.0 };
+                Some(Judgment {
+                    recall: recall_at_k(returned, expected, k),
+                    ndcg: ndcg_at_k(returned, grade, &vec![1.0; expected.len()], k),
+                    reciprocal_rank: reciprocal_rank(returned, |id| expected_set.contains(id), k),
+                })
+            }
+            None => None,
+        }
+    }
+}
+
+/// An embedding targeting a specific named vector field on the provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamedVector {
+    /// Provider vector field this embedding should be searched against
+    pub name: String,
+    /// Embedding vector
+    pub vector: Vec<f32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mixed_query_entries() {
+        let file = QueryFile::from_str(
+            r#"
+queries:
+  - "plain query"
+  - text: "with truth"
+    expected_ids: ["a", 42]
+  - id: q3
+    text: "empty truth"
+    expected_ids: []
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            file.texts(),
+            vec!["plain query", "with truth", "empty truth"]
+        );
+        assert!(file.queries[0].expected_ids.is_none());
+        assert_eq!(
+            file.queries[1].expected_ids.as_deref(),
+            Some(&["a".to_string(), "42".to_string()][..])
+        );
+        assert_eq!(file.queries[2].id.as_deref(), Some("q3"));
+        assert!(file.queries[2].expected_ids.is_none());
+    }
+
+    #[test]
+    fn test_graded_relevance() {
+        let file = QueryFile::from_str(
+            r#"
+queries:
+  - text: "graded"
+    relevance: {a: 1, b: 3, 7: 2, c: 0}
+"#,
+        )
+        .unwrap();
+
+        let query = &file.queries[0];
+        assert_eq!(
+            query.expected_ids.as_deref(),
+            Some(&["b".to_string(), "7".to_string(), "a".to_string()][..])
+        );
+
+        let mut embedded = EmbeddedQuery::new(query.text.clone(), vec![]);
+        embedded.expected_ids = query.expected_ids.clone();
+        embedded.relevance = query.relevance.clone();
+        let perfect = embedded.judge(&["b", "7", "a"], 3).unwrap();
+        assert!((perfect.ndcg - 1.0).abs() < 1e-9);
+        let late = embedded.judge(&["c", "a", "b"], 3).unwrap();
+        assert!(late.ndcg < 1.0);
+        assert!((late.reciprocal_rank - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_repeated_queries_share_ground_truth() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("truth.yaml");
+        std::fs::write(&path, "chair: [a, b]\n").unwrap();
+
+        let mut file = QueryFile::from_str("queries: [chair, desk, chair]").unwrap();
+        file.load_ground_truth(&path).unwrap();
+        let expected: Vec<_> = file
+            .queries
+            .iter()
+            .map(|q| q.expected_ids.clone())
+            .collect();
+        let chair = Some(vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(expected, [chair.clone(), None, chair]);
+    }
+
+    #[test]
+    fn test_seeded_perturbation_is_reproducible() {
+        let config = PerturbationConfig {
+            rate: 0.5,
+            kinds: vec![crate::config::PerturbationKind::Typo],
+        };
+        let perturbed = |seed| {
+            let mut file = QueryFile::from_str(
+                "queries: [wireless noise cancelling headphones, trail running shoes, \
+                 standing desk with drawers, cast iron skillet]",
+            )
+            .unwrap();
+            file.perturb(&config, Some(seed)).unwrap();
+            file.texts()
+        };
+
+        assert_eq!(perturbed(7), perturbed(7));
+    }
+
+    #[test]
+    fn test_seeded_templates_are_reproducible() {
+        let yaml = r#"
+templates:
+  - template: "{color} {item}"
+    values:
+      color: [red, green, blue, black]
+      item: [chair, desk, lamp, sofa]
+    sample: 5
+"#;
+        let expanded = |seed| {
+            QueryFile::from_str_seeded(yaml, Some(seed))
+                .unwrap()
+                .texts()
+        };
+        assert_eq!(expanded(7), expanded(7));
+        assert_eq!(expanded(7).len(), 5);
+    }
+
+    #[test]
+    fn test_weighted_sources_interleave() {
+        let set = |name: &str, n: usize| {
+            (0..n)
+                .map(|i| Query::new(format!("{name}{i}")))
+                .collect::<Vec<_>>()
+        };
+        let mixed = interleave(vec![(set("h", 2), 3.0), (set("t", 6), 1.0)]);
+        let texts: Vec<&str> = mixed.iter().map(|q| q.text.as_str()).collect();
+        assert_eq!(texts, ["h0", "h1", "t0", "h0", "h1", "h0", "t1", "h1"]);
+
+        let source: QuerySource = "queries/head.yaml:0.6".parse().unwrap();
+        assert_eq!(source.path, PathBuf::from("queries/head.yaml"));
+        assert_eq!(source.weight, Some(0.6));
+        assert_eq!("C:\\q.yaml".parse::<QuerySource>().unwrap().weight, None);
+        assert!("head.yaml:0".parse::<QuerySource>().is_err());
+
+        assert!(wildcard_match("*.yaml", "head.yaml"));
+        assert!(wildcard_match("h?ad*", "head.yaml"));
+        assert!(!wildcard_match("*.yaml", "head.yaml.bak"));
+    }
+
+    #[test]
+    fn test_embedded_query_file_loads_as_precomputed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("queries.yaml");
+
+        let mut first = EmbeddedQuery::new("first".into(), vec![0.5, -1.0, 2.0]);
+        first.id = Some("q1".into());
+        first.expected_ids = Some(vec!["a".into(), "b".into()]);
+        first.tags = vec!["short".into()];
+        let second = EmbeddedQuery::new("second".into(), vec![1.0, 0.0, 0.25]);
+        let vectors = QueryFile::save_embedded(&[first, second], &path).unwrap();
+        assert_eq!(vectors, dir.path().join("queries.fvecs"));
+
+        let loaded = QueryFile::from_file(&path)
+            .unwrap()
+            .into_precomputed()
+            .unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].id.as_deref(), Some("q1"));
+        assert_eq!(loaded[0].vector, vec![0.5, -1.0, 2.0]);
+        assert_eq!(loaded[0].expected_ids.as_ref().map(Vec::len), Some(2));
+        assert_eq!(loaded[0].tags, vec!["short"]);
+        assert_eq!(loaded[1].vector, vec![1.0, 0.0, 0.25]);
+    }
 }
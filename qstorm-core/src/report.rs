@@ -0,0 +1,159 @@
+//! End-of-run summary of a headless benchmark.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::assertion::AssertionResult;
+use crate::config::Config;
+use crate::error::Result;
+use crate::metrics::{BurstMetrics, LatencyMetrics, Metrics, TagMetrics};
+
+/// Everything needed to compare a finished run with another, in one document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunReport {
+    /// qstorm version that produced the report
+    pub version: String,
+    /// Commit checked out where the run was started, if it was a git repository
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_commit: Option<String>,
+    pub provider: String,
+    pub seed: Option<u64>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub duration_secs: f64,
+    /// Stop condition that ended the run early
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<String>,
+    pub bursts: usize,
+    pub total_queries: usize,
+    pub errors: ErrorBreakdown,
+    /// Mean QPS across bursts
+    pub qps: f64,
+    /// Latency percentiles across every query
+    pub latency: LatencyMetrics,
+    /// Recall@k across bursts, if ground truth was provided
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recall_at_k: Option<QualityStats>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ndcg_at_k: Option<QualityStats>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mrr: Option<QualityStats>,
+    /// Counts, latency, and recall per query tag
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, TagMetrics>,
+    /// `--assert` checks made against this report
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assertions: Vec<AssertionResult>,
+    /// The config the run used, with secrets removed
+    pub config: Config,
+}
+
+/// How the run's failed requests failed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ErrorBreakdown {
+    /// Failed queries, timeouts included
+    pub failures: usize,
+    /// Queries that hit the client-side timeout
+    pub timeouts: usize,
+    /// Queries the provider rejected or that couldn't be sent
+    pub errors: usize,
+    /// Failed upserts (mixed and ingest workloads)
+    pub write_failures: usize,
+    /// Share of queries that failed
+    pub error_rate: f64,
+}
+
+/// Spread of a per-burst quality metric
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityStats {
+    pub mean: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl QualityStats {
+    fn from_bursts(values: impl Iterator<Item = f64>) -> Option<Self> {
+        let values: Vec<f64> = values.collect();
+        if values.is_empty() {
+            return None;
+        }
+        Some(Self {
+            mean: values.iter().sum::<f64>() / values.len() as f64,
+            min: values.iter().copied().fold(f64::INFINITY, f64::min),
+            max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        })
+    }
+}
+
+impl RunReport {
+    /// Summarize `metrics` from a run that began at `started_at` and just ended
+    pub fn new(config: &Config, metrics: &Metrics, started_at: DateTime<Utc>) -> Self {
+        let finished_at = Utc::now();
+        let bursts = metrics.bursts();
+        let total_queries = metrics.total_queries();
+        let failures = total_queries - metrics.total_successes();
+        let timeouts = bursts.iter().map(|b| b.timeout_count).sum();
+        let write_failures = bursts
+            .iter()
+            .filter_map(|b| b.writes.as_ref())
+            .map(|w| w.failure_count)
+            .sum();
+
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_commit: None,
+            provider: config.provider.name.clone(),
+            seed: config.seed,
+            started_at,
+            finished_at,
+            duration_secs: (finished_at - started_at).num_milliseconds() as f64 / 1000.0,
+            stop_reason: None,
+            bursts: bursts.len(),
+            total_queries,
+            errors: ErrorBreakdown {
+                failures,
+                timeouts,
+                errors: failures - timeouts,
+                write_failures,
+                error_rate: if total_queries == 0 {
+                    0.0
+                } else {
+                    failures as f64 / total_queries as f64
+                },
+            },
+            qps: metrics.average_qps(),
+            latency: metrics.aggregate_latency(),
+            recall_at_k: QualityStats::from_bursts(bursts.iter().filter_map(|b| b.recall_at_k)),
+            ndcg_at_k: QualityStats::from_bursts(bursts.iter().filter_map(|b| b.ndcg_at_k)),
+            mrr: QualityStats::from_bursts(bursts.iter().filter_map(|b| b.mrr)),
+            tags: metrics.aggregate_tags(),
+            assertions: Vec::new(),
+            config: config.redacted(),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::open(path)?;
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    /// Write the report as pretty-printed JSON
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Write the report and charts of `bursts` as a standalone HTML page
+    pub fn save_html(&self, path: impl AsRef<Path>, bursts: &[BurstMetrics]) -> Result<()> {
+        std::fs::write(path, crate::html::render(self, bursts))?;
+        Ok(())
+    }
+}
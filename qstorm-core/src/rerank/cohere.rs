@@ -0,0 +1,99 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::RerankProvider;
+use crate::config::RerankConfig;
+use crate::error::{Error, Result};
+
+const RERANK_URL: &str = "https://api.cohere.com/v2/rerank";
+
+/// Cohere Rerank API-based reranker
+pub struct CohereReranker {
+    model: String,
+    api_key: String,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct RerankResponse {
+    results: Vec<RerankResult>,
+}
+
+#[derive(Deserialize)]
+struct RerankResult {
+    index: usize,
+    relevance_score: f32,
+}
+
+impl CohereReranker {
+    pub fn new(model: &str, config: &RerankConfig) -> Result<Self> {
+        let api_key = config
+            .api_key
+            .clone()
+            .or_else(|| std::env::var("COHERE_API_KEY").ok())
+            .ok_or_else(|| {
+                Error::Config(
+                    "Cohere API key required. Set 'api_key' in rerank config \
+                     or COHERE_API_KEY env var"
+                        .into(),
+                )
+            })?;
+
+        Ok(Self {
+            model: model.to_string(),
+            api_key,
+            client: reqwest::Client::new(),
+        })
+    }
+}
+
+#[async_trait]
+impl RerankProvider for CohereReranker {
+    async fn rerank(&self, query: &str, documents: &[String]) -> Result<Vec<f32>> {
+        if documents.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let body = json!({
+            "model": self.model,
+            "query": query,
+            "documents": documents,
+        });
+
+        let response = self
+            .client
+            .post(RERANK_URL)
+            .bearer_auth(&self.api_key)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| Error::QueryExecution {
+                message: format!("Cohere rerank request failed: {e}"),
+                code: None,
+                source: Some(e.into()),
+            })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(Error::query_message(format!(
+                "Cohere rerank request failed ({status}): {text}"
+            )));
+        }
+
+        let parsed: RerankResponse = response
+            .json()
+            .await
+            .map_err(|e| Error::InvalidResponse(format!("Malformed rerank response: {e}")))?;
+
+        // Results come back sorted by relevance; put them back in input order
+        let mut scores = vec![f32::NEG_INFINITY; documents.len()];
+        for result in parsed.results {
+            if let Some(score) = scores.get_mut(result.index) {
+                *score = result.relevance_score;
+            }
+        }
+        Ok(scores)
+    }
+}
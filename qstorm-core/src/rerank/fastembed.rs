@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use ::fastembed::{RerankInitOptions, RerankerModel, TextRerank};
+use async_trait::async_trait;
+
+use super::RerankProvider;
+use crate::error::{Error, Result};
+
+/// Fastembed-based local cross-encoder reranker
+pub struct FastEmbedReranker {
+    model: Arc<TextRerank>,
+}
+
+impl FastEmbedReranker {
+    pub fn new(model_name: &str) -> Result<Self> {
+        let model = parse_model(model_name)?;
+        let rerank =
+            TextRerank::try_new(RerankInitOptions::new(model).with_show_download_progress(true))
+                .map_err(|e| Error::Config(format!("Failed to load reranking model: {}", e)))?;
+        Ok(Self {
+            model: Arc::new(rerank),
+        })
+    }
+}
+
+#[async_trait]
+impl RerankProvider for FastEmbedReranker {
+    async fn rerank(&self, query: &str, documents: &[String]) -> Result<Vec<f32>> {
+        let model = Arc::clone(&self.model);
+        let query = query.to_string();
+        let documents = documents.to_vec();
+
+        // Scoring is CPU-bound; keep it off the async workers sending requests
+        let ranked = tokio::task::spawn_blocking(move || {
+            let documents: Vec<&str> = documents.iter().map(String::as_str).collect();
+            model.rerank(query.as_str(), documents, false, None)
+        })
+        .await
+        .map_err(|e| Error::QueryExecution {
+            message: format!("Reranking task failed: {}", e),
+            code: None,
+            source: Some(e.into()),
+        })?
+        .map_err(|e| Error::QueryExecution {
+            message: format!("Reranking failed: {}", e),
+            code: None,
+            source: Some(e.into()),
+        })?;
+
+        let mut scores = vec![f32::NEG_INFINITY; ranked.len()];
+        for result in ranked {
+            if let Some(score) = scores.get_mut(result.index) {
+                *score = result.score;
+            }
+        }
+        Ok(scores)
+    }
+}
+
+fn parse_model(name: &str) -> Result<RerankerModel> {
+    match name {
+        "BAAI/bge-reranker-base" | "bge-reranker-base" => Ok(RerankerModel::BGERerankerBase),
+        "rozgo/bge-reranker-v2-m3" | "bge-reranker-v2-m3" => Ok(RerankerModel::BGERerankerV2M3),
+        "jinaai/jina-reranker-v1-turbo-en" | "jina-reranker-v1-turbo-en" => {
+            Ok(RerankerModel::JINARerankerV1TurboEn)
+        }
+        "jinaai/jina-reranker-v2-base-multilingual" | "jina-reranker-v2-base-multilingual" => {
+            Ok(RerankerModel::JINARerankerV2BaseMultiligual)
+        }
+        _ => Err(Error::Config(format!(
+            "Unknown reranking model: {}. Supported: bge-reranker-base, bge-reranker-v2-m3, \
+             jina-reranker-v1-turbo-en, jina-reranker-v2-base-multilingual",
+            name
+        ))),
+    }
+}
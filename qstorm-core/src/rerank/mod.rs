@@ -0,0 +1,151 @@
+#[cfg(feature = "cohere-rerank")]
+mod cohere;
+#[cfg(feature = "embeddings")]
+mod fastembed;
+
+#[cfg(feature = "cohere-rerank")]
+pub use cohere::CohereReranker;
+#[cfg(feature = "embeddings")]
+pub use fastembed::FastEmbedReranker;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::config::RerankConfig;
+use crate::error::{Error, Result};
+use crate::types::SearchResults;
+
+/// Trait for second-stage rerankers scoring documents against a query
+#[async_trait]
+pub trait RerankProvider: Send + Sync {
+    /// Relevance score of each document for `query`, in input order
+    async fn rerank(&self, query: &str, documents: &[String]) -> Result<Vec<f32>>;
+}
+
+/// Unified reranker dispatching to the configured backend
+pub enum Reranker {
+    #[cfg(feature = "embeddings")]
+    FastEmbed(FastEmbedReranker),
+    #[cfg(feature = "cohere-rerank")]
+    Cohere(CohereReranker),
+    #[cfg(not(any(feature = "embeddings", feature = "cohere-rerank")))]
+    #[doc(hidden)]
+    _Disabled(std::convert::Infallible),
+}
+
+impl Reranker {
+    /// Create a reranker from configuration.
+    ///
+    /// Models namespaced with `cohere/` (e.g. `cohere/rerank-v3.5`) use the
+    /// Cohere Rerank API; all others run a local fastembed cross-encoder.
+    pub fn from_config(config: &RerankConfig) -> Result<Self> {
+        if let Some(model) = config.model.strip_prefix("cohere/") {
+            Self::new_cohere(model, config)
+        } else {
+            Self::new_fastembed(config)
+        }
+    }
+
+    #[cfg(feature = "cohere-rerank")]
+    fn new_cohere(model: &str, config: &RerankConfig) -> Result<Self> {
+        Ok(Self::Cohere(CohereReranker::new(model, config)?))
+    }
+
+    #[cfg(not(feature = "cohere-rerank"))]
+    fn new_cohere(_model: &str, config: &RerankConfig) -> Result<Self> {
+        Err(Error::Config(format!(
+            "Reranker '{}' requires the 'cohere-rerank' feature. \
+             Rebuild with --features cohere-rerank",
+            config.model
+        )))
+    }
+
+    #[cfg(feature = "embeddings")]
+    fn new_fastembed(config: &RerankConfig) -> Result<Self> {
+        Ok(Self::FastEmbed(FastEmbedReranker::new(&config.model)?))
+    }
+
+    #[cfg(not(feature = "embeddings"))]
+    fn new_fastembed(config: &RerankConfig) -> Result<Self> {
+        Err(Error::Config(format!(
+            "Reranker '{}' requires the 'embeddings' feature. \
+             Rebuild with --features embeddings",
+            config.model
+        )))
+    }
+
+    /// Score documents against a query
+    #[allow(unused_variables)]
+    pub async fn rerank(&self, query: &str, documents: &[String]) -> Result<Vec<f32>> {
+        match self {
+            #[cfg(feature = "embeddings")]
+            Self::FastEmbed(r) => r.rerank(query, documents).await,
+            #[cfg(feature = "cohere-rerank")]
+            Self::Cohere(r) => r.rerank(query, documents).await,
+            #[cfg(not(any(feature = "embeddings", feature = "cohere-rerank")))]
+            Self::_Disabled(never) => match *never {},
+        }
+    }
+}
+
+/// A reranker and how to apply it to a provider's results
+#[derive(Clone)]
+pub struct RerankStage {
+    reranker: Arc<Reranker>,
+    text_field: String,
+    top_n: Option<usize>,
+}
+
+impl RerankStage {
+    pub fn from_config(config: &RerankConfig) -> Result<Self> {
+        if config.top_n == Some(0) {
+            return Err(Error::Config("rerank.top_n must be at least 1".into()));
+        }
+        Ok(Self {
+            reranker: Arc::new(Reranker::from_config(config)?),
+            text_field: config.text_field.clone(),
+            top_n: config.top_n,
+        })
+    }
+
+    /// Reorder `results` by reranker score, keeping the best `top_n`
+    pub async fn apply(&self, query: &str, mut results: SearchResults) -> Result<SearchResults> {
+        let documents = results
+            .results
+            .iter()
+            .map(|result| {
+                let text = result
+                    .payload
+                    .as_ref()
+                    .and_then(|payload| payload.get(&self.text_field))
+                    .ok_or_else(|| {
+                        Error::InvalidResponse(format!(
+                            "Result '{}' has no '{}' field to rerank",
+                            result.id, self.text_field
+                        ))
+                    })?;
+                // Non-string fields are reranked as their JSON text
+                Ok(text.as_str().map_or_else(|| text.to_string(), str::to_string))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let scores = self.reranker.rerank(query, &documents).await?;
+        if scores.len() != documents.len() {
+            return Err(Error::InvalidResponse(format!(
+                "Reranker returned {} scores for {} documents",
+                scores.len(),
+                documents.len()
+            )));
+        }
+
+        for (result, score) in results.results.iter_mut().zip(scores) {
+            result.score = score;
+        }
+        results.results.sort_by(|a, b| b.score.total_cmp(&a.score));
+        if let Some(top_n) = self.top_n {
+            results.results.truncate(top_n);
+        }
+        Ok(results)
+    }
+}
@@ -0,0 +1,100 @@
+//! CPU and memory use of the qstorm process itself.
+//!
+//! A load generator that runs out of CPU queues its own requests, and the
+//! extra wait shows up as provider latency. Sampling the process alongside
+//! each burst shows when that happens.
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Kernel clock ticks per second in `/proc`, fixed at 100 for userspace on Linux
+#[cfg(target_os = "linux")]
+const USER_HZ: f64 = 100.0;
+
+/// Resource use of the qstorm process over a burst
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientResources {
+    /// CPU time used per wall-clock time, in percent of one core (can exceed 100)
+    pub cpu_percent: f64,
+    /// Cores available to the process; `cpu_percent` tops out at 100 times this
+    pub cpu_cores: usize,
+    /// Resident memory at the end of the burst, in bytes
+    pub rss_bytes: u64,
+}
+
+impl ClientResources {
+    /// Whether the process used nearly all the CPU available to it
+    pub fn saturated(&self) -> bool {
+        self.cpu_percent >= 90.0 * self.cpu_cores as f64
+    }
+}
+
+/// Measures process CPU use between calls
+#[derive(Debug, Default)]
+pub(crate) struct ResourceSampler {
+    start: Option<(Instant, Duration)>,
+}
+
+impl ResourceSampler {
+    /// Start measuring from now
+    pub(crate) fn start(&mut self) {
+        self.start = cpu_time().map(|cpu| (Instant::now(), cpu));
+    }
+
+    /// Resource use since `start`, then start again from now. `None` if
+    /// `start` wasn't called or the platform isn't supported.
+    pub(crate) fn sample(&mut self) -> Option<ClientResources> {
+        let (started, start_cpu) = self.start.take()?;
+        let cpu = cpu_time()?;
+        let now = Instant::now();
+        self.start = Some((now, cpu));
+
+        let wall = now.duration_since(started).as_secs_f64();
+        Some(ClientResources {
+            cpu_percent: if wall > 0.0 {
+                cpu.saturating_sub(start_cpu).as_secs_f64() / wall * 100.0
+            } else {
+                0.0
+            },
+            cpu_cores: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            rss_bytes: rss_bytes()?,
+        })
+    }
+}
+
+/// User plus system CPU time of every thread in the process
+#[cfg(target_os = "linux")]
+fn cpu_time() -> Option<Duration> {
+    let stat = std::fs::read_to_string("/proc/self/stat").ok()?;
+    // The command name may contain spaces, so count fields from after it
+    let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    // utime and stime are fields 14 and 15; the first after the name is field 3
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some(Duration::from_secs_f64((utime + stime) as f64 / USER_HZ))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn cpu_time() -> Option<Duration> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kb: u64 = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn rss_bytes() -> Option<u64> {
+    None
+}
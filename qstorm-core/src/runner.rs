@@ -1,67 +1,485 @@
-use std::sync::Arc;
-use std::time::Instant;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, RandomState};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use futures::stream::{FuturesUnordered, StreamExt};
+use chrono::{DateTime, TimeDelta, Utc};
+use futures::stream::{FuturesUnordered, Stream, StreamExt};
+use rand::Rng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
 use tokio::sync::Semaphore;
+use tokio::time::sleep_until;
 use tracing::{debug, info, warn};
 
-use crate::config::{BenchmarkConfig, SearchMode};
-use crate::error::Result;
-use crate::metrics::{BurstMetrics, Metrics};
-use crate::provider::SearchProvider;
+use crate::config::{
+    ArrivalProcess, BenchmarkConfig, GroundTruthConfig, LoadConfig, ScenarioConfig, SearchMode,
+    seeded_rng,
+};
+use crate::corpus::CorpusCursor;
+use crate::error::{Error, Result};
+use crate::events::{EventBus, RunnerEvent};
+use crate::ground_truth::GroundTruth;
+use crate::metrics::{
+    BurstMetrics, BurstSamples, Metrics, Recorder, SweepPoint, jaccard, merge_bursts,
+};
+use crate::pipeline::{self, Dispatch, LoadProfile, MetricSink, RunnerBuilder};
+use crate::provider::{ProviderFactory, SearchProvider};
 use crate::queries::EmbeddedQuery;
-use crate::types::SearchParams;
+use crate::rerank::RerankStage;
+use crate::samples::{
+    Failure, FailureLog, RequestKind, Sample, SampleLog, SampleStatus, SlowQueryLog,
+};
+use crate::scenario::ScenarioState;
+use crate::shard::{Shard, shard_config, share};
+use crate::soak::Resumed;
+use crate::state::RunState;
+use crate::stop::StopMonitor;
+use crate::synthetic;
+use crate::types::{Document, ScrollParams, SearchParams, SearchResults};
 
 /// Orchestrates benchmark execution for vector search
 pub struct BenchmarkRunner {
     provider: Box<dyn SearchProvider>,
     config: BenchmarkConfig,
     metrics: Metrics,
-    queries: Vec<EmbeddedQuery>,
+    queries: Arc<[EmbeddedQuery]>,
+    stop: Option<StopMonitor>,
+    stop_reason: Option<String>,
+    rng: StdRng,
+    /// Separate stream so shuffling doesn't change arrival times or write mixes
+    shuffle_rng: StdRng,
+    /// Query orders drawn from `shuffle_rng` so far
+    shuffles: usize,
+    /// Shared with the client threads, so they write different rows
+    corpus: Option<Arc<Mutex<CorpusCursor>>>,
+    scenario: Option<ScenarioState>,
+    seed: Option<u64>,
+    factory: Option<ProviderFactory>,
+    shards: Vec<Shard>,
+    /// Searches plus upsert requests sent by bursts so far
+    queries_sent: usize,
+    sample_log: Option<SampleLog>,
+    slow_log: Option<SlowQueryLog>,
+    failure_log: Option<FailureLog>,
+    rerank: Option<RerankStage>,
+    history: ResultHistory,
+    events: EventBus,
+    /// Where request events go: the parent's bus in a client thread, so its
+    /// listeners see every request but only the parent's bursts
+    request_events: Option<EventBus>,
+    /// Whether the provider was reachable last we knew
+    healthy: Option<bool>,
+    /// Index of the first query used by each burst
+    query_offset: usize,
+    /// Sends bursts in place of `config.load`. Only used through `&mut
+    /// self`; the mutexes make the runner `Sync` without asking it of the
+    /// profile and sinks.
+    load: Option<Mutex<Box<dyn LoadProfile>>>,
+    sinks: Vec<Mutex<Box<dyn MetricSink>>>,
 }
 
 impl BenchmarkRunner {
     pub fn new(provider: Box<dyn SearchProvider>, config: BenchmarkConfig) -> Self {
+        let stop = config.stop_if.clone().map(StopMonitor::new);
+        let windows: Vec<Duration> = config
+            .windows_secs
+            .iter()
+            .map(|&secs| Duration::from_secs(secs))
+            .collect();
+        let metrics = Metrics::new()
+            .with_percentiles(config.percentiles.clone())
+            .with_windows(&windows);
         Self {
             provider,
             config,
-            metrics: Metrics::new(),
-            queries: Vec::new(),
+            metrics,
+            queries: Arc::default(),
+            stop,
+            stop_reason: None,
+            rng: seeded_rng(None, "runner"),
+            shuffle_rng: seeded_rng(None, "shuffle"),
+            shuffles: 0,
+            corpus: None,
+            scenario: None,
+            seed: None,
+            factory: None,
+            shards: Vec::new(),
+            queries_sent: 0,
+            sample_log: None,
+            slow_log: None,
+            failure_log: None,
+            rerank: None,
+            history: ResultHistory::default(),
+            events: EventBus::new(),
+            request_events: None,
+            healthy: None,
+            query_offset: 0,
+            load: None,
+            sinks: Vec::new(),
         }
     }
 
-    /// Set the embedded queries to use for benchmarking
-    pub fn with_queries(mut self, queries: Vec<EmbeddedQuery>) -> Self {
-        self.queries = queries;
+    /// Assemble a runner from a provider and pluggable parts. See
+    /// [`crate::pipeline`].
+    pub fn builder(provider: Box<dyn SearchProvider>, config: BenchmarkConfig) -> RunnerBuilder {
+        RunnerBuilder::new(provider, config)
+    }
+
+    /// Set the embedded queries to use for benchmarking. Pass an existing
+    /// `Arc` to share one query set without copying it.
+    pub fn with_queries(mut self, queries: impl Into<Arc<[EmbeddedQuery]>>) -> Self {
+        self.queries = queries.into();
+        self
+    }
+
+    /// Run the phases of `scenario` in order, starting from the current
+    /// settings for anything a phase leaves unset
+    pub fn with_scenario(mut self, scenario: ScenarioConfig) -> Self {
+        self.scenario = Some(ScenarioState::new(scenario, self.config.clone()));
+        self
+    }
+
+    /// Make randomized scheduling (e.g. Poisson arrivals) reproducible
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.reseed(seed);
+        self
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.rng = seeded_rng(Some(seed), "runner");
+        self.shuffle_rng = seeded_rng(Some(seed), "shuffle");
+        self.shuffles = 0;
+        self.seed = Some(seed);
+    }
+
+    /// How to open the extra provider connections used by `client_threads`.
+    /// Each client thread builds and connects its own provider with it.
+    pub fn with_provider_factory(mut self, factory: ProviderFactory) -> Self {
+        self.factory = Some(factory);
         self
     }
 
+    /// Log every request sent during bursts as a raw sample
+    pub fn with_sample_log(mut self, log: SampleLog) -> Self {
+        self.sample_log = Some(log);
+        self
+    }
+
+    /// Log the slowest searches of each burst with their query text
+    pub fn with_slow_query_log(mut self, log: SlowQueryLog) -> Self {
+        self.slow_log = Some(log);
+        self
+    }
+
+    /// Keep failed requests with their error and query in `log`
+    pub fn with_failure_log(mut self, log: FailureLog) -> Self {
+        self.failure_log = Some(log);
+        self
+    }
+
+    /// Pass each search's results through a second-stage reranker, timing
+    /// it separately from retrieval
+    pub fn with_rerank(mut self, stage: RerankStage) -> Self {
+        self.rerank = Some(stage);
+        self
+    }
+
+    /// Send bursts with `profile` instead of following `benchmark.load`.
+    /// Only for runs on a single client thread.
+    pub fn with_load_profile(mut self, profile: impl LoadProfile + 'static) -> Self {
+        self.load = Some(Mutex::new(Box::new(profile)));
+        self
+    }
+
+    /// Also pass every finished burst to `sink`
+    pub fn with_sink(mut self, sink: impl MetricSink + 'static) -> Self {
+        self.sinks.push(Mutex::new(Box::new(sink)));
+        self
+    }
+
+    /// Send request events to a client thread's parent
+    pub(crate) fn with_request_events(mut self, events: EventBus) -> Self {
+        self.request_events = Some(events);
+        self
+    }
+
+    /// Take ingest documents from a client thread's parent's corpus
+    pub(crate) fn with_corpus(mut self, corpus: Arc<Mutex<CorpusCursor>>) -> Self {
+        self.corpus = Some(corpus);
+        self
+    }
+
+    /// A stream of what the runner does from now on: bursts, requests,
+    /// errors, and health changes. See [`crate::events`].
+    pub fn events(&self) -> impl Stream<Item = RunnerEvent> + Send + Unpin + 'static {
+        self.events.subscribe()
+    }
+
+    /// Settings for one client thread's slice of the parent's burst
+    pub(crate) fn set_shard_config(&mut self, config: BenchmarkConfig, query_offset: usize) {
+        self.config = config;
+        self.query_offset = query_offset;
+    }
+
+    /// Settings the next burst runs with
+    pub fn config(&self) -> &BenchmarkConfig {
+        &self.config
+    }
+
+    /// Change the settings of the bursts that follow, e.g. to try another
+    /// concurrency without reconnecting. A scenario still replaces them when
+    /// its next phase starts.
+    pub fn config_mut(&mut self) -> &mut BenchmarkConfig {
+        &mut self.config
+    }
+
+    /// Shared handle to the loaded queries
+    pub fn queries(&self) -> Arc<[EmbeddedQuery]> {
+        Arc::clone(&self.queries)
+    }
+
     /// Get the number of loaded queries
     pub fn query_count(&self) -> usize {
         self.queries.len()
     }
 
+    /// Carry on from where an interrupted run of the same config left off:
+    /// its bursts and latencies are restored, its requests count against
+    /// `max_total_queries`, and shuffling picks up where its query order was.
+    /// Call once queries are loaded, before the first burst.
+    pub fn resume(&mut self, resumed: Resumed) {
+        // Checkpoints don't count shuffles; each burst drew one if shuffling
+        let shuffles = if self.config.shuffle {
+            resumed.bursts.len()
+        } else {
+            0
+        };
+        self.restore(RunState {
+            seed: resumed.checkpoint.seed,
+            bursts: resumed.bursts,
+            latency: resumed.latency,
+            tags: resumed.tags,
+            queries_sent: resumed.checkpoint.queries_sent,
+            shuffles,
+        });
+    }
+
+    /// The run's progress so far, to persist and later [`restore`](Self::restore)
+    pub fn state(&self) -> RunState {
+        RunState {
+            seed: self.seed,
+            bursts: self.metrics.bursts().to_vec(),
+            latency: self.metrics.latency_histogram().clone(),
+            tags: self.metrics.tag_histograms().clone(),
+            queries_sent: self.queries_sent,
+            shuffles: self.shuffles,
+        }
+    }
+
+    /// Carry on from `state`, taken from a runner with the same config and
+    /// queries. Call once queries are loaded, before the first burst.
+    pub fn restore(&mut self, state: RunState) {
+        if let Some(seed) = state.seed
+            && self.seed != Some(seed)
+        {
+            self.reseed(seed);
+        }
+        self.metrics.restore(state.bursts, state.latency, state.tags);
+        self.queries_sent = state.queries_sent;
+
+        // Replay the earlier shuffles so the next burst draws the order an
+        // uninterrupted run would have
+        let mut order: Vec<usize> = (0..self.queries.len()).collect();
+        for _ in self.shuffles..state.shuffles {
+            order.shuffle(&mut self.shuffle_rng);
+        }
+        self.shuffles = self.shuffles.max(state.shuffles);
+    }
+
     /// Connect to the provider
     pub async fn connect(&mut self) -> Result<()> {
-        self.provider.connect().await
+        if self.config.client_threads > 1 && self.factory.is_none() {
+            return Err(Error::Config(
+                "client_threads needs a provider factory to open more connections".into(),
+            ));
+        }
+        let result = self.provider.connect().await;
+        match &result {
+            Ok(()) => self.set_health(true, None),
+            Err(e) => {
+                let message = e.to_string();
+                self.events.emit(|| RunnerEvent::Error {
+                    message: message.clone(),
+                });
+                self.set_health(false, Some(message));
+            }
+        }
+        result
+    }
+
+    /// Note whether the provider is reachable, telling listeners on a change
+    fn set_health(&mut self, healthy: bool, reason: Option<String>) {
+        if self.healthy.replace(healthy) != Some(healthy) {
+            self.events
+                .emit(|| RunnerEvent::HealthChanged { healthy, reason });
+        }
+    }
+
+    /// Drop the provider's connections, and the client threads', and connect
+    /// again, e.g. after the engine restarted. Metrics carry on, and client
+    /// threads start again with the next burst.
+    pub async fn reconnect(&mut self) -> Result<()> {
+        // A dead connection may not close cleanly; connecting again is what matters
+        if let Err(e) = self.disconnect().await {
+            warn!(error = %e, "Disconnecting before reconnect failed");
+        }
+        self.connect().await
+    }
+
+    /// Spawn the client threads. Deferred to the first burst so they share
+    /// the query set after ground truth has been attached.
+    async fn start_shards(&mut self) -> Result<()> {
+        let threads = self.config.client_threads;
+        let factory = self.factory.clone().ok_or_else(|| {
+            Error::Config("client_threads needs a provider factory to open more connections".into())
+        })?;
+        info!(threads, "Starting client threads");
+        // Each thread warms up its own connection, out of the same budget
+        let warmup = self
+            .budget_left()
+            .map_or(self.config.warmup_iterations, |left| {
+                self.config.warmup_iterations.min(left)
+            });
+        self.queries_sent += warmup;
+        if let Some(path) = self.config.ingest.as_ref().and_then(|i| i.corpus.as_ref())
+            && self.corpus.is_none()
+        {
+            self.corpus = Some(Arc::new(Mutex::new(CorpusCursor::open(path)?)));
+        }
+        for i in 0..threads {
+            let seed = self.seed.map(|seed| seed.wrapping_add(i as u64 + 1));
+            let sample_log = self.sample_log.clone();
+            let slow_log = self.slow_log.clone();
+            let failure_log = self.failure_log.clone();
+            let rerank = self.rerank.clone();
+            let events = self.events.clone();
+            let corpus = self.corpus.clone();
+            let configure = move |mut runner: BenchmarkRunner| {
+                runner = runner.with_request_events(events);
+                if let Some(corpus) = corpus {
+                    runner = runner.with_corpus(corpus);
+                }
+                if let Some(seed) = seed {
+                    runner = runner.with_seed(seed);
+                }
+                if let Some(log) = sample_log {
+                    runner = runner.with_sample_log(log);
+                }
+                if let Some(log) = slow_log {
+                    runner = runner.with_slow_query_log(log);
+                }
+                if let Some(log) = failure_log {
+                    runner = runner.with_failure_log(log);
+                }
+                if let Some(stage) = rerank {
+                    runner = runner.with_rerank(stage);
+                }
+                runner
+            };
+            let shard = Shard::spawn(
+                i,
+                factory.clone(),
+                shard_config(&self.config, i, threads),
+                Arc::clone(&self.queries),
+                share(warmup, i, threads),
+                configure,
+            )
+            .await?;
+            self.shards.push(shard);
+        }
+        Ok(())
     }
 
     /// Disconnect from the provider
     pub async fn disconnect(&mut self) -> Result<()> {
+        for shard in std::mem::take(&mut self.shards) {
+            shard.disconnect().await;
+        }
         self.provider.disconnect().await
     }
 
+    /// Compute exact ground truth for queries that don't carry expected IDs.
+    ///
+    /// Loads `cache` if it exists, otherwise scans the corpus file (or scrolls
+    /// the connected provider) and writes the result to `cache` when set.
+    /// Queries are copied on write if the query set is still shared.
+    pub async fn compute_ground_truth(&mut self, config: &GroundTruthConfig) -> Result<()> {
+        if self.queries.iter().all(|q| q.expected_ids.is_some()) {
+            return Ok(());
+        }
+
+        let k = config.k.unwrap_or(self.config.top_k);
+
+        let ground_truth = match &config.cache {
+            Some(cache) if std::path::Path::new(cache).exists() => {
+                info!(cache = %cache, "Loading cached ground truth");
+                GroundTruth::load(cache)?
+            }
+            _ => {
+                info!(
+                    k,
+                    queries = self.queries.len(),
+                    "Computing exact ground truth"
+                );
+                let ground_truth = match &config.corpus {
+                    Some(corpus) => {
+                        GroundTruth::from_vector_file(corpus, &self.queries, k, config.metric)?
+                    }
+                    None => {
+                        let params = ScrollParams {
+                            batch_size: config.batch_size,
+                            ..Default::default()
+                        };
+                        GroundTruth::from_provider(
+                            &*self.provider,
+                            &self.queries,
+                            k,
+                            config.metric,
+                            &params,
+                        )
+                        .await?
+                    }
+                };
+                if let Some(cache) = &config.cache {
+                    ground_truth.save(cache)?;
+                }
+                ground_truth
+            }
+        };
+
+        ground_truth.apply(Arc::make_mut(&mut self.queries));
+        Ok(())
+    }
+
     /// Run warmup iterations (results discarded)
     pub async fn warmup(&mut self) -> Result<()> {
+        self.warmup_iterations(self.config.warmup_iterations).await
+    }
+
+    /// Warmup searches count towards `max_total_queries`, so a budget
+    /// smaller than the warmup cuts it short
+    pub(crate) async fn warmup_iterations(&mut self, iterations: usize) -> Result<()> {
         if self.queries.is_empty() {
             warn!("No queries configured for warmup");
             return Ok(());
         }
+        let iterations = self
+            .budget_left()
+            .map_or(iterations, |left| iterations.min(left));
+        self.queries_sent += iterations;
 
-        info!(
-            iterations = self.config.warmup_iterations,
-            "Starting warmup"
-        );
+        info!(iterations, "Starting warmup");
 
         let params = SearchParams {
             top_k: self.config.top_k,
@@ -69,7 +487,7 @@ impl BenchmarkRunner {
             ..Default::default()
         };
 
-        for i in 0..self.config.warmup_iterations {
+        for i in 0..iterations {
             let query = &self.queries[i % self.queries.len()];
             let _ = self.execute_query(query, &params).await;
         }
@@ -80,96 +498,679 @@ impl BenchmarkRunner {
 
     /// Execute a single burst of vector queries concurrently
     pub async fn run_burst(&mut self) -> Result<BurstMetrics> {
+        let result = self.run_next_burst().await;
+        match &result {
+            Ok(burst) => {
+                let writes = burst.writes.as_ref();
+                let sent = burst.query_count + writes.map_or(0, |w| w.request_count);
+                let answered = burst.success_count > 0
+                    || writes.is_some_and(|w| w.request_count > w.failure_count);
+                if answered || sent == 0 {
+                    self.set_health(true, None);
+                } else {
+                    self.set_health(false, Some("every request in the burst failed".into()));
+                }
+                self.events
+                    .emit(|| RunnerEvent::BurstFinished(Arc::new(burst.clone())));
+            }
+            Err(e) => {
+                self.events.emit(|| RunnerEvent::Error {
+                    message: e.to_string(),
+                });
+                if matches!(e, Error::Connection { .. } | Error::NotConnected) {
+                    self.set_health(false, Some(e.to_string()));
+                }
+            }
+        }
+        result
+    }
+
+    async fn run_next_burst(&mut self) -> Result<BurstMetrics> {
+        if self.budget_exhausted() {
+            return Err(Error::BudgetExhausted(self.queries_sent));
+        }
+        if self.config.max_total_queries.is_some() && self.rerank.is_some() {
+            return Err(Error::Config(
+                "benchmark.max_total_queries can't be combined with reranking".into(),
+            ));
+        }
+
+        self.step_scenario().await?;
+        let phase = self.scenario.as_ref().and_then(ScenarioState::phase_label);
+
+        if self.config.client_threads > 1 {
+            if self.load.is_some() {
+                return Err(Error::Config(
+                    "a load profile can't be split across client threads; \
+                     set benchmark.client_threads to 1"
+                        .into(),
+                ));
+            }
+            if self.shards.is_empty() {
+                self.start_shards().await?;
+            }
+        }
+
+        // Shrink the last burst to whatever is left of the budget
+        let full_burst = self.config.burst_size;
+        if let Some(left) = self.budget_left() {
+            self.config.burst_size = full_burst.min(left / self.requests_per_search());
+        }
+
+        self.events.emit(|| RunnerEvent::BurstStarted {
+            index: self.metrics.bursts().len(),
+            phase: phase.clone(),
+            at: Utc::now(),
+        });
+        let burst = if self.shards.is_empty() {
+            self.run_local_burst(phase).await
+        } else {
+            self.run_sharded_burst(phase).await
+        };
+        self.config.burst_size = full_burst;
+        let mut burst = burst?;
+        if let Some(stats) = self.poll_server_stats().await {
+            self.metrics.record_server_stats(stats.clone());
+            burst.server_stats = Some(stats);
+        }
+        // Every page of a paged search is a request of its own
+        let searches = burst.pagination.as_ref().map_or(burst.query_count, |p| {
+            p.pages.iter().map(|page| page.request_count).sum()
+        });
+        self.queries_sent += searches + burst.writes.as_ref().map_or(0, |w| w.request_count);
+        burst.probe_recall = self.probe_recall().await;
+
+        if let Some(scenario) = &mut self.scenario {
+            scenario.record_burst();
+        }
+
+        if let Some(client) = burst.client.as_ref().filter(|c| c.saturated()) {
+            warn!(
+                cpu_percent = client.cpu_percent,
+                cores = client.cpu_cores,
+                "qstorm used nearly all available CPU; latencies may include client-side queueing"
+            );
+        }
+
+        for sink in &mut self.sinks {
+            let sink = sink.get_mut().unwrap_or_else(|e| e.into_inner());
+            sink.record(&burst, self.metrics.last_burst_samples())?;
+        }
+
+        if let Some(reason) = self.stop.as_mut().and_then(|stop| stop.observe(&burst)) {
+            warn!(%reason, "Stop condition met");
+            self.stop_reason = Some(reason);
+        }
+        Ok(burst)
+    }
+
+    /// Engine stats, if they're configured and due after this burst. A
+    /// provider that can't report them is asked only once.
+    async fn poll_server_stats(&mut self) -> Option<serde_json::Value> {
+        let every = self.config.server_stats.as_ref()?.every_bursts.max(1);
+        if !self.metrics.bursts().len().is_multiple_of(every) {
+            return None;
+        }
+        match self.provider.server_stats().await {
+            Ok(stats) => Some(stats),
+            Err(e @ Error::Unsupported(_)) => {
+                warn!(error = %e, "Not collecting server stats");
+                self.config.server_stats = None;
+                None
+            }
+            Err(e) => {
+                warn!(error = %e, "Failed to poll server stats");
+                None
+            }
+        }
+    }
+
+    /// Mean Recall@k over the probe sample, if a probe is configured and due
+    /// after this burst. The sample stays the same all run, so changes in
+    /// the series come from the engine rather than from which queries a
+    /// burst happened to draw. The probe runs between bursts: its searches go
+    /// out one at a time once the burst is over and hold up the next one, so
+    /// they add no load to either and aren't in their metrics. They do count
+    /// towards `max_total_queries`.
+    async fn probe_recall(&mut self) -> Option<f64> {
+        let probe = self.config.recall_probe.as_ref()?;
+        if !self
+            .metrics
+            .bursts()
+            .len()
+            .is_multiple_of(probe.every_bursts.max(1))
+        {
+            return None;
+        }
+        let judged: Vec<&EmbeddedQuery> = self
+            .queries
+            .iter()
+            .filter(|q| q.expected_ids.is_some())
+            .collect();
+        let step = judged.len().div_ceil(probe.sample.max(1)).max(1);
+        let params = SearchParams {
+            top_k: self.config.top_k,
+            timeout_ms: self.config.timeout_ms,
+            ..Default::default()
+        };
+
+        let probes: Vec<&EmbeddedQuery> = judged
+            .into_iter()
+            .step_by(step)
+            .take(self.budget_left().unwrap_or(usize::MAX))
+            .collect();
+        self.queries_sent += probes.len();
+
+        let mut recalls = Vec::new();
+        for query in probes {
+            match self.execute_query(query, &params).await {
+                Ok(results) => recalls.extend(
+                    query
+                        .judge(&results.ids(), self.config.top_k)
+                        .map(|j| j.recall),
+                ),
+                Err(e) => debug!(error = %e, "Recall probe search failed"),
+            }
+        }
+        (!recalls.is_empty()).then(|| recalls.iter().sum::<f64>() / recalls.len() as f64)
+    }
+
+    /// Split the burst across the client threads and merge their results
+    async fn run_sharded_burst(&mut self, phase: Option<String>) -> Result<BurstMetrics> {
+        let shards = self.shards.len();
+        self.metrics.start_pushed_burst();
+
+        // Start every shard before waiting on any so they run side by side
+        let mut pending = Vec::with_capacity(shards);
+        let mut query_offset = self.query_offset;
+        for (i, shard) in self.shards.iter().enumerate() {
+            let config = shard_config(&self.config, i, shards);
+            let burst_size = config.burst_size;
+            pending.push(shard.start_burst(config, query_offset).await?);
+            query_offset += burst_size;
+        }
+
+        let mut parts = Vec::with_capacity(shards);
+        for rx in pending {
+            let part = rx
+                .await
+                .map_err(|_| Error::connection_message("client thread exited mid-burst"))??;
+            parts.push(part);
+        }
+
+        let mut burst = merge_bursts(&parts)
+            .ok_or_else(|| Error::Config("No client threads running".into()))?;
+        burst.phase = phase;
+
+        let mut samples = BurstSamples::default();
+        for (_, part) in parts {
+            samples.latencies_us.extend(part.latencies_us);
+            samples.write_latencies_us.extend(part.write_latencies_us);
+            samples
+                .retrieval_latencies_us
+                .extend(part.retrieval_latencies_us);
+            samples.rerank_latencies_us.extend(part.rerank_latencies_us);
+            samples.overlaps.extend(part.overlaps);
+            for (tag, latencies) in part.tag_latencies_us {
+                samples
+                    .tag_latencies_us
+                    .entry(tag)
+                    .or_default()
+                    .extend(latencies);
+            }
+        }
+        Ok(self.metrics.push_burst(burst, samples))
+    }
+
+    /// Run a burst on this runner's own provider connection
+    async fn run_local_burst(&mut self, phase: Option<String>) -> Result<BurstMetrics> {
         if self.queries.is_empty() {
             return Err(crate::error::Error::Config("No queries configured".into()));
         }
 
-        let semaphore = Arc::new(Semaphore::new(self.config.concurrency));
         let params = Arc::new(SearchParams {
             top_k: self.config.top_k,
             timeout_ms: self.config.timeout_ms,
+            // The reranker scores the documents' text, so it needs payloads
+            include_payload: self.rerank.is_some(),
             ..Default::default()
         });
 
-        self.metrics.start_burst();
-
-        let query_indices: Vec<usize> = (0..self.config.burst_size)
-            .map(|i| i % self.queries.len())
+        // Queries carrying named vectors rotate through each one so every
+        // vector field on the provider gets stressed
+        let vector_names: Vec<&str> = self.queries[0]
+            .named_vectors
+            .iter()
+            .map(|v| v.name.as_str())
             .collect();
+        let named_params: Vec<Arc<SearchParams>> = vector_names
+            .iter()
+            .map(|name| {
+                Arc::new(SearchParams {
+                    vector_name: Some(name.to_string()),
+                    ..(*params).clone()
+                })
+            })
+            .collect();
+
+        if let Some(mixed) = &self.config.mixed {
+            if mixed.read + mixed.write == 0 {
+                return Err(Error::Config(
+                    "mixed workload needs a non-zero read or write share".into(),
+                ));
+            }
+            if mixed.write > 0 && !self.provider.capabilities().upsert {
+                return Err(Error::Unsupported(format!(
+                    "Provider '{}' does not support writing documents",
+                    self.provider.name()
+                )));
+            }
+        }
+
+        if self.config.batch_size == 0 {
+            return Err(Error::Config(
+                "benchmark.batch_size must be at least 1".into(),
+            ));
+        }
+        if let Some(p) = self
+            .config
+            .percentiles
+            .iter()
+            .find(|p| !(**p > 0.0 && **p <= 100.0))
+        {
+            return Err(Error::Config(format!(
+                "benchmark.percentiles must be between 0 and 100, got {p}"
+            )));
+        }
+        if self.config.windows_secs.contains(&0) {
+            return Err(Error::Config(
+                "benchmark.windows_secs must be at least 1 second".into(),
+            ));
+        }
+        if self.config.batch_size > 1 {
+            if !matches!(self.config.mode, SearchMode::Vector) {
+                return Err(Error::Config(
+                    "Batch search only supports vector mode".into(),
+                ));
+            }
+            if self.rerank.is_some() {
+                return Err(Error::Config(
+                    "Reranking doesn't support batch search; set benchmark.batch_size to 1".into(),
+                ));
+            }
+            if !self.provider.capabilities().batch_search {
+                return Err(Error::Unsupported(format!(
+                    "Provider '{}' does not support batch search",
+                    self.provider.name()
+                )));
+            }
+        }
+
+        if let Some(paginate) = &self.config.paginate {
+            if paginate.depth == 0 {
+                return Err(Error::Config(
+                    "benchmark.paginate.depth must be at least 1".into(),
+                ));
+            }
+            if self.config.batch_size > 1 || self.rerank.is_some() {
+                return Err(Error::Config(
+                    "benchmark.paginate can't be combined with batch search or reranking".into(),
+                ));
+            }
+            if !self.provider.capabilities().pagination {
+                return Err(Error::Unsupported(format!(
+                    "Provider '{}' does not support paging through results",
+                    self.provider.name()
+                )));
+            }
+        }
+
+        if let Some(ingest) = &self.config.ingest {
+            if self.config.mixed.is_some() {
+                return Err(Error::Config(
+                    "benchmark.mixed and benchmark.ingest cannot be combined".into(),
+                ));
+            }
+            if !self.provider.capabilities().upsert {
+                return Err(Error::Unsupported(format!(
+                    "Provider '{}' does not support writing documents",
+                    self.provider.name()
+                )));
+            }
+            if let Some(path) = &ingest.corpus
+                && self.corpus.is_none()
+            {
+                self.corpus = Some(Arc::new(Mutex::new(CorpusCursor::open(path)?)));
+            }
+        }
+
+        let slots = vector_names.len().max(1);
 
-        // Field-level borrows so we can use &mut self.metrics after futures complete
+        // Field-level borrows so requests can run alongside &mut self.rng and self.metrics
         let provider = &*self.provider;
         let queries = &self.queries;
         let mode = self.config.mode;
+        let top_k = self.config.top_k;
+        let query_offset = self.query_offset;
 
-        // Phase 1: dispatch all queries concurrently
-        let mut futures = FuturesUnordered::new();
-        for idx in query_indices {
-            let sem = semaphore.clone();
-            let params = params.clone();
-            let query = &queries[idx];
-
-            futures.push(async move {
-                let _permit = sem.acquire_owned().await.unwrap();
-                let start = Instant::now();
-                let result = match mode {
-                    SearchMode::Vector => provider.vector_search(&query.vector, &params).await,
-                    SearchMode::Hybrid => {
-                        provider
-                            .hybrid_search(&query.text, &query.vector, &params)
-                            .await
-                    }
+        // Visit the queries in a fresh order each burst so providers can't
+        // lean on a predictable sequence
+        let order: Option<Vec<usize>> = self.config.shuffle.then(|| {
+            let mut order: Vec<usize> = (0..queries.len()).collect();
+            order.shuffle(&mut self.shuffle_rng);
+            self.shuffles += 1;
+            order
+        });
+        let nth_query = |i: usize| {
+            let i = (query_offset + i) % queries.len();
+            order.as_ref().map_or(i, |order| order[i])
+        };
+
+        let burst_size = self.config.burst_size;
+        let batch_size = self.config.batch_size;
+        let mut requests: Vec<Request<'_>> = Vec::with_capacity(burst_size);
+        let mut searches = 0;
+        let mut batches = 0;
+        // Upserts count as one towards the burst size, searches count per query
+        let mut planned = 0;
+        while planned < burst_size {
+            planned += 1;
+
+            if let Some(ingest) = &self.config.ingest {
+                let documents = match &self.corpus {
+                    Some(corpus) => corpus
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .take(ingest.batch_size)?,
+                    None => synthetic::documents(
+                        queries,
+                        ingest.batch_size,
+                        ingest.id_format,
+                        &mut self.rng,
+                    ),
                 };
-                let latency = start.elapsed();
-                (result, latency, query.text.clone())
+                requests.push(Request::Upsert {
+                    documents,
+                    timeout_ms: self.config.timeout_ms,
+                });
+                continue;
+            }
+
+            if let Some(mixed) = &self.config.mixed
+                && self.rng.random_ratio(mixed.write, mixed.read + mixed.write)
+            {
+                requests.push(Request::Upsert {
+                    documents: synthetic::documents(
+                        queries,
+                        mixed.batch_size,
+                        mixed.id_format,
+                        &mut self.rng,
+                    ),
+                    timeout_ms: self.config.timeout_ms,
+                });
+                continue;
+            }
+
+            if batch_size > 1 {
+                let n = batch_size.min(burst_size - planned + 1);
+                planned += n - 1;
+                let slot = batches % slots;
+                batches += 1;
+                let indices: Vec<usize> = (searches..searches + n).map(nth_query).collect();
+                let batch: Vec<&EmbeddedQuery> = indices.iter().map(|&i| &queries[i]).collect();
+                searches += n;
+                requests.push(Request::BatchSearch {
+                    keys: indices.iter().map(|i| i * slots + slot).collect(),
+                    vectors: batch
+                        .iter()
+                        .map(|q| {
+                            q.named_vectors
+                                .get(slot)
+                                .map_or(q.vector.as_slice(), |named| named.vector.as_slice())
+                        })
+                        .collect(),
+                    queries: batch,
+                    params: named_params.get(slot).unwrap_or(&params).clone(),
+                });
+                continue;
+            }
+
+            let index = nth_query(searches / slots);
+            let query = &queries[index];
+            let slot = searches % slots;
+            let key = index * slots + slot;
+            searches += 1;
+            let (vector, params) = match query.named_vectors.get(slot) {
+                Some(named) => (named.vector.as_slice(), named_params[slot].clone()),
+                None => (query.vector.as_slice(), params.clone()),
+            };
+            requests.push(match &self.config.paginate {
+                Some(paginate) => Request::Paginate {
+                    query,
+                    key,
+                    vector,
+                    params,
+                    depth: paginate.depth,
+                },
+                None => Request::Search {
+                    query,
+                    key,
+                    vector,
+                    params,
+                },
             });
         }
 
-        // Phase 2: collect all results
-        let mut results = Vec::with_capacity(self.config.burst_size);
-        while let Some(item) = futures.next().await {
-            results.push(item);
-        }
-        drop(futures);
+        self.metrics.start_burst();
+        let samples = Mutex::new(Vec::new());
+        let failures = Mutex::new(Vec::new());
+        let recording = BurstRecording {
+            metrics: self.metrics.recorder().expect("burst just started"),
+            history: &self.history,
+            events: self.request_events.as_ref().unwrap_or(&self.events),
+            top_k,
+            started: Utc::now(),
+            index: self.metrics.bursts().len(),
+            samples: (self.sample_log.is_some() || self.slow_log.is_some()).then_some(&samples),
+            failures: self.failure_log.is_some().then_some(&failures),
+        };
 
-        // Phase 3: record metrics (requires &mut self.metrics, now safe)
-        for (result, latency, query_text) in results {
-            match result {
-                Ok(search_results) => {
-                    self.metrics.record_success(latency, None);
-                    debug!(
-                        latency_ms = latency.as_millis(),
-                        hits = search_results.results.len(),
-                        query = %query_text,
-                        "Query succeeded"
-                    );
-                }
-                Err(e) => {
-                    self.metrics.record_failure(latency);
-                    warn!(error = %e, latency_ms = latency.as_millis(), "Query failed");
-                }
+        // Each request records itself as it completes
+        let dispatch = match &mut self.load {
+            Some(profile) => profile
+                .get_mut()
+                .unwrap_or_else(|e| e.into_inner())
+                .dispatch(requests.len(), &mut self.rng)?,
+            None => pipeline::configured(&self.config, requests.len(), &mut self.rng)?,
+        };
+        let rerank = self.rerank.as_ref();
+        match dispatch {
+            Dispatch::Concurrent(concurrency) => {
+                run_closed_loop(provider, mode, rerank, requests, concurrency, &recording).await
+            }
+            Dispatch::Scheduled(offsets) => {
+                let peak_in_flight =
+                    run_open_loop(provider, mode, rerank, requests, offsets, &recording).await;
+                self.metrics.record_in_flight(peak_in_flight);
             }
         }
+        let samples = samples.into_inner().unwrap_or_else(|e| e.into_inner());
+        let failures = failures.into_inner().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(log) = &self.sample_log {
+            log.write(&samples)?;
+        }
+        if let Some(log) = &self.slow_log {
+            log.write(&samples)?;
+        }
+        if let Some(log) = &self.failure_log {
+            log.record(failures);
+        }
+
+        if let Some(phase) = phase {
+            self.metrics.record_phase(phase);
+        }
 
         self.metrics
             .finish_burst()
             .ok_or_else(|| crate::error::Error::Config("No burst in progress".into()))
     }
 
+    /// Enter the next scenario phase once the current one is over, running
+    /// its warmup. Phases that only warm up are passed straight through.
+    async fn step_scenario(&mut self) -> Result<()> {
+        while let Some((index, phase, config)) =
+            self.scenario.as_mut().and_then(ScenarioState::advance)
+        {
+            info!(phase = %phase.label(index), "Starting scenario phase");
+            self.config = config;
+            if let Some(iterations) = phase.warmup {
+                self.warmup_iterations(iterations).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// True the first time this is called after every phase of the configured
+    /// scenario has run. Later bursts keep the last phase's settings.
+    pub fn take_scenario_complete(&mut self) -> bool {
+        self.scenario
+            .as_mut()
+            .is_some_and(ScenarioState::take_complete)
+    }
+
+    /// True once what's left of `max_total_queries` can't pay for another
+    /// search
+    pub fn budget_exhausted(&self) -> bool {
+        self.budget_left()
+            .is_some_and(|left| left < self.requests_per_search())
+    }
+
+    /// Requests `max_total_queries` still allows, if it's set
+    fn budget_left(&self) -> Option<usize> {
+        self.config
+            .max_total_queries
+            .map(|limit| limit.saturating_sub(self.queries_sent))
+    }
+
+    /// Requests a single search in a burst can send, for sizing the last one
+    fn requests_per_search(&self) -> usize {
+        self.config
+            .paginate
+            .as_ref()
+            .map_or(1, |p| p.depth.div_ceil(self.config.top_k.max(1)).max(1))
+    }
+
+    /// Why the run should end, if a configured stop condition has been met
+    /// since the last call
+    pub fn take_stop_reason(&mut self) -> Option<String> {
+        self.stop_reason.take()
+    }
+
+    /// Run `bursts` bursts at the given concurrency and summarize them as one
+    /// point of a sweep. Metrics from earlier bursts are left untouched.
+    pub async fn run_sweep_step(
+        &mut self,
+        concurrency: usize,
+        bursts: usize,
+    ) -> Result<SweepPoint> {
+        if !matches!(self.config.load, LoadConfig::ClosedLoop) {
+            return Err(Error::Config(
+                "Concurrency sweeps require closed-loop load".into(),
+            ));
+        }
+        if concurrency == 0 {
+            return Err(Error::Config("Sweep concurrency must be at least 1".into()));
+        }
+
+        let previous_concurrency = std::mem::replace(&mut self.config.concurrency, concurrency);
+        let step_metrics = self.run_step(bursts).await;
+        self.config.concurrency = previous_concurrency;
+        let step_metrics = step_metrics?;
+
+        let query_count = step_metrics.total_queries();
+        let success_count = step_metrics.total_successes();
+        Ok(SweepPoint {
+            concurrency,
+            bursts,
+            query_count,
+            success_count,
+            failure_count: query_count - success_count,
+            qps: step_metrics.average_qps(),
+            latency: step_metrics.aggregate_latency(),
+            recall_at_k: step_metrics.average_recall(),
+        })
+    }
+
+    /// Run `bursts` bursts sending requests at a constant `qps`, keeping the
+    /// arrival process if one is configured. Returns just those bursts'
+    /// metrics; metrics from earlier bursts are left untouched.
+    pub async fn run_rate_step(&mut self, qps: f64, bursts: usize) -> Result<Metrics> {
+        let arrival = match self.config.load {
+            LoadConfig::ConstantRate { arrival, .. } => arrival,
+            LoadConfig::ClosedLoop => ArrivalProcess::default(),
+        };
+        let previous_load = std::mem::replace(
+            &mut self.config.load,
+            LoadConfig::ConstantRate { qps, arrival },
+        );
+        let step_metrics = self.run_step(bursts).await;
+        self.config.load = previous_load;
+        step_metrics
+    }
+
+    /// Run `bursts` bursts into a fresh set of metrics and return them
+    async fn run_step(&mut self, bursts: usize) -> Result<Metrics> {
+        if self.load.is_some() {
+            return Err(Error::Config(
+                "Sweeps set the load themselves and can't run with a load profile".into(),
+            ));
+        }
+        let previous_metrics = std::mem::take(&mut self.metrics);
+
+        let mut result = Ok(());
+        for _ in 0..bursts {
+            if let Err(e) = self.run_burst().await {
+                result = Err(e);
+                break;
+            }
+        }
+
+        let step_metrics = std::mem::replace(&mut self.metrics, previous_metrics);
+        result.map(|()| step_metrics)
+    }
+
     /// Dispatch a query based on the configured search mode
     async fn execute_query(
         &self,
         query: &EmbeddedQuery,
         params: &SearchParams,
     ) -> crate::error::Result<crate::types::SearchResults> {
-        match self.config.mode {
-            SearchMode::Vector => self.provider.vector_search(&query.vector, params).await,
-            SearchMode::Hybrid => {
-                self.provider
-                    .hybrid_search(&query.text, &query.vector, params)
-                    .await
+        self.execute_query_in_mode(query, self.config.mode, params)
+            .await
+    }
+
+    /// Search with the query's primary vector. With named vectors that's a
+    /// copy of the first one, so it goes to that vector's field rather than
+    /// the provider's default.
+    async fn execute_query_in_mode(
+        &self,
+        query: &EmbeddedQuery,
+        mode: SearchMode,
+        params: &SearchParams,
+    ) -> Result<SearchResults> {
+        let named;
+        let params = match query.named_vectors.first() {
+            Some(first) if params.vector_name.is_none() => {
+                named = SearchParams {
+                    vector_name: Some(first.name.clone()),
+                    ..params.clone()
+                };
+                &named
             }
-        }
+            _ => params,
+        };
+        dispatch(&*self.provider, mode, &query.text, &query.vector, params).await
     }
 
     /// Get reference to collected metrics
@@ -200,16 +1201,42 @@ impl BenchmarkRunner {
         };
 
         let results = self.execute_query(query, &params).await?;
-        Ok((query.text.clone(), results))
+        Ok((query.text.to_string(), results))
     }
 
-    /// Execute a single sample query with payloads included (for result inspection)
-    pub async fn run_sample_query(&self) -> Result<(String, crate::types::SearchResults)> {
+    /// Execute a query in `mode` instead of the configured one, with payloads
+    /// included (for comparing how the modes rank results)
+    pub async fn run_query_in_mode(
+        &self,
+        query: &EmbeddedQuery,
+        mode: SearchMode,
+    ) -> Result<crate::types::SearchResults> {
+        let params = SearchParams {
+            top_k: self.config.top_k,
+            timeout_ms: self.config.timeout_ms,
+            include_payload: true,
+            ..Default::default()
+        };
+
+        self.execute_query_in_mode(query, mode, &params).await
+    }
+
+    /// Execute the query at `index` in the loaded set with payloads included
+    /// (for result inspection)
+    pub async fn run_sample_query(
+        &self,
+        index: usize,
+    ) -> Result<(String, crate::types::SearchResults)> {
         if self.queries.is_empty() {
             return Err(crate::error::Error::Config("No queries configured".into()));
         }
 
-        let query = &self.queries[0];
+        let query = self.queries.get(index).ok_or_else(|| {
+            crate::error::Error::Config(format!(
+                "Query {index} is out of range ({} loaded)",
+                self.queries.len()
+            ))
+        })?;
         let params = SearchParams {
             top_k: self.config.top_k,
             timeout_ms: self.config.timeout_ms,
@@ -218,6 +1245,1033 @@ impl BenchmarkRunner {
         };
 
         let results = self.execute_query(query, &params).await?;
-        Ok((query.text.clone(), results))
+        Ok((query.text.to_string(), results))
+    }
+}
+
+/// Where a burst's requests are recorded as each one completes. Every
+/// request gets its own clone, so the metrics shard it writes to is its own.
+#[derive(Clone)]
+struct BurstRecording<'r> {
+    metrics: Recorder,
+    history: &'r ResultHistory,
+    /// Where request events go
+    events: &'r EventBus,
+    top_k: usize,
+    /// When the burst started, and its run-wide index
+    started: DateTime<Utc>,
+    index: usize,
+    /// Samples and failures kept for the logs, if any are open
+    samples: Option<&'r Mutex<Vec<Sample>>>,
+    failures: Option<&'r Mutex<Vec<Failure>>>,
+}
+
+impl BurstRecording<'_> {
+    fn record(&self, completed: Completed<'_>) {
+        let Completed {
+            outcome,
+            sent,
+            latency,
+        } = completed;
+        let sent_at = self.started + TimeDelta::from_std(sent).unwrap_or_default();
+        if let Some(samples) = self.samples {
+            let mut samples = samples.lock().unwrap_or_else(|e| e.into_inner());
+            push_samples(&mut samples, &outcome, sent_at, self.index, latency);
+        }
+        if let Some(failures) = self.failures {
+            let mut failures = failures.lock().unwrap_or_else(|e| e.into_inner());
+            push_failures(&mut failures, &outcome, sent_at);
+        }
+        if self.events.listening() {
+            emit_requests(self.events, &outcome, latency);
+        }
+
+        // Stage timings are recorded alongside the end-to-end latency below
+        if let Outcome::Search {
+            result,
+            rerank,
+            pages,
+            ..
+        } = &outcome
+        {
+            if let Some(rerank) = *rerank {
+                self.metrics
+                    .record_rerank(latency.saturating_sub(rerank), rerank, result.is_ok());
+            }
+            if !pages.is_empty() {
+                self.metrics.record_pages(self.top_k, pages);
+            }
+            // The engine only saw the retrieval, not the rerank
+            if let Some(took_us) = result.as_ref().ok().and_then(|r| r.took_us) {
+                let retrieval = latency.saturating_sub(rerank.unwrap_or_default());
+                self.metrics.record_server_time(retrieval, took_us);
+            }
+            if let Some(status) = response_status(result) {
+                self.metrics.record_status(status);
+            }
+        }
+        if let Outcome::BatchSearch {
+            result, queries, ..
+        } = &outcome
+        {
+            match result {
+                Ok(batch) => {
+                    for status in batch.iter().filter_map(|r| r.status) {
+                        self.metrics.record_status(status);
+                    }
+                }
+                Err(e) => {
+                    if let Some(status) = e.status() {
+                        for _ in queries {
+                            self.metrics.record_status(status);
+                        }
+                    }
+                }
+            }
+        }
+
+        match outcome {
+            Outcome::Search {
+                result: Ok(search_results),
+                query,
+                key,
+                ..
+            } => {
+                let judgment = query.judge(&search_results.ids(), self.top_k);
+                self.metrics
+                    .record_success(latency, judgment.map(|j| j.recall));
+                self.metrics
+                    .record_tags(&query.tags, latency, true, judgment.map(|j| j.recall));
+                self.metrics.record_results(&search_results.results);
+                if let Some(j) = judgment {
+                    self.metrics.record_ranking(j.ndcg, j.reciprocal_rank);
+                }
+                if let Some(overlap) = self.history.observe(key, &search_results) {
+                    self.metrics.record_stability(overlap);
+                }
+                debug!(
+                    latency_ms = latency.as_millis(),
+                    hits = search_results.results.len(),
+                    query = %query.text,
+                    "Query succeeded"
+                );
+            }
+            Outcome::Search {
+                result: Err(Error::Timeout(timeout_ms)),
+                query,
+                ..
+            } => {
+                self.metrics.record_timeout(latency);
+                self.metrics.record_tags(&query.tags, latency, false, None);
+                debug!(timeout_ms, query = %query.text, "Query timed out");
+            }
+            Outcome::Search {
+                result: Err(e),
+                query,
+                ..
+            } => {
+                self.metrics.record_failure(latency);
+                self.metrics.record_tags(&query.tags, latency, false, None);
+                warn!(error = %e, latency_ms = latency.as_millis(), "Query failed");
+            }
+            Outcome::BatchSearch {
+                result: Ok(batch_results),
+                queries,
+                keys,
+            } => {
+                let answered = batch_results.len();
+                for ((query, key), search_results) in queries.iter().zip(keys).zip(&batch_results) {
+                    let judgment = query.judge(&search_results.ids(), self.top_k);
+                    self.metrics
+                        .record_success(latency, judgment.map(|j| j.recall));
+                    self.metrics.record_tags(
+                        &query.tags,
+                        latency,
+                        true,
+                        judgment.map(|j| j.recall),
+                    );
+                    self.metrics.record_results(&search_results.results);
+                    if let Some(j) = judgment {
+                        self.metrics.record_ranking(j.ndcg, j.reciprocal_rank);
+                    }
+                    if let Some(took_us) = search_results.took_us {
+                        self.metrics.record_server_time(latency, took_us);
+                    }
+                    if let Some(overlap) = self.history.observe(key, search_results) {
+                        self.metrics.record_stability(overlap);
+                    }
+                }
+                // A short response leaves the remaining queries unanswered
+                for query in queries.iter().skip(answered) {
+                    self.metrics.record_failure(latency);
+                    self.metrics.record_tags(&query.tags, latency, false, None);
+                }
+                debug!(
+                    latency_ms = latency.as_millis(),
+                    queries = queries.len(),
+                    answered,
+                    "Batch search succeeded"
+                );
+            }
+            Outcome::BatchSearch {
+                result: Err(Error::Timeout(timeout_ms)),
+                queries,
+                ..
+            } => {
+                for query in &queries {
+                    self.metrics.record_timeout(latency);
+                    self.metrics.record_tags(&query.tags, latency, false, None);
+                }
+                debug!(
+                    timeout_ms,
+                    queries = queries.len(),
+                    "Batch search timed out"
+                );
+            }
+            Outcome::BatchSearch {
+                result: Err(e),
+                queries,
+                ..
+            } => {
+                for query in &queries {
+                    self.metrics.record_failure(latency);
+                    self.metrics.record_tags(&query.tags, latency, false, None);
+                }
+                warn!(error = %e, latency_ms = latency.as_millis(), "Batch search failed");
+            }
+            Outcome::Upsert { result, documents } => {
+                if let Err(e) = &result {
+                    warn!(error = %e, latency_ms = latency.as_millis(), "Upsert failed");
+                }
+                self.metrics
+                    .record_write(latency, documents, result.is_ok());
+            }
+        }
+    }
+}
+
+/// IDs each query last returned, for measuring how much results drift
+/// between bursts
+#[derive(Default)]
+struct ResultHistory {
+    /// Hashes of the IDs, so results aren't copied on every request
+    last: Mutex<HashMap<usize, Vec<u64>>>,
+    hasher: RandomState,
+}
+
+impl ResultHistory {
+    /// Remember the IDs in `results` for `key`, returning their Jaccard
+    /// overlap with the previous results for it
+    fn observe(&self, key: usize, results: &SearchResults) -> Option<f64> {
+        let ids: Vec<u64> = results
+            .results
+            .iter()
+            .map(|r| self.hasher.hash_one(&r.id))
+            .collect();
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        let previous = last.insert(key, ids)?;
+        Some(jaccard(&previous, &last[&key]))
+    }
+}
+
+/// One request to send during a burst. Searches carry a key identifying
+/// the query and vector field, so their results can be compared across bursts.
+enum Request<'a> {
+    Search {
+        query: &'a EmbeddedQuery,
+        key: usize,
+        vector: &'a [f32],
+        params: Arc<SearchParams>,
+    },
+    /// One query paged through its results, `params.top_k` at a time, until
+    /// `depth` results have been read
+    Paginate {
+        query: &'a EmbeddedQuery,
+        key: usize,
+        vector: &'a [f32],
+        params: Arc<SearchParams>,
+        depth: usize,
+    },
+    /// Several queries sent in one request, all against the same vector field
+    BatchSearch {
+        queries: Vec<&'a EmbeddedQuery>,
+        keys: Vec<usize>,
+        vectors: Vec<&'a [f32]>,
+        params: Arc<SearchParams>,
+    },
+    Upsert {
+        documents: Vec<Document>,
+        timeout_ms: u64,
+    },
+}
+
+/// Result of one request, before it is recorded in the metrics
+enum Outcome<'a> {
+    Search {
+        result: Result<SearchResults>,
+        query: &'a EmbeddedQuery,
+        key: usize,
+        /// Time spent in the rerank stage, if the search got that far
+        rerank: Option<Duration>,
+        /// Latency of each page request, for paginated searches
+        pages: Vec<Duration>,
+    },
+    BatchSearch {
+        result: Result<Vec<SearchResults>>,
+        queries: Vec<&'a EmbeddedQuery>,
+        keys: Vec<usize>,
+    },
+    Upsert {
+        result: Result<()>,
+        documents: usize,
+    },
+}
+
+/// A finished request: what came back, when it was sent relative to the
+/// start of the burst, and how long it took
+struct Completed<'a> {
+    outcome: Outcome<'a>,
+    sent: Duration,
+    latency: Duration,
+}
+
+impl<'a> Request<'a> {
+    async fn send(
+        self,
+        provider: &dyn SearchProvider,
+        mode: SearchMode,
+        rerank: Option<&RerankStage>,
+    ) -> Outcome<'a> {
+        match self {
+            Request::Search {
+                query,
+                key,
+                vector,
+                params,
+            } => {
+                let result = dispatch(provider, mode, &query.text, vector, &params).await;
+                match (result, rerank) {
+                    (Ok(results), Some(stage)) => {
+                        let start = Instant::now();
+                        let result = stage.apply(&query.text, results).await;
+                        Outcome::Search {
+                            result,
+                            query,
+                            key,
+                            rerank: Some(start.elapsed()),
+                            pages: Vec::new(),
+                        }
+                    }
+                    (result, _) => Outcome::Search {
+                        result,
+                        query,
+                        key,
+                        rerank: None,
+                        pages: Vec::new(),
+                    },
+                }
+            }
+            Request::Paginate {
+                query,
+                key,
+                vector,
+                params,
+                depth,
+            } => {
+                let (result, pages) = paginate(provider, mode, query, vector, &params, depth).await;
+                Outcome::Search {
+                    result,
+                    query,
+                    key,
+                    rerank: None,
+                    pages,
+                }
+            }
+            Request::BatchSearch {
+                queries,
+                keys,
+                vectors,
+                params,
+            } => Outcome::BatchSearch {
+                result: with_timeout(params.timeout_ms, provider.batch_search(&vectors, &params))
+                    .await,
+                queries,
+                keys,
+            },
+            Request::Upsert {
+                documents,
+                timeout_ms,
+            } => Outcome::Upsert {
+                result: with_timeout(timeout_ms, provider.upsert(&documents)).await,
+                documents: documents.len(),
+            },
+        }
+    }
+}
+
+/// HTTP status a search was answered with, if it got an HTTP response
+fn response_status(result: &Result<SearchResults>) -> Option<u16> {
+    match result {
+        Ok(results) => results.status,
+        Err(e) => e.status(),
+    }
+}
+
+/// Append the failures among one finished request; a failed batched search
+/// fails every query in it
+fn push_failures(failures: &mut Vec<Failure>, outcome: &Outcome<'_>, timestamp: DateTime<Utc>) {
+    let failure = |kind, error: &Error, query: Option<&EmbeddedQuery>| Failure {
+        timestamp,
+        kind,
+        class: error.class(),
+        message: error.to_string(),
+        retryable: error.is_retryable(),
+        query: query.map(|q| q.text.clone()),
+    };
+
+    match outcome {
+        Outcome::Search {
+            result: Err(e),
+            query,
+            ..
+        } => failures.push(failure(RequestKind::Search, e, Some(query))),
+        Outcome::Search { .. } => {}
+        Outcome::BatchSearch {
+            result, queries, ..
+        } => {
+            // A short response leaves the remaining queries unanswered
+            let unanswered = Error::InvalidResponse("missing from the batch response".into());
+            let (error, failed) = match result {
+                Ok(results) => (
+                    &unanswered,
+                    queries.get(results.len()..).unwrap_or_default(),
+                ),
+                Err(e) => (e, &queries[..]),
+            };
+            for &query in failed {
+                failures.push(failure(RequestKind::BatchSearch, error, Some(query)));
+            }
+        }
+        Outcome::Upsert { result: Err(e), .. } => {
+            failures.push(failure(RequestKind::Upsert, e, None))
+        }
+        Outcome::Upsert { .. } => {}
+    }
+}
+
+/// Append the raw samples for one finished request; batched searches give
+/// one sample per query
+fn push_samples(
+    samples: &mut Vec<Sample>,
+    outcome: &Outcome<'_>,
+    timestamp: DateTime<Utc>,
+    burst: usize,
+    latency: Duration,
+) {
+    let sample = |kind, result: std::result::Result<Option<u64>, &Error>| {
+        let (status, error) = status_of(result.map(|_| ()));
+        Sample {
+            timestamp,
+            burst,
+            kind,
+            status,
+            latency_us: latency.as_micros() as u64,
+            took_us: result.ok().flatten(),
+            query: None,
+            text: None,
+            documents: None,
+            rerank_us: None,
+            pages: None,
+            error,
+        }
+    };
+    let label = |query: &EmbeddedQuery| Some(query.id.as_ref().unwrap_or(&query.text).clone());
+    let text = |query: &EmbeddedQuery| query.id.is_some().then(|| query.text.clone());
+
+    match outcome {
+        Outcome::Search {
+            result,
+            query,
+            rerank,
+            pages,
+            ..
+        } => samples.push(Sample {
+            query: label(query),
+            text: text(query),
+            rerank_us: rerank.map(|d| d.as_micros() as u64),
+            pages: (!pages.is_empty()).then_some(pages.len()),
+            ..sample(
+                RequestKind::Search,
+                result.as_ref().map(|results| results.took_us),
+            )
+        }),
+        Outcome::BatchSearch {
+            result, queries, ..
+        } => {
+            // A short response leaves the remaining queries unanswered
+            let unanswered = Error::InvalidResponse("missing from the batch response".into());
+            for (i, query) in queries.iter().enumerate() {
+                let result = match result {
+                    Ok(results) => results.get(i).map(|r| r.took_us).ok_or(&unanswered),
+                    Err(e) => Err(e),
+                };
+                samples.push(Sample {
+                    query: label(query),
+                    text: text(query),
+                    ..sample(RequestKind::BatchSearch, result)
+                });
+            }
+        }
+        Outcome::Upsert { result, documents } => samples.push(Sample {
+            documents: Some(*documents),
+            ..sample(RequestKind::Upsert, result.as_ref().map(|_| None))
+        }),
+    }
+}
+
+/// Tell listeners about one finished request; batched searches give one
+/// event per query
+fn emit_requests(events: &EventBus, outcome: &Outcome<'_>, latency: Duration) {
+    let emit = |kind, result: std::result::Result<(), &Error>| {
+        let (status, error) = status_of(result);
+        events.emit(|| RunnerEvent::RequestCompleted {
+            kind,
+            status,
+            latency,
+            error,
+        });
+    };
+
+    match outcome {
+        Outcome::Search { result, .. } => {
+            emit(RequestKind::Search, result.as_ref().map(|_| ()));
+        }
+        Outcome::BatchSearch {
+            result, queries, ..
+        } => {
+            // A short response leaves the remaining queries unanswered
+            let unanswered = Error::InvalidResponse("missing from the batch response".into());
+            for i in 0..queries.len() {
+                let result = match result {
+                    Ok(results) if i < results.len() => Ok(()),
+                    Ok(_) => Err(&unanswered),
+                    Err(e) => Err(e),
+                };
+                emit(RequestKind::BatchSearch, result);
+            }
+        }
+        Outcome::Upsert { result, .. } => {
+            emit(RequestKind::Upsert, result.as_ref().map(|_| ()));
+        }
+    }
+}
+
+/// How a request ended, and the error if it failed without timing out
+fn status_of(result: std::result::Result<(), &Error>) -> (SampleStatus, Option<String>) {
+    match result {
+        Ok(()) => (SampleStatus::Ok, None),
+        Err(Error::Timeout(_)) => (SampleStatus::Timeout, None),
+        Err(e) => (SampleStatus::Error, Some(e.to_string())),
+    }
+}
+
+/// Run one search, abandoning it once `params.timeout_ms` elapses
+async fn dispatch(
+    provider: &dyn SearchProvider,
+    mode: SearchMode,
+    text: &str,
+    vector: &[f32],
+    params: &SearchParams,
+) -> Result<SearchResults> {
+    with_timeout(params.timeout_ms, async {
+        match mode {
+            SearchMode::Vector => provider.vector_search(vector, params).await,
+            SearchMode::Hybrid => provider.hybrid_search(text, vector, params).await,
+        }
+    })
+    .await
+}
+
+/// Fetch pages of results one after another until `depth` results have
+/// been read or a short page shows there are no more. Returns every result
+/// read along with each page request's latency.
+async fn paginate(
+    provider: &dyn SearchProvider,
+    mode: SearchMode,
+    query: &EmbeddedQuery,
+    vector: &[f32],
+    params: &SearchParams,
+    depth: usize,
+) -> (Result<SearchResults>, Vec<Duration>) {
+    let mut results = Vec::new();
+    let mut took_us = Some(0);
+    let mut pages = Vec::new();
+    let mut page_params = params.clone();
+
+    while page_params.offset < depth {
+        page_params.top_k = params.top_k.min(depth - page_params.offset);
+        let start = Instant::now();
+        let page = dispatch(provider, mode, &query.text, vector, &page_params).await;
+        pages.push(start.elapsed());
+
+        let page = match page {
+            Ok(page) => page,
+            Err(e) => return (Err(e), pages),
+        };
+        // Only report engine time if every page did
+        took_us = took_us.zip(page.took_us).map(|(total, took)| total + took);
+        let exhausted = page.results.len() < page_params.top_k;
+        results.extend(page.results);
+        if exhausted {
+            break;
+        }
+        page_params.offset += page_params.top_k;
+    }
+
+    let mut results = SearchResults::new(results);
+    results.took_us = took_us;
+    (Ok(results), pages)
+}
+
+/// Fail with `Error::Timeout` if `request` takes longer than `timeout_ms` (0 waits forever)
+async fn with_timeout<T>(timeout_ms: u64, request: impl Future<Output = Result<T>>) -> Result<T> {
+    if timeout_ms == 0 {
+        return request.await;
+    }
+    tokio::time::timeout(Duration::from_millis(timeout_ms), request)
+        .await
+        .unwrap_or(Err(Error::Timeout(timeout_ms)))
+}
+
+/// Send every request with at most `concurrency` outstanding at once,
+/// recording each as it completes
+async fn run_closed_loop(
+    provider: &dyn SearchProvider,
+    mode: SearchMode,
+    rerank: Option<&RerankStage>,
+    requests: Vec<Request<'_>>,
+    concurrency: usize,
+    recording: &BurstRecording<'_>,
+) {
+    let semaphore = Arc::new(Semaphore::new(concurrency));
+    let burst_start = Instant::now();
+
+    let mut futures = FuturesUnordered::new();
+    for request in requests {
+        let sem = semaphore.clone();
+        let recording = recording.clone();
+        futures.push(async move {
+            let _permit = sem.acquire_owned().await.unwrap();
+            let start = Instant::now();
+            let outcome = request.send(provider, mode, rerank).await;
+            recording.record(Completed {
+                outcome,
+                sent: start - burst_start,
+                latency: start.elapsed(),
+            });
+        });
+    }
+
+    while futures.next().await.is_some() {}
+}
+
+/// Send each request at its offset from now without waiting for earlier ones to finish,
+/// recording each as it completes.
+///
+/// Returns the peak number of requests in flight.
+async fn run_open_loop(
+    provider: &dyn SearchProvider,
+    mode: SearchMode,
+    rerank: Option<&RerankStage>,
+    requests: Vec<Request<'_>>,
+    offsets: Vec<Duration>,
+    recording: &BurstRecording<'_>,
+) -> usize {
+    let mut in_flight = FuturesUnordered::new();
+    let mut peak_in_flight = 0;
+    let start = tokio::time::Instant::now();
+
+    for (request, offset) in requests.into_iter().zip(offsets) {
+        let scheduled = start + offset;
+
+        // Drive requests in flight while waiting for the next send time
+        loop {
+            tokio::select! {
+                biased;
+                _ = sleep_until(scheduled) => break,
+                Some(()) = in_flight.next(), if !in_flight.is_empty() => {}
+            }
+        }
+
+        let recording = recording.clone();
+        in_flight.push(async move {
+            let outcome = request.send(provider, mode, rerank).await;
+            // Measured from the scheduled send time so a late dispatch counts
+            // against latency rather than vanishing
+            recording.record(Completed {
+                outcome,
+                sent: offset,
+                latency: scheduled.elapsed(),
+            });
+        });
+        peak_in_flight = peak_in_flight.max(in_flight.len());
+    }
+
+    while in_flight.next().await.is_some() {}
+
+    peak_in_flight
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use crate::config::{MockConfig, MockLatency, PaginateConfig, RecallProbeConfig};
+    use crate::provider::Capabilities;
+    use crate::providers::MockProvider;
+    use crate::queries::NamedVector;
+
+    use super::*;
+
+    fn mock(latency_ms: f64, error_rate: f64) -> MockProvider {
+        MockProvider::new(
+            "mock".into(),
+            MockConfig {
+                latency: MockLatency::Fixed { ms: latency_ms },
+                error_rate,
+                dimension: None,
+                documents: 10,
+            },
+        )
+    }
+
+    /// Records the vector field each search asked for
+    struct Fields {
+        inner: MockProvider,
+        searched: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    #[async_trait]
+    impl SearchProvider for Fields {
+        fn name(&self) -> &str {
+            self.inner.name()
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            self.inner.capabilities()
+        }
+
+        async fn connect(&mut self) -> Result<()> {
+            self.inner.connect().await
+        }
+
+        async fn disconnect(&mut self) -> Result<()> {
+            self.inner.disconnect().await
+        }
+
+        async fn health_check(&self) -> Result<bool> {
+            self.inner.health_check().await
+        }
+
+        async fn vector_search(
+            &self,
+            vector: &[f32],
+            params: &SearchParams,
+        ) -> Result<SearchResults> {
+            self.searched
+                .lock()
+                .unwrap()
+                .push(params.vector_name.clone());
+            self.inner.vector_search(vector, params).await
+        }
+    }
+
+    /// Records which query each search was for, by its vector's first value
+    struct Order {
+        inner: MockProvider,
+        searched: Arc<Mutex<Vec<f32>>>,
+    }
+
+    #[async_trait]
+    impl SearchProvider for Order {
+        fn name(&self) -> &str {
+            self.inner.name()
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            self.inner.capabilities()
+        }
+
+        async fn connect(&mut self) -> Result<()> {
+            self.inner.connect().await
+        }
+
+        async fn disconnect(&mut self) -> Result<()> {
+            self.inner.disconnect().await
+        }
+
+        async fn health_check(&self) -> Result<bool> {
+            self.inner.health_check().await
+        }
+
+        async fn vector_search(
+            &self,
+            vector: &[f32],
+            params: &SearchParams,
+        ) -> Result<SearchResults> {
+            self.searched.lock().unwrap().push(vector[0]);
+            self.inner.vector_search(vector, params).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_single_queries_search_the_first_named_vector() {
+        let searched = Arc::new(Mutex::new(Vec::new()));
+        let provider = Fields {
+            inner: mock(0.0, 0.0),
+            searched: Arc::clone(&searched),
+        };
+        let config = BenchmarkConfig {
+            warmup_iterations: 1,
+            ..Default::default()
+        };
+        let named = |name: &str| NamedVector {
+            name: name.into(),
+            vector: vec![0.1, 0.2],
+        };
+        let query = EmbeddedQuery {
+            named_vectors: vec![named("title"), named("body")],
+            ..EmbeddedQuery::new("chair".into(), vec![0.1, 0.2])
+        };
+        let mut runner = BenchmarkRunner::new(Box::new(provider), config).with_queries(vec![query]);
+        runner.connect().await.unwrap();
+
+        runner.warmup().await.unwrap();
+        runner.run_sample_query(0).await.unwrap();
+        runner
+            .run_query_in_mode(&runner.queries[0], SearchMode::Vector)
+            .await
+            .unwrap();
+        let title = Some("title".to_string());
+        assert_eq!(
+            *searched.lock().unwrap(),
+            [title.clone(), title.clone(), title]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_recall_probe() {
+        // Every search returns all ten documents
+        let mock = mock(0.0, 0.0);
+        let config = BenchmarkConfig {
+            warmup_iterations: 0,
+            burst_size: 3,
+            recall_probe: Some(RecallProbeConfig {
+                every_bursts: 2,
+                sample: 2,
+            }),
+            ..Default::default()
+        };
+        let query = |text: &str, expected: Option<&str>| EmbeddedQuery {
+            expected_ids: expected.map(|id| vec![id.to_string()]),
+            ..EmbeddedQuery::new(text.into(), vec![0.1, 0.2])
+        };
+        let mut runner = BenchmarkRunner::new(Box::new(mock), config).with_queries(vec![
+            query("found", Some("doc-1")),
+            query("unjudged", None),
+            query("missing", Some("doc-99")),
+        ]);
+        runner.connect().await.unwrap();
+
+        let first = runner.run_burst().await.unwrap();
+        let second = runner.run_burst().await.unwrap();
+        assert_eq!(first.probe_recall, None);
+        assert_eq!(second.probe_recall, Some(0.5));
+        assert_eq!(second.query_count, 3);
+    }
+
+    #[tokio::test]
+    async fn test_budget_counts_every_request() {
+        let config = BenchmarkConfig {
+            warmup_iterations: 3,
+            burst_size: 10,
+            max_total_queries: Some(25),
+            ..Default::default()
+        };
+        let mut runner = BenchmarkRunner::new(Box::new(mock(0.0, 0.0)), config)
+            .with_queries(vec![EmbeddedQuery::new("a".into(), vec![0.1, 0.2])]);
+        runner.connect().await.unwrap();
+        runner.warmup().await.unwrap();
+
+        let mut counts = Vec::new();
+        while !runner.budget_exhausted() {
+            counts.push(runner.run_burst().await.unwrap().query_count);
+        }
+        assert_eq!(counts, [10, 10, 2]);
+        assert!(matches!(
+            runner.run_burst().await,
+            Err(Error::BudgetExhausted(25))
+        ));
+
+        // Two pages of five results per query, so five requests pay for two
+        let config = BenchmarkConfig {
+            warmup_iterations: 0,
+            burst_size: 10,
+            top_k: 5,
+            paginate: Some(PaginateConfig { depth: 10 }),
+            max_total_queries: Some(5),
+            ..Default::default()
+        };
+        let mut runner = BenchmarkRunner::new(Box::new(mock(0.0, 0.0)), config)
+            .with_queries(vec![EmbeddedQuery::new("a".into(), vec![0.1, 0.2])]);
+        runner.connect().await.unwrap();
+        let burst = runner.run_burst().await.unwrap();
+        assert_eq!(burst.query_count, 2);
+        assert!(runner.budget_exhausted());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_open_loop_sends_on_schedule() {
+        let config = BenchmarkConfig {
+            warmup_iterations: 0,
+            burst_size: 10,
+            load: LoadConfig::ConstantRate {
+                qps: 10.0,
+                arrival: ArrivalProcess::Uniform,
+            },
+            ..Default::default()
+        };
+        let mut runner = BenchmarkRunner::new(Box::new(mock(250.0, 0.0)), config)
+            .with_queries(vec![EmbeddedQuery::new("a".into(), vec![0.1, 0.2])]);
+        runner.connect().await.unwrap();
+
+        let start = tokio::time::Instant::now();
+        let burst = runner.run_burst().await.unwrap();
+        // The last request goes out at 900ms, without waiting on earlier
+        // answers, and takes 250ms
+        assert_eq!(start.elapsed(), Duration::from_millis(1150));
+        assert_eq!(burst.success_count, 10);
+        assert_eq!(burst.peak_in_flight, Some(3));
+        // Measured from each scheduled send time
+        let latency = &burst.latency;
+        assert!((250_000..252_000).contains(&latency.p50_us), "{latency:?}");
+        assert!((250_000..252_000).contains(&latency.max_us), "{latency:?}");
+    }
+
+    /// How long a burst of 50 Poisson arrivals at 100 qps takes to send
+    async fn poisson_span(seed: u64) -> Duration {
+        let config = BenchmarkConfig {
+            warmup_iterations: 0,
+            burst_size: 50,
+            load: LoadConfig::ConstantRate {
+                qps: 100.0,
+                arrival: ArrivalProcess::Poisson,
+            },
+            ..Default::default()
+        };
+        let mut runner = BenchmarkRunner::new(Box::new(mock(0.0, 0.0)), config)
+            .with_queries(vec![EmbeddedQuery::new("a".into(), vec![0.1, 0.2])])
+            .with_seed(seed);
+        runner.connect().await.unwrap();
+        let start = tokio::time::Instant::now();
+        runner.run_burst().await.unwrap();
+        start.elapsed()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_poisson_arrivals_follow_the_seed() {
+        let span = poisson_span(1).await;
+        assert_eq!(poisson_span(1).await, span);
+        assert_ne!(poisson_span(2).await, span);
+        // Evenly paced sends would end at exactly 490ms
+        assert_ne!(span, Duration::from_millis(490));
+        assert!((0.2..1.0).contains(&span.as_secs_f64()), "{span:?}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_timeouts_are_counted_separately() {
+        let slow = async {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            Ok(())
+        };
+        assert!(matches!(
+            with_timeout(50, slow).await,
+            Err(Error::Timeout(50))
+        ));
+        // 0 waits however long the request takes
+        let slow = async {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            Ok(())
+        };
+        assert!(with_timeout(0, slow).await.is_ok());
+
+        let config = BenchmarkConfig {
+            warmup_iterations: 0,
+            burst_size: 4,
+            timeout_ms: 50,
+            ..Default::default()
+        };
+        let mut runner = BenchmarkRunner::new(Box::new(mock(100.0, 0.0)), config)
+            .with_queries(vec![EmbeddedQuery::new("a".into(), vec![0.1, 0.2])]);
+        runner.connect().await.unwrap();
+        let burst = runner.run_burst().await.unwrap();
+        assert_eq!(burst.failure_count, 4);
+        assert_eq!(burst.timeout_count, 4);
+    }
+
+    /// A connected runner shuffling eight queries, one search at a time
+    async fn shuffled(seed: u64) -> (BenchmarkRunner, Arc<Mutex<Vec<f32>>>) {
+        let searched = Arc::new(Mutex::new(Vec::new()));
+        let provider = Order {
+            inner: mock(0.0, 0.0),
+            searched: Arc::clone(&searched),
+        };
+        let config = BenchmarkConfig {
+            warmup_iterations: 0,
+            burst_size: 8,
+            concurrency: 1,
+            shuffle: true,
+            ..Default::default()
+        };
+        let queries = (0..8)
+            .map(|i| EmbeddedQuery::new(i.to_string(), vec![i as f32, 0.0]))
+            .collect::<Vec<_>>();
+        let mut runner = BenchmarkRunner::new(Box::new(provider), config)
+            .with_queries(queries)
+            .with_seed(seed);
+        runner.connect().await.unwrap();
+        (runner, searched)
+    }
+
+    #[tokio::test]
+    async fn test_shuffle_follows_the_seed_across_restore() {
+        let (mut runner, searched) = shuffled(7).await;
+        for _ in 0..3 {
+            runner.run_burst().await.unwrap();
+        }
+        let order = searched.lock().unwrap().clone();
+        let bursts: Vec<&[f32]> = order.chunks(8).collect();
+        assert_ne!(bursts[0], bursts[1]);
+        for burst in &bursts {
+            let mut sorted = burst.to_vec();
+            sorted.sort_by(f32::total_cmp);
+            assert_eq!(sorted, [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]);
+        }
+
+        let (mut again, searched) = shuffled(7).await;
+        for _ in 0..3 {
+            again.run_burst().await.unwrap();
+        }
+        assert_eq!(*searched.lock().unwrap(), order);
+
+        // A restored run draws the orders the uninterrupted one went on to
+        let (mut first, _) = shuffled(7).await;
+        first.run_burst().await.unwrap();
+        let (mut restored, searched) = shuffled(1).await;
+        restored.restore(first.state());
+        for _ in 0..2 {
+            restored.run_burst().await.unwrap();
+        }
+        assert_eq!(*searched.lock().unwrap(), order[8..]);
     }
 }
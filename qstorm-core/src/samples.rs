@@ -0,0 +1,490 @@
+//! Raw per-request samples for offline analysis.
+//!
+//! Every request sent during a burst can be logged as one JSON object per
+//! line, so latencies can be analysed beyond the summary percentiles and
+//! lined up against server-side traces by timestamp. The slow-query log
+//! keeps only the searches behind the tail, with their full text. Burst
+//! metrics can be logged the same way, optionally rotating to a new file by
+//! size or age so a long soak doesn't end up as one huge file. A path ending
+//! in `.parquet` is written as Parquet instead, with the `parquet` feature.
+//! Failed requests can also be kept in memory, for showing while a run goes.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "parquet")]
+use crate::columnar::{Columnar as Row, ParquetFile};
+use crate::config::SlowQueryConfig;
+use crate::error::{Error, Result};
+use crate::metrics::BurstMetrics;
+#[cfg(not(feature = "parquet"))]
+use serde::Serialize as Row;
+
+/// What kind of request a sample describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestKind {
+    Search,
+    /// One query out of a batched search request
+    BatchSearch,
+    Upsert,
+}
+
+/// How a request ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SampleStatus {
+    Ok,
+    Timeout,
+    Error,
+}
+
+/// A single request as observed by the client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sample {
+    /// When the request was sent (or scheduled, under an open-loop load)
+    pub timestamp: DateTime<Utc>,
+    /// Index of the burst the request belonged to
+    pub burst: usize,
+    pub kind: RequestKind,
+    pub status: SampleStatus,
+    /// Client-observed latency in microseconds
+    pub latency_us: u64,
+    /// Processing time reported by the engine in microseconds, when it
+    /// returns one. Written as `took_ms`, in fractional milliseconds.
+    #[serde(
+        default,
+        rename = "took_ms",
+        with = "crate::types::micros_as_millis",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub took_us: Option<u64>,
+    /// The query's id from the query file, or its text when it has none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<Arc<str>>,
+    /// The query's text, when `query` holds its id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<Arc<str>>,
+    /// Documents written by an upsert
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub documents: Option<usize>,
+    /// Of `latency_us`, the time spent in the rerank stage
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rerank_us: Option<u64>,
+    /// Page requests sent, for paginated searches
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pages: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// An output file, as NDJSON or Parquet depending on its extension
+enum Output {
+    Ndjson(BufWriter<File>),
+    #[cfg(feature = "parquet")]
+    Parquet(Box<ParquetFile>),
+}
+
+impl Output {
+    // Without the feature, `T` is only there to keep call sites the same
+    #[cfg_attr(not(feature = "parquet"), allow(clippy::extra_unused_type_parameters))]
+    fn create<T: Row>(path: &Path) -> Result<Self> {
+        let parquet = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("parquet"));
+        if !parquet {
+            return Ok(Self::Ndjson(BufWriter::new(File::create(path)?)));
+        }
+        #[cfg(feature = "parquet")]
+        return Ok(Self::Parquet(Box::new(ParquetFile::create(
+            path,
+            T::schema(),
+        )?)));
+        #[cfg(not(feature = "parquet"))]
+        Err(Error::Config(format!(
+            "{} needs qstorm built with the `parquet` feature",
+            path.display()
+        )))
+    }
+
+    /// Append rows; NDJSON is flushed to disk right away
+    fn write<T: Row>(&mut self, rows: &[T]) -> Result<()> {
+        match self {
+            Self::Ndjson(writer) => {
+                for row in rows {
+                    serde_json::to_writer(&mut *writer, row)?;
+                    writer.write_all(b"\n")?;
+                }
+                writer.flush()?;
+            }
+            #[cfg(feature = "parquet")]
+            Self::Parquet(file) => file.write(&T::batch(rows)?)?,
+        }
+        Ok(())
+    }
+
+    /// Size of the file so far, counting rows buffered for Parquet
+    fn size(&self) -> Result<u64> {
+        match self {
+            Self::Ndjson(writer) => Ok(writer.get_ref().metadata()?.len()),
+            #[cfg(feature = "parquet")]
+            Self::Parquet(file) => Ok(file.size()),
+        }
+    }
+
+    /// Flush the file, writing a Parquet footer
+    fn finish(self) -> Result<()> {
+        match self {
+            Self::Ndjson(mut writer) => Ok(writer.flush()?),
+            #[cfg(feature = "parquet")]
+            Self::Parquet(file) => file.close(),
+        }
+    }
+}
+
+/// An output file shared by a log's clones, taken out once it's closed
+type SharedOutput = Arc<Mutex<Option<Output>>>;
+
+/// Every output file created, so [`close_outputs`] can finish the ones
+/// still open
+static OUTPUTS: Mutex<Vec<Weak<Mutex<Option<Output>>>>> = Mutex::new(Vec::new());
+
+/// Create an output file and keep track of it for [`close_outputs`]
+fn open<T: Row>(path: &Path) -> Result<SharedOutput> {
+    let output = Arc::new(Mutex::new(Some(Output::create::<T>(path)?)));
+    let mut outputs = OUTPUTS.lock().unwrap_or_else(|e| e.into_inner());
+    outputs.retain(|output| output.strong_count() > 0);
+    outputs.push(Arc::downgrade(&output));
+    Ok(output)
+}
+
+/// Append `rows` to `output`, unless it has been closed
+fn write<T: Row>(output: &SharedOutput, rows: &[T]) -> Result<()> {
+    // A panic mid-write can at worst leave a partial line behind
+    let mut output = output.lock().unwrap_or_else(|e| e.into_inner());
+    match output.as_mut() {
+        Some(output) => output.write(rows),
+        None => Err(std::io::Error::other("output file already closed").into()),
+    }
+}
+
+/// Finish every sample, slow-query, and burst file still open, flushing
+/// NDJSON and writing Parquet footers, and report the first failure.
+/// Writing to them afterwards fails. Files are also finished when the last
+/// log using them is dropped, but only with a warning on failure, and not at
+/// all if the process exits without running destructors.
+pub fn close_outputs() -> Result<()> {
+    let outputs = std::mem::take(&mut *OUTPUTS.lock().unwrap_or_else(|e| e.into_inner()));
+    let mut result = Ok(());
+    for output in outputs.iter().filter_map(Weak::upgrade) {
+        let output = output.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(output) = output {
+            let finished = output.finish();
+            result = result.and(finished);
+        }
+    }
+    result
+}
+
+/// Sample file. Clones append to the same file, so client threads can share
+/// one log.
+#[derive(Clone)]
+pub struct SampleLog {
+    writer: SharedOutput,
+}
+
+impl SampleLog {
+    /// Create (or truncate) the sample file at `path`, as Parquet if it ends
+    /// in `.parquet` and NDJSON otherwise
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self {
+            writer: open::<Sample>(path.as_ref())?,
+        })
+    }
+
+    /// Append a burst's samples. NDJSON is flushed to disk right away; a
+    /// Parquet file is finished by [`close_outputs`] or when the last clone
+    /// is dropped.
+    pub fn write(&self, samples: &[Sample]) -> Result<()> {
+        write(&self.writer, samples)
+    }
+}
+
+/// When a burst log moves on to a new file; never, by default
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rotation {
+    /// Start a new file once the current one holds this many bytes
+    pub max_bytes: Option<u64>,
+    /// Start a new file once the current one has been open this long
+    pub max_age: Option<Duration>,
+}
+
+impl Rotation {
+    fn enabled(&self) -> bool {
+        self.max_bytes.is_some() || self.max_age.is_some()
+    }
+}
+
+/// Burst metrics file, one row per burst
+pub struct BurstLog {
+    path: PathBuf,
+    rotation: Rotation,
+    /// Number of the current file, from 1, or 0 when not rotating
+    segment: usize,
+    opened: Instant,
+    writer: SharedOutput,
+}
+
+impl BurstLog {
+    /// Create (or truncate) the burst file at `path`, as Parquet if it ends
+    /// in `.parquet` and NDJSON otherwise
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        Self::rotating(path, Rotation::default())
+    }
+
+    /// Like `create`, but with rotation enabled the files are numbered
+    /// (`bursts.00001.ndjson`, `bursts.00002.ndjson`, ...) and the next one
+    /// is started once `rotation` says the current one is full
+    pub fn rotating(path: impl AsRef<Path>, rotation: Rotation) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let segment = usize::from(rotation.enabled());
+        let writer = open::<BurstMetrics>(&segment_path(&path, segment))?;
+        Ok(Self {
+            path,
+            rotation,
+            segment,
+            opened: Instant::now(),
+            writer,
+        })
+    }
+
+    /// The file the next burst will be written to, unless it rotates first
+    pub fn current_path(&self) -> PathBuf {
+        segment_path(&self.path, self.segment)
+    }
+
+    /// Append a finished burst, first moving on to a new file if the current
+    /// one is full. A Parquet file is finished when it is rotated out, by
+    /// [`close_outputs`], or when the log is dropped.
+    pub fn write(&mut self, burst: &BurstMetrics) -> Result<()> {
+        if self.full()? {
+            let next = open::<BurstMetrics>(&segment_path(&self.path, self.segment + 1))?;
+            let finished = std::mem::replace(&mut self.writer, next);
+            self.segment += 1;
+            self.opened = Instant::now();
+            // Close explicitly so a failure to finish the file isn't only logged
+            let finished = finished.lock().unwrap_or_else(|e| e.into_inner()).take();
+            if let Some(finished) = finished {
+                finished.finish()?;
+            }
+        }
+        write(&self.writer, std::slice::from_ref(burst))
+    }
+
+    fn full(&self) -> Result<bool> {
+        if self
+            .rotation
+            .max_age
+            .is_some_and(|max| self.opened.elapsed() >= max)
+        {
+            return Ok(true);
+        }
+        let Some(max) = self.rotation.max_bytes else {
+            return Ok(false);
+        };
+        let writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        match writer.as_ref() {
+            Some(writer) => Ok(writer.size()? >= max),
+            None => Ok(false),
+        }
+    }
+}
+
+/// `path` with `segment` inserted before its extension, or `path` itself
+/// for segment 0
+fn segment_path(path: &Path, segment: usize) -> PathBuf {
+    if segment == 0 {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{stem}.{segment:05}.{}", ext.to_string_lossy()),
+        None => format!("{stem}.{segment:05}"),
+    };
+    path.with_file_name(name)
+}
+
+/// Searches slower than a threshold, or the slowest few of each burst,
+/// written as samples. Clones append to the same file.
+#[derive(Clone)]
+pub struct SlowQueryLog {
+    log: SampleLog,
+    threshold_us: Option<u64>,
+    top_n: Option<usize>,
+}
+
+impl SlowQueryLog {
+    /// Create (or truncate) the slow-query file from `config`
+    pub fn create(config: &SlowQueryConfig) -> Result<Self> {
+        if config.threshold_ms.is_none() && config.top_n.is_none() {
+            return Err(Error::Config(
+                "slow_queries needs a threshold_ms, a top_n, or both".into(),
+            ));
+        }
+        Ok(Self {
+            log: SampleLog::create(&config.path)?,
+            threshold_us: config.threshold_ms.map(|ms| (ms * 1000.0) as u64),
+            top_n: config.top_n,
+        })
+    }
+
+    /// Append the slow searches among a burst's samples, slowest first
+    pub fn write(&self, samples: &[Sample]) -> Result<()> {
+        let mut searches: Vec<&Sample> = samples
+            .iter()
+            .filter(|s| s.kind != RequestKind::Upsert)
+            .collect();
+        searches.sort_by_key(|s| Reverse(s.latency_us));
+
+        // Over the threshold, or among the top N; whichever keeps more
+        let over = self.threshold_us.map_or(0, |threshold| {
+            searches.partition_point(|s| s.latency_us >= threshold)
+        });
+        let keep = over.max(self.top_n.unwrap_or(0)).min(searches.len());
+        let slow: Vec<Sample> = searches[..keep].iter().map(|&s| s.clone()).collect();
+        self.log.write(&slow)
+    }
+}
+
+/// A failed request
+#[derive(Debug, Clone)]
+pub struct Failure {
+    /// When the request was sent
+    pub timestamp: DateTime<Utc>,
+    pub kind: RequestKind,
+    /// Kind of failure, e.g. `timeout` or `HTTP 503`
+    pub class: String,
+    /// The error as reported by the provider
+    pub message: String,
+    /// Whether sending the request again might succeed
+    pub retryable: bool,
+    /// Text of the query that failed; none for upserts
+    pub query: Option<Arc<str>>,
+}
+
+/// The most recent failures, plus a count of every failure by class. Clones
+/// share one log, so client threads can all record to it.
+#[derive(Clone)]
+pub struct FailureLog {
+    inner: Arc<Mutex<Failures>>,
+}
+
+struct Failures {
+    recent: VecDeque<Failure>,
+    capacity: usize,
+    counts: BTreeMap<String, usize>,
+}
+
+impl FailureLog {
+    /// Keep the last `capacity` failures
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Failures {
+                recent: VecDeque::with_capacity(capacity),
+                capacity,
+                counts: BTreeMap::new(),
+            })),
+        }
+    }
+
+    pub fn record(&self, failures: impl IntoIterator<Item = Failure>) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        for failure in failures {
+            *inner.counts.entry(failure.class.clone()).or_default() += 1;
+            if inner.recent.len() == inner.capacity {
+                inner.recent.pop_front();
+            }
+            if inner.capacity > 0 {
+                inner.recent.push_back(failure);
+            }
+        }
+    }
+
+    /// Recent failures, newest first
+    pub fn recent(&self) -> Vec<Failure> {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.recent.iter().rev().cloned().collect()
+    }
+
+    /// Failures so far by class, most common first
+    pub fn counts(&self) -> Vec<(String, usize)> {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let mut counts: Vec<(String, usize)> = inner
+            .counts
+            .iter()
+            .map(|(class, &count)| (class.clone(), count))
+            .collect();
+        counts.sort_by_key(|&(_, count)| Reverse(count));
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment_path_numbers_before_extension() {
+        let path = Path::new("out/results.ndjson");
+        assert_eq!(segment_path(path, 0), path);
+        assert_eq!(segment_path(path, 3), Path::new("out/results.00003.ndjson"));
+        assert_eq!(
+            segment_path(Path::new("bursts"), 12),
+            Path::new("bursts.00012")
+        );
+    }
+
+    #[test]
+    fn test_sample_log_appends_ndjson_from_every_clone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("samples.ndjson");
+        let sample = |latency_us, status| Sample {
+            timestamp: Utc::now(),
+            burst: 1,
+            kind: RequestKind::Search,
+            status,
+            latency_us,
+            took_us: Some(1500),
+            query: Some("q-1".into()),
+            text: None,
+            documents: None,
+            rerank_us: None,
+            pages: None,
+            error: None,
+        };
+
+        let log = SampleLog::create(&path).unwrap();
+        let clone = log.clone();
+        log.write(&[sample(2000, SampleStatus::Ok)]).unwrap();
+        clone.write(&[sample(9000, SampleStatus::Timeout)]).unwrap();
+
+        let lines: Vec<serde_json::Value> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["latency_us"], 2000);
+        assert_eq!(lines[0]["took_ms"], 1.5);
+        assert_eq!(lines[1]["status"], "timeout");
+        assert!(lines[1].get("text").is_none());
+    }
+}
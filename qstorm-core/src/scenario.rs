@@ -0,0 +1,175 @@
+use std::time::{Duration, Instant};
+
+use crate::config::{BenchmarkConfig, PhaseConfig, ScenarioConfig};
+use crate::error::{Error, Result};
+
+impl ScenarioConfig {
+    /// Check every phase can finish, so a scenario never stalls part-way
+    pub fn validate(&self) -> Result<()> {
+        if self.phases.is_empty() {
+            return Err(Error::Config("scenario has no phases".into()));
+        }
+        for (i, phase) in self.phases.iter().enumerate() {
+            if phase.duration_secs.is_none() && phase.bursts.is_none() && phase.warmup.is_none() {
+                return Err(Error::Config(format!(
+                    "scenario phase '{}' needs duration_secs, bursts, or warmup",
+                    phase.label(i)
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PhaseConfig {
+    /// `base` with this phase's overrides applied
+    pub fn apply(&self, base: &BenchmarkConfig) -> BenchmarkConfig {
+        let mut config = base.clone();
+        if let Some(mode) = self.mode {
+            config.mode = mode;
+        }
+        if let Some(burst_size) = self.burst_size {
+            config.burst_size = burst_size;
+        }
+        if let Some(concurrency) = self.concurrency {
+            config.concurrency = concurrency;
+        }
+        if let Some(timeout_ms) = self.timeout_ms {
+            config.timeout_ms = timeout_ms;
+        }
+        if let Some(top_k) = self.top_k {
+            config.top_k = top_k;
+        }
+        if let Some(batch_size) = self.batch_size {
+            config.batch_size = batch_size;
+        }
+        if let Some(load) = self.load {
+            config.load = load;
+        }
+        config
+    }
+
+    /// The phase's name, or its 1-based position when unnamed
+    pub fn label(&self, index: usize) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("phase {}", index + 1))
+    }
+
+    /// Whether the phase has run its course
+    fn finished(&self, started: Instant, bursts: usize) -> bool {
+        let out_of_bursts = self.bursts.is_some_and(|limit| bursts >= limit);
+        let out_of_time = self
+            .duration_secs
+            .is_some_and(|secs| started.elapsed() >= Duration::from_secs(secs));
+        let unbounded = self.bursts.is_none() && self.duration_secs.is_none();
+        out_of_bursts || out_of_time || unbounded
+    }
+}
+
+/// Tracks progress through a scenario's phases
+#[derive(Debug, Clone)]
+pub(crate) struct ScenarioState {
+    phases: Vec<PhaseConfig>,
+    base: BenchmarkConfig,
+    /// Index of the running phase; `None` before the first one starts
+    current: Option<usize>,
+    started: Instant,
+    bursts: usize,
+    /// Whether completion has already been reported
+    reported: bool,
+}
+
+impl ScenarioState {
+    pub(crate) fn new(scenario: ScenarioConfig, base: BenchmarkConfig) -> Self {
+        Self {
+            phases: scenario.phases,
+            base,
+            current: None,
+            started: Instant::now(),
+            bursts: 0,
+            reported: false,
+        }
+    }
+
+    /// Start the next phase if the current one is over. Returns its index,
+    /// settings, and the benchmark config to run it with.
+    pub(crate) fn advance(&mut self) -> Option<(usize, PhaseConfig, BenchmarkConfig)> {
+        let next = match self.current {
+            None => 0,
+            Some(i) if self.phases[i].finished(self.started, self.bursts) => i + 1,
+            Some(_) => return None,
+        };
+        let phase = self.phases.get(next)?.clone();
+
+        self.current = Some(next);
+        self.started = Instant::now();
+        self.bursts = 0;
+        let config = phase.apply(&self.base);
+        Some((next, phase, config))
+    }
+
+    /// Count a burst towards the running phase
+    pub(crate) fn record_burst(&mut self) {
+        self.bursts += 1;
+    }
+
+    /// Label of the running phase
+    pub(crate) fn phase_label(&self) -> Option<String> {
+        self.current.map(|i| self.phases[i].label(i))
+    }
+
+    /// True the first time this is called after the last phase has run its course
+    pub(crate) fn take_complete(&mut self) -> bool {
+        let complete = self.current.is_some_and(|i| {
+            i + 1 == self.phases.len() && self.phases[i].finished(self.started, self.bursts)
+        });
+        if !complete || self.reported {
+            return false;
+        }
+        self.reported = true;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scenario_advances_through_phases() {
+        let scenario = ScenarioConfig {
+            phases: vec![
+                PhaseConfig {
+                    warmup: Some(5),
+                    ..Default::default()
+                },
+                PhaseConfig {
+                    name: Some("steady".into()),
+                    bursts: Some(2),
+                    concurrency: Some(4),
+                    ..Default::default()
+                },
+            ],
+        };
+        scenario.validate().unwrap();
+        let mut state = ScenarioState::new(scenario, BenchmarkConfig::default());
+
+        let (index, phase, _) = state.advance().unwrap();
+        assert_eq!((index, phase.warmup), (0, Some(5)));
+
+        // A warmup-only phase is over as soon as it starts
+        let (index, _, config) = state.advance().unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(config.concurrency, 4);
+        assert_eq!(state.phase_label().as_deref(), Some("steady"));
+
+        state.record_burst();
+        assert!(state.advance().is_none());
+        assert!(!state.take_complete());
+        state.record_burst();
+        assert!(state.take_complete());
+        assert!(!state.take_complete());
+        assert!(state.advance().is_none());
+    }
+}
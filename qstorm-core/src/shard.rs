@@ -0,0 +1,178 @@
+use std::sync::Arc;
+use std::thread;
+
+use tokio::sync::{mpsc, oneshot};
+use tracing::warn;
+
+use crate::config::{BenchmarkConfig, LoadConfig};
+use crate::error::{Error, Result};
+use crate::metrics::{BurstMetrics, BurstSamples};
+use crate::provider::ProviderFactory;
+use crate::queries::EmbeddedQuery;
+use crate::runner::BenchmarkRunner;
+
+enum Command {
+    Burst {
+        config: Box<BenchmarkConfig>,
+        query_offset: usize,
+        reply: oneshot::Sender<Result<(BurstMetrics, BurstSamples)>>,
+    },
+    Disconnect {
+        reply: oneshot::Sender<Result<()>>,
+    },
+}
+
+/// A slice of the load running on its own OS thread, tokio runtime, and
+/// provider connection
+pub(crate) struct Shard {
+    commands: mpsc::Sender<Command>,
+}
+
+impl Shard {
+    /// Start a thread with a fresh provider from `factory`, set up by
+    /// `configure`, connect it, and warm it up with `warmup_iterations` queries
+    pub(crate) async fn spawn(
+        index: usize,
+        factory: ProviderFactory,
+        config: BenchmarkConfig,
+        queries: Arc<[EmbeddedQuery]>,
+        warmup_iterations: usize,
+        configure: impl FnOnce(BenchmarkRunner) -> BenchmarkRunner + Send + 'static,
+    ) -> Result<Self> {
+        let (commands, mut rx) = mpsc::channel::<Command>(1);
+        let (ready_tx, ready_rx) = oneshot::channel::<Result<()>>();
+
+        thread::Builder::new()
+            .name(format!("qstorm-client-{index}"))
+            .spawn(move || {
+                let runtime = match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(runtime) => runtime,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e.into()));
+                        return;
+                    }
+                };
+
+                runtime.block_on(async move {
+                    let setup = async {
+                        let mut runner = configure(
+                            BenchmarkRunner::new(factory()?, config).with_queries(queries),
+                        );
+                        runner.connect().await?;
+                        runner.warmup_iterations(warmup_iterations).await?;
+                        Ok(runner)
+                    };
+                    let mut runner = match setup.await {
+                        Ok(runner) => {
+                            let _ = ready_tx.send(Ok(()));
+                            runner
+                        }
+                        Err(e) => {
+                            let _ = ready_tx.send(Err(e));
+                            return;
+                        }
+                    };
+
+                    while let Some(command) = rx.recv().await {
+                        match command {
+                            Command::Burst {
+                                config,
+                                query_offset,
+                                reply,
+                            } => {
+                                runner.set_shard_config(*config, query_offset);
+                                let result = runner.run_burst().await.map(|burst| {
+                                    (burst, runner.metrics().last_burst_samples().clone())
+                                });
+                                let _ = reply.send(result);
+                            }
+                            Command::Disconnect { reply } => {
+                                let _ = reply.send(runner.disconnect().await);
+                                break;
+                            }
+                        }
+                    }
+                });
+            })?;
+
+        ready_rx.await.map_err(|_| {
+            Error::connection_message(format!("client thread {index} exited during setup"))
+        })??;
+        Ok(Self { commands })
+    }
+
+    /// Start a burst on this shard; await the returned receiver for its result
+    pub(crate) async fn start_burst(
+        &self,
+        config: BenchmarkConfig,
+        query_offset: usize,
+    ) -> Result<oneshot::Receiver<Result<(BurstMetrics, BurstSamples)>>> {
+        let (reply, rx) = oneshot::channel();
+        self.commands
+            .send(Command::Burst {
+                config: Box::new(config),
+                query_offset,
+                reply,
+            })
+            .await
+            .map_err(|_| Error::connection_message("client thread has exited"))?;
+        Ok(rx)
+    }
+
+    /// Disconnect the shard's provider and let its thread finish
+    pub(crate) async fn disconnect(self) {
+        let (reply, rx) = oneshot::channel();
+        if self
+            .commands
+            .send(Command::Disconnect { reply })
+            .await
+            .is_ok()
+            && let Ok(Err(e)) = rx.await
+        {
+            warn!(error = %e, "Client thread failed to disconnect cleanly");
+        }
+    }
+}
+
+/// Shard `shard`'s part of `total` when split `shards` ways
+pub(crate) fn share(total: usize, shard: usize, shards: usize) -> usize {
+    total / shards + usize::from(shard < total % shards)
+}
+
+/// Settings for one of `shards` threads: the burst, concurrency, and target
+/// rate are divided between them
+pub(crate) fn shard_config(
+    config: &BenchmarkConfig,
+    shard: usize,
+    shards: usize,
+) -> BenchmarkConfig {
+    let mut config = config.clone();
+    config.burst_size = share(config.burst_size, shard, shards);
+    config.concurrency = share(config.concurrency, shard, shards).max(1);
+    if let LoadConfig::ConstantRate { qps, .. } = &mut config.load {
+        *qps /= shards as f64;
+    }
+    config.client_threads = 1;
+    // The budget is tracked once by the parent, which already sized this burst
+    config.max_total_queries = None;
+    // Stop conditions are checked once on the merged burst
+    config.stop_if = None;
+    // Engine stats are polled once by the parent
+    config.server_stats = None;
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_share_splits_evenly() {
+        let shares: Vec<usize> = (0..4).map(|i| share(10, i, 4)).collect();
+        assert_eq!(shares, [3, 3, 2, 2]);
+        assert_eq!(shares.iter().sum::<usize>(), 10);
+    }
+}
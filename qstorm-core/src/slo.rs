@@ -0,0 +1,38 @@
+use crate::config::SloConfig;
+use crate::metrics::BurstMetrics;
+
+impl SloConfig {
+    /// Describe every objective `burst` breaks
+    pub fn breaches(&self, burst: &BurstMetrics) -> Vec<String> {
+        let mut breaches = Vec::new();
+
+        if let Some(limit) = self.p99_ms {
+            let p99_ms = burst.latency.p99_us as f64 / 1000.0;
+            if p99_ms > limit {
+                breaches.push(format!("p99 {p99_ms:.1}ms > {limit}ms"));
+            }
+        }
+
+        if let Some(limit) = self.error_rate
+            && burst.query_count > 0
+        {
+            let rate = burst.failure_count as f64 / burst.query_count as f64;
+            if rate > limit {
+                breaches.push(format!(
+                    "error rate {:.1}% > {:.1}%",
+                    rate * 100.0,
+                    limit * 100.0
+                ));
+            }
+        }
+
+        // Bursts without ground truth have no recall to hold to the objective
+        if let (Some(limit), Some(recall)) = (self.min_recall, burst.recall_at_k)
+            && recall < limit
+        {
+            breaches.push(format!("recall {recall:.3} < {limit}"));
+        }
+
+        breaches
+    }
+}
@@ -0,0 +1,383 @@
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use hdrhistogram::Histogram;
+use hdrhistogram::serialization::{Deserializer, Serializer, V2DeflateSerializer};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::config::SoakConfig;
+use crate::error::{Error, Result};
+use crate::metrics::{BurstMetrics, LatencyMetrics, Metrics};
+
+/// Burst log written alongside the checkpoint, one JSON object per line
+pub const BURSTS_FILE: &str = "bursts.ndjson";
+/// Latest run state, rewritten at every checkpoint
+pub const CHECKPOINT_FILE: &str = "checkpoint.json";
+/// Latency histograms as of the latest checkpoint, in HdrHistogram's
+/// compressed V2 format: every query first, then one per query tag
+pub const LATENCY_FILE: &str = "latency.hdr";
+
+/// Progress of a soak run as of its last checkpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub provider: String,
+    /// Seed the run used, reused when it's resumed
+    #[serde(default)]
+    pub seed: Option<u64>,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Time spent running as of this checkpoint, across every resume and
+    /// leaving out the time the run was stopped
+    #[serde(default)]
+    pub ran_for_ms: Option<u64>,
+    /// Bursts written to the burst log so far
+    pub bursts_completed: usize,
+    pub total_queries: usize,
+    pub total_failures: usize,
+    /// Searches and upsert requests sent so far, counted against
+    /// `max_total_queries` when the run is resumed
+    #[serde(default)]
+    pub queries_sent: usize,
+    /// Latency percentiles across every query so far
+    pub latency: LatencyMetrics,
+    /// Query tags with a histogram in the latency file, in file order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Whether the run ended cleanly rather than being interrupted
+    pub finished: bool,
+}
+
+impl Checkpoint {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::open(path)?;
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    /// Time the run has spent running. Checkpoints written before this was
+    /// recorded only have the time from start to last checkpoint, downtime
+    /// included.
+    pub fn ran_for(&self) -> Duration {
+        match self.ran_for_ms {
+            Some(ms) => Duration::from_millis(ms),
+            None => (self.updated_at - self.started_at)
+                .to_std()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Write via a temporary file and rename so a crash never leaves a torn checkpoint
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("json.tmp");
+        let mut writer = BufWriter::new(File::create(&tmp)?);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Latency histogram per query tag
+pub type TagHistograms = BTreeMap<String, Histogram<u64>>;
+
+/// What an interrupted soak run had done as of its last checkpoint
+pub struct Resumed {
+    pub checkpoint: Checkpoint,
+    /// The bursts the checkpoint counts, in order
+    pub bursts: Vec<BurstMetrics>,
+    /// Latencies of every query those bursts sent, in microseconds
+    pub latency: Histogram<u64>,
+    pub tags: TagHistograms,
+}
+
+/// Appends bursts to an NDJSON log and periodically checkpoints run state
+pub struct SoakRecorder {
+    checkpoint_path: PathBuf,
+    bursts: BufWriter<File>,
+    interval: Duration,
+    deadline: Option<Instant>,
+    last_checkpoint: Instant,
+    /// Running time before this session, and when this session started
+    ran_before: Duration,
+    session_start: Instant,
+    checkpoint: Checkpoint,
+}
+
+impl SoakRecorder {
+    /// Create the soak directory and start a fresh burst log in it
+    pub fn create(config: &SoakConfig, provider: &str, seed: Option<u64>) -> Result<Self> {
+        let dir = Path::new(&config.dir);
+        let checkpoint_path = dir.join(CHECKPOINT_FILE);
+        if checkpoint_path.exists() {
+            return Err(Error::Config(format!(
+                "{} already holds a soak checkpoint; choose another soak.dir",
+                dir.display()
+            )));
+        }
+        std::fs::create_dir_all(dir)?;
+
+        let bursts = BufWriter::new(File::create(dir.join(BURSTS_FILE))?);
+        let now = Utc::now();
+        Ok(Self {
+            checkpoint_path,
+            bursts,
+            interval: Duration::from_secs(config.checkpoint_interval_secs),
+            deadline: config
+                .duration_secs
+                .map(|secs| Instant::now() + Duration::from_secs(secs)),
+            last_checkpoint: Instant::now(),
+            ran_before: Duration::ZERO,
+            session_start: Instant::now(),
+            checkpoint: Checkpoint {
+                provider: provider.to_string(),
+                seed,
+                started_at: now,
+                updated_at: now,
+                ran_for_ms: Some(0),
+                bursts_completed: 0,
+                total_queries: 0,
+                total_failures: 0,
+                queries_sent: 0,
+                latency: LatencyMetrics::default(),
+                tags: Vec::new(),
+                finished: false,
+            },
+        })
+    }
+
+    /// Pick up the unfinished soak run in `config.dir` from its checkpoint.
+    /// Bursts logged after the checkpoint are dropped from the burst log,
+    /// since its totals and histograms don't include them, and the run's
+    /// duration counts the time it ran before being interrupted, but not the
+    /// time it spent stopped.
+    pub fn resume(config: &SoakConfig) -> Result<(Self, Resumed)> {
+        let dir = Path::new(&config.dir);
+        let checkpoint_path = dir.join(CHECKPOINT_FILE);
+        let checkpoint = Checkpoint::load(&checkpoint_path).map_err(|e| {
+            Error::Config(format!("Failed to read {}: {e}", checkpoint_path.display()))
+        })?;
+        if checkpoint.finished {
+            return Err(Error::Config(format!(
+                "The soak run in {} already finished; there's nothing to resume",
+                dir.display()
+            )));
+        }
+
+        let (latency, tags) = load_histograms(&dir.join(LATENCY_FILE), &checkpoint.tags)?;
+        let bursts_path = dir.join(BURSTS_FILE);
+        let (bursts, checkpointed_len) = read_bursts(&bursts_path, checkpoint.bursts_completed)?;
+        let log = OpenOptions::new().append(true).open(&bursts_path)?;
+        log.set_len(checkpointed_len)?;
+
+        let ran_for = checkpoint.ran_for();
+        let recorder = Self {
+            checkpoint_path,
+            bursts: BufWriter::new(log),
+            interval: Duration::from_secs(config.checkpoint_interval_secs),
+            deadline: config
+                .duration_secs
+                .map(|secs| Instant::now() + Duration::from_secs(secs).saturating_sub(ran_for)),
+            last_checkpoint: Instant::now(),
+            ran_before: ran_for,
+            session_start: Instant::now(),
+            checkpoint: checkpoint.clone(),
+        };
+        let resumed = Resumed {
+            checkpoint,
+            bursts,
+            latency,
+            tags,
+        };
+        Ok((recorder, resumed))
+    }
+
+    /// True once the configured duration has elapsed
+    pub fn expired(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Log a finished burst, checkpointing if the interval has passed.
+    /// `metrics` supplies the cumulative latency distribution.
+    pub fn record(&mut self, burst: &BurstMetrics, metrics: &Metrics) -> Result<()> {
+        serde_json::to_writer(&mut self.bursts, burst)?;
+        self.bursts.write_all(b"\n")?;
+
+        self.checkpoint.bursts_completed += 1;
+        self.checkpoint.total_queries += burst.query_count;
+        self.checkpoint.total_failures += burst.failure_count;
+        self.checkpoint.queries_sent +=
+            burst.query_count + burst.writes.as_ref().map_or(0, |w| w.request_count);
+
+        if self.last_checkpoint.elapsed() >= self.interval {
+            self.save(metrics)?;
+        }
+        Ok(())
+    }
+
+    /// Flush everything and mark the checkpoint as cleanly finished
+    pub fn finish(mut self, metrics: &Metrics) -> Result<Checkpoint> {
+        self.checkpoint.finished = true;
+        self.save(metrics)?;
+        Ok(self.checkpoint)
+    }
+
+    /// Flush everything but leave the checkpoint marked unfinished, e.g.
+    /// when the run was interrupted
+    pub fn close(mut self, metrics: &Metrics) -> Result<Checkpoint> {
+        self.save(metrics)?;
+        Ok(self.checkpoint)
+    }
+
+    fn save(&mut self, metrics: &Metrics) -> Result<()> {
+        self.bursts.flush()?;
+        self.bursts.get_ref().sync_data()?;
+
+        self.checkpoint.updated_at = Utc::now();
+        let ran_for = self.ran_before + self.session_start.elapsed();
+        self.checkpoint.ran_for_ms = Some(ran_for.as_millis() as u64);
+        self.checkpoint.latency = metrics.aggregate_latency();
+        self.checkpoint.tags = metrics.tag_histograms().keys().cloned().collect();
+        let dir = self.checkpoint_path.parent().unwrap_or(Path::new("."));
+        save_histograms(&dir.join(LATENCY_FILE), metrics)?;
+        self.checkpoint.save(&self.checkpoint_path)?;
+        self.last_checkpoint = Instant::now();
+
+        debug!(
+            bursts = self.checkpoint.bursts_completed,
+            path = %self.checkpoint_path.display(),
+            "Wrote soak checkpoint"
+        );
+        Ok(())
+    }
+}
+
+/// Write the latency histograms via a temporary file, like the checkpoint
+fn save_histograms(path: &Path, metrics: &Metrics) -> Result<()> {
+    let tmp = path.with_extension("hdr.tmp");
+    let mut writer = BufWriter::new(File::create(&tmp)?);
+    let mut serializer = V2DeflateSerializer::new();
+    for histogram in
+        std::iter::once(metrics.latency_histogram()).chain(metrics.tag_histograms().values())
+    {
+        serializer
+            .serialize(histogram, &mut writer)
+            .map_err(std::io::Error::other)?;
+    }
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn load_histograms(path: &Path, tags: &[String]) -> Result<(Histogram<u64>, TagHistograms)> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut deserializer = Deserializer::new();
+    let mut next = || -> Result<Histogram<u64>> {
+        deserializer
+            .deserialize(&mut reader)
+            .map_err(|e| Error::Config(format!("{} is corrupt: {e:?}", path.display())))
+    };
+    let latency = next()?;
+    let tags = tags
+        .iter()
+        .map(|tag| Ok((tag.clone(), next()?)))
+        .collect::<Result<_>>()?;
+    Ok((latency, tags))
+}
+
+/// The first `count` bursts of a burst log, and the length of the file up to
+/// the end of the last of them
+fn read_bursts(path: &Path, count: usize) -> Result<(Vec<BurstMetrics>, u64)> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut bursts = Vec::with_capacity(count);
+    let mut len = 0;
+    let mut line = String::new();
+    while bursts.len() < count {
+        line.clear();
+        let read = reader.read_line(&mut line)?;
+        if !line.ends_with('\n') {
+            return Err(Error::Config(format!(
+                "{} holds fewer bursts than its checkpoint counts",
+                path.display()
+            )));
+        }
+        bursts.push(serde_json::from_str(&line)?);
+        len += read as u64;
+    }
+    Ok((bursts, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resume_restores_checkpointed_bursts() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = SoakConfig {
+            dir: dir.path().to_string_lossy().into_owned(),
+            ..Default::default()
+        };
+
+        let mut metrics = Metrics::new();
+        let mut recorder = SoakRecorder::create(&config, "test", Some(7)).unwrap();
+        for latency_ms in [3, 5] {
+            metrics.start_burst();
+            metrics.record_success(Duration::from_millis(latency_ms), None);
+            let burst = metrics.finish_burst().unwrap();
+            recorder.record(&burst, &metrics).unwrap();
+        }
+        recorder.close(&metrics).unwrap();
+
+        // A burst logged after the checkpoint, cut off by the interruption
+        let bursts_path = dir.path().join(BURSTS_FILE);
+        let mut log = OpenOptions::new().append(true).open(&bursts_path).unwrap();
+        log.write_all(b"{\"timestamp\":").unwrap();
+        let checkpointed_len = std::fs::metadata(&bursts_path).unwrap().len() - 13;
+
+        let (_, resumed) = SoakRecorder::resume(&config).unwrap();
+        assert_eq!(resumed.bursts.len(), 2);
+        assert_eq!(resumed.checkpoint.seed, Some(7));
+        assert_eq!(resumed.checkpoint.queries_sent, 2);
+        assert_eq!(resumed.latency.len(), 2);
+        assert_eq!(
+            std::fs::metadata(&bursts_path).unwrap().len(),
+            checkpointed_len
+        );
+    }
+
+    #[test]
+    fn test_resume_leaves_out_downtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = SoakConfig {
+            dir: dir.path().to_string_lossy().into_owned(),
+            duration_secs: Some(3600),
+            ..Default::default()
+        };
+
+        let metrics = Metrics::new();
+        let recorder = SoakRecorder::create(&config, "test", None).unwrap();
+        recorder.close(&metrics).unwrap();
+
+        // Stopped for two hours after the run started
+        let path = dir.path().join(CHECKPOINT_FILE);
+        let mut checkpoint = Checkpoint::load(&path).unwrap();
+        checkpoint.started_at -= chrono::Duration::hours(2);
+        checkpoint.save(&path).unwrap();
+
+        let (recorder, resumed) = SoakRecorder::resume(&config).unwrap();
+        assert!(resumed.checkpoint.ran_for() < Duration::from_secs(60));
+        assert!(!recorder.expired());
+
+        // Older checkpoints only have their timestamps to go by
+        checkpoint.ran_for_ms = None;
+        assert!(checkpoint.ran_for() >= Duration::from_secs(7200));
+    }
+}
@@ -0,0 +1,139 @@
+//! A runner's progress as plain data.
+//!
+//! [`BenchmarkRunner::state`](crate::BenchmarkRunner::state) takes a
+//! [`RunState`] at any point between bursts, and
+//! [`BenchmarkRunner::restore`](crate::BenchmarkRunner::restore) carries on
+//! from one in a fresh runner, e.g. in another process after the state was
+//! saved with serde. Latency histograms are stored in HdrHistogram's
+//! compressed V2 format, so nothing is lost in the round trip.
+
+use hdrhistogram::Histogram;
+use hdrhistogram::serialization::{Deserializer, Serializer, V2DeflateSerializer};
+use serde::{Deserialize, Serialize};
+
+use crate::metrics::BurstMetrics;
+use crate::soak::TagHistograms;
+
+/// Everything a runner needs to carry on a run where it left off
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunState {
+    /// Seed the run used. A restored runner is reseeded with it so random
+    /// choices carry on the same sequence.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Every burst finished so far, in order
+    pub bursts: Vec<BurstMetrics>,
+    /// Latencies of every query so far, in microseconds
+    #[serde(with = "histogram")]
+    pub latency: Histogram<u64>,
+    /// Latencies of every query so far per query tag
+    #[serde(default, with = "tag_histograms")]
+    pub tags: TagHistograms,
+    /// Searches plus upsert requests sent so far, counted against
+    /// `max_total_queries`
+    pub queries_sent: usize,
+    /// Shuffled query orders drawn so far. Restoring replays them, so the
+    /// next burst visits the queries in the order the original run would
+    /// have; that takes the same queries the state was taken with.
+    #[serde(default)]
+    pub shuffles: usize,
+}
+
+fn encode(histogram: &Histogram<u64>) -> std::result::Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    V2DeflateSerializer::new()
+        .serialize(histogram, &mut bytes)
+        .map_err(|e| format!("{e:?}"))?;
+    Ok(bytes)
+}
+
+fn decode(bytes: &[u8]) -> std::result::Result<Histogram<u64>, String> {
+    Deserializer::new()
+        .deserialize(&mut &bytes[..])
+        .map_err(|e| format!("corrupt latency histogram: {e:?}"))
+}
+
+/// One histogram as its compressed bytes
+struct Encoded<'a>(&'a Histogram<u64>);
+
+impl Serialize for Encoded<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        encode(self.0)
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+mod histogram {
+    use super::*;
+
+    pub fn serialize<S: serde::Serializer>(
+        histogram: &Histogram<u64>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        Encoded(histogram).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Histogram<u64>, D::Error> {
+        let bytes = Vec::<u8>::deserialize(deserializer)?;
+        decode(&bytes).map_err(serde::de::Error::custom)
+    }
+}
+
+mod tag_histograms {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    pub fn serialize<S: serde::Serializer>(
+        tags: &TagHistograms,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(tags.iter().map(|(tag, histogram)| (tag, Encoded(histogram))))
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<TagHistograms, D::Error> {
+        BTreeMap::<String, Vec<u8>>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(tag, bytes)| Ok((tag, decode(&bytes).map_err(serde::de::Error::custom)?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::metrics::Metrics;
+
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut metrics = Metrics::new();
+        metrics.start_burst();
+        metrics.record_success(Duration::from_millis(4), None);
+        metrics.record_tags(&["fast".to_string()], Duration::from_millis(4), true, None);
+        metrics.finish_burst().unwrap();
+
+        let state = RunState {
+            seed: Some(3),
+            bursts: metrics.bursts().to_vec(),
+            latency: metrics.latency_histogram().clone(),
+            tags: metrics.tag_histograms().clone(),
+            queries_sent: 1,
+            shuffles: 1,
+        };
+        let json = serde_json::to_string(&state).unwrap();
+        let restored: RunState = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.bursts.len(), 1);
+        assert_eq!(restored.latency, state.latency);
+        assert_eq!(restored.tags["fast"], state.tags["fast"]);
+        assert_eq!((restored.seed, restored.shuffles), (Some(3), 1));
+    }
+}
@@ -0,0 +1,137 @@
+//! StatsD/DogStatsD sink for environments that can't be scraped.
+//!
+//! Each finished burst is pushed as a handful of counters and gauges over
+//! UDP. Tags use the DogStatsD `|#tag` extension and are left off entirely
+//! when none are configured, so plain StatsD servers still parse the lines.
+
+use std::net::UdpSocket;
+
+use tracing::warn;
+
+use crate::config::StatsdConfig;
+use crate::error::{Error, Result};
+use crate::metrics::BurstMetrics;
+
+/// Largest datagram sent, below the typical Ethernet MTU
+const MAX_PACKET_BYTES: usize = 1432;
+
+/// Sends per-burst metrics to a StatsD agent
+pub struct StatsdSink {
+    socket: UdpSocket,
+    prefix: String,
+    tags: String,
+}
+
+impl StatsdSink {
+    pub fn connect(config: &StatsdConfig) -> Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.connect(&config.address).map_err(|e| {
+            Error::Config(format!("Invalid statsd.address '{}': {e}", config.address))
+        })?;
+
+        let prefix = match config.prefix.trim_end_matches('.') {
+            "" => String::new(),
+            prefix => format!("{prefix}."),
+        };
+        let tags = if config.tags.is_empty() {
+            String::new()
+        } else {
+            format!("|#{}", config.tags.join(","))
+        };
+        Ok(Self {
+            socket,
+            prefix,
+            tags,
+        })
+    }
+
+    /// Push a finished burst's metrics. Delivery isn't confirmed, so a failed
+    /// send is logged rather than stopping the run.
+    pub fn record(&self, burst: &BurstMetrics) {
+        for packet in packets(&self.lines(burst)) {
+            if let Err(e) = self.socket.send(packet.as_bytes()) {
+                warn!("Failed to send StatsD metrics: {e}");
+                return;
+            }
+        }
+    }
+
+    fn lines(&self, burst: &BurstMetrics) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut push = |name: &str, value: f64, kind: &str| {
+            lines.push(format!("{}{name}:{value}|{kind}{}", self.prefix, self.tags));
+        };
+
+        push("queries", burst.query_count as f64, "c");
+        push("successes", burst.success_count as f64, "c");
+        push("failures", burst.failure_count as f64, "c");
+        push("timeouts", burst.timeout_count as f64, "c");
+        for (status, &count) in &burst.status_codes {
+            push(&format!("responses.{status}"), count as f64, "c");
+        }
+        push("qps", burst.qps, "g");
+
+        let ms = |us: u64| us as f64 / 1000.0;
+        push("latency.p50", ms(burst.latency.p50_us), "g");
+        push("latency.p90", ms(burst.latency.p90_us), "g");
+        push("latency.p95", ms(burst.latency.p95_us), "g");
+        push("latency.p99", ms(burst.latency.p99_us), "g");
+        for p in &burst.latency.percentiles {
+            // Dots separate name segments, so p99.9 is sent as p99_9
+            let name = format!("latency.p{}", p.percentile.to_string().replace('.', "_"));
+            push(&name, ms(p.latency_us), "g");
+        }
+        push("latency.max", ms(burst.latency.max_us), "g");
+        push("latency.stddev", burst.latency.stddev_us / 1000.0, "g");
+        push("latency.iqr", ms(burst.latency.iqr_us), "g");
+        if let Some(server) = &burst.server {
+            push("server_latency.p50", ms(server.latency.p50_us), "g");
+            push("server_latency.p99", ms(server.latency.p99_us), "g");
+        }
+
+        if let Some(results) = &burst.results {
+            push("results.empty_rate", results.empty_rate, "g");
+            push("results.mean_hits", results.mean_hits, "g");
+            if let Some(score) = &results.score {
+                push("results.score.min", score.min, "g");
+                push("results.score.mean", score.mean, "g");
+                push("results.score.max", score.max, "g");
+            }
+        }
+
+        if let Some(client) = &burst.client {
+            push("client.cpu_percent", client.cpu_percent, "g");
+            push("client.rss_bytes", client.rss_bytes as f64, "g");
+        }
+
+        if let Some(recall) = burst.recall_at_k {
+            push("recall", recall, "g");
+        }
+        if let Some(ndcg) = burst.ndcg_at_k {
+            push("ndcg", ndcg, "g");
+        }
+        if let Some(mrr) = burst.mrr {
+            push("mrr", mrr, "g");
+        }
+        lines
+    }
+}
+
+/// Pack lines into newline-separated datagrams of at most `MAX_PACKET_BYTES`
+fn packets(lines: &[String]) -> Vec<String> {
+    let mut packets = Vec::new();
+    let mut packet = String::new();
+    for line in lines {
+        if !packet.is_empty() && packet.len() + 1 + line.len() > MAX_PACKET_BYTES {
+            packets.push(std::mem::take(&mut packet));
+        }
+        if !packet.is_empty() {
+            packet.push('\n');
+        }
+        packet.push_str(line);
+    }
+    if !packet.is_empty() {
+        packets.push(packet);
+    }
+    packets
+}
@@ -0,0 +1,133 @@
+use crate::config::StopConditions;
+use crate::metrics::BurstMetrics;
+
+impl StopConditions {
+    /// Describe the first limit `burst` breaks, if any
+    pub fn breach(&self, burst: &BurstMetrics) -> Option<String> {
+        if let Some(limit) = self.error_rate
+            && burst.query_count > 0
+        {
+            let rate = burst.failure_count as f64 / burst.query_count as f64;
+            if rate > limit {
+                return Some(format!(
+                    "error rate {:.1}% exceeds {:.1}%",
+                    rate * 100.0,
+                    limit * 100.0
+                ));
+            }
+        }
+
+        if let Some(limit) = self.p99_ms {
+            let p99_ms = burst.latency.p99_us as f64 / 1000.0;
+            if p99_ms > limit {
+                return Some(format!("p99 latency {p99_ms:.1}ms exceeds {limit}ms"));
+            }
+        }
+
+        None
+    }
+}
+
+/// Counts consecutive breaching bursts against the configured stop conditions
+#[derive(Debug, Clone)]
+pub(crate) struct StopMonitor {
+    conditions: StopConditions,
+    streak: usize,
+}
+
+impl StopMonitor {
+    pub(crate) fn new(conditions: StopConditions) -> Self {
+        Self {
+            conditions,
+            streak: 0,
+        }
+    }
+
+    /// Feed a finished burst. Returns why the run should stop once limits have
+    /// been broken for `for_bursts` bursts in a row, then starts counting afresh.
+    pub(crate) fn observe(&mut self, burst: &BurstMetrics) -> Option<String> {
+        let Some(reason) = self.conditions.breach(burst) else {
+            self.streak = 0;
+            return None;
+        };
+
+        self.streak += 1;
+        if self.streak < self.conditions.for_bursts.max(1) {
+            return None;
+        }
+
+        let streak = std::mem::take(&mut self.streak);
+        Some(if streak > 1 {
+            format!("{reason} for {streak} consecutive bursts")
+        } else {
+            reason
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::LatencyMetrics;
+
+    fn burst(failures: usize, p99_ms: u64) -> BurstMetrics {
+        BurstMetrics {
+            timestamp: chrono::Utc::now(),
+            duration_ms: 1000,
+            query_count: 100,
+            success_count: 100 - failures,
+            failure_count: failures,
+            timeout_count: 0,
+            latency: LatencyMetrics {
+                min_us: 0,
+                max_us: p99_ms * 1000,
+                mean_us: 0.0,
+                p50_us: 0,
+                p90_us: 0,
+                p95_us: 0,
+                p99_us: p99_ms * 1000,
+                stddev_us: 0.0,
+                cv: 0.0,
+                iqr_us: 0,
+                percentiles: Vec::new(),
+            },
+            server: None,
+            qps: 100.0,
+            recall_at_k: None,
+            ndcg_at_k: None,
+            mrr: None,
+            probe_recall: None,
+            peak_in_flight: None,
+            writes: None,
+            phase: None,
+            rerank: None,
+            pagination: None,
+            stability: None,
+            windows: Vec::new(),
+            results: None,
+            client: None,
+            server_stats: None,
+            status_codes: Default::default(),
+            tags: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_stop_monitor_streak() {
+        let mut monitor = StopMonitor::new(StopConditions {
+            error_rate: Some(0.05),
+            p99_ms: Some(200.0),
+            for_bursts: 3,
+        });
+
+        assert!(monitor.observe(&burst(10, 50)).is_none());
+        assert!(monitor.observe(&burst(0, 250)).is_none());
+        // A healthy burst resets the streak
+        assert!(monitor.observe(&burst(0, 50)).is_none());
+
+        assert!(monitor.observe(&burst(0, 300)).is_none());
+        assert!(monitor.observe(&burst(6, 50)).is_none());
+        let reason = monitor.observe(&burst(0, 300)).unwrap();
+        assert!(reason.contains("p99"), "{reason}");
+    }
+}
@@ -0,0 +1,59 @@
+//! Records of the `ndjson` output format.
+//!
+//! A headless run can print itself as one JSON object per line: a header
+//! describing the run, one record per burst, and a summary once it ends.
+//! Every record has a `type` field, and the header carries a
+//! `format_version` that only changes when existing fields change meaning
+//! or go away, so downstream tools can parse the stream without guessing.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::metrics::BurstMetrics;
+use crate::report::RunReport;
+
+/// Version of the record layout, bumped on incompatible changes
+pub const FORMAT_VERSION: u32 = 1;
+
+/// One line of `ndjson` output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Record {
+    /// First line: what is being run
+    Header(Box<RunHeader>),
+    /// A finished burst, with the same fields as `json` output
+    Burst(Box<BurstMetrics>),
+    /// Last line: the whole run, as written by `--report-file`
+    Summary(Box<RunReport>),
+}
+
+/// What a run is about to do, written before its first burst
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunHeader {
+    pub format_version: u32,
+    /// qstorm version producing the stream
+    pub version: String,
+    /// Commit checked out where the run was started, if it was a git repository
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_commit: Option<String>,
+    pub provider: String,
+    pub seed: Option<u64>,
+    pub started_at: DateTime<Utc>,
+    /// The config the run uses, with secrets removed
+    pub config: Config,
+}
+
+impl RunHeader {
+    pub fn new(config: &Config, started_at: DateTime<Utc>) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_commit: None,
+            provider: config.provider.name.clone(),
+            seed: config.seed,
+            started_at,
+            config: config.redacted(),
+        }
+    }
+}
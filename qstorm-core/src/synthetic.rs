@@ -0,0 +1,99 @@
+use rand::Rng;
+use rand::seq::IndexedRandom;
+use serde_json::json;
+
+use crate::config::IdFormat;
+use crate::queries::EmbeddedQuery;
+use crate::types::Document;
+
+/// Payload key marking documents written by qstorm, so they can be cleaned up
+pub const SYNTHETIC_MARKER: &str = "qstorm_synthetic";
+
+/// Relative size of the noise added to a query vector, as a fraction of its norm
+const JITTER: f32 = 0.1;
+
+/// Make `n` documents that land near real queries in embedding space.
+///
+/// Each one takes a random query's vector plus a little noise (keeping its
+/// norm) and stores the query text under `text`.
+pub(crate) fn documents(
+    queries: &[EmbeddedQuery],
+    n: usize,
+    id_format: IdFormat,
+    rng: &mut impl Rng,
+) -> Vec<Document> {
+    (0..n)
+        .filter_map(|_| {
+            let query = queries.choose(rng)?;
+            Some(Document {
+                id: synthetic_id(id_format, rng),
+                vector: Some(jitter(&query.vector, rng)),
+                payload: Some(json!({
+                    "text": query.text,
+                    SYNTHETIC_MARKER: true,
+                })),
+            })
+        })
+        .collect()
+}
+
+fn synthetic_id(format: IdFormat, rng: &mut impl Rng) -> String {
+    match format {
+        IdFormat::Uuid => {
+            let bits: u128 = rng.random();
+            // Version 4, RFC 4122 variant
+            let bits = (bits & !(0xf << 76) | (0x4 << 76)) & !(0x3 << 62) | (0x2 << 62);
+            let hex = format!("{bits:032x}");
+            format!(
+                "{}-{}-{}-{}-{}",
+                &hex[..8],
+                &hex[8..12],
+                &hex[12..16],
+                &hex[16..20],
+                &hex[20..]
+            )
+        }
+        // Stay within a signed bigint
+        IdFormat::Numeric => rng.random_range(1u64 << 62..i64::MAX as u64).to_string(),
+    }
+}
+
+fn jitter(vector: &[f32], rng: &mut impl Rng) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return vector.to_vec();
+    }
+
+    let scale = JITTER * norm / (vector.len() as f32).sqrt();
+    let noisy: Vec<f32> = vector
+        .iter()
+        .map(|x| x + rng.random_range(-scale..scale))
+        .collect();
+    let noisy_norm = noisy.iter().map(|x| x * x).sum::<f32>().sqrt();
+    noisy.into_iter().map(|x| x * norm / noisy_norm).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synthetic_documents() {
+        let queries = vec![EmbeddedQuery::new("red shoes".into(), vec![0.6, 0.8, 0.0])];
+        let mut rng = rand::rng();
+
+        let docs = documents(&queries, 3, IdFormat::Uuid, &mut rng);
+        assert_eq!(docs.len(), 3);
+        for doc in &docs {
+            assert_eq!(doc.id.len(), 36);
+            assert_eq!(doc.id.as_bytes()[14], b'4');
+            let vector = doc.vector.as_ref().unwrap();
+            let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+            assert!((norm - 1.0).abs() < 1e-4);
+            assert_eq!(doc.payload.as_ref().unwrap()["text"], "red shoes");
+        }
+
+        let docs = documents(&queries, 1, IdFormat::Numeric, &mut rng);
+        assert!(docs[0].id.parse::<i64>().unwrap() >= 1 << 62);
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::BTreeMap;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// A query template expanded into many concrete queries at load time
+///
+/// Placeholders are written as `{name}` and filled from `values`; `{{` and
+/// `}}` produce literal braces.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryTemplate {
+    /// Template text, e.g. `"best {brand} running shoes"`
+    pub template: String,
+    /// Substitution values for each placeholder
+    pub values: BTreeMap<String, Vec<String>>,
+    /// Draw this many distinct combinations at random instead of the full cartesian product
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample: Option<usize>,
+    /// Largest cartesian product expanded in full; bigger ones need `sample`
+    #[serde(default = "default_max_combinations")]
+    pub max_combinations: usize,
+}
+
+fn default_max_combinations() -> usize {
+    100_000
+}
+
+enum Segment<'a> {
+    Literal(String),
+    Var(&'a [String]),
+}
+
+impl QueryTemplate {
+    /// Number of queries the full cartesian product would produce
+    pub fn combinations(&self) -> Result<usize> {
+        let segments = self.parse()?;
+        self.count(&segments)
+    }
+
+    /// Expand into concrete query texts
+    pub fn expand(&self, rng: &mut impl Rng) -> Result<Vec<String>> {
+        let segments = self.parse()?;
+        let total = self.count(&segments)?;
+
+        match self.sample {
+            Some(n) if n < total => {
+                let mut picked = rand::seq::index::sample(rng, total, n).into_vec();
+                picked.sort_unstable();
+                Ok(picked.into_iter().map(|i| render(&segments, i)).collect())
+            }
+            _ if total > self.max_combinations => Err(Error::Config(format!(
+                "Template '{}' expands to {total} queries, more than max_combinations ({}); \
+                 set sample to draw fewer or raise max_combinations",
+                self.template, self.max_combinations
+            ))),
+            _ => Ok((0..total).map(|i| render(&segments, i)).collect()),
+        }
+    }
+
+    fn count(&self, segments: &[Segment]) -> Result<usize> {
+        segments
+            .iter()
+            .map(|s| match s {
+                Segment::Literal(_) => 1,
+                Segment::Var(values) => values.len(),
+            })
+            .try_fold(1usize, usize::checked_mul)
+            .ok_or_else(|| {
+                Error::Config(format!(
+                    "Template '{}' has too many combinations to count",
+                    self.template
+                ))
+            })
+    }
+
+    fn parse(&self) -> Result<Vec<Segment<'_>>> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = self.template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => {
+                                return Err(Error::Config(format!(
+                                    "Template '{}' has a '{{' with no closing '}}'",
+                                    self.template
+                                )));
+                            }
+                        }
+                    }
+                    let values = self.values.get(name.trim()).ok_or_else(|| {
+                        Error::Config(format!(
+                            "Template '{}' uses '{{{}}}' but no values are defined for it",
+                            self.template, name
+                        ))
+                    })?;
+                    if values.is_empty() {
+                        return Err(Error::Config(format!(
+                            "Template '{}' has an empty value list for '{}'",
+                            self.template, name
+                        )));
+                    }
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Var(values));
+                }
+                _ => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(segments)
+    }
+}
+
+/// Render the `index`-th combination, treating variables as digits of a mixed-radix number
+fn render(segments: &[Segment], mut index: usize) -> String {
+    let mut parts = Vec::with_capacity(segments.len());
+    for segment in segments.iter().rev() {
+        match segment {
+            Segment::Literal(text) => parts.push(text.as_str()),
+            Segment::Var(values) => {
+                parts.push(values[index % values.len()].as_str());
+                index /= values.len();
+            }
+        }
+    }
+    parts.reverse();
+    parts.concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(text: &str, sample: Option<usize>) -> QueryTemplate {
+        QueryTemplate {
+            template: text.into(),
+            values: BTreeMap::from([
+                ("brand".into(), vec!["nike".into(), "asics".into()]),
+                (
+                    "use".into(),
+                    vec!["trail".into(), "road".into(), "track".into()],
+                ),
+            ]),
+            sample,
+            max_combinations: default_max_combinations(),
+        }
+    }
+
+    #[test]
+    fn test_cartesian_expansion() {
+        let t = template("best {brand} {use} shoes {{sale}}", None);
+        let queries = t.expand(&mut rand::rng()).unwrap();
+        assert_eq!(queries.len(), 6);
+        assert_eq!(queries[0], "best nike trail shoes {sale}");
+        assert_eq!(queries[5], "best asics track shoes {sale}");
+    }
+
+    #[test]
+    fn test_sampled_expansion() {
+        let t = template("{brand} {use}", Some(4));
+        let queries = t.expand(&mut rand::rng()).unwrap();
+        assert_eq!(queries.len(), 4);
+        let unique: std::collections::HashSet<_> = queries.iter().collect();
+        assert_eq!(unique.len(), 4);
+    }
+
+    #[test]
+    fn test_unknown_placeholder() {
+        assert!(
+            template("{color} shoes", None)
+                .expand(&mut rand::rng())
+                .is_err()
+        );
+        let err = template("best {brand", None)
+            .expand(&mut rand::rng())
+            .unwrap_err();
+        assert!(err.to_string().contains("no closing"), "{err}");
+    }
+
+    #[test]
+    fn test_large_expansions_need_sample() {
+        let mut t = template("{brand} {use}", None);
+        t.max_combinations = 5;
+        let err = t.expand(&mut rand::rng()).unwrap_err();
+        assert!(err.to_string().contains("expands to 6 queries"), "{err}");
+        t.sample = Some(3);
+        assert_eq!(t.expand(&mut rand::rng()).unwrap().len(), 3);
+
+        // A product too big to count can't be sampled from either
+        let values: Vec<String> = (0..1 << 16).map(|i| i.to_string()).collect();
+        let t = QueryTemplate {
+            template: "{a}{a}{a}{a}{a}".into(),
+            values: BTreeMap::from([("a".into(), values)]),
+            sample: Some(10),
+            max_combinations: default_max_combinations(),
+        };
+        assert!(t.combinations().is_err());
+        assert!(t.expand(&mut rand::rng()).is_err());
+    }
+}
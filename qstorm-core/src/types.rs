@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 
 /// A single search result from a provider
@@ -16,23 +18,29 @@ pub struct SearchResult {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResults {
     pub results: Vec<SearchResult>,
-    /// Time taken by the search engine (if reported), in milliseconds
-    pub took_ms: Option<u64>,
+    /// Time taken by the search engine (if reported), in microseconds.
+    /// Written as `took_ms`, in fractional milliseconds.
+    #[serde(default, rename = "took_ms", with = "micros_as_millis")]
+    pub took_us: Option<u64>,
     /// Total hits (may be more than returned results)
     pub total_hits: Option<u64>,
+    /// HTTP status of the response, for HTTP-based providers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
 }
 
 impl SearchResults {
     pub fn new(results: Vec<SearchResult>) -> Self {
         Self {
             results,
-            took_ms: None,
+            took_us: None,
             total_hits: None,
+            status: None,
         }
     }
 
-    pub fn with_took(mut self, took_ms: u64) -> Self {
-        self.took_ms = Some(took_ms);
+    pub fn with_took(mut self, took: Duration) -> Self {
+        self.took_us = Some(took.as_micros() as u64);
         self
     }
 
@@ -41,6 +49,11 @@ impl SearchResults {
         self
     }
 
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = Some(status);
+        self
+    }
+
     /// Get document IDs in order (for recall calculation)
     pub fn ids(&self) -> Vec<&str> {
         self.results.iter().map(|r| r.id.as_str()).collect()
@@ -53,6 +66,9 @@ pub struct SearchParams {
     /// Number of results to return
     #[serde(default = "default_top_k")]
     pub top_k: usize,
+    /// Results to skip before the first one returned, for paging
+    #[serde(default)]
+    pub offset: usize,
     /// Minimum score threshold (provider-specific interpretation)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub min_score: Option<f32>,
@@ -62,6 +78,13 @@ pub struct SearchParams {
     /// Include document payloads in results
     #[serde(default)]
     pub include_payload: bool,
+    /// Named vector field to search (overrides the provider's `vector_field`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vector_name: Option<String>,
+    /// Engine-specific knobs for this request, merged over the provider's
+    /// configured `extra`
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub extra: serde_json::Value,
 }
 
 fn default_top_k() -> usize {
@@ -72,13 +95,153 @@ fn default_timeout() -> u64 {
     5000
 }
 
+impl SearchParams {
+    /// Vector field to search: the per-request override, else the provider's configured field
+    pub fn vector_field<'a>(&'a self, configured: Option<&'a str>) -> Option<&'a str> {
+        self.vector_name.as_deref().or(configured)
+    }
+
+    /// The provider's configured `extra` with this request's merged over it:
+    /// objects are merged key by key, anything else is replaced
+    pub fn extra_over(&self, configured: &serde_json::Value) -> serde_json::Value {
+        let mut extra = configured.clone();
+        merge_json(&mut extra, &self.extra);
+        extra
+    }
+}
+
+/// Merge `patch` into `target`: objects key by key, anything else replaced
+pub(crate) fn merge_json(target: &mut serde_json::Value, patch: &serde_json::Value) {
+    match (target, patch) {
+        (_, serde_json::Value::Null) => {}
+        (serde_json::Value::Object(target), serde_json::Value::Object(patch)) => {
+            for (key, value) in patch {
+                merge_json(
+                    target.entry(key.clone()).or_insert(serde_json::Value::Null),
+                    value,
+                );
+            }
+        }
+        (target, patch) => *target = patch.clone(),
+    }
+}
+
 impl Default for SearchParams {
     fn default() -> Self {
         Self {
             top_k: default_top_k(),
+            offset: 0,
             min_score: None,
             timeout_ms: default_timeout(),
             include_payload: false,
+            vector_name: None,
+            extra: serde_json::Value::Null,
+        }
+    }
+}
+
+/// A stored document, as scrolled out of a provider's index or upserted into it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Document {
+    /// Document identifier
+    pub id: String,
+    /// Stored vector (when requested)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vector: Option<Vec<f32>>,
+    /// Stored payload/document content (when requested)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload: Option<serde_json::Value>,
+}
+
+/// Parameters for scrolling documents out of a provider
+#[derive(Debug, Clone)]
+pub struct ScrollParams {
+    /// Documents per page
+    pub batch_size: usize,
+    /// Include stored vectors
+    pub with_vectors: bool,
+    /// Include stored payloads
+    pub with_payload: bool,
+    /// Named vector field to return (overrides the provider's `vector_field`)
+    pub vector_name: Option<String>,
+}
+
+impl Default for ScrollParams {
+    fn default() -> Self {
+        Self {
+            batch_size: 1000,
+            with_vectors: true,
+            with_payload: false,
+            vector_name: None,
         }
     }
 }
+
+/// One page of documents returned by a scroll
+#[derive(Debug, Clone)]
+pub struct ScrollPage {
+    pub documents: Vec<Document>,
+    /// Cursor for the next page (`None` once the index is exhausted)
+    pub next_cursor: Option<String>,
+}
+
+/// Serde for a time kept in microseconds but written in fractional
+/// milliseconds, so `took_ms` keeps the name and unit it always had
+pub(crate) mod micros_as_millis {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(us: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error> {
+        us.map(|us| us as f64 / 1000.0).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<u64>, D::Error> {
+        let ms = Option::<f64>::deserialize(deserializer)?;
+        Ok(ms.map(|ms| (ms * 1000.0).round() as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::*;
+
+    #[test]
+    fn test_took_is_written_in_milliseconds() {
+        let results = SearchResults::new(Vec::new()).with_took(Duration::from_micros(1500));
+        let json = serde_json::to_value(&results).unwrap();
+        assert_eq!(json["took_ms"], 1.5);
+        assert!(json.get("took_us").is_none());
+
+        let parsed: SearchResults = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.took_us, Some(1500));
+        let parsed: SearchResults =
+            serde_json::from_str(r#"{"results": [], "took_ms": 7}"#).unwrap();
+        assert_eq!(parsed.took_us, Some(7000));
+    }
+
+    #[test]
+    fn test_request_extra_merges_over_configured() {
+        let configured = json!({
+            "knn": { "num_candidates": 100, "filter": { "term": { "lang": "en" } } },
+            "size": 10,
+        });
+        let params = SearchParams {
+            extra: json!({ "knn": { "num_candidates": 500, "filter": null }, "size": [1] }),
+            ..Default::default()
+        };
+        assert_eq!(
+            params.extra_over(&configured),
+            json!({
+                "knn": { "num_candidates": 500, "filter": { "term": { "lang": "en" } } },
+                "size": [1],
+            })
+        );
+        // A request without its own extra leaves the configured one alone
+        let params = SearchParams::default();
+        assert_eq!(params.extra_over(&configured), configured);
+        assert_eq!(params.extra_over(&Value::Null), Value::Null);
+    }
+}
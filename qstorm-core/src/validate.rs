@@ -0,0 +1,288 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use crate::embedder::QueryEmbedder;
+use crate::error::Error;
+use crate::provider::SearchProvider;
+use crate::queries::QueryFile;
+
+/// Examples listed per problem before the rest are summarized
+const MAX_EXAMPLES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A single problem found during validation
+#[derive(Debug, Clone)]
+pub struct Issue {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.severity {
+            Severity::Warning => write!(f, "warning: {}", self.message),
+            Severity::Error => write!(f, "error: {}", self.message),
+        }
+    }
+}
+
+/// Collects every problem with a config/query setup instead of stopping at the first
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    pub issues: Vec<Issue>,
+}
+
+impl ValidationReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.issues.push(Issue {
+            severity: Severity::Error,
+            message: message.into(),
+        });
+    }
+
+    pub fn warning(&mut self, message: impl Into<String>) {
+        self.issues.push(Issue {
+            severity: Severity::Warning,
+            message: message.into(),
+        });
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.count(Severity::Error)
+    }
+
+    pub fn warning_count(&self) -> usize {
+        self.count(Severity::Warning)
+    }
+
+    /// True when no errors were found (warnings are allowed)
+    pub fn is_ok(&self) -> bool {
+        self.error_count() == 0
+    }
+
+    fn count(&self, severity: Severity) -> usize {
+        self.issues
+            .iter()
+            .filter(|i| i.severity == severity)
+            .count()
+    }
+
+    /// Check that the query file has queries, none are blank, and none repeat
+    pub fn check_queries(&mut self, query_file: &QueryFile) {
+        if query_file.queries.is_empty() {
+            self.error("query file contains no queries");
+            return;
+        }
+
+        let blank = query_file
+            .queries
+            .iter()
+            .filter(|q| q.text.trim().is_empty())
+            .count();
+        if blank > 0 {
+            self.error(format!("{blank} queries have empty text"));
+        }
+
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for query in &query_file.queries {
+            *counts.entry(query.text.as_str()).or_default() += 1;
+        }
+        let duplicates: BTreeSet<&str> = counts
+            .into_iter()
+            .filter(|(text, n)| *n > 1 && !text.trim().is_empty())
+            .map(|(text, _)| text)
+            .collect();
+        if !duplicates.is_empty() {
+            self.warning(format!(
+                "{} query texts appear more than once: {}",
+                duplicates.len(),
+                examples(duplicates.iter())
+            ));
+        }
+
+        let with_truth = query_file
+            .queries
+            .iter()
+            .filter(|q| q.expected_ids.is_some())
+            .count();
+        if with_truth > 0 && with_truth < query_file.queries.len() {
+            self.warning(format!(
+                "only {with_truth} of {} queries have ground truth; recall covers that subset",
+                query_file.queries.len()
+            ));
+        }
+    }
+
+    /// Check that the models and output formats named in the raw config were
+    /// compiled in; loading the config already checks the provider type.
+    /// `precomputed` is true when every query brings its own vector, so no
+    /// embedding model is needed.
+    pub fn check_features(&mut self, config: &serde_yaml::Value, precomputed: bool) {
+        if !precomputed {
+            let mut models: Vec<&str> = config["vectors"]
+                .as_sequence()
+                .into_iter()
+                .flatten()
+                .filter_map(|v| v["embedding"]["model"].as_str())
+                .collect();
+            match config["embedding"]["model"].as_str() {
+                Some(model) => models.push(model),
+                // Without any embedding config the default fastembed model is used
+                None if models.is_empty() => models.push("BAAI/bge-small-en-v1.5"),
+                None => {}
+            }
+            for model in models {
+                let (feature, compiled) = if model.starts_with("openai/") {
+                    ("openai-embeddings", cfg!(feature = "openai-embeddings"))
+                } else {
+                    ("embeddings", cfg!(feature = "embeddings"))
+                };
+                if !compiled {
+                    self.error(format!(
+                        "embedding model '{model}' needs qstorm built with the `{feature}` feature"
+                    ));
+                }
+            }
+        }
+
+        if let Some(model) = config["rerank"]["model"].as_str() {
+            let (feature, compiled) = if model.starts_with("cohere/") {
+                ("cohere-rerank", cfg!(feature = "cohere-rerank"))
+            } else {
+                ("embeddings", cfg!(feature = "embeddings"))
+            };
+            if !compiled {
+                self.error(format!(
+                    "rerank model '{model}' needs qstorm built with the `{feature}` feature"
+                ));
+            }
+        }
+
+        for section in ["samples", "slow_queries"] {
+            if let Some(path) = config[section]["path"].as_str()
+                && path.ends_with(".parquet")
+                && !cfg!(feature = "parquet")
+            {
+                self.error(format!(
+                    "{section}.path '{path}' needs qstorm built with the `parquet` feature"
+                ));
+            }
+        }
+    }
+
+    /// Check the index exists, ground-truth IDs are in it, and vector
+    /// dimensions match the collection
+    pub async fn check_provider(
+        &mut self,
+        provider: &dyn SearchProvider,
+        query_file: Option<&QueryFile>,
+        embedder: Option<&QueryEmbedder>,
+    ) {
+        match provider.index_exists().await {
+            Ok(true) | Err(Error::Unsupported(_)) => {}
+            Ok(false) => {
+                self.error(format!(
+                    "provider '{}': the configured index does not exist",
+                    provider.name()
+                ));
+                // Every other check would only fail the same way
+                return;
+            }
+            Err(e) => self.warning(format!("could not check that the index exists: {e}")),
+        }
+
+        if let Some(query_file) = query_file {
+            self.check_expected_ids(provider, query_file).await;
+
+            // Precomputed vectors are searched as-is, so they must match the primary field
+            if let Some(dim) = query_file
+                .queries
+                .iter()
+                .find_map(|q| q.vector.as_ref().map(Vec::len))
+            {
+                self.check_dimension(provider, None, dim, "query file vectors")
+                    .await;
+            }
+        }
+
+        if let Some(embedder) = embedder {
+            for (name, dim) in embedder.dimensions() {
+                let source = match name {
+                    Some(name) => format!("embedder for vector '{name}'"),
+                    None => "embedder".to_string(),
+                };
+                self.check_dimension(provider, name, dim, &source).await;
+            }
+        }
+    }
+
+    async fn check_expected_ids(&mut self, provider: &dyn SearchProvider, query_file: &QueryFile) {
+        let expected: BTreeSet<String> = query_file
+            .queries
+            .iter()
+            .filter_map(|q| q.expected_ids.as_ref())
+            .flatten()
+            .cloned()
+            .collect();
+        if expected.is_empty() {
+            return;
+        }
+
+        let ids: Vec<String> = expected.iter().cloned().collect();
+        match provider.existing_ids(&ids).await {
+            Ok(found) => {
+                let missing: Vec<&String> =
+                    expected.iter().filter(|id| !found.contains(*id)).collect();
+                if !missing.is_empty() {
+                    self.error(format!(
+                        "{} of {} ground-truth IDs are not in the index: {}",
+                        missing.len(),
+                        expected.len(),
+                        examples(missing.iter())
+                    ));
+                }
+            }
+            Err(e) => self.warning(format!("could not verify ground-truth IDs: {e}")),
+        }
+    }
+
+    async fn check_dimension(
+        &mut self,
+        provider: &dyn SearchProvider,
+        vector_name: Option<&str>,
+        dim: usize,
+        source: &str,
+    ) {
+        match provider.vector_dimension(vector_name).await {
+            Ok(Some(expected)) if expected != dim => self.error(format!(
+                "{source} produces {dim}-dimensional vectors but the collection expects {expected}"
+            )),
+            Ok(Some(_)) => {}
+            Ok(None) => self.warning(format!(
+                "could not determine the collection's vector dimension to check the {source}"
+            )),
+            Err(e) => self.warning(format!("could not read collection vector dimension: {e}")),
+        }
+    }
+}
+
+fn examples<T: fmt::Display>(items: impl ExactSizeIterator<Item = T>) -> String {
+    let total = items.len();
+    let mut shown: Vec<String> = items
+        .take(MAX_EXAMPLES)
+        .map(|item| format!("'{item}'"))
+        .collect();
+    if total > MAX_EXAMPLES {
+        shown.push(format!("... and {} more", total - MAX_EXAMPLES));
+    }
+    shown.join(", ")
+}
@@ -0,0 +1,84 @@
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+
+use crate::error::Result;
+
+/// Element types stored in TEXMEX vector files
+pub(crate) trait VecsElement: Sized {
+    fn from_le_bytes(bytes: [u8; 4]) -> Self;
+}
+
+impl VecsElement for f32 {
+    fn from_le_bytes(bytes: [u8; 4]) -> Self {
+        f32::from_le_bytes(bytes)
+    }
+}
+
+impl VecsElement for i32 {
+    fn from_le_bytes(bytes: [u8; 4]) -> Self {
+        i32::from_le_bytes(bytes)
+    }
+}
+
+/// Iterator over rows of an `.fvecs`/`.ivecs` file (little-endian i32 dimension, then values)
+pub(crate) struct VecsReader<R, T> {
+    reader: R,
+    _element: PhantomData<T>,
+}
+
+impl<R: Read, T: VecsElement> VecsReader<R, T> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            reader,
+            _element: PhantomData,
+        }
+    }
+}
+
+impl<R: Read, T: VecsElement> Iterator for VecsReader<R, T> {
+    type Item = Result<Vec<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut dim = [0u8; 4];
+        match self.reader.read_exact(&mut dim) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return None,
+            Err(e) => return Some(Err(e.into())),
+        }
+
+        let dim = i32::from_le_bytes(dim) as usize;
+        let mut buf = vec![0u8; dim * 4];
+        if let Err(e) = self.reader.read_exact(&mut buf) {
+            return Some(Err(e.into()));
+        }
+
+        Some(Ok(buf
+            .chunks_exact(4)
+            .map(|b| T::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect()))
+    }
+}
+
+/// Open a vector file for streaming reads
+pub(crate) fn open<T: VecsElement>(
+    path: impl AsRef<Path>,
+) -> Result<VecsReader<BufReader<std::fs::File>, T>> {
+    Ok(VecsReader::new(BufReader::new(std::fs::File::open(path)?)))
+}
+
+/// Write rows as an `.fvecs` file
+pub(crate) fn write<'a>(
+    path: impl AsRef<Path>,
+    rows: impl IntoIterator<Item = &'a [f32]>,
+) -> Result<()> {
+    let mut writer = BufWriter::new(std::fs::File::create(path)?);
+    for row in rows {
+        writer.write_all(&(row.len() as i32).to_le_bytes())?;
+        for value in row {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
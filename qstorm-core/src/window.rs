@@ -0,0 +1,100 @@
+//! Rolling time windows over individual requests.
+//!
+//! Burst metrics reset at every burst boundary; a window instead covers the
+//! last N seconds of requests, however many bursts they came from, so
+//! continuous runs can report a smooth time series.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::metrics::{LatencyMetrics, compute_latency_metrics};
+
+/// Requests completed within the last `window_secs` seconds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowMetrics {
+    pub window_secs: u64,
+    pub query_count: usize,
+    pub failure_count: usize,
+    /// Queries per second over the window, or over the time since the first
+    /// request if that is shorter
+    pub qps: f64,
+    pub latency: LatencyMetrics,
+}
+
+/// Requests completed in the last `length`, oldest first
+#[derive(Debug, Clone)]
+pub(crate) struct SlidingWindow {
+    length: Duration,
+    requests: VecDeque<(Instant, u64, bool)>,
+    first: Option<Instant>,
+}
+
+impl SlidingWindow {
+    pub(crate) fn new(length: Duration) -> Self {
+        Self {
+            length,
+            requests: VecDeque::new(),
+            first: None,
+        }
+    }
+
+    /// Note a request that completed at `at`
+    pub(crate) fn record(&mut self, at: Instant, latency_us: u64, success: bool) {
+        self.first.get_or_insert(at);
+        self.requests.push_back((at, latency_us, success));
+        self.evict(at);
+    }
+
+    /// Metrics over the requests still inside the window at `now`
+    pub(crate) fn snapshot(&mut self, now: Instant) -> WindowMetrics {
+        self.evict(now);
+        let latencies: Vec<u64> = self.requests.iter().map(|&(_, us, _)| us).collect();
+        let span = self
+            .first
+            .map_or(Duration::ZERO, |first| now.duration_since(first))
+            .min(self.length);
+        WindowMetrics {
+            window_secs: self.length.as_secs(),
+            query_count: latencies.len(),
+            failure_count: self.requests.iter().filter(|&&(_, _, ok)| !ok).count(),
+            qps: if span.is_zero() {
+                0.0
+            } else {
+                latencies.len() as f64 / span.as_secs_f64()
+            },
+            latency: compute_latency_metrics(&latencies),
+        }
+    }
+
+    fn evict(&mut self, now: Instant) {
+        while let Some(&(at, _, _)) = self.requests.front() {
+            if now.duration_since(at) <= self.length {
+                break;
+            }
+            self.requests.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_drops_requests_older_than_its_length() {
+        let start = Instant::now();
+        let mut window = SlidingWindow::new(Duration::from_secs(10));
+        for i in 0..20 {
+            window.record(start + Duration::from_secs(i), 1000 * (i + 1), i % 5 != 0);
+        }
+
+        let snapshot = window.snapshot(start + Duration::from_secs(19));
+        // Requests at 9s..=19s are within 10s of the end
+        assert_eq!(snapshot.query_count, 11);
+        assert_eq!(snapshot.failure_count, 2);
+        assert!((snapshot.qps - 1.1).abs() < 1e-9);
+        assert_eq!(snapshot.latency.max_us, 20_000);
+    }
+}